@@ -0,0 +1,18 @@
+-- Enemy trajectories, used by formations with a `path script NAME` line.
+--
+-- Every function receives the progress of a ship along its path, from 0
+-- to 1, and returns its position in fractions of the screen size.
+
+-- Dive toward the middle of the screen, then leave the way it came.
+function dive(t)
+    local x = 1.1 - 1.2 * math.sin(t * math.pi) * 0.6
+    local y = 0.5 + 0.3 * math.cos(t * math.pi * 2)
+    return x, y
+end
+
+-- Corkscrew from right to left.
+function corkscrew(t)
+    local x = 1.1 - 1.2 * t
+    local y = 0.5 + 0.25 * math.sin(t * math.pi * 6)
+    return x, y
+end
@@ -1,16 +1,41 @@
 // main.rs
 
+extern crate backtrace;
+#[macro_use]
+extern crate log;
 extern crate rand;
+#[cfg(feature = "parallel")]
+extern crate rayon;
+#[cfg(feature = "scripting")]
+extern crate rlua;
 extern crate sdl2;
+extern crate sdl2_sys;
 extern crate sdl2_image;
 extern crate sdl2_ttf;
 
 mod phi;
 mod views;
 
+/// The window icon: the ship at rest, from its spritesheet
+const WINDOW_ICON: &'static str = "player_ship";
+const WINDOW_ICON_FRAME: usize = 3;
+
 fn main() {
-    ::phi::spawn("ArcadeRS Demo", |phi| {
-        Box::new(::views::main_menu::MainMenuView::new(phi))
+    let benchmark = ::views::benchmark::duration_from_args();
+    let editor = ::views::editor::path_from_args();
+    let determinism = ::views::determinism::replay_from_args();
+    let view = ::views::registry::name_from_args();
+
+    ::phi::spawn("ArcadeRS Demo", |phi| -> Box<::phi::View> {
+        phi.set_window_icon(WINDOW_ICON, Some(WINDOW_ICON_FRAME));
+
+        match (benchmark, editor, determinism, view) {
+            (Some(duration), _, _, _) => Box::new(::views::benchmark::BenchmarkView::new(phi, duration)),
+            (None, Some(path), _, _) => Box::new(::views::editor::EditorView::new(phi, &path)),
+            (None, None, Some(replay), _) => Box::new(::views::determinism::DeterminismView::new(phi, replay)),
+            (None, None, None, Some(name)) => ::views::registry::build(phi, &name),
+            (None, None, None, None) => Box::new(::views::loading::LoadingView::new()),
+        }
     });
 }
 
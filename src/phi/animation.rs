@@ -0,0 +1,214 @@
+// phi/animation.rs
+
+use ::std::collections::HashMap;
+use ::std::mem;
+use ::std::path::Path;
+use ::std::rc::Rc;
+
+use ::phi::assets::{read_tables, unquote};
+use ::phi::gfx::{AnimatedSprite, Sprite};
+
+/// What an animation does once it showed its last frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnimationMode {
+    /// Start over from the first frame
+    Loop,
+    /// Stay on the last frame
+    Once,
+    /// Go back through the frames to the first one, then forth again
+    PingPong,
+}
+
+impl AnimationMode {
+    fn parse(mode: &str) -> Option<AnimationMode> {
+        match mode {
+            "loop" => Some(AnimationMode::Loop),
+            "once" => Some(AnimationMode::Once),
+            "pingpong" => Some(AnimationMode::PingPong),
+            _ => None,
+        }
+    }
+
+    /// Return which of 'count' frames to show once 'steps' frames went by
+    pub fn frame(self, steps: usize, count: usize) -> usize {
+        match self {
+            AnimationMode::Loop => steps % count,
+            AnimationMode::Once => steps.min(count - 1),
+            AnimationMode::PingPong if count > 1 => {
+                let period = 2 * count - 2;
+                let step = steps % period;
+                if step < count { step } else { period - step }
+            },
+            AnimationMode::PingPong => 0,
+        }
+    }
+}
+
+/// A named animation of a spritesheet: the frames it shows, in order, how
+/// many it shows every second, and what it does once it reaches the end
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub frames: Vec<usize>,
+    pub fps: f64,
+    pub mode: AnimationMode,
+}
+
+/// Parse a list of frames such as `0-3, 7, 9-8`, where `a-b` stands for
+/// every frame from a to b, backward if b comes before a
+fn parse_frames(frames: &str) -> Option<Vec<usize>> {
+    let mut indices = vec![];
+    for item in frames.split(',') {
+        let mut bounds = item.splitn(2, '-');
+        let first = match bounds.next().and_then(|first| first.trim().parse::<usize>().ok()) {
+            Some(first) => first,
+            None => return None,
+        };
+        let last = match bounds.next() {
+            Some(last) => match last.trim().parse::<usize>() {
+                Ok(last) => last,
+                Err(_) => return None,
+            },
+            None => first,
+        };
+
+        if first <= last {
+            indices.extend(first..last + 1);
+        }
+        else {
+            indices.extend((last..first + 1).rev());
+        }
+    }
+    Some(indices)
+}
+
+/// The frames of a spritesheet along with the animations defined for it.
+/// The animations are read from the sidecar file with the same name as the
+/// spritesheet and the `.anim` extension, made of one table per animation:
+///
+/// ```toml
+/// [spin]
+/// frames = "0-142"
+/// fps = 20
+/// mode = "loop"
+/// ```
+///
+/// `frames` lists the frames of the sheet the animation shows, as single
+/// frames and ranges separated by commas. `fps` defaults to 10, and `mode`,
+/// which is one of "loop", "once" and "pingpong", to "loop".
+///
+/// Every animation cuts its frames once, and the sprites playing it all
+/// share them.
+pub struct AnimationSet {
+    frames: Vec<Sprite>,
+    animations: HashMap<String, Animation>,
+
+    // The frames of every animation, in the order it shows them
+    strips: HashMap<String, Rc<Vec<Sprite>>>,
+}
+
+impl AnimationSet {
+    /// Read the animations of the spritesheet at 'sheet_path', cut into
+    /// 'frames'. Animations which are malformed or show frames the sheet
+    /// does not have are left out. Without a sidecar file, the set holds a
+    /// single looping animation called "default" through every frame.
+    pub fn load(sheet_path: &str, frames: Vec<Sprite>) -> AnimationSet {
+        let path = Path::new(sheet_path).with_extension("anim");
+        let path = path.to_string_lossy();
+
+        let mut animations = HashMap::new();
+        let tables = match read_tables(&path) {
+            Some(tables) => tables,
+            None => {
+                animations.insert("default".to_string(), Animation {
+                    frames: (0..frames.len()).collect(),
+                    fps: 10.0,
+                    mode: AnimationMode::Loop,
+                });
+                return AnimationSet::new(frames, animations);
+            }
+        };
+
+        for (name, values) in tables {
+            let mut animation = Animation {
+                frames: vec![],
+                fps: 10.0,
+                mode: AnimationMode::Loop,
+            };
+
+            for (key, value) in values {
+                let valid = match &key[..] {
+                    "frames" => unquote(&value).and_then(parse_frames)
+                        .map(|indices| animation.frames = indices).is_some(),
+                    "fps" => value.parse::<f64>().ok().and_then(|fps| if fps > 0.0 { Some(fps) } else { None })
+                        .map(|fps| animation.fps = fps).is_some(),
+                    "mode" => unquote(&value).and_then(AnimationMode::parse)
+                        .map(|mode| animation.mode = mode).is_some(),
+                    _ => false,
+                };
+                if !valid {
+                    warn!("Ignoring {} = {} for the animation {} in {}", key, value, name, path);
+                }
+            }
+
+            if animation.frames.is_empty() {
+                warn!("Ignoring the animation {} in {}, which has no frames", name, path);
+            }
+            else if let Some(&frame) = animation.frames.iter().find(|&&frame| frame >= frames.len()) {
+                warn!("Ignoring the animation {} in {}, as the sheet has no frame {}", name, path, frame);
+            }
+            else {
+                animations.insert(name, animation);
+            }
+        }
+
+        AnimationSet::new(frames, animations)
+    }
+
+    fn new(frames: Vec<Sprite>, animations: HashMap<String, Animation>) -> AnimationSet {
+        let strips = animations.iter().map(|(name, animation)| {
+            let strip = animation.frames.iter().map(|&frame| frames[frame].clone()).collect();
+            (name.clone(), Rc::new(strip))
+        }).collect();
+
+        AnimationSet {
+            frames: frames,
+            animations: animations,
+            strips: strips,
+        }
+    }
+
+    /// Return the number of frames of the spritesheet
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Return the animation called 'name', if there is one
+    pub fn get(&self, name: &str) -> Option<&Animation> {
+        self.animations.get(name)
+    }
+
+    /// Return a sprite playing the animation called 'name' from its start,
+    /// or None if there is no such animation
+    pub fn sprite(&self, name: &str) -> Option<AnimatedSprite> {
+        match (self.animations.get(name), self.strips.get(name)) {
+            (Some(animation), Some(strip)) => {
+                let mut sprite = AnimatedSprite::from_shared(strip.clone(), animation.fps);
+                sprite.set_mode(animation.mode);
+                Some(sprite)
+            },
+            _ => None,
+        }
+    }
+
+    /// Return whether a sprite still plays one of the animations
+    pub fn is_in_use(&self) -> bool {
+        self.strips.values().any(|strip| Rc::strong_count(strip) > 1)
+    }
+
+    /// The approximate memory taken by the frames of the set, in bytes,
+    /// without the textures they show
+    pub fn memory(&self) -> usize {
+        let frames = self.frames.len() + self.strips.values().map(|strip| strip.len()).sum::<usize>();
+        frames * mem::size_of::<Sprite>()
+    }
+}
@@ -0,0 +1,360 @@
+// phi/assets.rs
+
+use ::phi::animation::AnimationSet;
+use ::phi::data::Rectangle;
+use ::phi::gfx::Sprite;
+use ::phi::themes::Theme;
+use ::sdl2::render::Renderer;
+use ::std::collections::{HashMap, HashSet};
+use ::std::fs::File;
+use ::std::io::{BufRead, BufReader};
+use ::std::rc::Rc;
+use ::std::time::Instant;
+
+pub const MANIFEST_PATH: &'static str = "assets/manifest.toml";
+
+/// Where an asset of the manifest is found, how its frames are laid out if
+/// it is a spritesheet, and which fonts stand in for it if it is a font
+struct ManifestEntry {
+    path: String,
+
+    // The size of every frame, and how many there are. Frames are read
+    // row after row, starting at the top-left corner of the image.
+    frame_size: Option<(f64, f64)>,
+    frames: Option<usize>,
+
+    // The fonts, by name or path, providing the characters this one lacks,
+    // tried in order
+    fallbacks: Vec<String>,
+
+    // The atlas the image is packed into along with the other images of
+    // the same atlas, if any
+    atlas: Option<String>,
+}
+
+/// A table of a data file, made of its name and its `key = value` lines
+pub type Table = (String, Vec<(String, String)>);
+
+/// Read the tables of the data file at 'path', or None if it cannot be
+/// opened. Data files are written in a subset of TOML: `[name]` starts a
+/// table, which holds `key = value` lines. Comments start with `#`.
+pub fn read_tables(path: &str) -> Option<Vec<Table>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+
+    let mut tables: Vec<Table> = vec![];
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            tables.push((line[1..line.len() - 1].trim().to_string(), vec![]));
+            continue;
+        }
+
+        let mut parts = line.splitn(2, '=');
+        match (tables.last_mut(), parts.next(), parts.next()) {
+            (Some(&mut (_, ref mut entries)), Some(key), Some(value)) =>
+                entries.push((key.trim().to_string(), value.trim().to_string())),
+            _ => warn!("Ignoring malformed line in {}: {}", path, line),
+        }
+    }
+
+    Some(tables)
+}
+
+/// Return the content of the string 'value' of a data file, without its
+/// quotes, or None if it is not a string.
+pub fn unquote(value: &str) -> Option<&str> {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        Some(&value[1..value.len() - 1])
+    }
+    else {
+        None
+    }
+}
+
+/// Return the strings of the array 'value', written `["a", "b"]`, or None
+/// if it is not an array of strings.
+pub fn unquote_array(value: &str) -> Option<Vec<&str>> {
+    if !value.starts_with('[') || !value.ends_with(']') {
+        return None;
+    }
+
+    let items = value[1..value.len() - 1].trim();
+    if items.is_empty() {
+        return Some(vec![]);
+    }
+    items.split(',').map(|item| unquote(item.trim())).collect()
+}
+
+/// Read the manifest at 'path', which maps logical names to assets. Every
+/// asset is a table named after it, holding a `path` string and, for
+/// spritesheets, the `frame_w`, `frame_h` and `frames` integers. Fonts may
+/// list `fallbacks`, an array of other fonts. Small images drawn together
+/// may name an `atlas` to be packed into; see phi::atlas.
+fn load_manifest(path: &str) -> HashMap<String, ManifestEntry> {
+    let mut entries = HashMap::new();
+
+    let tables = match read_tables(path) {
+        Some(tables) => tables,
+        None => {
+            warn!("Could not open the asset manifest {}", path);
+            return entries;
+        }
+    };
+
+    for (name, values) in tables {
+        let mut entry = ManifestEntry {
+            path: String::new(),
+            frame_size: None,
+            frames: None,
+            fallbacks: vec![],
+            atlas: None,
+        };
+        let (mut frame_w, mut frame_h) = (None, None);
+
+        for (key, value) in values {
+            let valid = match &key[..] {
+                "path" => unquote(&value).map(|asset| entry.path = asset.to_string()).is_some(),
+                "frame_w" => value.parse().ok().map(|w| frame_w = Some(w)).is_some(),
+                "frame_h" => value.parse().ok().map(|h| frame_h = Some(h)).is_some(),
+                "frames" => value.parse().ok().map(|frames| entry.frames = Some(frames)).is_some(),
+                "atlas" => unquote(&value).map(|atlas| entry.atlas = Some(atlas.to_string())).is_some(),
+                "fallbacks" => unquote_array(&value).map(|fonts| {
+                    entry.fallbacks = fonts.iter().map(|font| font.to_string()).collect();
+                }).is_some(),
+                _ => false,
+            };
+            if !valid {
+                warn!("Ignoring {} = {} for the asset {} in {}", key, value, name, path);
+            }
+        }
+
+        if let (Some(w), Some(h)) = (frame_w, frame_h) {
+            entry.frame_size = Some((w, h));
+        }
+        entries.insert(name, entry);
+    }
+
+    entries
+}
+
+/// Keeps the textures loaded from the filesystem, so that every image is
+/// loaded once however many sprites show it. Assets may be requested by
+/// their name in the manifest, or directly by their path.
+pub struct Assets {
+    manifest: HashMap<String, ManifestEntry>,
+    theme: Theme,
+    textures: HashMap<String, Sprite>,
+
+    // The spritesheets cut into animations, by path, which every sprite
+    // playing them shares
+    animation_sets: HashMap<String, Rc<AnimationSet>>,
+
+    // The textures kept loaded even when no sprite uses them
+    pinned: HashSet<String>,
+}
+
+impl Assets {
+    /// Create an empty cache of the assets listed in the manifest at 'path'
+    pub fn load(manifest_path: &str) -> Assets {
+        Assets {
+            manifest: load_manifest(manifest_path),
+            theme: Theme::default(),
+            textures: HashMap::new(),
+            animation_sets: HashMap::new(),
+            pinned: HashSet::new(),
+        }
+    }
+
+    /// Return the path of the asset called 'name' in the current theme or
+    /// the manifest. Names which are in neither are taken as paths already.
+    pub fn path<'a>(&'a self, name: &'a str) -> &'a str {
+        self.theme.path(name)
+            .or_else(|| self.manifest.get(name).map(|entry| &entry.path[..]))
+            .unwrap_or(name)
+    }
+
+    /// Return the paths of the fonts standing in for the font 'name' when it
+    /// lacks a character, in the order they should be tried
+    pub fn fallbacks(&self, name: &str) -> Vec<String> {
+        self.manifest.get(name).map_or(vec![], |entry| {
+            entry.fallbacks.iter().map(|font| self.path(font).to_string()).collect()
+        })
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Use the assets of 'theme' from now on. Sprites already loaded keep
+    /// showing the previous ones.
+    pub fn set_theme(&mut self, theme: Theme) {
+        info!("Using the theme {}", theme.name);
+        self.theme = theme;
+    }
+
+    /// Return a sprite showing the whole image 'name', loading it if it is
+    /// not loaded yet. Images of an atlas are all loaded the first time one
+    /// of them is asked for.
+    pub fn sprite(&mut self, renderer: &Renderer, name: &str) -> Option<Sprite> {
+        let path = self.path(name).to_string();
+        if let Some(sprite) = self.textures.get(&path) {
+            return Some(sprite.clone());
+        }
+
+        let atlas = self.manifest.get(name).and_then(|entry| entry.atlas.clone());
+        if let Some(atlas) = atlas {
+            self.load_atlas(renderer, &atlas);
+            if let Some(sprite) = self.textures.get(&path) {
+                return Some(sprite.clone());
+            }
+        }
+
+        let sprite = match Sprite::load(renderer, &path) {
+            Some(sprite) => sprite,
+            None => return None,
+        };
+        self.textures.insert(path, sprite.clone());
+        Some(sprite)
+    }
+
+    /// Pack the images of the atlas 'atlas' which are not loaded yet into a
+    /// texture of their own
+    fn load_atlas(&mut self, renderer: &Renderer, atlas: &str) {
+        let mut paths: Vec<String> = self.manifest.iter()
+            .filter(|&(_, entry)| entry.atlas.as_ref().map_or(false, |other| other == atlas))
+            .map(|(name, _)| self.path(name).to_string())
+            .filter(|path| !self.textures.contains_key(path))
+            .collect();
+        paths.sort();
+        paths.dedup();
+
+        debug!("Loading the atlas {}", atlas);
+        for (path, sprite) in ::phi::atlas::build(renderer, &paths) {
+            self.textures.insert(path, sprite);
+        }
+    }
+
+    /// Return where frame 'index' of the spritesheet 'name' lies in an
+    /// image 'sheet_width' pixels wide, or None if the manifest does not
+    /// describe its frames or it has no such frame.
+    pub fn frame_rect(&self, name: &str, index: usize, sheet_width: f64) -> Option<Rectangle> {
+        match self.manifest.get(name) {
+            Some(&ManifestEntry { frame_size: Some((w, h)), frames: Some(frames), .. })
+                    if index < frames => {
+                let columns = ((sheet_width / w) as usize).max(1);
+                Some(Rectangle {
+                    x: w * (index % columns) as f64,
+                    y: h * (index / columns) as f64,
+                    w: w,
+                    h: h,
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// Return the frames of the spritesheet 'name', cut as the manifest
+    /// says, or None if the manifest does not describe its frames.
+    pub fn frames(&mut self, renderer: &Renderer, name: &str) -> Option<Vec<Sprite>> {
+        let frames = match self.manifest.get(name) {
+            Some(&ManifestEntry { frame_size: Some(_), frames: Some(frames), .. }) => frames,
+            _ => {
+                warn!("The asset manifest does not describe the frames of {}", name);
+                return None;
+            }
+        };
+
+        let sheet = match self.sprite(renderer, name) {
+            Some(sheet) => sheet,
+            None => return None,
+        };
+        let sheet_width = sheet.size().0;
+
+        (0..frames)
+            .map(|frame| self.frame_rect(name, frame, sheet_width)
+                 .and_then(|rect| sheet.region(rect)))
+            .collect()
+    }
+
+    /// Return the frames of the spritesheet 'name' along with the
+    /// animations defined next to it, or None if the manifest does not
+    /// describe its frames. The sheet is cut only the first time it is
+    /// asked for, and shared from then on.
+    pub fn animations(&mut self, renderer: &Renderer, name: &str) -> Option<Rc<AnimationSet>> {
+        let path = self.path(name).to_string();
+        if let Some(set) = self.animation_sets.get(&path) {
+            return Some(set.clone());
+        }
+
+        let start = Instant::now();
+        let frames = match self.frames(renderer, name) {
+            Some(frames) => frames,
+            None => return None,
+        };
+        let set = Rc::new(AnimationSet::load(&path, frames));
+
+        let elapsed = start.elapsed();
+        debug!("Cut {} into animations in {:.1} ms, taking {} bytes",
+               path, elapsed.as_secs() as f64 * 1e3 + elapsed.subsec_nanos() as f64 / 1e6, set.memory());
+        self.animation_sets.insert(path, set.clone());
+        Some(set)
+    }
+
+    /// Load the image 'name' ahead of time and keep it loaded until the end
+    /// of the game. Return whether it could be loaded.
+    pub fn preload(&mut self, renderer: &Renderer, name: &str) -> bool {
+        let path = self.path(name).to_string();
+        self.pinned.insert(path);
+        self.sprite(renderer, name).is_some()
+    }
+
+    /// The approximate video memory taken by the loaded textures, in bytes.
+    /// Images packed in the same atlas share its texture, counted once.
+    pub fn texture_memory(&self) -> usize {
+        let mut counted: Vec<&Sprite> = vec![];
+        for sprite in self.textures.values() {
+            if !counted.iter().any(|other| other.shows_same_texture(sprite)) {
+                counted.push(sprite);
+            }
+        }
+        counted.iter().map(|sprite| sprite.texture_memory()).sum()
+    }
+
+    /// Unload the textures which no sprite uses anymore, unless they were
+    /// preloaded. Atlases stay loaded, as their images share them. Return
+    /// the memory this freed, in bytes.
+    pub fn purge_unused(&mut self) -> usize {
+        // The animations no sprite plays anymore keep their textures in use
+        let unused_sets: Vec<String> = self.animation_sets.iter()
+            .filter(|&(_, set)| Rc::strong_count(set) == 1 && !set.is_in_use())
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in unused_sets {
+            debug!("Unloading the animations of {}", path);
+            self.animation_sets.remove(&path);
+        }
+
+        let before = self.texture_memory();
+        let unused: Vec<String> = self.textures.iter()
+            .filter(|&(path, sprite)| !sprite.is_shared() && !self.pinned.contains(path))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in unused {
+            debug!("Unloading the texture {}", path);
+            self.textures.remove(&path);
+        }
+        before - self.texture_memory()
+    }
+}
@@ -0,0 +1,171 @@
+// phi/atlas.rs
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::Sprite;
+use ::sdl2::pixels::PixelFormatEnum;
+use ::sdl2::rect::Rect as SdlRect;
+use ::sdl2::render::{BlendMode, Renderer};
+use ::sdl2::surface::Surface;
+use ::sdl2_image::LoadSurface;
+use ::std::path::Path;
+
+/// The width of the atlases, unless an image is wider
+const ATLAS_WIDTH: u32 = 1024;
+
+/// Pixels left empty around every image, so that scaled sprites do not
+/// bleed into their neighbours
+const PADDING: u32 = 1;
+
+/// Place images of the given sizes in shelves: rows as tall as their
+/// tallest image, filled from left to right, the tallest images first.
+/// Return the top-left corner of every image, in the order of 'sizes', and
+/// the size of the whole atlas.
+pub fn pack(sizes: &[(u32, u32)], width: u32) -> (Vec<(u32, u32)>, (u32, u32)) {
+    let width = sizes.iter().map(|&(w, _)| w + PADDING).max().unwrap_or(0).max(width);
+
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by(|&a, &b| sizes[b].1.cmp(&sizes[a].1));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut shelf_top, mut shelf_height) = (0, 0, 0);
+    for index in order {
+        let (w, h) = sizes[index];
+        if x + w + PADDING > width {
+            // Start a new shelf under the current one
+            shelf_top += shelf_height;
+            x = 0;
+            shelf_height = 0;
+        }
+
+        positions[index] = (x, shelf_top);
+        x += w + PADDING;
+        shelf_height = shelf_height.max(h + PADDING);
+    }
+
+    (positions, (width, shelf_top + shelf_height))
+}
+
+/// Load the images at 'paths' into a single texture, and return a sprite
+/// showing every image which could be loaded, along with its path. Drawing
+/// these sprites one after the other never switches textures.
+pub fn build(renderer: &Renderer, paths: &[String]) -> Vec<(String, Sprite)> {
+    let mut images = vec![];
+    for path in paths {
+        match Surface::from_file(Path::new(path)) {
+            Ok(image) => images.push((path.clone(), image)),
+            Err(err) => warn!("Could not load the texture {}: {}", path, err),
+        }
+    }
+
+    let sizes: Vec<(u32, u32)> = images.iter()
+        .map(|&(_, ref image)| (image.width(), image.height()))
+        .collect();
+    let (positions, (width, height)) = pack(&sizes, ATLAS_WIDTH);
+    if width == 0 || height == 0 {
+        return vec![];
+    }
+
+    let mut atlas = match Surface::new(width, height, PixelFormatEnum::ARGB8888) {
+        Ok(atlas) => atlas,
+        Err(err) => {
+            warn!("Could not create an atlas of {}x{} pixels: {}", width, height, err);
+            return vec![];
+        }
+    };
+
+    // Copy the images with their transparency instead of blending them
+    // onto the blank atlas
+    let mut placed = vec![];
+    for ((path, mut image), (x, y)) in images.into_iter().zip(positions) {
+        let (w, h) = (image.width(), image.height());
+        let dest = SdlRect::new(x as i32, y as i32, w, h).unwrap();
+        if image.set_blend_mode(BlendMode::None).is_err()
+                || image.blit(None, &mut atlas, dest).is_err() {
+            warn!("Could not copy {} into an atlas", path);
+            continue;
+        }
+
+        placed.push((path, Rectangle {
+            x: x as f64,
+            y: y as f64,
+            w: w as f64,
+            h: h as f64,
+        }));
+    }
+
+    let mut texture = match renderer.create_texture_from_surface(&atlas) {
+        Ok(texture) => texture,
+        Err(err) => {
+            warn!("Could not create the texture of an atlas: {}", err);
+            return vec![];
+        }
+    };
+    texture.set_blend_mode(BlendMode::Blend);
+
+    debug!("Packed {} images into an atlas of {}x{} pixels", placed.len(), width, height);
+    let sheet = Sprite::new(texture);
+    placed.into_iter()
+        .filter_map(|(path, rect)| sheet.region(rect).map(|sprite| (path, sprite)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Whether the images of 'sizes' at 'positions' stay apart by at least
+    // PADDING and within an atlas of 'size'
+    fn packed_apart(sizes: &[(u32, u32)], positions: &[(u32, u32)], size: (u32, u32)) -> bool {
+        let cell = |index: usize| {
+            let ((x, y), (w, h)) = (positions[index], sizes[index]);
+            (x, y, x + w + PADDING, y + h + PADDING)
+        };
+        (0..sizes.len()).all(|a| {
+            let (left, top, right, bottom) = cell(a);
+            right <= size.0 && bottom <= size.1 && (0..a).all(|b| {
+                let (other_left, other_top, other_right, other_bottom) = cell(b);
+                right <= other_left || other_right <= left
+                    || bottom <= other_top || other_bottom <= top
+            })
+        })
+    }
+
+    #[test]
+    fn nothing_packs_into_nothing() {
+        assert_eq!(pack(&[], 64), (vec![], (64, 0)));
+    }
+
+    #[test]
+    fn shelves_fill_from_left_to_right() {
+        let sizes = [(10, 10), (10, 10), (10, 10)];
+        let (positions, size) = pack(&sizes, 64);
+        assert_eq!(positions, vec![(0, 0), (11, 0), (22, 0)]);
+        assert_eq!(size, (64, 11));
+    }
+
+    #[test]
+    fn tallest_images_go_first() {
+        let sizes = [(30, 5), (30, 20), (30, 10)];
+        let (positions, size) = pack(&sizes, 64);
+        assert_eq!(positions, vec![(0, 21), (0, 0), (31, 0)]);
+        assert_eq!(size, (64, 27));
+    }
+
+    #[test]
+    fn wide_images_widen_the_atlas() {
+        let sizes = [(100, 4), (8, 8)];
+        let (positions, size) = pack(&sizes, 64);
+        assert_eq!(size.0, 101);
+        assert!(packed_apart(&sizes, &positions, size));
+    }
+
+    #[test]
+    fn images_never_overlap() {
+        let sizes: Vec<(u32, u32)> = (0..200)
+            .map(|index| (1 + index * 37 % 90, 1 + index * 53 % 70))
+            .collect();
+        let (positions, size) = pack(&sizes, 256);
+        assert_eq!(size.0, 256);
+        assert!(packed_apart(&sizes, &positions, size));
+    }
+}
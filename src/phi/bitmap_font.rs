@@ -0,0 +1,70 @@
+// phi/bitmap_font.rs
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::sdl2::pixels::Color;
+use ::sdl2::render::Renderer;
+
+/// A few characters rendered once into a single texture, then copied glyph
+/// by glyph. Unlike `Phi::ttf_str_sprite`, showing new text uploads nothing
+/// to the video card, which suits text changing every frame, such as
+/// counters and damage numbers. Created by `Phi::bitmap_font`.
+pub struct BitmapFont {
+    glyphs: Vec<(char, Sprite)>,
+}
+
+impl BitmapFont {
+    /// Create a font from 'glyphs', which all show regions of the same
+    /// texture
+    pub fn new(glyphs: Vec<(char, Sprite)>) -> BitmapFont {
+        BitmapFont {
+            glyphs: glyphs,
+        }
+    }
+
+    fn glyph(&self, ch: char) -> Option<&Sprite> {
+        self.glyphs.iter()
+            .find(|&&(glyph, _)| glyph == ch)
+            .map(|&(_, ref sprite)| sprite)
+    }
+
+    /// Return the size of 'text' drawn 'scale' times larger than the
+    /// glyphs. Characters the font lacks take no space.
+    pub fn text_size(&self, text: &str, scale: f64) -> (f64, f64) {
+        let (mut w, mut h) = (0.0, 0.0f64);
+        for sprite in text.chars().filter_map(|ch| self.glyph(ch)) {
+            let (glyph_w, glyph_h) = sprite.size();
+            w += glyph_w * scale;
+            h = h.max(glyph_h * scale);
+        }
+        (w, h)
+    }
+
+    /// Tint the text drawn from now on with 'color', its alpha included.
+    /// The glyphs are white, so the text takes exactly that color.
+    pub fn set_color(&self, color: Color) {
+        if let Some(&(_, ref sprite)) = self.glyphs.first() {
+            sprite.set_color_mod(color);
+            sprite.set_alpha_mod(color.rgba().3);
+        }
+    }
+
+    /// Draw 'text' with its top-left corner at 'pos', 'scale' times larger
+    /// than the glyphs
+    pub fn render(&self, renderer: &mut Renderer, text: &str, pos: (f64, f64), scale: f64) {
+        let mut x = pos.0;
+        for sprite in text.chars().filter_map(|ch| self.glyph(ch)) {
+            let (w, h) = sprite.size();
+            let dest = Rectangle {
+                x: x.round(),
+                y: pos.1.round(),
+                w: (w * scale).round(),
+                h: (h * scale).round(),
+            };
+            if dest.w >= 1.0 && dest.h >= 1.0 {
+                sprite.render(renderer, dest);
+            }
+            x += w * scale;
+        }
+    }
+}
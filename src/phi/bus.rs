@@ -0,0 +1,114 @@
+// phi/bus.rs
+
+/// Something which happened in the game. The system which notices it, such
+/// as the collision code, pushes it on the bus; the score, the HUD, the
+/// camera, the statistics and the like react to it, without the two knowing
+/// about each other.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// A bullet of the player hit something; heavy bullets stop the frame
+    BulletHit { pos: (f64, f64), heavy: bool },
+
+    /// The player dealt 'amount' damage to something with hit points,
+    /// critically if 'crit'
+    Damage { pos: (f64, f64), amount: u32, crit: bool },
+
+    AsteroidDestroyed { pos: (f64, f64), score: u32 },
+    EnemyDestroyed { pos: (f64, f64), score: u32 },
+    HazardDestroyed { pos: (f64, f64), score: u32 },
+    BossPartDestroyed { pos: (f64, f64), score: u32 },
+    BossDestroyed { pos: (f64, f64), score: u32 },
+
+    /// A mine went off, on its own or shot by the player
+    MineExploded { pos: (f64, f64) },
+
+    /// The ship was damaged, and has 'hp' hit points left
+    ShipHit { pos: (f64, f64), hp: u32 },
+
+    /// The player fired 'count' bullets at once
+    ShotsFired { count: u32 },
+
+    /// Show a banner across the middle of the screen
+    Announce(Announcement),
+}
+
+/// A message shown in a banner across the middle of the screen, such as
+/// the number of a new wave. Banners are shown one after the other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Announcement {
+    pub text: String,
+
+    /// Seconds the banner stays on screen, sliding in and out included
+    pub duration: f64,
+
+    /// The sound played as the banner slides in
+    pub sound: Option<String>,
+
+    /// Whether the banner warns of a danger, in which case it is red and
+    /// blinks, unless flashes are reduced
+    pub urgent: bool,
+}
+
+impl Announcement {
+    pub fn new(text: &str, duration: f64) -> Announcement {
+        Announcement {
+            text: text.to_string(),
+            duration: duration,
+            sound: None,
+            urgent: false,
+        }
+    }
+
+    /// Play the sound called 'sound' as the banner slides in
+    pub fn with_sound(mut self, sound: &str) -> Announcement {
+        self.sound = Some(sound.to_string());
+        self
+    }
+
+    /// Make the banner warn of a danger
+    pub fn urgent(mut self) -> Announcement {
+        self.urgent = true;
+        self
+    }
+}
+
+impl GameEvent {
+    /// The points the event is worth before the combo multiplier, if any
+    pub fn score(&self) -> u32 {
+        match *self {
+            GameEvent::AsteroidDestroyed { score, .. } |
+            GameEvent::EnemyDestroyed { score, .. } |
+            GameEvent::HazardDestroyed { score, .. } |
+            GameEvent::BossPartDestroyed { score, .. } |
+            GameEvent::BossDestroyed { score, .. } => score,
+            _ => 0,
+        }
+    }
+}
+
+/// The events of the current frame. Events are kept until the frame ends,
+/// so that every system may read all of them, then cleared by `spawn`.
+pub struct EventBus {
+    events: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        EventBus {
+            events: vec![],
+        }
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// The events pushed during this frame, oldest first
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
@@ -0,0 +1,152 @@
+// phi/camera.rs
+
+use ::std::f64::consts::PI;
+
+use ::sdl2::render::Renderer;
+
+use ::phi::data::Rectangle;
+use ::phi::random::Distributions;
+
+/// Below this amplitude, in pixels, a shake is considered over
+const SHAKE_THRESHOLD: f64 = 0.1;
+
+/// A shock applied to the camera
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shake {
+    /// Initial displacement of the view, in pixels
+    pub magnitude: f64,
+
+    /// Oscillations per second
+    pub frequency: f64,
+
+    /// Rate at which the magnitude decreases; it is divided by e every
+    /// 1/decay seconds
+    pub decay: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ActiveShake {
+    shake: Shake,
+    time: f64,
+
+    // So that simultaneous shakes do not oscillate in lockstep
+    phase: f64,
+}
+
+impl ActiveShake {
+    fn amplitude(&self) -> f64 {
+        self.shake.magnitude * (-self.shake.decay * self.time).exp()
+    }
+}
+
+/// A move of the camera from one offset to another
+#[derive(Clone, Copy, Debug)]
+struct Pan {
+    from: (f64, f64),
+    to: (f64, f64),
+    duration: f64,
+    time: f64,
+}
+
+impl Pan {
+    fn position(&self) -> (f64, f64) {
+        let t = if self.duration > 0.0 { (self.time / self.duration).min(1.0) } else { 1.0 };
+        // Ease in and out, so that the camera starts and stops smoothly
+        let t = t * t * (3.0 - 2.0 * t);
+        (self.from.0 + (self.to.0 - self.from.0) * t,
+         self.from.1 + (self.to.1 - self.from.1) * t)
+    }
+}
+
+/// Displaces everything rendered through it, to shake the screen or to
+/// move the view during cutscenes. Shakes stack: every impulse oscillates
+/// and fades on its own, and their displacements add up.
+pub struct Camera {
+    shakes: Vec<ActiveShake>,
+    pan: Pan,
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {
+            shakes: vec![],
+            pan: Pan {
+                from: (0.0, 0.0),
+                to: (0.0, 0.0),
+                duration: 0.0,
+                time: 0.0,
+            },
+        }
+    }
+
+    /// Move the view to be displaced by 'x' and 'y' pixels, over 'duration'
+    /// seconds, from wherever it is now
+    pub fn move_to(&mut self, x: f64, y: f64, duration: f64) {
+        self.pan = Pan {
+            from: self.pan.position(),
+            to: (x, y),
+            duration: duration,
+            time: 0.0,
+        };
+    }
+
+    /// Start shaking the screen, on top of any ongoing shake
+    pub fn shake(&mut self, shake: Shake) {
+        self.shakes.push(ActiveShake {
+            shake: shake,
+            time: 0.0,
+            phase: ::rand::thread_rng().angle(),
+        });
+    }
+
+    /// Let the shakes fade, and forget those which became imperceptible
+    pub fn update(&mut self, dt: f64) {
+        self.pan.time += dt;
+        for active in &mut self.shakes {
+            active.time += dt;
+        }
+        self.shakes.retain(|active| active.amplitude() >= SHAKE_THRESHOLD);
+    }
+
+    /// Return the current displacement of the view, in pixels, with the
+    /// amplitude of the shakes multiplied by 'scale'
+    pub fn offset(&self, scale: f64) -> (f64, f64) {
+        self.shakes.iter().fold(self.pan.position(), |(x, y), active| {
+            let angle = 2.0 * PI * active.shake.frequency * active.time + active.phase;
+            let amplitude = active.amplitude() * scale;
+            // Use different frequencies on both axes so that the view
+            // moves around rather than along a line
+            (x + amplitude * angle.sin(), y + amplitude * (angle * 1.3).cos())
+        })
+    }
+
+    /// Render everything that follows displaced by the current offset,
+    /// until 'reset' is called
+    pub fn apply(&self, renderer: &mut Renderer, scale: f64) {
+        let (x, y) = self.offset(scale);
+        let (w, h) = renderer.output_size().unwrap();
+        renderer.set_viewport(Rectangle {
+            x: x.round(),
+            y: y.round(),
+            w: w as f64,
+            h: h as f64,
+        }.to_sdl());
+    }
+
+    /// Return the part of the scene shown in a screen of size (w, h) once
+    /// displaced by the current offset
+    pub fn visible_area(&self, scale: f64, (w, h): (f64, f64)) -> Rectangle {
+        let (x, y) = self.offset(scale);
+        Rectangle {
+            x: -x.round(),
+            y: -y.round(),
+            w: w,
+            h: h,
+        }
+    }
+
+    /// Render everything that follows in place, such as the HUD
+    pub fn reset(&self, renderer: &mut Renderer) {
+        renderer.set_viewport(None);
+    }
+}
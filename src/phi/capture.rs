@@ -0,0 +1,122 @@
+// phi/capture.rs
+
+use ::std::collections::VecDeque;
+use ::std::fs;
+use ::std::thread;
+use ::std::time::{SystemTime, UNIX_EPOCH};
+
+use ::sdl2::pixels::PixelFormatEnum;
+use ::sdl2::render::Renderer;
+
+use ::phi::gif::{self, Frame};
+
+pub const CLIPS_DIR: &'static str = "clips";
+
+/// Seconds of play kept to be saved as a clip
+const CLIP_SECONDS: f64 = 10.0;
+
+/// Frames kept every second
+const CLIP_FPS: f64 = 10.0;
+
+/// The widest a clip is, in pixels; larger windows are scaled down
+const CLIP_MAX_WIDTH: u32 = 320;
+
+/// Keeps the last seconds of play, scaled down, so that they can be saved
+/// as an animated GIF to share a highlight
+pub struct ClipRecorder {
+    // The frames kept, oldest first, and their size
+    frames: VecDeque<Frame>,
+    width: u32,
+    height: u32,
+
+    // Seconds since the last frame was kept
+    since_last: f64,
+}
+
+impl ClipRecorder {
+    pub fn new() -> ClipRecorder {
+        ClipRecorder {
+            frames: VecDeque::new(),
+            width: 0,
+            height: 0,
+            since_last: 0.0,
+        }
+    }
+
+    /// Keep the frame just drawn by 'renderer', if it is time to, and
+    /// forget the frames which are too old
+    pub fn capture(&mut self, renderer: &Renderer, elapsed: f64) {
+        self.since_last += elapsed;
+        if self.since_last < 1.0 / CLIP_FPS {
+            return;
+        }
+        self.since_last = 0.0;
+
+        let (win_w, win_h) = match renderer.output_size() {
+            Ok(size) => size,
+            Err(_) => return,
+        };
+        let pixels = match renderer.read_pixels(None, PixelFormatEnum::RGB24) {
+            Ok(pixels) => pixels,
+            Err(err) => {
+                warn!("Could not capture the frame: {}", err);
+                return;
+            },
+        };
+
+        // Keep every few pixels of every few rows, in the colors of the
+        // palette of the clip
+        let step = (win_w + CLIP_MAX_WIDTH - 1) / CLIP_MAX_WIDTH;
+        let (width, height) = (win_w / step, win_h / step);
+        if (width, height) != (self.width, self.height) {
+            // The window was resized, and the frames kept no longer match
+            self.frames.clear();
+            self.width = width;
+            self.height = height;
+        }
+
+        let mut indices = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let offset = (((y * step) * win_w + x * step) * 3) as usize;
+                indices.push(gif::color_index(pixels[offset], pixels[offset + 1], pixels[offset + 2]));
+            }
+        }
+
+        self.frames.push_back(Frame {
+            pixels: indices,
+            delay: (100.0 / CLIP_FPS) as u16,
+        });
+        while self.frames.len() as f64 > CLIP_SECONDS * CLIP_FPS {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Save the frames kept as a GIF in the clips directory, named after
+    /// the current time. The file is written in the background; return
+    /// its path, or None if there is nothing to save.
+    pub fn save(&self) -> Option<String> {
+        if self.frames.is_empty() {
+            return None;
+        }
+
+        let date = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let path = format!("{}/{}.gif", CLIPS_DIR, date);
+        let frames: Vec<Frame> = self.frames.iter().cloned().collect();
+        let (width, height) = (self.width as u16, self.height as u16);
+
+        let written = path.clone();
+        thread::spawn(move || {
+            let result = fs::create_dir_all(CLIPS_DIR)
+                .and_then(|_| gif::write(&written, width, height, &frames));
+            match result {
+                Ok(()) => info!("Saved the clip {}", written),
+                Err(err) => warn!("Could not save the clip {}: {}", written, err),
+            }
+        });
+
+        Some(path)
+    }
+}
@@ -0,0 +1,462 @@
+// phi/controllers.rs
+
+use ::sdl2::{GameControllerSubsystem, HapticSubsystem, JoystickSubsystem};
+use ::sdl2::controller::{Axis, Button, GameController};
+use ::sdl2_sys::haptic as ll_haptic;
+use ::sdl2_sys::joystick as ll_joystick;
+use ::std::fs::File;
+use ::std::io::{BufRead, BufReader};
+
+/// The number of players who may each have their own controller
+pub const MAX_PLAYERS: usize = 2;
+
+/// Where to look for the SDL game controller database, in order: a copy
+/// next to the game, which players can update, then the one we ship.
+pub const MAPPINGS_PATHS: [&'static str; 2] = [
+    "gamecontrollerdb.txt",
+    "assets/gamecontrollerdb.txt",
+];
+
+/// Turns raw analog stick positions into usable values: the stick is
+/// ignored below 'inner', considered fully tilted past 'outer', and the
+/// magnitude in between is raised to 'curve' (> 1 gives finer control of
+/// slow movements).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DeadZone {
+    pub inner: f64,
+    pub outer: f64,
+    pub curve: f64,
+}
+
+impl DeadZone {
+    /// Map a stick position, whose coordinates are in [-1, 1], to a
+    /// position pointing in the same direction with a magnitude in [0, 1].
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let magnitude = (x * x + y * y).sqrt();
+        if magnitude <= self.inner || self.outer <= self.inner {
+            return (0.0, 0.0);
+        }
+
+        let scaled = ((magnitude - self.inner) / (self.outer - self.inner))
+            .min(1.0)
+            .powf(self.curve);
+
+        (x / magnitude * scaled, y / magnitude * scaled)
+    }
+}
+
+/// A button of a game controller, or one of its triggers, which counts as
+/// pressed once pulled halfway
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PadInput {
+    Button(Button),
+    Trigger(Axis),
+}
+
+impl PadInput {
+    /// The name of the input in SDL mappings, such as `a` or `righttrigger`
+    pub fn name(self) -> String {
+        match self {
+            PadInput::Button(button) => button.string(),
+            PadInput::Trigger(axis) => axis.string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<PadInput> {
+        match Button::from_string(name) {
+            Some(button) => Some(PadInput::Button(button)),
+            None => Axis::from_string(name)
+                .and_then(|axis| if is_trigger(axis) { Some(PadInput::Trigger(axis)) } else { None }),
+        }
+    }
+}
+
+/// The actions of the game which controller inputs can be bound to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PadAction {
+    Fire,
+    NextWeapon,
+    Pause,
+}
+
+impl PadAction {
+    pub fn all() -> Vec<PadAction> {
+        vec![PadAction::Fire, PadAction::NextWeapon, PadAction::Pause]
+    }
+
+    /// The name of the action in the settings
+    pub fn name(self) -> &'static str {
+        match self {
+            PadAction::Fire => "fire",
+            PadAction::NextWeapon => "next_weapon",
+            PadAction::Pause => "pause",
+        }
+    }
+
+    /// The key of the action's label in the string tables
+    pub fn label_key(self) -> &'static str {
+        match self {
+            PadAction::Fire => "controls.fire",
+            PadAction::NextWeapon => "controls.next_weapon",
+            PadAction::Pause => "controls.pause",
+        }
+    }
+}
+
+/// How the buttons and sticks of one kind of controller are used. Bindings
+/// are kept in the settings by controller GUID, so that every model of pad
+/// can be set up on its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PadBindings {
+    pub fire: PadInput,
+    pub next_weapon: PadInput,
+    pub pause: PadInput,
+
+    /// Whether the right stick moves the ship and the left one aims
+    pub swap_sticks: bool,
+
+    /// Whether pushing a stick up points down, and the other way around
+    pub invert_y: bool,
+}
+
+impl PadBindings {
+    pub fn default() -> PadBindings {
+        PadBindings {
+            fire: PadInput::Button(Button::A),
+            next_weapon: PadInput::Button(Button::RightShoulder),
+            pause: PadInput::Button(Button::Start),
+            swap_sticks: false,
+            invert_y: false,
+        }
+    }
+
+    /// Return the input bound to 'action'
+    pub fn input(&self, action: PadAction) -> PadInput {
+        match action {
+            PadAction::Fire => self.fire,
+            PadAction::NextWeapon => self.next_weapon,
+            PadAction::Pause => self.pause,
+        }
+    }
+
+    pub fn bind(&mut self, action: PadAction, input: PadInput) {
+        match action {
+            PadAction::Fire => self.fire = input,
+            PadAction::NextWeapon => self.next_weapon = input,
+            PadAction::Pause => self.pause = input,
+        }
+    }
+
+    /// Read the bindings of a controller from a settings entry written by
+    /// to_setting, which starts with the GUID of the controller. Entries
+    /// missing from it keep their default value.
+    pub fn parse(value: &str) -> Option<(String, PadBindings)> {
+        let mut words = value.split_whitespace();
+        let guid = match words.next() {
+            Some(guid) => guid.to_string(),
+            None => return None,
+        };
+
+        let mut bindings = PadBindings::default();
+        for word in words {
+            let mut parts = word.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key, value),
+                _ => continue,
+            };
+
+            match key {
+                "swap_sticks" => bindings.swap_sticks = value == "true",
+                "invert_y" => bindings.invert_y = value == "true",
+                key => {
+                    let action = PadAction::all().into_iter().find(|action| action.name() == key);
+                    if let (Some(action), Some(input)) = (action, PadInput::from_name(value)) {
+                        bindings.bind(action, input);
+                    }
+                },
+            }
+        }
+
+        Some((guid, bindings))
+    }
+
+    /// Write the bindings of the controller 'guid' as a settings entry
+    pub fn to_setting(&self, guid: &str) -> String {
+        let mut setting = guid.to_string();
+        for action in PadAction::all() {
+            setting.push_str(&format!(" {}={}", action.name(), self.input(action).name()));
+        }
+        setting.push_str(&format!(" swap_sticks={} invert_y={}", self.swap_sticks, self.invert_y));
+        setting
+    }
+
+    /// Return the raw position of the stick of 'player' which moves the
+    /// ship, as Controllers::left_stick does
+    pub fn move_stick(&self, controllers: &Controllers, player: usize) -> Option<(f64, f64)> {
+        let stick = if self.swap_sticks { controllers.right_stick(player) }
+                    else { controllers.left_stick(player) };
+        stick.map(|stick| self.orient(stick))
+    }
+
+    /// Return the raw position of the stick of 'player' which aims
+    pub fn aim_stick(&self, controllers: &Controllers, player: usize) -> Option<(f64, f64)> {
+        let stick = if self.swap_sticks { controllers.left_stick(player) }
+                    else { controllers.right_stick(player) };
+        stick.map(|stick| self.orient(stick))
+    }
+
+    fn orient(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        if self.invert_y { (x, -y) } else { (x, y) }
+    }
+}
+
+/// Keeps track of the game controllers plugged in, and of which player
+/// each of them belongs to.
+pub struct Controllers {
+    subsystem: GameControllerSubsystem,
+    joystick: JoystickSubsystem,
+
+    // Not used directly, but SDL can only open force feedback devices
+    // while it is initialized
+    _haptic: HapticSubsystem,
+
+    // The controller of every player, indexed by player, along with the
+    // instance id of its joystick, which identifies it in SDL events
+    players: Vec<Option<(GameController, i32)>>,
+
+    // The force feedback device of every player's controller, if it has one
+    rumblers: Vec<Option<Rumbler>>,
+}
+
+impl Controllers {
+    pub fn new(subsystem: GameControllerSubsystem, joystick: JoystickSubsystem,
+               haptic: HapticSubsystem) -> Controllers {
+        let mut players = Vec::with_capacity(MAX_PLAYERS);
+        let mut rumblers = Vec::with_capacity(MAX_PLAYERS);
+        for _ in 0..MAX_PLAYERS {
+            players.push(None);
+            rumblers.push(None);
+        }
+
+        Controllers {
+            subsystem: subsystem,
+            joystick: joystick,
+            _haptic: haptic,
+            players: players,
+            rumblers: rumblers,
+        }
+    }
+
+    /// Load the controller mappings from the first database of 'paths'
+    /// which can be read. Return the number of mappings it added.
+    pub fn load_mappings(&mut self, paths: &[&str]) -> Option<i32> {
+        for path in paths {
+            let file = match File::open(path) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
+
+            // sdl2 0.13 cannot load a whole database, so we add its
+            // mappings one by one, skipping blank lines and comments.
+            let mut count = 0;
+            for line in BufReader::new(file).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                if self.add_mapping(line) {
+                    count += 1;
+                }
+            }
+
+            return Some(count);
+        }
+
+        None
+    }
+
+    /// Add a single mapping, written in the format of the SDL game
+    /// controller database. Return whether it was accepted.
+    pub fn add_mapping(&mut self, mapping: &str) -> bool {
+        self.subsystem.add_mapping(mapping).is_ok()
+    }
+
+    /// Open the joystick at 'index' if it is a game controller, and give
+    /// it to the first player who has none. Return that player.
+    pub fn connect(&mut self, index: u32) -> Option<usize> {
+        if !self.subsystem.is_game_controller(index) {
+            return None;
+        }
+
+        let player = match self.players.iter().position(|pad| pad.is_none()) {
+            Some(player) => player,
+            None => return None,
+        };
+
+        let controller = match self.subsystem.open(index) {
+            Ok(controller) => controller,
+            Err(_) => return None,
+        };
+
+        // Game controllers do not expose their instance id, so we read it
+        // from the underlying joystick, which SDL shares between both.
+        let instance_id = match self.joystick.open(index) {
+            Ok(joystick) => joystick.instance_id(),
+            Err(_) => return None,
+        };
+
+        // Not every controller can vibrate
+        self.rumblers[player] = Rumbler::open(index);
+        self.players[player] = Some((controller, instance_id));
+
+        Some(player)
+    }
+
+    /// Close the controller with the given joystick instance id, which has
+    /// been unplugged. Return the player it belonged to.
+    pub fn disconnect(&mut self, instance_id: i32) -> Option<usize> {
+        let player = self.player_of(instance_id);
+
+        if let Some(player) = player {
+            self.players[player] = None;
+            self.rumblers[player] = None;
+        }
+
+        player
+    }
+
+    /// Return the controller of 'player', if one is plugged in.
+    pub fn get(&self, player: usize) -> Option<&GameController> {
+        self.players.get(player)
+            .and_then(|pad| pad.as_ref())
+            .map(|&(ref controller, _)| controller)
+    }
+
+    pub fn is_connected(&self, player: usize) -> bool {
+        self.get(player).is_some()
+    }
+
+    /// Return the player holding the controller with the given joystick
+    /// instance id
+    pub fn player_of(&self, instance_id: i32) -> Option<usize> {
+        self.players.iter().position(|pad| match *pad {
+            Some((_, id)) => id == instance_id,
+            None => false,
+        })
+    }
+
+    /// Return the GUID of the controller of 'player', which is the same for
+    /// every controller of the same model
+    pub fn guid(&self, player: usize) -> Option<String> {
+        self.get(player)
+            .map(|controller| controller.mapping())
+            .and_then(|mapping| mapping.split(',').next().map(|guid| guid.to_string()))
+    }
+
+    /// Whether 'input' is held on the controller of 'player'
+    pub fn is_held(&self, player: usize, input: PadInput) -> bool {
+        self.get(player).map_or(false, |controller| match input {
+            PadInput::Button(button) => controller.button(button),
+            PadInput::Trigger(axis) => axis_value(controller.axis(axis)) >= TRIGGER_THRESHOLD,
+        })
+    }
+
+    /// Return the raw position of the left stick of 'player', with both
+    /// coordinates in [-1, 1] and y pointing down.
+    pub fn left_stick(&self, player: usize) -> Option<(f64, f64)> {
+        self.get(player).map(|controller| {
+            (axis_value(controller.axis(Axis::LeftX)),
+             axis_value(controller.axis(Axis::LeftY)))
+        })
+    }
+
+    /// Return the raw position of the right stick of 'player', with both
+    /// coordinates in [-1, 1] and y pointing down.
+    pub fn right_stick(&self, player: usize) -> Option<(f64, f64)> {
+        self.get(player).map(|controller| {
+            (axis_value(controller.axis(Axis::RightX)),
+             axis_value(controller.axis(Axis::RightY)))
+        })
+    }
+
+    /// Make the controller of 'player' vibrate with a strength in [0, 1]
+    /// for 'duration' seconds. Does nothing if it cannot vibrate.
+    pub fn rumble(&mut self, player: usize, intensity: f64, duration: f64) {
+        if let Some(&mut Some(ref mut rumbler)) = self.rumblers.get_mut(player) {
+            rumbler.play(intensity, duration);
+        }
+    }
+}
+
+// The force feedback device of a controller, used for simple rumbles.
+//
+// sdl2 0.13 does not wrap SDL's haptic API yet, so this talks to it
+// directly; drop it for the safe wrapper once the dependency is bumped.
+struct Rumbler {
+    joystick: *mut ll_joystick::SDL_Joystick,
+    haptic: *mut ll_haptic::SDL_Haptic,
+}
+
+impl Rumbler {
+    // Open the joystick at 'index' as a rumbler, if it can vibrate
+    fn open(index: u32) -> Option<Rumbler> {
+        unsafe {
+            // SDL counts the references to an open joystick, so this does
+            // not interfere with the game controller using the same device.
+            let joystick = ll_joystick::SDL_JoystickOpen(index as i32);
+            if joystick.is_null() {
+                return None;
+            }
+
+            let haptic = ll_haptic::SDL_HapticOpenFromJoystick(joystick);
+            if haptic.is_null() {
+                ll_joystick::SDL_JoystickClose(joystick);
+                return None;
+            }
+
+            // Building the Rumbler first lets Drop close both on failure
+            let rumbler = Rumbler { joystick: joystick, haptic: haptic };
+            if ll_haptic::SDL_HapticRumbleInit(haptic) != 0 {
+                return None;
+            }
+
+            Some(rumbler)
+        }
+    }
+
+    // Vibrate with a strength in [0, 1] for 'duration' seconds
+    fn play(&mut self, intensity: f64, duration: f64) {
+        let intensity = intensity.max(0.0).min(1.0) as f32;
+        let length = (duration.max(0.0) * 1_000.0) as u32;
+        unsafe {
+            ll_haptic::SDL_HapticRumblePlay(self.haptic, intensity, length);
+        }
+    }
+}
+
+impl Drop for Rumbler {
+    fn drop(&mut self) {
+        unsafe {
+            ll_haptic::SDL_HapticClose(self.haptic);
+            ll_joystick::SDL_JoystickClose(self.joystick);
+        }
+    }
+}
+
+/// How far a trigger must be pulled to count as pressed, in [0, 1]
+pub const TRIGGER_THRESHOLD: f64 = 0.5;
+
+/// Whether 'axis' is one of the triggers, rather than of the sticks
+pub fn is_trigger(axis: Axis) -> bool {
+    axis == Axis::TriggerLeft || axis == Axis::TriggerRight
+}
+
+// Normalize an SDL axis value to [-1, 1]
+fn axis_value(raw: i16) -> f64 {
+    (raw as f64 / 32_767.0).max(-1.0)
+}
@@ -0,0 +1,57 @@
+// phi/crash.rs
+
+use ::std::fs::File;
+use ::std::io::Write;
+use ::std::panic::{self, PanicInfo};
+
+use ::backtrace::Backtrace;
+use ::sdl2::messagebox::{self, MESSAGEBOX_ERROR};
+
+/// Where the details of a crash are written
+pub const CRASH_LOG_PATH: &'static str = "crash.log";
+
+/// Report panics to the player instead of dying silently: the panic and a
+/// backtrace are written to CRASH_LOG_PATH, and a message box tells the
+/// player where to find them. The panic is then handled as usual.
+pub fn install_panic_hook(title: &str) {
+    let title = format!("{} crashed", title);
+    let default_hook = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        let message = describe(info);
+        let report = format!("{}\n\n{:?}\n", message, Backtrace::new());
+
+        let logged = File::create(CRASH_LOG_PATH)
+            .and_then(|mut file| file.write_all(report.as_bytes()))
+            .is_ok();
+        let text = if logged {
+            format!("{}\n\nThe details were written to {}.", message, CRASH_LOG_PATH)
+        }
+        else {
+            message
+        };
+
+        // Showing the message box does not require SDL to be initialized,
+        // so it also works if the panic occurred while setting it up
+        let _ = messagebox::show_simple_message_box(MESSAGEBOX_ERROR, &title, &text, None);
+
+        default_hook(info);
+    }));
+}
+
+// Return what went wrong and where, as told by the panic
+fn describe(info: &PanicInfo) -> String {
+    let payload = info.payload();
+    let cause = match payload.downcast_ref::<&str>() {
+        Some(cause) => cause.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(cause) => cause.clone(),
+            None => "unknown error".to_string(),
+        },
+    };
+
+    match info.location() {
+        Some(location) => format!("Error: {} (at {}:{})", cause, location.file(), location.line()),
+        None => format!("Error: {}", cause),
+    }
+}
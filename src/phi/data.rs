@@ -1,5 +1,6 @@
 // src/phi.mod.s
 
+use ::phi::path::parse_point;
 use ::sdl2::rect::Rect as SdlRect;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -55,6 +56,18 @@ impl Rectangle {
         })
     }
 
+    /// Return a (perhaps moved) rectangle which is contained by a parent
+    /// rectangle whenever it fits. Along an axis where self is larger than
+    /// its parent, it is centered on the parent instead.
+    pub fn clamp_inside(self, parent: Rectangle) -> Rectangle {
+        Rectangle {
+            w: self.w,
+            h: self.h,
+            x: clamp_span(self.x, self.w, parent.x, parent.w),
+            y: clamp_span(self.y, self.h, parent.y, parent.h),
+        }
+    }
+
     pub fn contains(&self, rect: Rectangle) -> bool {
         let xmin = rect.x;
         let xmax = xmin + rect.w;
@@ -73,4 +86,425 @@ impl Rectangle {
         self.y < (other.y + other.h) &&
         (self.y + self.h) > other.y
     }
+
+    /// Whether self and 'other' touch or overlap. Unlike overlaps, rectangles
+    /// which only share an edge or a corner intersect.
+    pub fn intersects(&self, other: Rectangle) -> bool {
+        self.x <= (other.x + other.w) &&
+        (self.x + self.w) >= other.x &&
+        self.y <= (other.y + other.h) &&
+        (self.y + self.h) >= other.y
+    }
+
+    /// Return the area shared by self and 'other', or None if they do not
+    /// overlap.
+    pub fn intersection(&self, other: Rectangle) -> Option<Rectangle> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+
+        Some(Rectangle {
+            x: x,
+            y: y,
+            w: (self.x + self.w).min(other.x + other.w) - x,
+            h: (self.y + self.h).min(other.y + other.h) - y,
+        })
+    }
+
+    /// Return the smallest rectangle containing both self and 'other'.
+    pub fn union(&self, other: Rectangle) -> Rectangle {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+
+        Rectangle {
+            x: x,
+            y: y,
+            w: (self.x + self.w).max(other.x + other.w) - x,
+            h: (self.y + self.h).max(other.y + other.h) - y,
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    /// Return a rectangle of the same size whose center is at 'center'.
+    pub fn with_center(self, center: (f64, f64)) -> Rectangle {
+        Rectangle {
+            x: center.0 - self.w / 2.0,
+            y: center.1 - self.h / 2.0,
+            ..self
+        }
+    }
+
+    /// Grow the rectangle by 'dx' on the left and right sides, and by 'dy'
+    /// on the top and bottom ones. Negative values shrink it, down to a
+    /// size of zero.
+    pub fn inflate(self, dx: f64, dy: f64) -> Rectangle {
+        let w = (self.w + 2.0 * dx).max(0.0);
+        let h = (self.h + 2.0 * dy).max(0.0);
+
+        Rectangle {
+            x: self.x + (self.w - w) / 2.0,
+            y: self.y + (self.h - h) / 2.0,
+            w: w,
+            h: h,
+        }
+    }
+
+    /// Multiply the size of the rectangle by 'factor', keeping its center
+    /// in place.
+    pub fn scale_about_center(self, factor: f64) -> Rectangle {
+        assert!(factor >= 0.0);
+
+        Rectangle {
+            w: self.w * factor,
+            h: self.h * factor,
+            ..self
+        }.with_center(self.center())
+    }
+
+    /// Whether self touches the disc of the given center and radius.
+    pub fn overlaps_circle(&self, (cx, cy): (f64, f64), radius: f64) -> bool {
+        // Distance between the center and the closest point of self
+        let dx = cx - cx.max(self.x).min(self.x + self.w);
+        let dy = cy - cy.max(self.y).min(self.y + self.h);
+        dx * dx + dy * dy <= radius * radius
+    }
+
+    pub fn contains_point(&self, (x, y): (f64, f64)) -> bool {
+        x >= self.x && x <= (self.x + self.w) &&
+        y >= self.y && y <= (self.y + self.h)
+    }
+}
+
+// Move the span [start, start + len] inside [p_start, p_start + p_len],
+// or center it there if it is too long to fit.
+fn clamp_span(start: f64, len: f64, p_start: f64, p_len: f64) -> f64 {
+    if len > p_len {
+        p_start + (p_len - len) / 2.0
+    }
+    else if start < p_start {
+        p_start
+    }
+    else if start + len > p_start + p_len {
+        p_start + p_len - len
+    }
+    else {
+        start
+    }
+}
+
+/// A rectangle rotated by 'angle' radians (clockwise, as y points down)
+/// around its center; used for hitboxes which do not line up with the
+/// screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OrientedRect {
+    pub center: (f64, f64),
+    pub half_w: f64,
+    pub half_h: f64,
+    pub angle: f64,
+}
+
+impl OrientedRect {
+    /// Create an oriented rectangle covering 'rect' rotated around its
+    /// center.
+    pub fn from_rect(rect: Rectangle, angle: f64) -> OrientedRect {
+        OrientedRect {
+            center: rect.center(),
+            half_w: rect.w / 2.0,
+            half_h: rect.h / 2.0,
+            angle: angle,
+        }
+    }
+
+    /// Return the unit vectors along the width and height of self.
+    pub fn axes(&self) -> [(f64, f64); 2] {
+        let (sin, cos) = self.angle.sin_cos();
+        [(cos, sin), (-sin, cos)]
+    }
+
+    /// Return the corners of self, in clockwise order.
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        let axes = self.axes();
+        let (ux, uy) = axes[0];
+        let (vx, vy) = axes[1];
+        let (cx, cy) = self.center;
+        let (w, h) = (self.half_w, self.half_h);
+
+        [(cx - ux * w - vx * h, cy - uy * w - vy * h),
+         (cx + ux * w - vx * h, cy + uy * w - vy * h),
+         (cx + ux * w + vx * h, cy + uy * w + vy * h),
+         (cx - ux * w + vx * h, cy - uy * w + vy * h)]
+    }
+
+    /// Return the smallest screen-aligned rectangle containing self.
+    pub fn bounding_box(&self) -> Rectangle {
+        bounding_box(&self.corners())
+    }
+
+    pub fn overlaps(&self, other: &OrientedRect) -> bool {
+        let axes = self.axes();
+        let other_axes = other.axes();
+
+        separating_axis_overlap(&self.corners(), &other.corners(),
+            &[axes[0], axes[1], other_axes[0], other_axes[1]])
+    }
+
+    pub fn overlaps_rect(&self, rect: Rectangle) -> bool {
+        self.overlaps(&OrientedRect::from_rect(rect, 0.0))
+    }
+}
+
+/// Return the smallest screen-aligned rectangle containing every point.
+pub fn bounding_box(points: &[(f64, f64)]) -> Rectangle {
+    let mut min = (::std::f64::INFINITY, ::std::f64::INFINITY);
+    let mut max = (::std::f64::NEG_INFINITY, ::std::f64::NEG_INFINITY);
+
+    for &(x, y) in points {
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+
+    Rectangle {
+        x: min.0,
+        y: min.1,
+        w: max.0 - min.0,
+        h: max.1 - min.1,
+    }
+}
+
+/// Separating axis test: two convex shapes, given by their vertices, are
+/// disjoint if and only if their projections on one of the normals of
+/// their edges ('axes') do not overlap.
+pub fn separating_axis_overlap(a: &[(f64, f64)], b: &[(f64, f64)],
+                               axes: &[(f64, f64)]) -> bool {
+    axes.iter().all(|&axis| {
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        a_min < b_max && b_min < a_max
+    })
+}
+
+// Return the interval covered by the points once projected on 'axis'
+fn project(points: &[(f64, f64)], axis: (f64, f64)) -> (f64, f64) {
+    points.iter()
+        .map(|&(x, y)| x * axis.0 + y * axis.1)
+        .fold((::std::f64::INFINITY, ::std::f64::NEG_INFINITY),
+              |(min, max), p| (min.min(p), max.max(p)))
+}
+
+/// A convex polygon, whose vertices are given in clockwise or
+/// counter-clockwise order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub points: Vec<(f64, f64)>,
+}
+
+impl Polygon {
+    /// Parse a polygon written as space-separated `x,y` vertices.
+    pub fn parse(text: &str) -> Option<Polygon> {
+        let mut points = vec![];
+
+        for vertex in text.split_whitespace() {
+            match parse_point(vertex) {
+                Some(point) => points.push(point),
+                None => return None,
+            }
+        }
+
+        if points.len() < 3 {
+            None
+        }
+        else {
+            Some(Polygon { points: points })
+        }
+    }
+
+    /// Return the polygon moved by (dx, dy).
+    pub fn translate(&self, dx: f64, dy: f64) -> Polygon {
+        Polygon {
+            points: self.points.iter().map(|&(x, y)| (x + dx, y + dy)).collect(),
+        }
+    }
+
+    /// Return the normals of the edges of self.
+    pub fn axes(&self) -> Vec<(f64, f64)> {
+        let n = self.points.len();
+        (0..n).map(|i| {
+            let (x1, y1) = self.points[i];
+            let (x2, y2) = self.points[(i + 1) % n];
+            (y1 - y2, x2 - x1)
+        }).collect()
+    }
+
+    pub fn bounding_box(&self) -> Rectangle {
+        bounding_box(&self.points)
+    }
+
+    pub fn overlaps(&self, other: &Polygon) -> bool {
+        let mut axes = self.axes();
+        axes.extend(other.axes());
+        separating_axis_overlap(&self.points, &other.points, &axes)
+    }
+
+    pub fn overlaps_rect(&self, rect: Rectangle) -> bool {
+        self.overlaps_oriented(&OrientedRect::from_rect(rect, 0.0))
+    }
+
+    pub fn overlaps_oriented(&self, rect: &OrientedRect) -> bool {
+        let mut axes = self.axes();
+        axes.extend(rect.axes().iter().cloned());
+        separating_axis_overlap(&self.points, &rect.corners(), &axes)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::std::f64::consts::PI;
+
+    fn rect(x: f64, y: f64, w: f64, h: f64) -> Rectangle {
+        Rectangle { x: x, y: y, w: w, h: h }
+    }
+
+    #[test]
+    fn intersects_includes_edges() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert!(a.intersects(rect(5.0, 5.0, 10.0, 10.0)));
+        assert!(a.intersects(rect(10.0, 0.0, 5.0, 5.0)));
+        assert!(a.intersects(rect(10.0, 10.0, 5.0, 5.0)));
+        assert!(!a.intersects(rect(10.5, 0.0, 5.0, 5.0)));
+
+        // Sharing an edge is not overlapping
+        assert!(!a.overlaps(rect(10.0, 0.0, 5.0, 5.0)));
+    }
+
+    #[test]
+    fn intersection_is_the_shared_area() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(a.intersection(rect(5.0, -5.0, 10.0, 10.0)), Some(rect(5.0, 0.0, 5.0, 5.0)));
+        assert_eq!(a.intersection(rect(2.0, 2.0, 4.0, 4.0)), Some(rect(2.0, 2.0, 4.0, 4.0)));
+        assert_eq!(a.intersection(rect(10.0, 0.0, 5.0, 5.0)), None);
+        assert_eq!(a.intersection(rect(20.0, 20.0, 5.0, 5.0)), None);
+    }
+
+    #[test]
+    fn union_contains_both() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        let b = rect(20.0, -5.0, 5.0, 5.0);
+        let union = a.union(b);
+        assert_eq!(union, rect(0.0, -5.0, 25.0, 15.0));
+        assert!(union.contains(a) && union.contains(b));
+        assert_eq!(a.union(rect(2.0, 2.0, 1.0, 1.0)), a);
+    }
+
+    #[test]
+    fn center_and_scaling_keep_the_center() {
+        let a = rect(10.0, 20.0, 30.0, 40.0);
+        assert_eq!(a.center(), (25.0, 40.0));
+        assert_eq!(a.with_center((0.0, 0.0)), rect(-15.0, -20.0, 30.0, 40.0));
+        assert_eq!(a.scale_about_center(0.5), rect(17.5, 30.0, 15.0, 20.0));
+        assert_eq!(a.inflate(5.0, -5.0), rect(5.0, 25.0, 40.0, 30.0));
+
+        // Shrinking stops at an empty rectangle on the center
+        assert_eq!(a.inflate(-100.0, 0.0), rect(25.0, 20.0, 0.0, 40.0));
+    }
+
+    #[test]
+    fn clamp_inside_moves_into_the_parent() {
+        let parent = rect(0.0, 0.0, 100.0, 50.0);
+        assert_eq!(rect(-10.0, 45.0, 20.0, 10.0).clamp_inside(parent), rect(0.0, 40.0, 20.0, 10.0));
+        assert_eq!(rect(90.0, -5.0, 20.0, 10.0).clamp_inside(parent), rect(80.0, 0.0, 20.0, 10.0));
+        assert_eq!(rect(30.0, 20.0, 20.0, 10.0).clamp_inside(parent), rect(30.0, 20.0, 20.0, 10.0));
+    }
+
+    #[test]
+    fn clamp_inside_centers_what_does_not_fit() {
+        let parent = rect(0.0, 0.0, 100.0, 50.0);
+
+        // Too wide, so centered horizontally, but still moved vertically
+        let clamped = rect(30.0, 60.0, 120.0, 10.0).clamp_inside(parent);
+        assert_eq!(clamped, rect(-10.0, 40.0, 120.0, 10.0));
+        assert_eq!(rect(30.0, 60.0, 120.0, 10.0).move_inside(parent), None);
+    }
+
+    #[test]
+    fn contains_point_includes_edges() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        assert!(a.contains_point((5.0, 5.0)));
+        assert!(a.contains_point((0.0, 10.0)));
+        assert!(!a.contains_point((-0.1, 5.0)));
+        assert!(!a.contains_point((5.0, 10.1)));
+    }
+
+    #[test]
+    fn oriented_rect_without_angle_matches_the_rect() {
+        let oriented = OrientedRect::from_rect(rect(0.0, 0.0, 20.0, 10.0), 0.0);
+        assert_eq!(oriented.corners(), [(0.0, 0.0), (20.0, 0.0), (20.0, 10.0), (0.0, 10.0)]);
+        assert_eq!(oriented.bounding_box(), rect(0.0, 0.0, 20.0, 10.0));
+        assert!(oriented.overlaps_rect(rect(15.0, 5.0, 10.0, 10.0)));
+        assert!(!oriented.overlaps_rect(rect(25.0, 5.0, 10.0, 10.0)));
+    }
+
+    #[test]
+    fn oriented_rects_collide_by_their_shape() {
+        // A square turned into a diamond, whose corners lie 10√2 away from
+        // its center
+        let diamond = OrientedRect::from_rect(rect(-10.0, -10.0, 20.0, 20.0), PI / 4.0);
+        let bounds = diamond.bounding_box();
+        assert!((bounds.x + 10.0 * 2f64.sqrt()).abs() < 1e-9);
+        assert!((bounds.w - 20.0 * 2f64.sqrt()).abs() < 1e-9);
+
+        // Inside the bounding box of the diamond but outside of its edges
+        let corner = rect(9.0, 9.0, 4.0, 4.0);
+        assert!(bounds.overlaps(corner));
+        assert!(!diamond.overlaps_rect(corner));
+
+        assert!(diamond.overlaps_rect(rect(3.0, 3.0, 4.0, 4.0)));
+        assert!(diamond.overlaps(&OrientedRect::from_rect(rect(12.0, -1.0, 10.0, 2.0), 0.3)));
+        assert!(!diamond.overlaps(&OrientedRect::from_rect(rect(16.0, -1.0, 10.0, 2.0), 0.0)));
+    }
+
+    fn triangle() -> Polygon {
+        Polygon::parse("0,0 10,0 0,10").unwrap()
+    }
+
+    #[test]
+    fn polygon_parses_its_vertices() {
+        assert_eq!(triangle().points, vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]);
+        assert_eq!(Polygon::parse(" 1.5,2  3,4 \t5,-6 ").unwrap().points,
+                   vec![(1.5, 2.0), (3.0, 4.0), (5.0, -6.0)]);
+        assert_eq!(Polygon::parse("0,0 10,0"), None);
+        assert_eq!(Polygon::parse("0,0 10;0 0,10"), None);
+        assert_eq!(Polygon::parse(""), None);
+    }
+
+    #[test]
+    fn polygon_moves_with_its_bounding_box() {
+        let moved = triangle().translate(5.0, -2.0);
+        assert_eq!(moved.points, vec![(5.0, -2.0), (15.0, -2.0), (5.0, 8.0)]);
+        assert_eq!(moved.bounding_box(), rect(5.0, -2.0, 10.0, 10.0));
+    }
+
+    #[test]
+    fn polygons_collide_by_their_shape() {
+        // The hypotenuse of the triangle runs along x + y = 10
+        let triangle = triangle();
+        assert!(triangle.overlaps(&triangle.translate(4.0, 4.0)));
+        assert!(!triangle.overlaps(&triangle.translate(6.0, 6.0)));
+
+        assert!(triangle.overlaps_rect(rect(3.0, 3.0, 2.0, 2.0)));
+        assert!(!triangle.overlaps_rect(rect(6.0, 6.0, 4.0, 4.0)));
+
+        // Turned into a diamond, the same square no longer reaches the
+        // hypotenuse
+        let square = rect(4.5, 4.5, 4.0, 4.0);
+        assert!(triangle.overlaps_rect(square));
+        assert!(!triangle.overlaps_oriented(&OrientedRect::from_rect(square, PI / 4.0)));
+    }
 }
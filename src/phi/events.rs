@@ -1,5 +1,52 @@
 // phi/events.rs
 
+/// Auto-repeat behaviour of held keys, as seen through `events.now.repeat`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyRepeat {
+    /// Time a key must be held before it starts repeating, in seconds
+    pub delay: f64,
+
+    /// Number of repeats every second once the delay has elapsed
+    pub rate: f64,
+}
+
+impl KeyRepeat {
+    /// Number of presses (the initial one included) generated by a key
+    /// which has been held for 'held' seconds.
+    fn presses(&self, held: f64) -> u64 {
+        if held < self.delay {
+            1
+        }
+        else {
+            2 + ((held - self.delay) * self.rate) as u64
+        }
+    }
+
+    /// Whether holding a key from 'before' to 'after' seconds generates
+    /// at least one repeated press.
+    pub fn fires(&self, before: f64, after: f64) -> bool {
+        self.presses(after) > self.presses(before)
+    }
+}
+
+/// Convert a position in the window, as reported by mouse events, into
+/// pixels of the renderer. They differ on high-DPI displays, where the
+/// drawable area is larger than the window.
+pub fn window_to_pixels(renderer: &::sdl2::render::Renderer, x: i32, y: i32) -> (f64, f64) {
+    let (scale_x, scale_y) = match (renderer.window(), renderer.output_size()) {
+        (Some(window), Ok((out_w, out_h))) => {
+            let (win_w, win_h) = window.size();
+            if win_w == 0 || win_h == 0 {
+                (1.0, 1.0)
+            }
+            else {
+                (out_w as f64 / win_w as f64, out_h as f64 / win_h as f64)
+            }
+        },
+        _ => (1.0, 1.0),
+    };
+    (x as f64 * scale_x, y as f64 * scale_y)
+}
 
 macro_rules! struct_events {
 
@@ -12,28 +59,101 @@ macro_rules! struct_events {
 	) => {
 
 		use ::sdl2::EventPump;
+		use ::phi::controllers::{Controllers, PadInput};
+		use ::sdl2::keyboard::{Keycode, Mod};
+		use ::std::collections::HashSet;
+
+		// Update the held and just-changed state of a modifier from
+		// whether it is down according to the latest keyboard event.
+		fn update_modifier(held: &mut bool, now: &mut Option<bool>, down: bool) {
+			if down != *held {
+				*now = Some(down);
+			}
+			*held = down;
+		}
 
 		pub struct ImmediateEvents {
 
 			resize: Option<(u32, u32)>,
 
+			// Text typed during this frame while text input is active,
+			// already composed by SDL (shift, dead keys, IME, ...)
+			pub text: String,
+
+			// Text currently being composed by an input method, if any,
+			// with the cursor position and selection length inside it
+			pub text_editing: Option<(String, i32, i32)>,
+
+			// Modifiers, with the same meaning as keyboard events; either
+			// the left or the right key counts.
+			pub mod_shift: Option<bool>,
+			pub mod_ctrl: Option<bool>,
+			pub mod_alt: Option<bool>,
+
+			// The player whose controller was just plugged in or unplugged
+			pub controller_connected: Option<usize>,
+			pub controller_disconnected: Option<usize>,
+
+			// The controller buttons and triggers just pressed, along with
+			// the player holding the controller
+			pub pad_inputs: Vec<(usize, PadInput)>,
+
+			// The mouse buttons, with the same meaning as keyboard events
+			pub mouse_left: Option<bool>,
+			pub mouse_right: Option<bool>,
+
+			// Every key, tracked by the macro or not, which was just
+			// pressed or released; see Events::pressed and Events::released
+			keys_pressed: HashSet<Keycode>,
+			keys_released: HashSet<Keycode>,
+
 			// For every keyboard event, we have an Option<bool>
 			// Some(true)  => Was just pressed
 			// Some(false) => Was just released
 			// None        => Nothing happened _now_
 			$( pub $k_alias: Option<bool> , )*
-			$( pub $e_alias: bool ),*
+			$( pub $e_alias: bool , )*
+
+			// Presses of every key including auto-repeats, according to
+			// the current KeyRepeat setting of Events
+			pub repeat: RepeatedKeys,
+		}
+
+		pub struct RepeatedKeys {
+			// true => Was just pressed, or is held and just repeated
+			$( pub $k_alias: bool ),*
+		}
+
+		// For how long, in seconds, every key has been held
+		struct HeldTimes {
+			$( $k_alias: f64 ),*
 		}
 
 		impl ImmediateEvents {
 			pub fn new() -> ImmediateEvents {
 				ImmediateEvents {
 					resize:  None,
+					text: String::new(),
+					text_editing: None,
+					mod_shift: None,
+					mod_ctrl: None,
+					mod_alt: None,
+					controller_connected: None,
+					controller_disconnected: None,
+					pad_inputs: vec![],
+					mouse_left: None,
+					mouse_right: None,
+					keys_pressed: HashSet::new(),
+					keys_released: HashSet::new(),
 
 					// When reinitialized, nothing has yet happened,
 					// so all are set to none.
 					$( $k_alias: None , )*
-					$( $e_alias: false ),*
+					$( $e_alias: false , )*
+
+					repeat: RepeatedKeys {
+						$( $k_alias: false ),*
+					},
 				}
 			}
 		}
@@ -41,6 +161,39 @@ macro_rules! struct_events {
 		pub struct Events {
 			pump: EventPump,
 			pub now: ImmediateEvents,
+			pub controllers: Controllers,
+
+			// Whether a Shift, Ctrl or Alt key is currently held
+			pub mod_shift: bool,
+			pub mod_ctrl: bool,
+			pub mod_alt: bool,
+
+			// None => keys never repeat
+			key_repeat: Option<KeyRepeat>,
+			held_times: HeldTimes,
+
+			// Every key currently held, tracked by the macro or not
+			keys_down: HashSet<Keycode>,
+
+			// The triggers currently pulled past the threshold, by player
+			triggers_held: Vec<(usize, ::sdl2::controller::Axis)>,
+
+			// The position of the mouse cursor in the window, in pixels of
+			// the renderer, which is where the menus and the HUD are laid
+			// out; see mouse_world for the point of the scene under it
+			pub mouse: (f64, f64),
+
+			// How far the camera of the view displaces the scene, so that
+			// the mouse may be placed in it
+			world_offset: (f64, f64),
+
+			// Whether the mouse buttons are held
+			pub mouse_left: bool,
+			pub mouse_right: bool,
+
+			// What the player used last, the keyboard and mouse or a
+			// controller, which prompts show the keys or the buttons of
+			pub device: ::phi::prompts::Device,
 
 			// true  => pressed
 			// false => not pressed
@@ -49,20 +202,116 @@ macro_rules! struct_events {
 
 		impl<'p> Events {
 
-			pub fn new(pump: EventPump) -> Events {
+			pub fn new(pump: EventPump, controllers: Controllers) -> Events {
 				Events {
 					pump: pump,
 					now: ImmediateEvents::new(),
+					controllers: controllers,
+					mod_shift: false,
+					mod_ctrl: false,
+					mod_alt: false,
+
+					key_repeat: None,
+					held_times: HeldTimes {
+						$( $k_alias: 0.0 ),*
+					},
+
+					keys_down: HashSet::new(),
+					triggers_held: vec![],
+
+					mouse: (0.0, 0.0),
+					world_offset: (0.0, 0.0),
+					mouse_left: false,
+					mouse_right: false,
+					device: ::phi::prompts::Device::Keyboard,
 
 					// By default, initialize every key with _not pressed_
 					$( $k_alias: false ), *
 				}
 			}
 
-			pub fn pump(&mut self, renderer: &mut ::sdl2::render::Renderer) {
+			fn update_modifiers(&mut self, keymod: Mod) {
+				use ::sdl2::keyboard::{LSHIFTMOD, RSHIFTMOD, LCTRLMOD, RCTRLMOD,
+				                       LALTMOD, RALTMOD};
+
+				update_modifier(&mut self.mod_shift, &mut self.now.mod_shift,
+				                keymod.intersects(LSHIFTMOD | RSHIFTMOD));
+				update_modifier(&mut self.mod_ctrl, &mut self.now.mod_ctrl,
+				                keymod.intersects(LCTRLMOD | RCTRLMOD));
+				update_modifier(&mut self.mod_alt, &mut self.now.mod_alt,
+				                keymod.intersects(LALTMOD | RALTMOD));
+			}
+
+			/// Set how held keys repeat in `now.repeat`, or disable
+			/// repetition with None.
+			pub fn set_key_repeat(&mut self, key_repeat: Option<KeyRepeat>) {
+				self.key_repeat = key_repeat;
+			}
+
+			pub fn key_repeat(&self) -> Option<KeyRepeat> {
+				self.key_repeat
+			}
+
+			/// Whether 'key' is currently held.
+			pub fn is_down(&self, key: Keycode) -> bool {
+				self.keys_down.contains(&key)
+			}
+
+			/// Whether 'key' was pressed during this frame. Holding a key
+			/// does not generate multiple presses.
+			pub fn pressed(&self, key: Keycode) -> bool {
+				self.now.keys_pressed.contains(&key)
+			}
+
+			/// Whether 'key' was released during this frame.
+			pub fn released(&self, key: Keycode) -> bool {
+				self.now.keys_released.contains(&key)
+			}
+
+			/// Whether any key, controller input or mouse button was
+			/// pressed during this frame.
+			pub fn any_pressed(&self) -> bool {
+				!self.now.keys_pressed.is_empty() || !self.now.pad_inputs.is_empty()
+					|| self.now.mouse_left == Some(true) || self.now.mouse_right == Some(true)
+			}
+
+			/// Tell how far the camera displaced the scene last drawn, so
+			/// that `mouse_world` matches what is on screen. Views without
+			/// a camera leave it to (0, 0), which it returns to whenever
+			/// the view changes.
+			pub fn set_world_offset(&mut self, offset: (f64, f64)) {
+				self.world_offset = offset;
+			}
+
+			/// The point of the scene under the mouse cursor: the position
+			/// of the cursor with the displacement of the camera undone
+			pub fn mouse_world(&self) -> (f64, f64) {
+				(self.mouse.0 - self.world_offset.0, self.mouse.1 - self.world_offset.1)
+			}
+
+			/// Every key tracked by the macro, by the name of its field,
+			/// with whether it is held and what happened to it during this
+			/// frame, in the order the keys are declared.
+			pub fn tracked_keys(&self) -> Vec<(&'static str, bool, Option<bool>)> {
+				vec![ $( (stringify!($k_alias), self.$k_alias, self.now.$k_alias) ),* ]
+			}
+
+			/// Whether 'input' was pressed during this frame on the
+			/// controller of 'player'.
+			pub fn pad_pressed(&self, player: usize, input: PadInput) -> bool {
+				self.now.pad_inputs.contains(&(player, input))
+			}
+
+			/// Poll the pending events, 'elapsed' seconds after the
+			/// previous call.
+			pub fn pump(&mut self, renderer: &mut ::sdl2::render::Renderer, elapsed: f64) {
 				self.now = ImmediateEvents::new();
 
-				for event in self.pump.poll_iter() {
+				// Collect the events first, so that handling them may borrow
+				// the whole of self
+				let events: Vec<_> = self.pump.poll_iter().collect();
+
+				for event in events {
 
 					use ::sdl2::event::Event::*;
 					use ::sdl2::keyboard::Keycode::*;
@@ -74,40 +323,141 @@ macro_rules! struct_events {
 									Some(renderer.output_size().unwrap());
 						},
 
-						KeyDown { keycode, .. } => match keycode {
-
-							// $(...),* containing $k_sdl and $k_alias means:
-							// "for every ($k_alias : $k_sdl) pair,
-							// check whether the keycode is Some($k_ndl).
-							// If it is, then set the $k_alias field to true."
-							$(
-								Some($k_sdl) => {
-									// Prevent multiple presses when keeping
-									// a key down.  Was previously now pressed?
-									if !self.$k_alias {
-										// Key pressed
-										self.now.$k_alias = Some(true);
-									}
+						// SDL also sends this for the controllers already
+						// plugged in when the game starts
+						ControllerDeviceAdded { which, .. } => {
+							if let Some(player) = self.controllers.connect(which as u32) {
+								self.now.controller_connected = Some(player);
+							}
+						},
+
+						ControllerDeviceRemoved { which, .. } => {
+							if let Some(player) = self.controllers.disconnect(which) {
+								self.now.controller_disconnected = Some(player);
+							}
+						},
 
-									self.$k_alias = true;
+						ControllerButtonDown { which, button, .. } => {
+							if let Some(player) = self.controllers.player_of(which) {
+								self.now.pad_inputs.push((player, PadInput::Button(button)));
+							}
+						},
+
+						// Triggers are pressed when pulled past the threshold,
+						// and released when let go below it
+						ControllerAxisMotion { which, axis, value, .. } => {
+							if let Some(player) = self.controllers.player_of(which) {
+								if ::phi::controllers::is_trigger(axis) {
+									let pulled = value as f64 / 32_767.0 >= ::phi::controllers::TRIGGER_THRESHOLD;
+									let held = self.triggers_held.contains(&(player, axis));
+									if pulled && !held {
+										self.triggers_held.push((player, axis));
+										self.now.pad_inputs.push((player, PadInput::Trigger(axis)));
+									}
+									else if !pulled && held {
+										self.triggers_held.retain(|&held| held != (player, axis));
+									}
 								}
+							}
+						},
+
+						MouseMotion { x, y, .. } => {
+							self.mouse = ::phi::events::window_to_pixels(renderer, x, y);
+						},
+
+						MouseButtonDown { mouse_btn, x, y, .. } => {
+							self.mouse = ::phi::events::window_to_pixels(renderer, x, y);
+
+							match mouse_btn {
+								::sdl2::mouse::Mouse::Left => {
+									self.now.mouse_left = Some(true);
+									self.mouse_left = true;
+								},
+								::sdl2::mouse::Mouse::Right => {
+									self.now.mouse_right = Some(true);
+									self.mouse_right = true;
+								},
+								_ => {}
+							}
+						},
+
+						MouseButtonUp { mouse_btn, x, y, .. } => {
+							self.mouse = ::phi::events::window_to_pixels(renderer, x, y);
+
+							match mouse_btn {
+								::sdl2::mouse::Mouse::Left => {
+									self.now.mouse_left = Some(false);
+									self.mouse_left = false;
+								},
+								::sdl2::mouse::Mouse::Right => {
+									self.now.mouse_right = Some(false);
+									self.mouse_right = false;
+								},
+								_ => {}
+							}
+						},
 
-							), *  // and add a comma after every option
+						TextInput { text, .. } => {
+							self.now.text.push_str(&text);
+						},
 
-							_ => {}
+						TextEditing { text, start, length, .. } => {
+							self.now.text_editing = Some((text, start, length));
 						},
 
-						KeyUp { keycode, .. } => match keycode {
+						KeyDown { keycode, keymod, .. } => {
+							self.update_modifiers(keymod);
 
-							$(
-								Some($k_sdl) => {
-									// Key released
-									self.now.$k_alias = Some(false);
-									self.$k_alias = false;
+							if let Some(key) = keycode {
+								if self.keys_down.insert(key) {
+									self.now.keys_pressed.insert(key);
 								}
-							), *
+							}
+
+							match keycode {
+
+								// $(...),* containing $k_sdl and $k_alias means:
+								// "for every ($k_alias : $k_sdl) pair,
+								// check whether the keycode is Some($k_ndl).
+								// If it is, then set the $k_alias field to true."
+								$(
+									Some($k_sdl) => {
+										// Prevent multiple presses when keeping
+										// a key down.  Was previously now pressed?
+										if !self.$k_alias {
+											// Key pressed
+											self.now.$k_alias = Some(true);
+										}
+
+										self.$k_alias = true;
+									}
 
-							_ => {}
+								), *  // and add a comma after every option
+
+								_ => {}
+							}
+						},
+
+						KeyUp { keycode, keymod, .. } => {
+							self.update_modifiers(keymod);
+
+							if let Some(key) = keycode {
+								self.keys_down.remove(&key);
+								self.now.keys_released.insert(key);
+							}
+
+							match keycode {
+
+								$(
+									Some($k_sdl) => {
+										// Key released
+										self.now.$k_alias = Some(false);
+										self.$k_alias = false;
+									}
+								), *
+
+								_ => {}
+							}
 						},
 
 						$(
@@ -121,6 +471,31 @@ macro_rules! struct_events {
 						_ => {}
 					}
 				}
+
+				if !self.now.pad_inputs.is_empty() {
+					self.device = ::phi::prompts::Device::Pad;
+				}
+				else if !self.now.keys_pressed.is_empty() || self.now.mouse_left == Some(true)
+						|| self.now.mouse_right == Some(true) {
+					self.device = ::phi::prompts::Device::Keyboard;
+				}
+
+				// Generate the repeated presses of the keys being held
+				$(
+					if self.now.$k_alias == Some(true) {
+						self.held_times.$k_alias = 0.0;
+						self.now.repeat.$k_alias = true;
+					}
+					else if self.$k_alias {
+						let before = self.held_times.$k_alias;
+						let after = before + elapsed;
+						self.held_times.$k_alias = after;
+
+						if let Some(repeat) = self.key_repeat {
+							self.now.repeat.$k_alias = repeat.fires(before, after);
+						}
+					}
+				)*
 			}
 		}
 
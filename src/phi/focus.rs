@@ -0,0 +1,202 @@
+// phi/focus.rs
+
+use ::sdl2::controller::Button;
+
+use ::phi::Phi;
+use ::phi::controllers::PadInput;
+use ::phi::data::Rectangle;
+
+/// Return the widget under 'point', on screen, if any. 'bounds' are where
+/// the widgets are laid out before their container is scrolled by 'scroll';
+/// only the part of the container within 'clip', on screen, may be hit.
+/// Where widgets overlap, the last one drawn is picked.
+pub fn hit_test(bounds: &[Rectangle], scroll: (f64, f64), clip: Option<Rectangle>,
+                point: (f64, f64)) -> Option<usize> {
+    if clip.map_or(false, |clip| !clip.contains_point(point)) {
+        return None;
+    }
+    let point = (point.0 + scroll.0, point.1 + scroll.1);
+    bounds.iter().rposition(|rect| rect.contains_point(point))
+}
+
+/// Whether any player pressed 'button' during this frame
+fn pad_pressed(phi: &Phi, button: Button) -> bool {
+    phi.events.now.pad_inputs.iter().any(|&(_, input)| input == PadInput::Button(button))
+}
+
+/// Which of the widgets of a menu has the focus. Up and down, on the
+/// keyboard or the directional pad of any controller, move it to the
+/// previous and the next widget, wrapping around; the mouse moves it to
+/// the widget under the cursor, as laid out during the last frame. Disabled
+/// widgets never get the focus, and choosing one is refused.
+pub struct Focus {
+    index: usize,
+    count: usize,
+    enabled: Vec<bool>,
+
+    // Where the widgets were drawn during the last frame
+    bounds: Vec<Rectangle>,
+    scroll: (f64, f64),
+    clip: Option<Rectangle>,
+
+    // Where the cursor was, so that the focus only follows it while it
+    // moves, and the keyboard may take it away from a still cursor
+    last_mouse: (f64, f64),
+}
+
+impl Focus {
+    /// Create the focus of 'count' widgets, on the first one
+    pub fn new(count: usize) -> Focus {
+        Focus {
+            index: 0,
+            count: count,
+            enabled: vec![true; count],
+            bounds: vec![],
+            scroll: (0.0, 0.0),
+            clip: None,
+            last_mouse: (-1.0, -1.0),
+        }
+    }
+
+    /// The widget which has the focus
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Give the focus to the widget 'index', or the last one if there are
+    /// not that many
+    pub fn focus(&mut self, index: usize) {
+        self.index = index.min(self.count.saturating_sub(1));
+    }
+
+    /// Change the number of widgets, keeping the focus on the same one if
+    /// it remains; new widgets are enabled
+    pub fn set_count(&mut self, count: usize) {
+        self.count = count;
+        self.enabled.resize(count, true);
+        let index = self.index;
+        self.focus(index);
+    }
+
+    pub fn is_enabled(&self, index: usize) -> bool {
+        self.enabled.get(index).cloned().unwrap_or(false)
+    }
+
+    /// Enable or disable the widget 'index'. If it had the focus, the focus
+    /// moves on to the next widget enabled.
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) {
+        if index < self.count {
+            self.enabled[index] = enabled;
+        }
+        if !self.is_enabled(self.index) {
+            self.step(1);
+        }
+    }
+
+    /// Move the focus by 'step' widgets, wrapping around, and on until an
+    /// enabled one. It stays in place if every widget is disabled.
+    fn step(&mut self, step: isize) {
+        let count = self.count as isize;
+        let mut index = self.index as isize;
+        for _ in 0..count {
+            index = ((index + step) % count + count) % count;
+            if self.enabled[index as usize] {
+                self.index = index as usize;
+                return;
+            }
+        }
+    }
+
+    /// Tell where the widgets were drawn, for the mouse to pick them
+    pub fn set_layout(&mut self, bounds: Vec<Rectangle>, scroll: (f64, f64), clip: Option<Rectangle>) {
+        self.bounds = bounds;
+        self.scroll = scroll;
+        self.clip = clip;
+    }
+
+    /// The widget under the mouse cursor, if any
+    pub fn hovered(&self, phi: &Phi) -> Option<usize> {
+        hit_test(&self.bounds, self.scroll, self.clip, phi.events.mouse)
+            .and_then(|index| if index < self.count { Some(index) } else { None })
+    }
+
+    /// Move the focus as the player asks during this frame, and return
+    /// whether it moved
+    pub fn navigate(&mut self, phi: &Phi) -> bool {
+        if self.count == 0 {
+            return false;
+        }
+        let before = self.index;
+
+        if phi.events.now.repeat.key_up || pad_pressed(phi, Button::DPadUp) {
+            self.step(-1);
+        }
+        if phi.events.now.repeat.key_down || pad_pressed(phi, Button::DPadDown) {
+            self.step(1);
+        }
+
+        let mouse = phi.events.mouse;
+        if mouse != self.last_mouse || phi.events.now.mouse_left == Some(true) {
+            self.last_mouse = mouse;
+            match self.hovered(phi) {
+                Some(index) if self.is_enabled(index) => self.index = index,
+                _ => {},
+            }
+        }
+
+        self.index != before
+    }
+
+    /// How the player asks to change the value of the widget which has the
+    /// focus during this frame: -1 with Left, on the keyboard or the
+    /// directional pad of any controller, 1 with Right, otherwise 0
+    pub fn adjustment(&self, phi: &Phi) -> i32 {
+        if !self.is_enabled(self.index) {
+            return 0;
+        }
+        let left = phi.events.now.repeat.key_left || pad_pressed(phi, Button::DPadLeft);
+        let right = phi.events.now.repeat.key_right || pad_pressed(phi, Button::DPadRight);
+        match (left, right) {
+            (true, false) => -1,
+            (false, true) => 1,
+            _ => 0,
+        }
+    }
+
+    /// The widget the player chose during this frame, if any: the one with
+    /// the focus with Return or Space, or the A button of any controller,
+    /// or the one clicked
+    fn chosen(&self, phi: &Phi) -> Option<usize> {
+        if self.count == 0 {
+            None
+        }
+        else if phi.events.now.key_return == Some(true) || phi.events.now.key_space == Some(true)
+                || pad_pressed(phi, Button::A) {
+            Some(self.index)
+        }
+        else if phi.events.now.mouse_left == Some(true) {
+            self.hovered(phi)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Whether the player chose the widget which has the focus during this
+    /// frame, and it is enabled
+    pub fn activated(&self, phi: &Phi) -> bool {
+        self.chosen(phi).map_or(false, |index| index == self.index && self.is_enabled(index))
+    }
+
+    /// Whether the player chose a disabled widget during this frame, which
+    /// menus answer with an error sound
+    pub fn refused(&self, phi: &Phi) -> bool {
+        self.chosen(phi).map_or(false, |index| !self.is_enabled(index))
+    }
+
+    /// Whether the player asked to leave the menu during this frame: with
+    /// Escape, or the B button of any controller
+    pub fn cancelled(&self, phi: &Phi) -> bool {
+        phi.events.now.key_escape == Some(true) || pad_pressed(phi, Button::B)
+    }
+}
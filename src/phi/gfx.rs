@@ -1,10 +1,39 @@
-use ::phi::data::Rectangle;
+use ::phi::animation::AnimationMode;
+use ::phi::data::{Polygon, Rectangle};
 use ::std::cell::RefCell;
+use ::std::fs::File;
+use ::std::io::{BufRead, BufReader, Read};
 use ::std::path::Path;
 use ::std::rc::Rc;
-use ::sdl2::render::{Renderer, Texture};
+use ::sdl2::pixels::{Color, PixelFormatEnum};
+use ::sdl2::rect::Rect as SdlRect;
+use ::sdl2::render::{BlendMode, Renderer, Texture};
+use ::sdl2::surface::Surface;
 use ::sdl2_image::LoadTexture;
 
+/// How what is drawn combines with what was drawn before it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Blending {
+    /// Drawn over it, as see-through as its alpha
+    Normal,
+    /// Added to it, so that it only ever gets lighter, as with light;
+    /// suits glows, fire and sparks
+    Additive,
+    /// Multiplied with it, so that it only ever gets darker, as through
+    /// tinted glass
+    Multiply,
+}
+
+impl Blending {
+    pub fn to_sdl(self) -> BlendMode {
+        match self {
+            Blending::Normal => BlendMode::Blend,
+            Blending::Additive => BlendMode::Add,
+            Blending::Multiply => BlendMode::Mod,
+        }
+    }
+}
+
 // Common interface for rendering a graphical component
 // to an area of the current window
 pub trait Renderable {
@@ -44,7 +73,11 @@ impl Sprite {
     }
 
     pub fn load(renderer: &Renderer, path: &str) -> Option<Sprite> {
-        renderer.load_texture(Path::new(path)).ok().map(Sprite::new)
+        debug!("Loading the texture {}", path);
+        renderer.load_texture(Path::new(path))
+            .map_err(|err| warn!("Could not load the texture {}: {}", path, err))
+            .ok()
+            .map(Sprite::new)
     }
 
     pub fn region(&self, rect: Rectangle) -> Option<Sprite> {
@@ -69,6 +102,118 @@ impl Sprite {
     pub fn size(&self) -> (f64, f64) {
         (self.src.w, self.src.h)
     }
+
+    /// The approximate video memory taken by the whole texture this sprite
+    /// shows a region of, in bytes, assuming 4 bytes per pixel
+    pub fn texture_memory(&self) -> usize {
+        let query = self.tex.borrow().query();
+        query.width as usize * query.height as usize * 4
+    }
+
+    /// Multiply the colors of the texture this sprite shows a region of,
+    /// and thus of every sprite sharing it, by 'color'
+    pub fn set_color_mod(&self, color: Color) {
+        let (r, g, b) = color.rgb();
+        self.tex.borrow_mut().set_color_mod(r, g, b);
+    }
+
+    /// Make the texture this sprite shows a region of, and thus every
+    /// sprite sharing it, 'alpha' opaque
+    pub fn set_alpha_mod(&self, alpha: u8) {
+        self.tex.borrow_mut().set_alpha_mod(alpha);
+    }
+
+    /// Whether other sprites show the same texture
+    pub fn is_shared(&self) -> bool {
+        Rc::strong_count(&self.tex) > 1
+    }
+
+    /// Whether 'other' shows a region of the same texture
+    pub fn shows_same_texture(&self, other: &Sprite) -> bool {
+        &*self.tex as *const RefCell<Texture> == &*other.tex as *const RefCell<Texture>
+    }
+
+    // Render the sprite mirrored horizontally and/or vertically
+    pub fn render_flipped(&self, renderer: &mut Renderer, dest: Rectangle,
+                          flip_horizontal: bool, flip_vertical: bool) {
+        renderer.copy_ex(&mut self.tex.borrow_mut(), self.src.to_sdl(), dest.to_sdl(),
+                         0.0, None, flip_horizontal, flip_vertical);
+    }
+
+    // Render the sprite combined with what is under it by 'blending'
+    pub fn render_blended(&self, renderer: &mut Renderer, dest: Rectangle, blending: Blending) {
+        let mut tex = self.tex.borrow_mut();
+        tex.set_blend_mode(blending.to_sdl());
+        renderer.copy(&mut tex, self.src.to_sdl(), dest.to_sdl());
+        tex.set_blend_mode(BlendMode::Blend);
+    }
+
+    // Render the sprite washed out toward white, as when flashing after a
+    // hit. Color modulation can only darken a texture, so the sprite is
+    // added on top of itself instead.
+    pub fn render_flash(&self, renderer: &mut Renderer, dest: Rectangle) {
+        self.render(renderer, dest);
+        self.render_blended(renderer, dest, Blending::Additive);
+        self.render_blended(renderer, dest, Blending::Additive);
+    }
+}
+
+/// Load the hitboxes of the 'frame_count' frames of the spritesheet at
+/// 'sprite_path' from the sidecar file with the same name and the
+/// `.hitbox` extension. Return None if there is no such file.
+///
+/// Every line of that file reads `N: x,y x,y x,y ...`, which attaches a
+/// convex polygon to frame N, or `*: ...` to attach it to every frame.
+/// Coordinates are relative to the top-left corner of the frame. Empty
+/// lines and lines starting with `#` are ignored.
+pub fn load_hitboxes(sprite_path: &str, frame_count: usize) -> Option<Vec<Vec<Polygon>>> {
+    let path = Path::new(sprite_path).with_extension("hitbox");
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+
+    let mut frames = vec![vec![]; frame_count];
+
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return None,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, ':');
+        let (frame, points) = match (parts.next(), parts.next()) {
+            (Some(frame), Some(points)) => (frame.trim(), points),
+            _ => {
+                warn!("Ignoring malformed hitbox in {:?}: {}", path, line);
+                continue;
+            }
+        };
+
+        let polygon = match Polygon::parse(points) {
+            Some(polygon) => polygon,
+            None => {
+                warn!("Ignoring malformed hitbox in {:?}: {}", path, line);
+                continue;
+            }
+        };
+
+        if frame == "*" {
+            for hitboxes in &mut frames {
+                hitboxes.push(polygon.clone());
+            }
+        }
+        else if let Some(hitboxes) = frame.parse::<usize>().ok()
+                .and_then(|frame| frames.get_mut(frame)) {
+            hitboxes.push(polygon);
+        }
+    }
+
+    Some(frames)
 }
 
 impl Renderable for Sprite {
@@ -79,6 +224,85 @@ impl Renderable for Sprite {
     }
 }
 
+/// A sprite drawn in place of the system mouse cursor
+#[derive(Clone)]
+pub struct Cursor {
+    pub sprite: Sprite,
+
+    // The point of the sprite, relative to its top-left corner,
+    // which sits exactly under the mouse
+    pub hotspot: (f64, f64),
+}
+
+impl Cursor {
+    /// Create a cross 'side' pixels wide in 'color', whose hotspot is its
+    /// center, as a crosshair
+    pub fn crosshair(renderer: &Renderer, side: u32, color: Color) -> Option<Cursor> {
+        let middle = side as i32 / 2 - 1;
+        Cursor::from_rects(renderer, (side, side), &[
+            (0, middle, side, 2, color),
+            (middle, 0, 2, side, color),
+        ], (side as f64 / 2.0, side as f64 / 2.0))
+    }
+
+    /// Create an arrow 'height' pixels high, white with a black outline,
+    /// pointing at its hotspot in its top-left corner
+    pub fn arrow(renderer: &Renderer, height: u32) -> Option<Cursor> {
+        let width = height * 2 / 3 + 1;
+        let mut rects = vec![];
+
+        // Every row is a line of black as wide as the arrow there, with
+        // its inside in white, and the last row is the black bottom edge
+        for row in 0..height {
+            let row_w = row * 2 / 3 + 1;
+            rects.push((0, row as i32, row_w, 1, Color::RGB(0, 0, 0)));
+            if row > 0 && row + 1 < height && row_w > 2 {
+                rects.push((1, row as i32, row_w - 2, 1, Color::RGB(255, 255, 255)));
+            }
+        }
+
+        Cursor::from_rects(renderer, (width, height), &rects, (0.0, 0.0))
+    }
+
+    // Create a cursor of 'size' pixels out of rectangles of color, given
+    // as (x, y, w, h, color) and filled in order over a transparent image
+    fn from_rects(renderer: &Renderer, size: (u32, u32), rects: &[(i32, i32, u32, u32, Color)],
+                  hotspot: (f64, f64)) -> Option<Cursor> {
+        let mut image = match Surface::new(size.0, size.1, PixelFormatEnum::ARGB8888) {
+            Ok(image) => image,
+            Err(_) => return None,
+        };
+        for &(x, y, w, h, color) in rects {
+            let rect = SdlRect::new(x, y, w, h).unwrap();
+            if image.fill_rect(rect, color).is_err() {
+                return None;
+            }
+        }
+
+        let mut texture = match renderer.create_texture_from_surface(&image) {
+            Ok(texture) => texture,
+            Err(_) => return None,
+        };
+        texture.set_blend_mode(BlendMode::Blend);
+
+        Some(Cursor {
+            sprite: Sprite::new(texture),
+            hotspot: hotspot,
+        })
+    }
+
+    // Render the cursor so that its hotspot lies at 'pos'
+    pub fn render(&self, renderer: &mut Renderer, pos: (f64, f64)) {
+        let (w, h) = self.sprite.size();
+        self.sprite.render(renderer, Rectangle {
+            x: pos.0 - self.hotspot.0,
+            y: pos.1 - self.hotspot.1,
+            w: w,
+            h: h,
+        });
+    }
+}
+
 #[derive(Clone)]
 pub struct AnimatedSprite {
     // The fraemes that will be rendered, in order
@@ -90,6 +314,12 @@ pub struct AnimatedSprite {
     // The total time that the sprite has been alive, in seconds,
     // from which the current frae is derived
     current_time: f64,
+
+    // What happens once the last frame was shown
+    mode: AnimationMode,
+
+    // The collision rectangle of every frame, relative to the frame
+    hitboxes: Option<Rc<Vec<Rectangle>>>,
 }
 
 impl AnimatedSprite {
@@ -99,15 +329,29 @@ impl AnimatedSprite {
             sprites: Rc::new(sprites),
             frame_delay: frame_delay,
             current_time: 0.0,
+            mode: AnimationMode::Loop,
+            hitboxes: None,
         }
     }
 
     // Create a new animated sprite that goes to a next frame 'fps' times every second
     pub fn new_with_fps(sprites: Vec<Sprite>, fps: f64) -> AnimatedSprite {
+        AnimatedSprite::from_shared(Rc::new(sprites), fps)
+    }
+
+    // Create a new animated sprite going through frames shared with other
+    // sprites, 'fps' times every second
+    pub fn from_shared(sprites: Rc<Vec<Sprite>>, fps: f64) -> AnimatedSprite {
         if fps == 0.0 {
             panic!("FPS of 0.0 is invalid.");
         }
-        AnimatedSprite::new(sprites, 1.0 / fps)
+        AnimatedSprite {
+            sprites: sprites,
+            frame_delay: 1.0 / fps,
+            current_time: 0.0,
+            mode: AnimationMode::Loop,
+            hitboxes: None,
+        }
     }
 
     // Return the number of frames in this animation
@@ -115,6 +359,47 @@ impl AnimatedSprite {
         self.sprites.len()
     }
 
+    // Return the index of the frame to render at the current time
+    pub fn current_frame(&self) -> usize {
+        self.mode.frame((self.current_time / self.frame_delay) as usize, self.frame_count())
+    }
+
+    // Set what happens once the last frame was shown; animations loop by
+    // default
+    pub fn set_mode(&mut self, mode: AnimationMode) {
+        self.mode = mode;
+    }
+
+    // Return whether an animation played once reached its last frame.
+    // Other animations never finish.
+    pub fn is_finished(&self) -> bool {
+        self.mode == AnimationMode::Once
+            && (self.current_time / self.frame_delay) as usize >= self.frame_count() - 1
+    }
+
+    // Return the number of frames the animation goes through every second
+    pub fn fps(&self) -> f64 {
+        1.0 / self.frame_delay
+    }
+
+    // Give every frame its own collision rectangle, relative to the frame.
+    // Panics unless there is exactly one rectangle per frame.
+    pub fn set_hitboxes(&mut self, hitboxes: Vec<Rectangle>) {
+        assert_eq!(hitboxes.len(), self.frame_count());
+        self.hitboxes = Some(Rc::new(hitboxes));
+    }
+
+    // Return the collision rectangle of the current frame, relative to
+    // the frame, if the frames have some
+    pub fn current_hitbox(&self) -> Option<Rectangle> {
+        self.hitboxes.as_ref().map(|hitboxes| hitboxes[self.current_frame()])
+    }
+
+    // Render the current frame washed out toward white
+    pub fn render_flash(&self, renderer: &mut Renderer, dest: Rectangle) {
+        self.sprites[self.current_frame()].render_flash(renderer, dest);
+    }
+
     // Set the time it takes to get from one frame to the next, in seconds.
     // If the value is negative, we 'rewind' the animation
     pub fn set_frame_delay(&mut self, frame_delay: f64) {
@@ -147,10 +432,374 @@ impl Renderable for AnimatedSprite {
 
     // Render the current frame of the sprite
     fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
-        let current_frame =
-            (self.current_time / self.frame_delay) as usize % self.frame_count();
-
-        let sprite = &self.sprites[current_frame];
+        let sprite = &self.sprites[self.current_frame()];
         sprite.render(renderer, dest);
     }
 }
+
+/// Collects sprite copies for a frame and submits them grouped by texture,
+/// so that every texture is borrowed (and bound by the renderer) only once
+/// instead of once per copy.
+///
+/// Copies sharing a texture are drawn in the order they were pushed, but no
+/// ordering is guaranteed between different textures: only batch sprites
+/// which belong to the same layer.
+pub struct SpriteBatch {
+    copies: Vec<(Rc<RefCell<Texture>>, Rectangle, Rectangle)>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> SpriteBatch {
+        SpriteBatch {
+            copies: vec![],
+        }
+    }
+
+    // Create an empty batch able to hold 'capacity' copies before reallocating
+    pub fn with_capacity(capacity: usize) -> SpriteBatch {
+        SpriteBatch {
+            copies: Vec::with_capacity(capacity),
+        }
+    }
+
+    // Queue the sprite to be drawn at 'dest' on the next flush
+    pub fn push(&mut self, sprite: &Sprite, dest: Rectangle) {
+        self.copies.push((sprite.tex.clone(), sprite.src, dest));
+    }
+
+    // Return the number of copies waiting to be flushed
+    pub fn len(&self) -> usize {
+        self.copies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.copies.is_empty()
+    }
+
+    // Render every queued copy, one texture at a time, and empty the batch
+    pub fn flush(&mut self, renderer: &mut Renderer) {
+        // The sort is stable, so copies of a same texture keep their order
+        self.copies.sort_by_key(|copy| texture_key(&copy.0));
+
+        let mut start = 0;
+        while start < self.copies.len() {
+            let key = texture_key(&self.copies[start].0);
+            let tex_rc = self.copies[start].0.clone();
+            let mut tex = tex_rc.borrow_mut();
+
+            let mut end = start;
+            while end < self.copies.len() && texture_key(&self.copies[end].0) == key {
+                let src = self.copies[end].1;
+                let dest = self.copies[end].2;
+                renderer.copy(&mut tex, src.to_sdl(), dest.to_sdl());
+                end += 1;
+            }
+
+            start = end;
+        }
+
+        self.copies.clear();
+    }
+}
+
+// Identify a shared texture by the address of its cell
+fn texture_key(tex: &Rc<RefCell<Texture>>) -> usize {
+    &**tex as *const RefCell<Texture> as usize
+}
+
+/// The bits of a tile id in which Tiled stores whether it is flipped
+const TILE_FLIP_FLAGS: u32 = 0xe000_0000;
+
+/// A layer of a tilemap
+pub struct TileLayer {
+    pub name: String,
+    pub visible: bool,
+
+    // The id of the tile in every cell, row after row; 0 is an empty cell
+    tiles: Vec<u32>,
+}
+
+impl TileLayer {
+    /// Return the id of the tile at 'column' and 'row', with 0 for an empty
+    /// cell or one outside of the layer
+    pub fn tile(&self, columns: usize, column: usize, row: usize) -> u32 {
+        if column >= columns {
+            return 0;
+        }
+        self.tiles.get(row * columns + column).map_or(0, |&id| id & !TILE_FLIP_FLAGS)
+    }
+}
+
+/// A map made with the Tiled editor: layers of tiles, cut out of tilesets
+/// laid out as grids. Only orthogonal maps saved as TMX, with their layers
+/// encoded as CSV, are read; flipped tiles are drawn unflipped.
+pub struct Tilemap {
+    /// The size of the map in tiles, and of every tile in pixels
+    pub columns: usize,
+    pub rows: usize,
+    pub tile_w: f64,
+    pub tile_h: f64,
+
+    pub layers: Vec<TileLayer>,
+
+    // The sprite of every tile, by id
+    tiles: Vec<Option<Sprite>>,
+}
+
+impl Tilemap {
+    /// Read the map at 'path', along with its tilesets, whether they are
+    /// embedded or in TSX files of their own. Paths are relative to the
+    /// file naming them.
+    pub fn load(renderer: &Renderer, path: &str) -> Option<Tilemap> {
+        let tags = match read_xml(path) {
+            Some(tags) => tags,
+            None => return None,
+        };
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+
+        let (columns, rows, tile_w, tile_h) = match tags.iter().find(|tag| tag.name == "map") {
+            Some(root) => match (root.parse("width"), root.parse("height"),
+                                 root.parse("tilewidth"), root.parse("tileheight")) {
+                (Some(columns), Some(rows), Some(tile_w), Some(tile_h)) =>
+                    (columns, rows, tile_w, tile_h),
+                _ => {
+                    warn!("The map {} has no size", path);
+                    return None;
+                }
+            },
+            None => {
+                warn!("{} is not a map", path);
+                return None;
+            }
+        };
+
+        let mut map = Tilemap {
+            columns: columns,
+            rows: rows,
+            tile_w: tile_w,
+            tile_h: tile_h,
+            layers: vec![],
+            tiles: vec![],
+        };
+
+        for (i, tag) in tags.iter().enumerate() {
+            match &tag.name[..] {
+                "tileset" => {
+                    let first_id = tag.parse("firstgid").unwrap_or(1);
+                    match tag.attribute("source") {
+                        // The tileset is in a file of its own
+                        Some(source) => {
+                            let tsx_path = dir.join(source);
+                            let tsx_path = tsx_path.to_string_lossy();
+                            let tsx = match read_xml(&tsx_path) {
+                                Some(tsx) => tsx,
+                                None => continue,
+                            };
+                            let tsx_dir = Path::new(&*tsx_path).parent().unwrap_or(Path::new(""));
+                            match (tsx.iter().find(|tag| tag.name == "tileset"),
+                                   tsx.iter().find(|tag| tag.name == "image")) {
+                                (Some(tileset), Some(image)) =>
+                                    map.add_tileset(renderer, first_id, tileset, image, tsx_dir),
+                                _ => warn!("The tileset {} has no image", tsx_path),
+                            }
+                        },
+                        None => match tags.get(i + 1) {
+                            Some(image) if image.name == "image" =>
+                                map.add_tileset(renderer, first_id, tag, image, dir),
+                            _ => warn!("A tileset of {} has no image", path),
+                        },
+                    }
+                },
+                "layer" => map.layers.push(TileLayer {
+                    name: tag.attribute("name").unwrap_or("").to_string(),
+                    visible: tag.attribute("visible") != Some("0"),
+                    tiles: vec![],
+                }),
+                "data" => {
+                    if tag.attribute("encoding") != Some("csv") {
+                        warn!("The layers of {} are not encoded as CSV", path);
+                        return None;
+                    }
+                    if let Some(layer) = map.layers.last_mut() {
+                        layer.tiles = tag.text.split(',')
+                            .filter_map(|id| id.trim().parse().ok())
+                            .collect();
+                        if layer.tiles.len() != columns * rows {
+                            warn!("The layer {} of {} has {} tiles instead of {}",
+                                  layer.name, path, layer.tiles.len(), columns * rows);
+                        }
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        if map.columns == 0 || map.rows == 0 {
+            warn!("The map {} is empty", path);
+            return None;
+        }
+        Some(map)
+    }
+
+    /// Cut the tiles of a tileset out of its image, numbering them from
+    /// 'first_id'
+    fn add_tileset(&mut self, renderer: &Renderer, first_id: usize,
+                   tileset: &XmlTag, image: &XmlTag, dir: &Path) {
+        let source = match image.attribute("source") {
+            Some(source) => dir.join(source),
+            None => return,
+        };
+        let sheet = match Sprite::load(renderer, &source.to_string_lossy()) {
+            Some(sheet) => sheet,
+            None => return,
+        };
+
+        let tile_w = tileset.parse("tilewidth").unwrap_or(self.tile_w);
+        let tile_h = tileset.parse("tileheight").unwrap_or(self.tile_h);
+        let margin = tileset.parse("margin").unwrap_or(0.0);
+        let spacing = tileset.parse("spacing").unwrap_or(0.0);
+        let (sheet_w, _) = sheet.size();
+        let columns = tileset.parse("columns")
+            .unwrap_or(((sheet_w - margin * 2.0 + spacing) / (tile_w + spacing)) as usize).max(1);
+        let count: usize = tileset.parse("tilecount").unwrap_or(0);
+
+        if self.tiles.len() < first_id + count {
+            self.tiles.resize(first_id + count, None);
+        }
+        for i in 0..count {
+            self.tiles[first_id + i] = sheet.region(Rectangle {
+                x: margin + (i % columns) as f64 * (tile_w + spacing),
+                y: margin + (i / columns) as f64 * (tile_h + spacing),
+                w: tile_w,
+                h: tile_h,
+            });
+        }
+    }
+
+    /// Return how much the map is enlarged to fill the height 'win_h'
+    pub fn scale(&self, win_h: f64) -> f64 {
+        win_h / (self.tile_h * self.rows as f64)
+    }
+
+    /// Render the visible layers, scaled to fill the height of the window
+    /// and scrolled 'scroll' pixels to the left. The map repeats
+    /// horizontally, so that it may scroll forever.
+    pub fn render(&self, renderer: &mut Renderer, scroll: f64) {
+        let (win_w, win_h) = renderer.output_size().unwrap();
+        let scale = self.scale(win_h as f64);
+        let (tile_w, tile_h) = (self.tile_w * scale, self.tile_h * scale);
+        let map_w = tile_w * self.columns as f64;
+
+        let scroll = ((scroll % map_w) + map_w) % map_w;
+        let first = (scroll / tile_w).floor() as usize;
+        let shown = (win_w as f64 / tile_w).ceil() as usize + 1;
+
+        // Layers are flushed one by one so that they stack in order
+        let mut batch = SpriteBatch::with_capacity(shown * self.rows);
+        for layer in self.layers.iter().filter(|layer| layer.visible) {
+            for i in 0..shown {
+                let column = (first + i) % self.columns;
+                let x = (first + i) as f64 * tile_w - scroll;
+
+                for row in 0..self.rows {
+                    let id = layer.tile(self.columns, column, row) as usize;
+                    if let Some(&Some(ref sprite)) = self.tiles.get(id) {
+                        batch.push(sprite, Rectangle {
+                            x: x.floor(),
+                            y: (row as f64 * tile_h).floor(),
+                            w: tile_w.ceil(),
+                            h: tile_h.ceil(),
+                        });
+                    }
+                }
+            }
+            batch.flush(renderer);
+        }
+    }
+}
+
+/// A tag of an XML file, with its attributes and the text following it, up
+/// to the next tag. Closing tags are named after what they close, preceded
+/// by `/`.
+struct XmlTag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+}
+
+impl XmlTag {
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter()
+            .find(|&&(ref key, _)| key == name)
+            .map(|&(_, ref value)| &value[..])
+    }
+
+    fn parse<T: ::std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.attribute(name).and_then(|value| value.parse().ok())
+    }
+}
+
+/// Read the tags of the XML file at 'path', in order. This is only as much
+/// of XML as the files of Tiled need: there is no support for comments
+/// holding tags, CDATA or entities besides the predefined ones.
+fn read_xml(path: &str) -> Option<Vec<XmlTag>> {
+    let mut xml = String::new();
+    if let Err(err) = File::open(path).and_then(|mut file| file.read_to_string(&mut xml)) {
+        warn!("Could not read {}: {}", path, err);
+        return None;
+    }
+
+    let mut tags = vec![];
+    for chunk in xml.split('<').skip(1) {
+        let end = match chunk.find('>') {
+            Some(end) => end,
+            None => {
+                warn!("Ignoring an unclosed tag in {}", path);
+                continue;
+            }
+        };
+        let body = chunk[..end].trim_right_matches('/');
+        if body.starts_with('?') || body.starts_with('!') {
+            continue;
+        }
+
+        let name = body.split_whitespace().next().unwrap_or("").to_string();
+        tags.push(XmlTag {
+            attributes: xml_attributes(&body[name.len()..]),
+            name: name,
+            text: unescape_xml(&chunk[end + 1..]),
+        });
+    }
+    Some(tags)
+}
+
+// Read the `key="value"` attributes of a tag
+fn xml_attributes(mut rest: &str) -> Vec<(String, String)> {
+    let mut attributes = vec![];
+
+    while let Some(equals) = rest.find('=') {
+        let key = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_left();
+        let quote = match value.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => quote,
+            _ => break,
+        };
+        let close = match value[1..].find(quote) {
+            Some(close) => close + 1,
+            None => break,
+        };
+
+        attributes.push((key.to_string(), unescape_xml(&value[1..close])));
+        rest = &value[close + 1..];
+    }
+
+    attributes
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,267 @@
+// phi/gif.rs
+
+use ::std::collections::HashMap;
+use ::std::fs::File;
+use ::std::io::{self, BufWriter, Write};
+
+/// The number of levels of every channel in the palette of the images,
+/// which holds every combination of them
+const LEVELS: u32 = 6;
+
+/// The widest codes of the LZW compression, as set by the format
+const MAX_CODE_BITS: u32 = 12;
+
+/// Return the index in the palette of the color closest to (r, g, b)
+pub fn color_index(r: u8, g: u8, b: u8) -> u8 {
+    let level = |channel: u8| (channel as u32 * LEVELS / 256);
+    (level(r) * LEVELS * LEVELS + level(g) * LEVELS + level(b)) as u8
+}
+
+/// An image of an animation, made of indices in the palette, with the
+/// hundredths of a second it stays on screen
+#[derive(Clone)]
+pub struct Frame {
+    pub pixels: Vec<u8>,
+    pub delay: u16,
+}
+
+/// Write the looping animation made of 'frames', all 'width' by 'height'
+/// pixels, as a GIF file at 'path'
+pub fn write(path: &str, width: u16, height: u16, frames: &[Frame]) -> io::Result<()> {
+    let mut file = BufWriter::new(try!(File::create(path)));
+
+    // The header and the size of the screen, followed by a palette of 256
+    // colors, the last of which are left black
+    try!(file.write_all(b"GIF89a"));
+    try!(write_u16(&mut file, width));
+    try!(write_u16(&mut file, height));
+    try!(file.write_all(&[0xf7, 0, 0]));
+    for index in 0..256 {
+        let rgb = if index < LEVELS * LEVELS * LEVELS {
+            let level = |value: u32| (value * 255 / (LEVELS - 1)) as u8;
+            [level(index / (LEVELS * LEVELS)), level(index / LEVELS % LEVELS), level(index % LEVELS)]
+        }
+        else {
+            [0, 0, 0]
+        };
+        try!(file.write_all(&rgb));
+    }
+
+    // Loop forever
+    try!(file.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00"));
+
+    for frame in frames {
+        // How long the frame stays, then where it is drawn
+        try!(file.write_all(&[0x21, 0xf9, 0x04, 0x00]));
+        try!(write_u16(&mut file, frame.delay));
+        try!(file.write_all(&[0x00, 0x00, 0x2c]));
+        try!(write_u16(&mut file, 0));
+        try!(write_u16(&mut file, 0));
+        try!(write_u16(&mut file, width));
+        try!(write_u16(&mut file, height));
+        try!(file.write_all(&[0x00]));
+
+        // The compressed pixels, in blocks of at most 255 bytes
+        try!(file.write_all(&[8]));
+        for block in compress(&frame.pixels).chunks(255) {
+            try!(file.write_all(&[block.len() as u8]));
+            try!(file.write_all(block));
+        }
+        try!(file.write_all(&[0x00]));
+    }
+
+    try!(file.write_all(&[0x3b]));
+    file.flush()
+}
+
+fn write_u16<W: Write>(out: &mut W, value: u16) -> io::Result<()> {
+    out.write_all(&[value as u8, (value >> 8) as u8])
+}
+
+/// Compress the indices of 8-bit colors with the variable-length LZW of
+/// the GIF format
+fn compress(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1 << 8;
+    let end = clear + 1;
+
+    let mut out = BitWriter::new();
+    let mut codes: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut bits = 9;
+
+    out.write(clear, bits);
+    let mut prefix = match pixels.first() {
+        Some(&first) => first as u16,
+        None => {
+            out.write(end, bits);
+            return out.finish();
+        },
+    };
+
+    for &pixel in &pixels[1..] {
+        if let Some(&code) = codes.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+
+        out.write(prefix, bits);
+        if next < 1 << MAX_CODE_BITS {
+            codes.insert((prefix, pixel), next);
+            next += 1;
+            // Codes widen once the table outgrows them; decoders follow
+            // one code later, as they add every code after reading it
+            if next > 1 << bits && bits < MAX_CODE_BITS {
+                bits += 1;
+            }
+        }
+        else {
+            // The table is full: start a new one
+            out.write(clear, bits);
+            codes.clear();
+            next = end + 1;
+            bits = 9;
+        }
+        prefix = pixel as u16;
+    }
+
+    out.write(prefix, bits);
+    out.write(end, bits);
+    out.finish()
+}
+
+/// Packs codes into bytes, least significant bits first
+struct BitWriter {
+    bytes: Vec<u8>,
+    pending: u32,
+    pending_bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            bytes: vec![],
+            pending: 0,
+            pending_bits: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, bits: u32) {
+        self.pending |= (code as u32) << self.pending_bits;
+        self.pending_bits += bits;
+        while self.pending_bits >= 8 {
+            self.bytes.push(self.pending as u8);
+            self.pending >>= 8;
+            self.pending_bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.pending_bits > 0 {
+            self.bytes.push(self.pending as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Read back the codes of 'data' the way a GIF decoder does, and return
+    // the pixels they stand for
+    fn decompress(data: &[u8]) -> Vec<u8> {
+        let clear = 1 << 8;
+        let end = clear + 1;
+
+        let mut pixels = vec![];
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut previous: Option<Vec<u8>> = None;
+        let mut bits = 9;
+        let mut position = 0;
+
+        loop {
+            let mut code = 0;
+            for bit in 0..bits {
+                let byte = data[(position + bit) / 8];
+                code |= (((byte >> ((position + bit) % 8)) & 1) as usize) << bit;
+            }
+            position += bits;
+
+            if code == clear {
+                table = (0..256).map(|value| vec![value as u8]).collect();
+                table.push(vec![]);
+                table.push(vec![]);
+                previous = None;
+                bits = 9;
+                continue;
+            }
+            if code == end {
+                return pixels;
+            }
+
+            let string = if code < table.len() {
+                table[code].clone()
+            }
+            else {
+                let mut string = previous.clone().unwrap();
+                string.push(string[0]);
+                string
+            };
+            if let Some(mut added) = previous {
+                if table.len() < 1 << MAX_CODE_BITS {
+                    added.push(string[0]);
+                    table.push(added);
+                }
+            }
+            if table.len() == 1 << bits && bits < MAX_CODE_BITS as usize {
+                bits += 1;
+            }
+
+            pixels.extend_from_slice(&string);
+            previous = Some(string);
+        }
+    }
+
+    #[test]
+    fn empty_images_hold_no_pixels() {
+        assert_eq!(decompress(&compress(&[])), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn pixels_survive_compression() {
+        let images: Vec<Vec<u8>> = vec![
+            vec![7],
+            vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+            vec![0, 1, 0, 1, 0, 1, 2, 0, 1, 2],
+            (0..2_000).map(|index| (index % 215) as u8).collect(),
+        ];
+        for pixels in images {
+            assert_eq!(decompress(&compress(&pixels)), pixels);
+        }
+    }
+
+    #[test]
+    fn full_tables_start_over() {
+        // Pseudo-random pixels make new codes fast enough to fill the table
+        // several times over
+        let mut state = 1u32;
+        let pixels: Vec<u8> = (0..50_000).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (state >> 16) as u8
+        }).collect();
+        assert_eq!(decompress(&compress(&pixels)), pixels);
+    }
+
+    #[test]
+    fn repetition_compresses() {
+        assert!(compress(&[3; 10_000]).len() < 500);
+    }
+
+    #[test]
+    fn colors_map_to_the_palette() {
+        assert_eq!(color_index(0, 0, 0), 0);
+        assert_eq!(color_index(255, 255, 255), 215);
+        assert_eq!(color_index(255, 0, 0), 180);
+        assert_eq!(color_index(0, 0, 255), 5);
+    }
+}
@@ -0,0 +1,191 @@
+// phi/input_display.rs
+
+use ::sdl2::controller::{Axis, Button};
+use ::sdl2::pixels::Color;
+use ::sdl2::render::{BlendMode, Renderer};
+
+use ::phi::Events;
+use ::phi::bitmap_font::BitmapFont;
+use ::phi::controllers::{DeadZone, PadInput, MAX_PLAYERS};
+use ::phi::data::Rectangle;
+
+/// The characters of the names of the inputs
+pub const GLYPHS: &'static str = "abcdefghijklmnopqrstuvwxyz0123456789_ ";
+
+/// The inputs of the controllers shown, besides the sticks
+pub const PAD_INPUTS: [PadInput; 17] = [
+    PadInput::Button(Button::A),
+    PadInput::Button(Button::B),
+    PadInput::Button(Button::X),
+    PadInput::Button(Button::Y),
+    PadInput::Button(Button::Back),
+    PadInput::Button(Button::Guide),
+    PadInput::Button(Button::Start),
+    PadInput::Button(Button::LeftStick),
+    PadInput::Button(Button::RightStick),
+    PadInput::Button(Button::LeftShoulder),
+    PadInput::Button(Button::RightShoulder),
+    PadInput::Button(Button::DPadUp),
+    PadInput::Button(Button::DPadDown),
+    PadInput::Button(Button::DPadLeft),
+    PadInput::Button(Button::DPadRight),
+    PadInput::Trigger(Axis::TriggerLeft),
+    PadInput::Trigger(Axis::TriggerRight),
+];
+
+/// The colors of inputs which were just pressed, are held, were just
+/// released, or are idle
+pub const PRESSED: Color = Color::RGB(255, 255, 120);
+pub const HELD: Color = Color::RGB(80, 230, 100);
+pub const RELEASED: Color = Color::RGB(230, 80, 80);
+pub const IDLE: Color = Color::RGB(110, 110, 110);
+
+/// How many names fit on a row, and the sizes of the display, before the
+/// UI scale is applied
+const KEYS_PER_ROW: usize = 6;
+const PAD_INPUTS_PER_ROW: usize = 5;
+const STICK_SIDE: f64 = 32.0;
+const GAP: f64 = 6.0;
+const MARGIN: f64 = 10.0;
+
+/// Return the color of an input 'held', which 'changed' during this frame
+/// as keyboard events tell it
+pub fn state_color(held: bool, changed: Option<bool>) -> Color {
+    match changed {
+        Some(true) => PRESSED,
+        Some(false) => RELEASED,
+        None if held => HELD,
+        None => IDLE,
+    }
+}
+
+// A row of the display: names of inputs, or the sticks of a controller
+enum Row {
+    Names(Vec<(String, Color)>),
+    Sticks(usize),
+}
+
+/// Shows the state of every input the game reads in the bottom right
+/// corner of the screen: the keys tracked by the events macro, the
+/// modifiers and mouse buttons, and the buttons, triggers and sticks of
+/// every controller plugged in. Toggled with F1.
+pub struct InputDisplay {
+    font: BitmapFont,
+}
+
+impl InputDisplay {
+    /// Create a display writing with 'font', which must hold GLYPHS
+    pub fn new(font: BitmapFont) -> InputDisplay {
+        InputDisplay {
+            font: font,
+        }
+    }
+
+    fn rows(events: &Events) -> Vec<Row> {
+        let mut rows = vec![];
+
+        let keys: Vec<(String, Color)> = events.tracked_keys().into_iter()
+            .map(|(name, held, changed)| (name.trim_left_matches("key_").to_string(), state_color(held, changed)))
+            .collect();
+        rows.extend(keys.chunks(KEYS_PER_ROW).map(|chunk| Row::Names(chunk.to_vec())));
+
+        rows.push(Row::Names(vec![
+            ("shift".to_string(), state_color(events.mod_shift, events.now.mod_shift)),
+            ("ctrl".to_string(), state_color(events.mod_ctrl, events.now.mod_ctrl)),
+            ("alt".to_string(), state_color(events.mod_alt, events.now.mod_alt)),
+            ("mouse_left".to_string(), state_color(events.mouse_left, events.now.mouse_left)),
+            ("mouse_right".to_string(), state_color(events.mouse_right, events.now.mouse_right)),
+        ]));
+
+        for player in (0..MAX_PLAYERS).filter(|&player| events.controllers.is_connected(player)) {
+            rows.push(Row::Names(vec![(format!("pad {}", player + 1), Color::RGB(255, 255, 255))]));
+
+            // Controllers only tell which inputs were just pressed
+            let inputs: Vec<(String, Color)> = PAD_INPUTS.iter().map(|&input| {
+                let pressed = events.now.pad_inputs.contains(&(player, input));
+                let held = events.controllers.is_held(player, input);
+                (input.name(), state_color(held, if pressed { Some(true) } else { None }))
+            }).collect();
+            rows.extend(inputs.chunks(PAD_INPUTS_PER_ROW).map(|chunk| Row::Names(chunk.to_vec())));
+            rows.push(Row::Sticks(player));
+        }
+
+        rows
+    }
+
+    pub fn render(&self, renderer: &mut Renderer, events: &Events, dead_zone: DeadZone, scale: f64) {
+        let rows = InputDisplay::rows(events);
+        let gap = GAP * scale;
+        let margin = MARGIN * scale;
+        let stick_side = STICK_SIDE * scale;
+        let line_h = self.font.text_size("a", 1.0).1;
+
+        let row_size = |row: &Row| match *row {
+            Row::Names(ref names) => {
+                let w: f64 = names.iter().map(|&(ref name, _)| self.font.text_size(name, 1.0).0 + gap).sum();
+                (w - gap, line_h)
+            },
+            Row::Sticks(_) => (stick_side * 2.0 + gap, stick_side + gap),
+        };
+        let (w, h) = rows.iter().map(&row_size)
+            .fold((0.0, 0.0), |(w, h): (f64, f64), (row_w, row_h)| (w.max(row_w), h + row_h));
+
+        let (out_w, out_h) = renderer.output_size().unwrap();
+        let left = out_w as f64 - w - margin * 2.0;
+        let mut y = out_h as f64 - h - margin * 2.0;
+
+        renderer.set_blend_mode(BlendMode::Blend);
+        renderer.set_draw_color(Color::RGBA(0, 0, 0, 170));
+        renderer.fill_rect(Rectangle {
+            x: left - margin,
+            y: y - margin,
+            w: w + margin * 2.0,
+            h: h + margin * 2.0,
+        }.to_sdl().unwrap());
+        renderer.set_blend_mode(BlendMode::None);
+
+        for row in &rows {
+            match *row {
+                Row::Names(ref names) => {
+                    let mut x = left;
+                    for &(ref name, color) in names {
+                        self.font.set_color(color);
+                        self.font.render(renderer, name, (x, y), 1.0);
+                        x += self.font.text_size(name, 1.0).0 + gap;
+                    }
+                },
+                Row::Sticks(player) => {
+                    // Where each stick is, in white, and where the game
+                    // reads it through the dead zone, in green
+                    let sticks = [events.controllers.left_stick(player),
+                                  events.controllers.right_stick(player)];
+                    for (i, stick) in sticks.iter().enumerate() {
+                        let area = Rectangle {
+                            x: left + i as f64 * (stick_side + gap),
+                            y: y,
+                            w: stick_side,
+                            h: stick_side,
+                        };
+                        renderer.set_draw_color(Color::RGB(40, 40, 40));
+                        renderer.fill_rect(area.to_sdl().unwrap());
+
+                        let raw = stick.unwrap_or((0.0, 0.0));
+                        let read = dead_zone.apply(raw);
+                        let (cx, cy) = area.center();
+                        for &((sx, sy), color) in &[(raw, Color::RGB(255, 255, 255)), (read, HELD)] {
+                            renderer.set_draw_color(color);
+                            renderer.fill_rect(Rectangle {
+                                x: cx + sx * stick_side / 2.0 - 2.0,
+                                y: cy + sy * stick_side / 2.0 - 2.0,
+                                w: 4.0,
+                                h: 4.0,
+                            }.to_sdl().unwrap());
+                        }
+                    }
+                },
+            }
+            y += row_size(row).1;
+        }
+        self.font.set_color(Color::RGB(255, 255, 255));
+    }
+}
@@ -0,0 +1,96 @@
+// phi/layers.rs
+
+use ::phi::Phi;
+use ::phi::camera::Camera;
+
+/// The layers a frame is drawn in, from the back to the front
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Layer {
+    /// The backgrounds, and the scenery everything else moves over
+    Background,
+    /// The ship and whatever it meets
+    Entities,
+    /// The bullets
+    Projectiles,
+    /// What is drawn over the scene, such as the foreground and floating
+    /// text
+    Effects,
+    /// What covers the whole screen, under the HUD, such as dimming and
+    /// flashes, which the camera does not move
+    Screen,
+    /// The score, the gauges and the messages to the player
+    Hud,
+    /// What must stay on top of everything, such as the crosshair
+    Overlay,
+}
+
+impl Layer {
+    /// Whether the layer shows the world, and is thus moved by the camera,
+    /// rather than the screen
+    pub fn is_world(self) -> bool {
+        self < Layer::Screen
+    }
+}
+
+/// The drawing of a frame. Whatever has something to draw registers it in
+/// its layer, then the queue draws the layers in a single pass from the
+/// back to the front. Within a layer, what has the lowest depth is drawn
+/// first, and what has the same depth in the order it was registered.
+pub struct RenderQueue<'a> {
+    items: Vec<(Layer, i32, Box<FnMut(&mut Phi) + 'a>)>,
+}
+
+impl<'a> RenderQueue<'a> {
+    pub fn new() -> RenderQueue<'a> {
+        RenderQueue {
+            items: vec![],
+        }
+    }
+
+    /// Draw with 'draw' in 'layer', at a depth of 0
+    pub fn push<F>(&mut self, layer: Layer, draw: F)
+            where F: FnMut(&mut Phi) + 'a {
+        self.push_at(layer, 0, draw);
+    }
+
+    /// Draw with 'draw' in 'layer', at 'depth'
+    pub fn push_at<F>(&mut self, layer: Layer, depth: i32, draw: F)
+            where F: FnMut(&mut Phi) + 'a {
+        self.items.push((layer, depth, Box::new(draw)));
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Draw everything registered, and empty the queue. The layers showing
+    /// the world are moved by 'camera', shaken as much as the settings
+    /// allow.
+    pub fn draw(&mut self, phi: &mut Phi, camera: &Camera) {
+        // The sort is stable, so what shares a depth keeps its order
+        self.items.sort_by_key(|&(layer, depth, _)| (layer, depth));
+
+        let shake_scale = phi.settings.shake_scale();
+        let mut in_world = false;
+        for (layer, _, mut draw) in self.items.drain(..) {
+            if layer.is_world() != in_world {
+                in_world = layer.is_world();
+                if in_world {
+                    camera.apply(&mut phi.renderer, shake_scale);
+                }
+                else {
+                    camera.reset(&mut phi.renderer);
+                }
+            }
+            draw(phi);
+        }
+
+        if in_world {
+            camera.reset(&mut phi.renderer);
+        }
+    }
+}
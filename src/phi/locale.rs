@@ -0,0 +1,106 @@
+// phi/locale.rs
+
+use ::phi::assets::unquote;
+use ::std::collections::HashMap;
+use ::std::fmt::Display;
+use ::std::fs::{self, File};
+use ::std::io::{BufRead, BufReader};
+
+/// The directory holding a `<language>.toml` table of strings per language
+pub const LANGUAGES_DIR: &'static str = "assets/lang";
+
+/// The language whose strings are used when a translation lacks some
+pub const DEFAULT_LANGUAGE: &'static str = "en";
+
+// Read the `key = "string"` lines of the table of 'language'
+fn load_strings(language: &str) -> HashMap<String, String> {
+    let mut strings = HashMap::new();
+    let path = format!("{}/{}.toml", LANGUAGES_DIR, language);
+
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(_) => {
+            warn!("Could not open the strings of the language {}", language);
+            return strings;
+        }
+    };
+
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, '=');
+        match (parts.next(), parts.next().and_then(|value| unquote(value.trim()))) {
+            (Some(key), Some(value)) => {
+                strings.insert(key.trim().to_string(), value.to_string());
+            },
+            _ => warn!("Ignoring malformed line in {}: {}", path, line),
+        }
+    }
+
+    strings
+}
+
+/// Return the languages which have a table of strings, sorted by code
+pub fn languages() -> Vec<String> {
+    let mut languages: Vec<String> = fs::read_dir(LANGUAGES_DIR).into_iter()
+        .flat_map(|entries| entries)
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "toml"))
+        .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(String::from))
+        .collect();
+    languages.sort();
+    languages
+}
+
+/// The strings shown to the player, in their language. Strings are looked
+/// up by key, such as `menu.quit`, and may hold the placeholders `{0}`,
+/// `{1}`, ... which are replaced by arguments.
+pub struct Locale {
+    pub language: String,
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn load(language: &str) -> Locale {
+        Locale {
+            language: language.to_string(),
+            strings: load_strings(language),
+            fallback: if language == DEFAULT_LANGUAGE { HashMap::new() }
+                      else { load_strings(DEFAULT_LANGUAGE) },
+        }
+    }
+
+    /// The name of the language, in that language
+    pub fn name(&self) -> String {
+        self.text("language.name")
+    }
+
+    /// Return the string 'key', or the key itself if no language has it
+    pub fn text(&self, key: &str) -> String {
+        self.strings.get(key)
+            .or_else(|| self.fallback.get(key))
+            .cloned()
+            .unwrap_or_else(|| {
+                debug!("Missing string {} in the language {}", key, self.language);
+                key.to_string()
+            })
+    }
+
+    /// Return the string 'key' with its placeholders replaced by 'args'
+    pub fn format(&self, key: &str, args: &[&Display]) -> String {
+        let mut text = self.text(key);
+        for (index, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", index), &arg.to_string());
+        }
+        text
+    }
+}
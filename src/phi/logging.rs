@@ -0,0 +1,159 @@
+// phi/logging.rs
+
+//! A logger for the `log` facade which writes every record, with the time
+//! since startup and its target (usually the module it comes from), both
+//! to stderr and to a log file. The file is rotated once it grows too big.
+//!
+//! Which records are kept is decided by a filter such as
+//! `info,arcade_rs::views=debug`: a default level, followed by levels for
+//! the targets starting with a given prefix, the longest prefix winning.
+
+use ::std::env;
+use ::std::fs::{self, File, OpenOptions};
+use ::std::io::{self, Write};
+use ::std::sync::Mutex;
+use ::std::time::Instant;
+
+use ::log::{self, LevelFilter, Log, Metadata, Record};
+
+/// Where the logs of the current session are written
+pub const LOG_PATH: &'static str = "arcade.log";
+
+/// The size, in bytes, beyond which the log file is rotated
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// The number of rotated log files kept, as arcade.log.1, arcade.log.2...
+const ROTATED_LOGS: usize = 3;
+
+/// The command line option which overrides the filter of the settings
+const LOG_LEVEL_OPTION: &'static str = "--log-level=";
+
+struct Logger {
+    // The level of the records kept when no directive matches their target,
+    // and the directives as (target prefix, level)
+    default: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
+
+    start: Instant,
+
+    // The log file and its current size; None if it could not be opened
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl Logger {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives.iter()
+            .filter(|&&(ref prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|&&(ref prefix, _)| prefix.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    // Move the log file out of the way, and start a new one
+    fn rotate() -> Option<(File, u64)> {
+        for i in (1..ROTATED_LOGS).rev() {
+            let _ = fs::rename(format!("{}.{}", LOG_PATH, i), format!("{}.{}", LOG_PATH, i + 1));
+        }
+        let _ = fs::rename(LOG_PATH, format!("{}.1", LOG_PATH));
+        File::create(LOG_PATH).ok().map(|file| (file, 0))
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let elapsed = self.start.elapsed();
+        let line = format!("[{:>5}.{:03}] {:<5} {}: {}\n",
+                           elapsed.as_secs(), elapsed.subsec_nanos() / 1_000_000,
+                           record.level(), record.target(), record.args());
+
+        let _ = io::stderr().write_all(line.as_bytes());
+
+        if let Ok(mut file) = self.file.lock() {
+            let full = file.as_ref().map_or(false, |&(_, size)| size >= MAX_LOG_SIZE);
+            if full {
+                *file = Logger::rotate();
+            }
+            if let Some((ref mut file, ref mut size)) = *file {
+                if file.write_all(line.as_bytes()).is_ok() {
+                    *size += line.len() as u64;
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            if let Some((ref mut file, _)) = *file {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// Parse a filter such as `info,arcade_rs::phi=debug`. Invalid directives
+/// are ignored; the default level is `info` unless the filter sets one.
+fn parse_filter(filter: &str) -> (LevelFilter, Vec<(String, LevelFilter)>) {
+    let mut default = LevelFilter::Info;
+    let mut directives = vec![];
+
+    for directive in filter.split(',').map(|d| d.trim()).filter(|d| !d.is_empty()) {
+        let mut parts = directive.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(level), None) => match level.parse() {
+                Ok(level) => default = level,
+                // A target alone enables everything it logs
+                Err(_) => directives.push((level.to_string(), LevelFilter::Trace)),
+            },
+            (Some(target), Some(level)) => {
+                if let Ok(level) = level.trim().parse() {
+                    directives.push((target.trim().to_string(), level));
+                }
+            },
+            _ => {},
+        }
+    }
+
+    (default, directives)
+}
+
+/// Return the filter given on the command line, if any
+pub fn filter_from_args() -> Option<String> {
+    env::args()
+        .find(|arg| arg.starts_with(LOG_LEVEL_OPTION))
+        .map(|arg| arg[LOG_LEVEL_OPTION.len()..].to_string())
+}
+
+/// Start logging the records which pass 'filter'. The previous log file is
+/// kept as a rotated one.
+pub fn init(filter: &str) {
+    let (default, directives) = parse_filter(filter);
+    let max_level = directives.iter()
+        .map(|&(_, level)| level)
+        .fold(default, ::std::cmp::max);
+
+    // Keep the log of the previous session around
+    let file = if fs::metadata(LOG_PATH).is_ok() {
+        Logger::rotate()
+    }
+    else {
+        OpenOptions::new().create(true).append(true).open(LOG_PATH).ok().map(|file| (file, 0))
+    };
+
+    let logger = Logger {
+        default: default,
+        directives: directives,
+        start: Instant::now(),
+        file: Mutex::new(file),
+    };
+
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
@@ -1,17 +1,75 @@
 // phi/mod.rs
 
-use self::gfx::Sprite;
-use ::sdl2::render::Renderer;
+use self::animation::AnimationSet;
+use self::assets::Assets;
+use self::bitmap_font::BitmapFont;
+use self::bus::EventBus;
+use self::capture::ClipRecorder;
+use self::controllers::PadBindings;
+use self::data::Rectangle;
+use self::gfx::{Cursor, Renderable, Sprite};
+use self::input_display::InputDisplay;
+use self::locale::Locale;
+use self::overlay::Overlay;
+use self::palette::Palette;
+use self::profiler::Profiler;
+use self::quality::Quality;
+use self::settings::Settings;
+use ::sdl2::pixels::PixelFormatEnum;
+use ::sdl2::render::{BlendMode, Renderer};
+use ::sdl2::surface::Surface;
 use ::sdl2::pixels::Color;
 use ::sdl2::timer;
 use ::sdl2::rect::Rect as SdlRect;
-use ::std::collections::HashMap;
+use ::sdl2_image::LoadSurface;
+use ::std::collections::{HashMap, HashSet};
 use ::std::path::Path;
+use ::std::rc::Rc;
+use ::std::time::Instant;
 
 #[macro_use]
 mod events;
+pub mod animation;
+pub mod assets;
+pub mod atlas;
+pub mod bitmap_font;
+pub mod bus;
+pub mod camera;
+pub mod capture;
+pub mod controllers;
+pub mod crash;
 pub mod data;
+pub mod focus;
 pub mod gfx;
+pub mod gif;
+pub mod input_display;
+pub mod layers;
+pub mod locale;
+pub mod logging;
+pub mod net;
+pub mod overlay;
+pub mod palette;
+pub mod parallel;
+pub mod particles;
+pub mod path;
+pub mod profiler;
+pub mod prompts;
+pub mod quality;
+pub mod random;
+pub mod settings;
+pub mod shapes;
+pub mod text;
+pub mod themes;
+pub mod trail;
+
+pub use self::events::KeyRepeat;
+
+/// The most (font, size) pairs kept loaded at once
+const MAX_CACHED_FONTS: usize = 16;
+
+/// The bounds of the UI scale
+const MIN_UI_SCALE: f64 = 0.5;
+const MAX_UI_SCALE: f64 = 4.0;
 
 struct_events! {
     keyboard: {
@@ -22,10 +80,24 @@ struct_events! {
         key_right: Right,
         key_space: Space,
         key_return: Return,
+        key_backspace: Backspace,
 
         key_1: Num1,
         key_2: Num2,
-        key_3: Num3
+        key_3: Num3,
+
+        key_f1: F1,
+        key_f2: F2,
+        key_f3: F3,
+        key_f4: F4,
+        key_f5: F5,
+        key_f6: F6,
+        key_f7: F7,
+        key_f8: F8,
+        key_f9: F9,
+        key_f10: F10,
+        key_f11: F11,
+        key_f12: F12
     },
 
     else: {
@@ -38,18 +110,103 @@ struct_events! {
 pub struct Phi<'window> {
     pub events: Events,
     pub renderer: Renderer<'window>,
+    pub settings: Settings,
+    pub locale: Locale,
+    pub assets: Assets,
+    pub profiler: Profiler,
+
+    // How much is drawn, lowered while frames take too long
+    pub quality: Quality,
+
+    // What happened in the game during the current frame
+    pub bus: EventBus,
+
+    // The dimming and flashes drawn over the scene
+    pub overlay: Overlay,
+
+    // Whether frames are held back to at most 60 every second
+    pub limit_fps: bool,
+
+    // How much larger than intended the display draws things, found when
+    // the window is created; see ui_scale
+    display_scale: f64,
+
+    video: ::sdl2::VideoSubsystem,
+    mouse: ::sdl2::mouse::MouseUtil,
+
+    // Drawn over every frame at the mouse position, if any
+    cursor: Option<Cursor>,
+
+    // The window title is made of the game's name, what the current view
+    // is doing, if it says, and the frame rate in debug builds
+    title: String,
+    title_status: Option<String>,
+    title_fps: Option<u16>,
+
+    // The last seconds of play, saved as a clip with F12
+    clips: ClipRecorder,
+
+    // Whether the time spent in every profiled scope is drawn over the
+    // frame, and the names of the scopes, rendered once
+    show_profiler: bool,
+    profiler_labels: HashMap<&'static str, Sprite>,
+    memory_label: Option<(usize, Sprite)>,
+
+    // Whether the state of every input is drawn over the frame, and what
+    // draws it, created the first time it is shown
+    show_inputs: bool,
+    input_display: Option<InputDisplay>,
 
-    cached_fonts: HashMap<(&'static str, i32), ::sdl2_ttf::Font>,
+    // The fonts loaded at every size, along with the last time they were
+    // used, counted in texts laid out and fonts preloaded
+    cached_fonts: HashMap<(String, i32), (::sdl2_ttf::Font, u64)>,
+    font_uses: u64,
+
+    // The fonts which could not be loaded, so that they are not tried again
+    missing_fonts: HashSet<String>,
 }
 
 impl<'window> Phi<'window> {
-    fn new(events: Events, renderer: Renderer<'window>) -> Phi<'window> {
+    fn new(title: &str, events: Events, renderer: Renderer<'window>,
+           video: ::sdl2::VideoSubsystem, mouse: ::sdl2::mouse::MouseUtil) -> Phi<'window> {
         //::sdl2_image::init(::sdl2_image::INIT_PNG);
 
+        let settings = Settings::load(settings::SETTINGS_PATH);
+        let mut assets = Assets::load(assets::MANIFEST_PATH);
+        if let Some(theme) = themes::load_themes(themes::THEMES_PATH).into_iter()
+                .find(|theme| theme.name == settings.theme) {
+            assets.set_theme(theme);
+        }
+
+        let display_scale = detect_display_scale(&renderer);
+
         Phi {
             events: events,
             renderer: renderer,
+            locale: Locale::load(&settings.language),
+            settings: settings,
+            video: video,
+            mouse: mouse,
+            assets: assets,
+            profiler: Profiler::new(),
+            quality: Quality::new(),
+            bus: EventBus::new(),
+            overlay: Overlay::new(),
+            limit_fps: true,
+            display_scale: display_scale,
+            cursor: None,
+            title: title.to_string(),
+            title_status: None,
+            title_fps: None,
+            clips: ClipRecorder::new(),
+            show_profiler: false,
+            profiler_labels: HashMap::new(),
+            memory_label: None,
+            show_inputs: false,
+            input_display: None,
             cached_fonts: HashMap::new(),
+            font_uses: 0,
+            missing_fonts: HashSet::new(),
         }
     }
 
@@ -58,27 +215,537 @@ impl<'window> Phi<'window> {
         (w as f64, h as f64)
     }
 
-    pub fn ttf_str_sprite(&mut self, text: &str, font_path: &'static str, size:i32, color: Color) -> Option<Sprite> {
-        // First we determine whether the Font is cached - if so, use it
-        if let Some(font) = self.cached_fonts.get(&(font_path, size)) {
-            return font.render(text, ::sdl2_ttf::blended(color)).ok()
-                // If this worked be try to make this surface into a texture
-                .and_then(|surface| self.renderer.create_texture_from_surface(&surface).ok())
-                // If this worked we load
-                .map(Sprite::new)
+    /// How much the text and menus are enlarged: the `ui_scale` setting, or
+    /// the scale of the display if it is 0
+    pub fn ui_scale(&self) -> f64 {
+        if self.settings.ui_scale > 0.0 {
+            self.settings.ui_scale.max(MIN_UI_SCALE).min(MAX_UI_SCALE)
+        }
+        else {
+            self.display_scale
+        }
+    }
+
+    /// Convert a length of the menus or of the HUD, as laid out for an
+    /// ordinary display, into pixels
+    pub fn scaled(&self, length: f64) -> f64 {
+        length * self.ui_scale()
+    }
+
+    // Convert a font size into the one drawn at the current UI scale
+    fn scaled_font_size(&self, size: i32) -> i32 {
+        ((size as f64 * self.ui_scale()).round() as i32).max(1)
+    }
+
+    /// The palette of the colors chosen in the settings
+    pub fn palette(&self) -> Palette {
+        Palette::from_name(&self.settings.palette)
+    }
+
+    /// Return 'color', drawn for a gameplay element, as the player's
+    /// palette shows it
+    pub fn palette_color(&self, color: Color) -> Color {
+        self.palette().apply(color)
+    }
+
+    /// Return the bindings of the controller of 'player', or the default
+    /// ones if they have no controller
+    pub fn pad_bindings(&self, player: usize) -> PadBindings {
+        self.events.controllers.guid(player)
+            .map_or(PadBindings::default(), |guid| self.settings.pad_bindings(&guid))
+    }
+
+    /// Make the controller of 'player' vibrate with a strength in [0, 1]
+    /// for 'duration' seconds, unless vibration is disabled in the settings.
+    pub fn rumble(&mut self, player: usize, intensity: f64, duration: f64) {
+        if self.settings.vibration {
+            self.events.controllers.rumble(player, intensity, duration);
+        }
+    }
+
+    /// Return a sprite showing the whole image 'name', from the asset
+    /// manifest or given by its path, which is loaded only the first time
+    /// it is asked for.
+    pub fn load_sprite(&mut self, name: &str) -> Option<Sprite> {
+        self.assets.sprite(&self.renderer, name)
+    }
+
+    /// Return the frames of the spritesheet 'name' from the asset manifest
+    pub fn load_frames(&mut self, name: &str) -> Option<Vec<Sprite>> {
+        self.assets.frames(&self.renderer, name)
+    }
+
+    /// Return the frames of the spritesheet 'name' along with the
+    /// animations defined for it; see phi::animation.
+    pub fn load_animations(&mut self, name: &str) -> Option<Rc<AnimationSet>> {
+        self.assets.animations(&self.renderer, name)
+    }
+
+    /// Put 'text' in the system clipboard. Return whether it worked.
+    pub fn set_clipboard_text(&mut self, text: &str) -> bool {
+        self.video.clipboard().set_clipboard_text(text).is_ok()
+    }
+
+    /// Return the text in the system clipboard, if there is any.
+    pub fn clipboard_text(&self) -> Option<String> {
+        let clipboard = self.video.clipboard();
+        if clipboard.has_clipboard_text() {
+            clipboard.clipboard_text().ok()
+        }
+        else {
+            None
+        }
+    }
+
+    /// Show what the current view is doing, such as "Paused", in the window
+    /// title, or nothing more than the game's name with None.
+    pub fn set_title_status(&mut self, status: Option<&str>) {
+        if self.title_status.as_ref().map(|status| &status[..]) != status {
+            self.title_status = status.map(String::from);
+            self.update_title();
+        }
+    }
+
+    // Show the frame rate in the window title
+    fn set_title_fps(&mut self, fps: u16) {
+        self.title_fps = Some(fps);
+        self.update_title();
+    }
+
+    fn update_title(&mut self) {
+        let mut title = self.title.clone();
+        if let Some(ref status) = self.title_status {
+            title = format!("{} - {}", title, status);
+        }
+        if let Some(fps) = self.title_fps {
+            title = format!("{} ({} FPS)", title, fps);
+        }
+
+        if let Some(window) = self.renderer.window_mut() {
+            window.set_title(&title);
+        }
+    }
+
+    /// Use the image 'name', from the asset manifest or given by its path,
+    /// as the window icon; only its frame 'frame' if it is a spritesheet.
+    /// Return whether it worked.
+    pub fn set_window_icon(&mut self, name: &str, frame: Option<usize>) -> bool {
+        let path = self.assets.path(name).to_string();
+        let image = match Surface::from_file(Path::new(&path)) {
+            Ok(image) => image,
+            Err(err) => {
+                warn!("Could not load the window icon {}: {}", path, err);
+                return false;
+            }
+        };
+
+        let icon = match frame {
+            None => image,
+            Some(index) => {
+                let rect = match self.assets.frame_rect(name, index, image.width() as f64) {
+                    Some(rect) => rect,
+                    None => {
+                        warn!("The asset manifest has no frame {} in {}", index, name);
+                        return false;
+                    }
+                };
+
+                // Copy the frame with its transparency to an image of its own
+                let mut icon = match Surface::new(rect.w as u32, rect.h as u32, PixelFormatEnum::ARGB8888) {
+                    Ok(icon) => icon,
+                    Err(_) => return false,
+                };
+                let mut image = image;
+                if image.set_blend_mode(BlendMode::None).is_err()
+                        || image.blit(rect.to_sdl(), &mut icon, None).is_err() {
+                    return false;
+                }
+                icon
+            },
+        };
+
+        match self.renderer.window_mut() {
+            Some(window) => {
+                window.set_icon(icon);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Show or hide the system mouse cursor.
+    pub fn show_system_cursor(&mut self, show: bool) {
+        self.mouse.show_cursor(show);
+    }
+
+    /// Replace the system cursor by a sprite drawn at the mouse position
+    /// after every frame, or bring the system cursor back with None.
+    pub fn set_cursor(&mut self, cursor: Option<Cursor>) {
+        self.show_system_cursor(cursor.is_none());
+        self.cursor = cursor;
+    }
+
+    // Draw the custom cursor, if any, over the current frame
+    fn render_cursor(&mut self) {
+        if let Some(ref cursor) = self.cursor {
+            cursor.render(&mut self.renderer, self.events.mouse);
+        }
+    }
+
+    // Draw the state of every input in the bottom right corner
+    fn render_inputs(&mut self) {
+        if self.input_display.is_none() {
+            self.input_display = self.bitmap_font(input_display::GLYPHS, "menu_font", 12)
+                .map(InputDisplay::new);
+        }
+
+        let dead_zone = self.settings.dead_zone();
+        let scale = self.ui_scale();
+        if let Some(ref display) = self.input_display {
+            display.render(&mut self.renderer, &self.events, dead_zone, scale);
+        }
+    }
+
+    // Draw a bar for the average time spent in every profiled scope, next
+    // to its name, with a mark at the duration of a frame at 60 FPS
+    fn render_profiler(&mut self) {
+        const FRAME_BUDGET: f64 = 1.0 / 60.0;
+        const BAR_SCALE: f64 = 200.0 / FRAME_BUDGET;
+        const ROW_HEIGHT: f64 = 16.0;
+        const LABEL_WIDTH: f64 = 80.0;
+
+        let scale = self.ui_scale();
+        let bar_scale = BAR_SCALE * scale;
+        let row_height = ROW_HEIGHT * scale;
+        let label_width = LABEL_WIDTH * scale;
+
+        let scopes = self.profiler.averages().to_vec();
+        let (x, mut y) = (10.0 * scale, 40.0 * scale);
+
+        self.renderer.set_draw_color(Color::RGB(255, 80, 80));
+        self.renderer.fill_rect(Rectangle {
+            x: x + label_width + FRAME_BUDGET * bar_scale,
+            y: y,
+            w: scale.max(1.0),
+            h: (row_height * scopes.len() as f64).max(1.0),
+        }.to_sdl().unwrap());
+
+        for (name, duration) in scopes {
+            if !self.profiler_labels.contains_key(name) {
+                if let Some(label) = self.ttf_str_sprite(name, "menu_font", 12,
+                                                         Color::RGB(255, 255, 255)) {
+                    self.profiler_labels.insert(name, label);
+                }
+            }
+            if let Some(label) = self.profiler_labels.get(name) {
+                let (w, h) = label.size();
+                label.render(&mut self.renderer, Rectangle { x: x, y: y, w: w, h: h });
+            }
+
+            let color = if duration > FRAME_BUDGET { Color::RGB(230, 60, 60) }
+                        else { Color::RGB(90, 220, 120) };
+            self.renderer.set_draw_color(color);
+            self.renderer.fill_rect(Rectangle {
+                x: x + label_width,
+                y: y + 3.0 * scale,
+                w: (duration * bar_scale).max(1.0),
+                h: (row_height - 6.0 * scale).max(1.0),
+            }.to_sdl().unwrap());
+
+            y += row_height;
+        }
+
+        // Then the memory taken by the textures, in tenths of megabytes
+        let memory = self.assets.texture_memory() * 10 / (1024 * 1024);
+        if self.memory_label.as_ref().map_or(true, |&(shown, _)| shown != memory) {
+            self.memory_label = self.ttf_str_sprite(
+                &format!("textures: {}.{} MB", memory / 10, memory % 10),
+                "menu_font", 12, Color::RGB(255, 255, 255))
+                .map(|label| (memory, label));
+        }
+        if let Some((_, ref label)) = self.memory_label {
+            let (w, h) = label.size();
+            label.render(&mut self.renderer, Rectangle { x: x, y: y + 4.0 * scale, w: w, h: h });
+        }
+    }
+
+    /// Start receiving typed text in `events.now.text`. While text input is
+    /// active, SDL may also show an on-screen keyboard or an IME window.
+    pub fn start_text_input(&mut self) {
+        self.video.text_input().start();
+    }
+
+    /// Stop receiving typed text.
+    pub fn stop_text_input(&mut self) {
+        self.video.text_input().stop();
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        self.video.text_input().is_active()
+    }
+
+    /// Render 'text' with the font 'font', from the asset manifest or given
+    /// by its path. Characters the font lacks are drawn with the first of
+    /// its fallbacks in the manifest which provides them. The size is
+    /// enlarged by the UI scale.
+    pub fn ttf_str_sprite(&mut self, text: &str, font: &str, size: i32, color: Color) -> Option<Sprite> {
+        let size = self.scaled_font_size(size);
+        self.render_text(text, font, size, color)
+            // If this worked be try to make this surface into a texture
+            .and_then(|surface| self.renderer.create_texture_from_surface(&surface).ok())
+            // If this worked we load
+            .map(Sprite::new)
+    }
+
+    /// Render every character of 'chars' with the font 'font' into a single
+    /// texture, in white so that the text may be tinted. The size is
+    /// enlarged by the UI scale.
+    pub fn bitmap_font(&mut self, chars: &str, font: &str, size: i32) -> Option<BitmapFont> {
+        let size = self.scaled_font_size(size);
+        let mut glyphs = vec![];
+        for ch in chars.chars() {
+            match self.render_text(&ch.to_string(), font, size, Color::RGB(255, 255, 255)) {
+                Some(surface) => glyphs.push((ch, surface)),
+                None => return None,
+            }
+        }
+
+        // Lay the glyphs out side by side, a pixel apart so that scaled
+        // glyphs do not bleed into their neighbours
+        let width: u32 = glyphs.iter().map(|&(_, ref glyph)| glyph.width() + 1).sum();
+        let height = glyphs.iter().map(|&(_, ref glyph)| glyph.height()).max().unwrap_or(0);
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let mut sheet = match Surface::new(width, height, PixelFormatEnum::ARGB8888) {
+            Ok(sheet) => sheet,
+            Err(_) => return None,
+        };
+
+        let mut regions = vec![];
+        let mut x = 0;
+        for (ch, mut glyph) in glyphs {
+            let (w, h) = (glyph.width(), glyph.height());
+            let dest = SdlRect::new(x as i32, 0, w, h).unwrap();
+            if glyph.set_blend_mode(BlendMode::None).is_err()
+                    || glyph.blit(None, &mut sheet, dest).is_err() {
+                return None;
+            }
+            regions.push((ch, Rectangle {
+                x: x as f64,
+                y: 0.0,
+                w: w as f64,
+                h: h as f64,
+            }));
+            x += w + 1;
+        }
+
+        let mut texture = match self.renderer.create_texture_from_surface(&sheet) {
+            Ok(texture) => texture,
+            Err(_) => return None,
+        };
+        texture.set_blend_mode(BlendMode::Blend);
+
+        let sheet = Sprite::new(texture);
+        Some(BitmapFont::new(regions.into_iter()
+            .filter_map(|(ch, rect)| sheet.region(rect).map(|sprite| (ch, sprite)))
+            .collect()))
+    }
+
+    /// Return the size of 'text' once rendered by ttf_str_sprite, in pixels
+    pub fn text_size(&mut self, text: &str, font: &str, size: i32) -> Option<(f64, f64)> {
+        let size = self.scaled_font_size(size);
+        let runs = match self.font_runs(text, font, size) {
+            Some(runs) => runs,
+            None => return None,
+        };
+
+        let (mut w, mut h) = (0.0, 0.0f64);
+        for (path, run) in runs {
+            let font = match self.cached_fonts.get(&(path, size)) {
+                Some(&(ref font, _)) => font,
+                None => return None,
+            };
+            match font.size_of(&run) {
+                Ok((run_w, run_h)) => {
+                    w += run_w as f64;
+                    h = h.max(run_h as f64);
+                },
+                Err(_) => return None,
+            }
+        }
+        Some((w, h))
+    }
+
+    /// Break 'text' into lines which ttf_str_sprite renders no wider than
+    /// 'max_width'
+    pub fn wrap_text(&mut self, text: &str, font: &str, size: i32, max_width: f64) -> Vec<String> {
+        text::wrap(text, max_width, |line| self.text_size(line, font, size).map_or(0.0, |(w, _)| w))
+    }
+
+    /// Shorten 'text' so that ttf_str_sprite renders it no wider than
+    /// 'max_width'
+    pub fn fit_text(&mut self, text: &str, font: &str, size: i32, max_width: f64) -> String {
+        text::ellipsize(text, max_width, |line| self.text_size(line, font, size).map_or(0.0, |(w, _)| w))
+    }
+
+    // Split 'text' into runs of characters drawn with the same font, given
+    // by its path: 'font' if it provides them, otherwise the first of its
+    // fallbacks which does. Return None if 'font' cannot be loaded. The
+    // size is already scaled.
+    fn font_runs(&mut self, text: &str, font: &str, size: i32) -> Option<Vec<(String, String)>> {
+        self.font_uses += 1;
+
+        let primary = self.assets.path(font).to_string();
+        if !self.load_font(&primary, size) {
+            return None;
+        }
+        let mut chain = vec![primary];
+        for fallback in self.assets.fallbacks(font) {
+            if self.load_font(&fallback, size) {
+                chain.push(fallback);
+            }
+        }
+
+        let mut runs: Vec<(String, String)> = vec![];
+        for ch in text.chars() {
+            let path = chain.iter()
+                .find(|path| self.cached_fonts.get(&((*path).clone(), size))
+                      .map_or(false, |&(ref font, _)| font.find_glyph(ch).is_some()))
+                .unwrap_or(&chain[0]);
+
+            if runs.last().map_or(false, |&(ref last, _)| last == path) {
+                runs.last_mut().unwrap().1.push(ch);
+            }
+            else {
+                runs.push((path.clone(), ch.to_string()));
+            }
+        }
+
+        Some(runs)
+    }
+
+    // Render 'text' as ttf_str_sprite does, to a surface
+    fn render_text(&mut self, text: &str, font: &str, size: i32, color: Color) -> Option<Surface<'static>> {
+        let runs = match self.font_runs(text, font, size) {
+            Some(runs) => runs,
+            None => return None,
+        };
+
+        let mut parts = vec![];
+        for (path, run) in runs {
+            let font = match self.cached_fonts.get(&(path, size)) {
+                Some(&(ref font, _)) => font,
+                None => return None,
+            };
+            match font.render(&run, ::sdl2_ttf::blended(color)) {
+                Ok(part) => parts.push(part),
+                Err(_) => return None,
+            }
+        }
+        if parts.len() == 1 {
+            return parts.pop();
+        }
+
+        // Lay the runs out side by side
+        let width: u32 = parts.iter().map(|part| part.width()).sum();
+        let height = parts.iter().map(|part| part.height()).max().unwrap_or(0);
+        let mut surface = match Surface::new(width, height, PixelFormatEnum::ARGB8888) {
+            Ok(surface) => surface,
+            Err(_) => return None,
+        };
+
+        let mut x = 0;
+        for mut part in parts {
+            // Copy the glyphs with their transparency instead of blending
+            // them onto the blank surface
+            let dest = SdlRect::new(x as i32, 0, part.width(), part.height()).unwrap();
+            if part.set_blend_mode(BlendMode::None).is_err()
+                    || part.blit(None, &mut surface, dest).is_err() {
+                return None;
+            }
+            x += part.width();
+        }
+        Some(surface)
+    }
+
+    /// Load the font 'font' at the given size, enlarged by the UI scale,
+    /// into the cache, unless it is there already. Return whether the font
+    /// is available.
+    pub fn preload_font(&mut self, font: &str, size: i32) -> bool {
+        let size = self.scaled_font_size(size);
+        self.font_uses += 1;
+        self.load_font(font, size)
+    }
+
+    // Load the font 'font' at exactly the given size, as preload_font does,
+    // and count it as used now. Fonts used now are never unloaded to make
+    // room, so that the chain of fallbacks being loaded stays whole.
+    fn load_font(&mut self, font: &str, size: i32) -> bool {
+        let font_path = self.assets.path(font).to_string();
+        let font_path = &font_path[..];
+        let key = (font_path.to_string(), size);
+        if let Some(entry) = self.cached_fonts.get_mut(&key) {
+            entry.1 = self.font_uses;
+            return true;
         }
-        // Otherwise try to load the requested font
-        ::sdl2_ttf::Font::from_file(Path::new(font_path), size).ok()
+        if self.missing_fonts.contains(font_path) {
+            return false;
+        }
+
+        debug!("Loading the font {} at size {}", font_path, size);
+        ::sdl2_ttf::Font::from_file(Path::new(font_path), size)
+            .map_err(|err| {
+                warn!("Could not load the font {}: {}", font_path, err);
+                self.missing_fonts.insert(font_path.to_string());
+            })
+            .ok()
             // We must wrap the next steps in a closure because Borrow Checker
-            .and_then(|font| {
+            .map(|font| {
+                // Make room for the new font by dropping the one which was
+                // used the longest time ago, unless every font is in use
+                if self.cached_fonts.len() >= MAX_CACHED_FONTS {
+                    let now = self.font_uses;
+                    let oldest = self.cached_fonts.iter()
+                        .filter(|&(_, &(_, last_use))| last_use < now)
+                        .min_by_key(|&(_, &(_, last_use))| last_use)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        debug!("Unloading the font {} at size {}", oldest.0, oldest.1);
+                        self.cached_fonts.remove(&oldest);
+                    }
+                }
+
                 // If this works we cached the font we loaded
-                self.cached_fonts.insert((font_path, size), font);
-                // Then we call this method recursively
-                self.ttf_str_sprite(text, font_path, size, color)
+                self.cached_fonts.insert(key, (font, self.font_uses));
             })
+            .is_some()
+    }
+
+    /// Unload every cached font and label. They are loaded again when next
+    /// needed, so this only trades time for memory.
+    pub fn clear_caches(&mut self) {
+        debug!("Clearing {} cached fonts", self.cached_fonts.len());
+        self.cached_fonts.clear();
+        self.missing_fonts.clear();
+        self.profiler_labels.clear();
+        self.memory_label = None;
     }
 }
 
+// Find how much larger than intended things should be drawn on the display
+// of the window: the ratio of its pixels to its size on high-DPI displays,
+// which SDL reports through a renderer output larger than the window.
+fn detect_display_scale(renderer: &Renderer) -> f64 {
+    if let (Some(window), Ok((out_w, _))) = (renderer.window(), renderer.output_size()) {
+        let (win_w, _) = window.size();
+        if win_w > 0 && out_w > win_w {
+            return (out_w as f64 / win_w as f64).min(MAX_UI_SCALE);
+        }
+    }
+    1.0
+}
+
 //impl<'window> Drop for Phi<'window> {
 //    fn drop(&mut self) {
 //        ::sdl2_image::quit();
@@ -91,24 +758,141 @@ impl<'window> Phi<'window> {
 pub enum ViewAction {
     None,
     Quit,
+
+    /// Replace every view, including those under overlays, by the given one
     ChangeView(Box<View>),
+
+    /// Show the given view over the current one, which is kept as it is
+    /// until the new view is popped
+    PushView(Box<View>),
+
+    /// Close the current view and go back to the one under it
+    PopView,
 }
 
 pub trait View {
 
-    /// Called when self becomes main, rendered view.
+    /// Called when self becomes main, rendered view: when it is first
+    /// shown, and when the view shown over it is popped. The text input and
+    /// key repeat it had when it was covered are given back before.
     fn resume(&mut self, _context: &mut Phi) {
     }
 
-    /// Called when self stops being main, rendered view.
+    /// Called when self stops being main, rendered view: when another view
+    /// replaces it or is shown over it. Text input, key repeat and the
+    /// custom cursor are turned off right after, so views only undo what
+    /// else they changed.
     fn pause(&mut self, _context: &mut Phi) {
     }
 
     /// Called every frame to take care of both the logic and
-    /// the rendering of the view; expressed in seconds.
+    /// the rendering of the view; expressed in seconds. Views keep time
+    /// only through 'elapsed', so their timers stand still while they are
+    /// covered.
     fn render(&mut self, context: &mut Phi, elapsed: f64) -> ViewAction;
+
+    /// Whether the view is drawn over the one under it, rather than on
+    /// its own. The view under it is then drawn with draw_frozen first.
+    fn is_overlay(&self) -> bool {
+        false
+    }
+
+    /// Draw the view as it was last shown, without updating it, under an
+    /// overlay.
+    fn draw_frozen(&mut self, _context: &mut Phi) {
+    }
+}
+
+
+// The input modes a view had set up when another view was shown over it
+#[derive(Clone, Copy)]
+struct SavedInput {
+    text_input: bool,
+    key_repeat: Option<KeyRepeat>,
 }
 
+// The views being shown, the last one over the others. Views are paused and
+// resumed as they are covered and uncovered, with the input modes they had
+// set up saved in between.
+struct ViewStack {
+    views: Vec<(Box<View>, SavedInput)>,
+}
+
+impl ViewStack {
+    fn new(view: Box<View>, context: &mut Phi) -> ViewStack {
+        let mut stack = ViewStack { views: vec![] };
+        stack.push(view, context);
+        stack
+    }
+
+    // Render the view on top, along with the one under it if it is an
+    // overlay
+    fn render(&mut self, context: &mut Phi, elapsed: f64) -> ViewAction {
+        let top = self.views.len() - 1;
+        if top > 0 && self.views[top].0.is_overlay() {
+            self.views[top - 1].0.draw_frozen(context);
+        }
+        self.views[top].0.render(context, elapsed)
+    }
+
+    // Show 'view' over the others
+    fn push(&mut self, view: Box<View>, context: &mut Phi) {
+        if let Some(&mut (ref mut top, ref mut saved)) = self.views.last_mut() {
+            *saved = SavedInput {
+                text_input: context.is_text_input_active(),
+                key_repeat: context.events.key_repeat(),
+            };
+            top.pause(context);
+            ViewStack::reset_input(context);
+        }
+
+        let mut view = view;
+        view.resume(context);
+        self.views.push((view, SavedInput { text_input: false, key_repeat: None }));
+    }
+
+    // Close the view on top, and give the one under it back what it had.
+    // Return false if no view is left.
+    fn pop(&mut self, context: &mut Phi) -> bool {
+        if let Some((mut view, _)) = self.views.pop() {
+            view.pause(context);
+            ViewStack::reset_input(context);
+        }
+
+        match self.views.last_mut() {
+            Some(&mut (ref mut view, ref saved)) => {
+                if saved.text_input {
+                    context.start_text_input();
+                }
+                context.events.set_key_repeat(saved.key_repeat);
+                view.resume(context);
+                true
+            },
+            None => false,
+        }
+    }
+
+    // Close every view, then show 'view'
+    fn replace(&mut self, view: Box<View>, context: &mut Phi) {
+        self.clear(context);
+        self.push(view, context);
+    }
+
+    // Close every view. Those under the top one were paused already.
+    fn clear(&mut self, context: &mut Phi) {
+        if let Some((mut view, _)) = self.views.pop() {
+            view.pause(context);
+            ViewStack::reset_input(context);
+        }
+        self.views.clear();
+    }
+
+    fn reset_input(context: &mut Phi) {
+        context.stop_text_input();
+        context.events.set_key_repeat(None);
+        context.set_cursor(None);
+    }
+}
 
 /// Create a window with a name 'title', initialize the underlying
 /// libraries, and start the game with the 'View' returned by 'init()'.
@@ -116,6 +900,9 @@ pub trait View {
 pub fn spawn<F>(title: &str, init: F)
         where F: Fn(&mut Phi) -> Box<View> {
 
+    // Tell the player about crashes, from now on
+    crash::install_panic_hook(title);
+
     // Initialize SDL2
     let     sdl_context = ::sdl2::init().unwrap();
     let     _image_context = ::sdl2_image::init(::sdl2_image::INIT_PNG).unwrap();
@@ -124,21 +911,46 @@ pub fn spawn<F>(title: &str, init: F)
     let     sdl_video = sdl_context.video().unwrap();
 
     // Create the window
-    let window = sdl_video.window("ArcadeRS Shooter", 800, 600)
-            .position_centered().opengl().resizable()
+    let window = sdl_video.window(title, 800, 600)
+            .position_centered().opengl().resizable().allow_highdpi()
             .build().unwrap();
 
     let mut context = Phi::new(
-        Events::new(sdl_context.event_pump().unwrap() ),
+        title,
+        Events::new(
+            sdl_context.event_pump().unwrap(),
+            controllers::Controllers::new(
+                sdl_context.game_controller().unwrap(),
+                sdl_context.joystick().unwrap(),
+                sdl_context.haptic().unwrap()) ),
         window.renderer()
             .accelerated()
-            .build().unwrap() );
+            .build().unwrap(),
+        sdl_video.clone(),
+        sdl_context.mouse() );
+
+    // The command line takes precedence over the settings
+    let log_filter = logging::filter_from_args()
+        .unwrap_or_else(|| context.settings.log_level.clone());
+    logging::init(&log_filter);
+    info!("Starting {}", title);
+    info!("UI scale: {}", context.ui_scale());
+
+    // Controller mappings must be known before SDL reports the pads
+    if context.events.controllers.load_mappings(&controllers::MAPPINGS_PATHS).is_none() {
+        warn!("Could not load the game controller database");
+    }
+    for mapping in &context.settings.controller_mappings {
+        if !context.events.controllers.add_mapping(mapping) {
+            warn!("Invalid controller mapping in settings: {}", mapping);
+        }
+    }
 
-    let mut current_view = init(&mut context);
-    //: Box<::phi::View> =
-    //        Box::new(::views::DefaultView );
+    // SDL enables text input by default; views opt into it when needed
+    context.stop_text_input();
 
-    current_view.resume(&mut context);
+    let first_view = init(&mut context);
+    let mut views = ViewStack::new(first_view, &mut context);
 
     // Frame timing stuff
     let interval = 1_000 / 60;
@@ -155,7 +967,7 @@ pub fn spawn<F>(title: &str, init: F)
         let elapsed = dt as f64 / 1_000.0;
 
         // Wait a bit if the frame has come too fast
-        if dt < interval {
+        if context.limit_fps && dt < interval {
             sdl_timer.delay(interval - dt);
             continue;
         }
@@ -164,30 +976,145 @@ pub fn spawn<F>(title: &str, init: F)
         fps += 1;
 
         if now - last_second > 1_000 {
-            println!("FPS: {}", fps);
+            debug!("FPS: {}", fps);
+            if cfg!(debug_assertions) {
+                context.set_title_fps(fps);
+            }
             last_second = now;
             fps = 0;
         }
 
         // Logic and rendering . . .
 
-        context.events.pump(&mut context.renderer);
+        let work_start = Instant::now();
+        context.profiler.begin("events");
+        context.events.pump(&mut context.renderer, elapsed);
+        context.profiler.end("events");
+
+        if context.events.now.key_f3 == Some(true) {
+            context.show_profiler = !context.show_profiler;
+        }
+        if context.events.now.key_f1 == Some(true) {
+            context.show_inputs = !context.show_inputs;
+        }
+
+        context.profiler.begin("view");
+        let action = views.render(&mut context, elapsed);
+        context.profiler.end("view");
+
+        // Presenting the frame may wait for the display, and does not
+        // count against the budget of the frame
+        let work = work_start.elapsed();
+        let work = work.as_secs() as f64 + work.subsec_nanos() as f64 / 1e9;
+        context.quality.update(&context.settings, work, elapsed);
 
-        match current_view.render(&mut context, elapsed) {
+        // Every system had its chance to react to the events of the frame,
+        // and to ask for effects over it
+        context.bus.clear();
+        context.overlay.update(elapsed);
 
-            ViewAction::None => context.renderer.present(),
+        let is_none = match action { ViewAction::None => true, _ => false };
+        match action {
+
+            ViewAction::None => {
+                // Clips show the game as it is played, without the overlays
+                if context.settings.clip_capture {
+                    context.clips.capture(&context.renderer, elapsed);
+                }
+                if context.events.now.key_f12 == Some(true) {
+                    match context.clips.save() {
+                        Some(path) => info!("Saving the last seconds of play to {}", path),
+                        None => warn!("There is no clip to save"),
+                    }
+                }
+
+                if context.show_profiler {
+                    context.render_profiler();
+                }
+                if context.show_inputs {
+                    context.render_inputs();
+                }
+                context.render_cursor();
+
+                context.profiler.begin("present");
+                context.renderer.present();
+                context.profiler.end("present");
+                context.profiler.end_frame();
+            },
 
             ViewAction::Quit => {
-                current_view.pause(&mut context);
+                info!("Quitting");
+                views.clear(&mut context);
                 break;
             },
 
             ViewAction::ChangeView(new_view) => {
-                current_view.pause(&mut context);
-                current_view = new_view;
-                current_view.resume(&mut context);
-            }
+                debug!("Changing view");
+                views.replace(new_view, &mut context);
+
+                // Textures only the previous views used are not needed anymore
+                let freed = context.assets.purge_unused();
+                if freed > 0 {
+                    debug!("Freed {} KB of textures", freed / 1024);
+                }
+            },
+
+            ViewAction::PushView(new_view) => {
+                debug!("Pushing a view over {} others", views.views.len());
+                views.push(new_view, &mut context);
+            },
+
+            ViewAction::PopView => {
+                debug!("Popping a view");
+                if !views.pop(&mut context) {
+                    break;
+                }
+            },
+        }
+
+        // The time spent switching views, loading the new one's assets, is
+        // not counted in its first frame
+        if !is_none {
+            before = sdl_timer.ticks();
+
+            // The new view sets up its own camera, if it has one
+            context.events.set_world_offset((0.0, 0.0));
         }
 
     }
 }
+
+/// Run 'f' with a context whose window is never shown, nor drawn by the
+/// graphics card, for the tests which need one
+#[cfg(test)]
+pub fn headless<F>(f: F)
+        where F: FnOnce(&mut Phi) {
+
+    // No display is needed, nor may be there
+    ::std::env::set_var("SDL_VIDEODRIVER", "dummy");
+
+    let     sdl_context = ::sdl2::init().unwrap();
+    let     _image_context = ::sdl2_image::init(::sdl2_image::INIT_PNG).unwrap();
+    let     _ttf_context = ::sdl2_ttf::init().unwrap();
+    let     sdl_video = sdl_context.video().unwrap();
+
+    let window = sdl_video.window("test", 800, 600)
+            .hidden()
+            .build().unwrap();
+
+    let mut context = Phi::new(
+        "test",
+        Events::new(
+            sdl_context.event_pump().unwrap(),
+            controllers::Controllers::new(
+                sdl_context.game_controller().unwrap(),
+                sdl_context.joystick().unwrap(),
+                sdl_context.haptic().unwrap()) ),
+        window.renderer()
+            .software()
+            .build().unwrap(),
+        sdl_video.clone(),
+        sdl_context.mouse() );
+
+    f(&mut context);
+}
@@ -0,0 +1,279 @@
+// phi/net.rs
+
+//! Exchange the inputs of two players over UDP, in lockstep: every frame is
+//! only simulated once the inputs of both players for that frame are
+//! known, so that two deterministic simulations stay identical.
+
+use ::std::collections::HashMap;
+use ::std::io;
+use ::std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use ::std::time::Instant;
+
+/// The port on which games are hosted unless told otherwise
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// The number of past inputs repeated in every input packet, so that the
+/// loss of a packet does not stall the game
+const REDUNDANCY: u32 = 8;
+
+/// The number of frames of inputs kept around after they were simulated
+const HISTORY: u32 = 120;
+
+/// Seconds between two latency measurements, and between two attempts to
+/// reach the host while joining
+const PING_INTERVAL: f64 = 1.0;
+
+const HELLO: u8 = 0;
+const WELCOME: u8 = 1;
+const INPUT: u8 = 2;
+const PING: u8 = 3;
+const PONG: u8 = 4;
+
+/// The buttons held by a player during a frame, one bit per button
+pub type Input = u16;
+
+pub const INPUT_UP: Input = 1 << 0;
+pub const INPUT_DOWN: Input = 1 << 1;
+pub const INPUT_LEFT: Input = 1 << 2;
+pub const INPUT_RIGHT: Input = 1 << 3;
+pub const INPUT_FIRE: Input = 1 << 4;
+pub const INPUT_CONFIRM: Input = 1 << 5;
+
+/// The cannons picked by the player, from 1 to 3, held in two bits
+pub const INPUT_CANNON_SHIFT: u32 = 6;
+pub const INPUT_CANNON: Input = 3 << INPUT_CANNON_SHIFT;
+
+/// A connection between the two players of a game
+pub struct Session {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    connected: bool,
+
+    // The seed both players play with, picked by the host, and known to
+    // the other player once connected
+    seed: Option<u64>,
+
+    // The inputs of both players, by frame
+    local: HashMap<u32, Input>,
+    remote: HashMap<u32, Input>,
+
+    // The latest frame for which the local input is known
+    latest: Option<u32>,
+
+    // The reference for the timestamps of pings, when the last one was
+    // sent, and the last round trip time measured, in seconds
+    epoch: Instant,
+    last_ping: f64,
+    latency: Option<f64>,
+}
+
+impl Session {
+    /// Wait for a player to join on 'port', to play with 'seed'
+    pub fn host(port: u16, seed: u64) -> io::Result<Session> {
+        let socket = try!(UdpSocket::bind(("0.0.0.0", port)));
+        let mut session = try!(Session::new(socket, None));
+        session.seed = Some(seed);
+        Ok(session)
+    }
+
+    /// Join the game hosted at 'address', such as `192.168.0.12:7878`
+    pub fn join(address: &str) -> io::Result<Session> {
+        let peer = try!(try!(address.to_socket_addrs()).next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address to join")
+        }));
+        let socket = try!(UdpSocket::bind(("0.0.0.0", 0)));
+        Session::new(socket, Some(peer))
+    }
+
+    fn new(socket: UdpSocket, peer: Option<SocketAddr>) -> io::Result<Session> {
+        try!(socket.set_nonblocking(true));
+        Ok(Session {
+            socket: socket,
+            peer: peer,
+            connected: false,
+            seed: None,
+            local: HashMap::new(),
+            remote: HashMap::new(),
+            latest: None,
+            epoch: Instant::now(),
+            last_ping: -PING_INTERVAL,
+            latency: None,
+        })
+    }
+
+    /// Whether the other player answered
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Return the seed both players play with, once it is known
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Return the last measured round trip time, in seconds
+    pub fn latency(&self) -> Option<f64> {
+        self.latency
+    }
+
+    /// Handle the packets received since the last call, and keep the
+    /// connection alive. Call this once every frame.
+    pub fn poll(&mut self) {
+        let mut buffer = [0u8; 512];
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((len, from)) => self.receive(&buffer[..len], from),
+                // Nothing left to read
+                Err(_) => break,
+            }
+        }
+
+        let now = self.now();
+        if now - self.last_ping >= PING_INTERVAL {
+            self.last_ping = now;
+            if self.connected {
+                let mut packet = vec![PING];
+                write_u64(&mut packet, (now * 1_000_000.0) as u64);
+                self.send(&packet);
+            }
+            else if self.peer.is_some() {
+                self.send(&[HELLO]);
+            }
+        }
+
+        // Send the latest inputs again, in case the last packet was lost
+        if let Some(latest) = self.latest {
+            self.send_inputs(latest);
+        }
+    }
+
+    /// Record the input of the local player for 'frame', and send it
+    pub fn send_input(&mut self, frame: u32, input: Input) {
+        self.local.insert(frame, input);
+        self.latest = Some(frame);
+        self.send_inputs(frame);
+
+        // Forget the frames both players simulated long ago
+        if frame > HISTORY {
+            let oldest = frame - HISTORY;
+            self.local.retain(|&f, _| f >= oldest);
+            self.remote.retain(|&f, _| f >= oldest);
+        }
+    }
+
+    /// Return the input of the local player for 'frame', if it was sent
+    pub fn local_input(&self, frame: u32) -> Option<Input> {
+        self.local.get(&frame).cloned()
+    }
+
+    /// Return the input of the other player for 'frame', if it arrived
+    pub fn remote_input(&self, frame: u32) -> Option<Input> {
+        self.remote.get(&frame).cloned()
+    }
+
+    fn now(&self) -> f64 {
+        let elapsed = self.epoch.elapsed();
+        elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9
+    }
+
+    fn send(&self, packet: &[u8]) {
+        if let Some(peer) = self.peer {
+            // Lost packets are made up for by the redundancy of inputs
+            let _ = self.socket.send_to(packet, peer);
+        }
+    }
+
+    // Send the local inputs up to 'frame', along with the few before it
+    fn send_inputs(&self, frame: u32) {
+        // Walk back from 'frame' to the first gap, if any, so that the
+        // inputs sent are consecutive
+        let first = frame.saturating_sub(REDUNDANCY - 1);
+        let mut inputs: Vec<Input> = (first..frame + 1).rev()
+            .map(|f| self.local.get(&f).cloned())
+            .take_while(|input| input.is_some())
+            .map(|input| input.unwrap())
+            .collect();
+        inputs.reverse();
+
+        let mut packet = vec![INPUT];
+        write_u32(&mut packet, frame);
+        packet.push(inputs.len() as u8);
+        for input in inputs {
+            write_u16(&mut packet, input);
+        }
+        self.send(&packet);
+    }
+
+    fn receive(&mut self, packet: &[u8], from: SocketAddr) {
+        // The host accepts the first player who says hello
+        if self.peer.is_none() && packet.first() == Some(&HELLO) {
+            self.peer = Some(from);
+        }
+        if self.peer != Some(from) {
+            return;
+        }
+
+        match packet.first() {
+            Some(&HELLO) => {
+                self.connected = true;
+                let mut welcome = vec![WELCOME];
+                write_u64(&mut welcome, self.seed.unwrap_or(0));
+                self.send(&welcome);
+            },
+            Some(&WELCOME) if packet.len() == 9 => {
+                self.seed = Some(read_u64(&packet[1..]));
+                self.connected = true;
+            },
+            Some(&INPUT) if packet.len() >= 6 => {
+                let frame = read_u32(&packet[1..]);
+                let count = packet[5] as u32;
+                if packet.len() < 6 + 2 * count as usize || count == 0 || count - 1 > frame {
+                    return;
+                }
+
+                // The inputs end with the one for 'frame'
+                for i in 0..count {
+                    let input = read_u16(&packet[6 + 2 * i as usize..]);
+                    self.remote.insert(frame - (count - 1 - i), input);
+                }
+            },
+            Some(&PING) if packet.len() == 9 => {
+                let mut pong = packet.to_vec();
+                pong[0] = PONG;
+                self.send(&pong);
+            },
+            Some(&PONG) if packet.len() == 9 => {
+                let sent = read_u64(&packet[1..]) as f64 / 1_000_000.0;
+                self.latency = Some(self.now() - sent);
+            },
+            _ => {},
+        }
+    }
+}
+
+fn write_u16(packet: &mut Vec<u8>, value: u16) {
+    packet.push((value >> 8) as u8);
+    packet.push(value as u8);
+}
+
+fn write_u32(packet: &mut Vec<u8>, value: u32) {
+    write_u16(packet, (value >> 16) as u16);
+    write_u16(packet, value as u16);
+}
+
+fn write_u64(packet: &mut Vec<u8>, value: u64) {
+    write_u32(packet, (value >> 32) as u32);
+    write_u32(packet, value as u32);
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    (bytes[0] as u16) << 8 | bytes[1] as u16
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    (read_u16(bytes) as u32) << 16 | read_u16(&bytes[2..]) as u32
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    (read_u32(bytes) as u64) << 32 | read_u32(&bytes[4..]) as u64
+}
@@ -0,0 +1,114 @@
+// phi/overlay.rs
+
+use ::sdl2::pixels::Color;
+use ::sdl2::render::{BlendMode, Renderer};
+
+use ::phi::data::Rectangle;
+
+/// The color the scene is dimmed toward
+const DIM_COLOR: (u8, u8, u8) = (10, 10, 20);
+
+/// How fast the dimming follows what is asked, in units per second; it
+/// takes a quarter of a second to dim fully
+const DIM_SPEED: f64 = 4.0;
+
+/// The most opaque a flash gets while flashes are reduced
+const REDUCED_FLASH_ALPHA: u8 = 40;
+
+/// Effects covering the whole scene, over the entities but under the HUD,
+/// which any code may ask for through `Phi::overlay`:
+///
+/// * dimming, asked for every frame it should last, such as while the game
+///   is paused or the boss enters; the strongest dimming asked for during a
+///   frame wins, and the scene brightens back once nothing asks anymore;
+/// * flashes, which fade out on their own, such as when the ship is hit.
+pub struct Overlay {
+    // How dim the scene is, and how dim it was asked to be this frame,
+    // from 0 to 1
+    dim: f64,
+    dim_target: f64,
+
+    // The color of the flash, how long it lasts, and the seconds left
+    flash: Color,
+    flash_duration: f64,
+    flash_left: f64,
+}
+
+impl Overlay {
+    pub fn new() -> Overlay {
+        Overlay {
+            dim: 0.0,
+            dim_target: 0.0,
+            flash: Color::RGBA(255, 255, 255, 0),
+            flash_duration: 0.0,
+            flash_left: 0.0,
+        }
+    }
+
+    /// Dim the scene by 'amount', from 0 to 1, during this frame
+    pub fn dim(&mut self, amount: f64) {
+        self.dim_target = self.dim_target.max(amount.max(0.0).min(1.0));
+    }
+
+    /// Flash the scene with 'color', fading out over 'duration' seconds.
+    /// The alpha of the color is how opaque the flash starts. A flash
+    /// replaces the one under way, unless that one is still brighter.
+    pub fn flash(&mut self, color: Color, duration: f64) {
+        let (_, _, _, alpha) = color.rgba();
+        if alpha as f64 >= self.flash_alpha() {
+            self.flash = color;
+            self.flash_duration = duration;
+            self.flash_left = duration;
+        }
+    }
+
+    /// Return how opaque the flash currently is, from 0 to 255
+    fn flash_alpha(&self) -> f64 {
+        if self.flash_left <= 0.0 || self.flash_duration <= 0.0 {
+            return 0.0;
+        }
+        let (_, _, _, alpha) = self.flash.rgba();
+        alpha as f64 * self.flash_left / self.flash_duration
+    }
+
+    /// Move the effects on by 'elapsed' seconds; the dimming asked for
+    /// during the frame is then forgotten
+    pub fn update(&mut self, elapsed: f64) {
+        let step = DIM_SPEED * elapsed;
+        if self.dim < self.dim_target {
+            self.dim = (self.dim + step).min(self.dim_target);
+        }
+        else {
+            self.dim = (self.dim - step).max(self.dim_target);
+        }
+        self.dim_target = 0.0;
+
+        self.flash_left = (self.flash_left - elapsed).max(0.0);
+    }
+
+    /// Render the effects over 'area'. Flashes are faint if 'reduce_flashes'.
+    pub fn render(&self, renderer: &mut Renderer, area: Rectangle, reduce_flashes: bool) {
+        let flash_alpha = self.flash_alpha();
+        if self.dim <= 0.0 && flash_alpha <= 0.0 {
+            return;
+        }
+
+        renderer.set_blend_mode(BlendMode::Blend);
+
+        if self.dim > 0.0 {
+            let (r, g, b) = DIM_COLOR;
+            renderer.set_draw_color(Color::RGBA(r, g, b, (self.dim * 255.0) as u8));
+            renderer.fill_rect(area.to_sdl().unwrap());
+        }
+
+        if flash_alpha > 0.0 {
+            let alpha = if reduce_flashes { (flash_alpha as u8).min(REDUCED_FLASH_ALPHA) }
+                        else { flash_alpha as u8 };
+            let (r, g, b) = self.flash.rgb();
+            renderer.set_draw_color(Color::RGBA(r, g, b, alpha));
+            renderer.fill_rect(area.to_sdl().unwrap());
+        }
+
+        renderer.set_blend_mode(BlendMode::None);
+    }
+}
@@ -0,0 +1,150 @@
+// phi/palette.rs
+
+use ::sdl2::pixels::Color;
+
+/// How the colors of the game are shown. The colorblind palettes move every
+/// hue onto a set which stays distinct without telling red from green; the
+/// high-contrast one makes colors vivid and the backgrounds dim.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Palette {
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    HighContrast,
+}
+
+impl Palette {
+    pub fn all() -> Vec<Palette> {
+        vec![Palette::Standard, Palette::Deuteranopia, Palette::Protanopia, Palette::HighContrast]
+    }
+
+    /// The name of the palette in the settings
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Standard => "standard",
+            Palette::Deuteranopia => "deuteranopia",
+            Palette::Protanopia => "protanopia",
+            Palette::HighContrast => "high_contrast",
+        }
+    }
+
+    /// Find the palette called 'name' in the settings, or the standard one
+    pub fn from_name(name: &str) -> Palette {
+        Palette::all().into_iter()
+            .find(|palette| palette.name() == name)
+            .unwrap_or(Palette::Standard)
+    }
+
+    /// The key of the palette's name in the string tables
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Palette::Standard => "palette.standard",
+            Palette::Deuteranopia => "palette.deuteranopia",
+            Palette::Protanopia => "palette.protanopia",
+            Palette::HighContrast => "palette.high_contrast",
+        }
+    }
+
+    /// Return 'color' as the palette shows it. Grays are kept as they are.
+    pub fn apply(self, color: Color) -> Color {
+        let (r, g, b, a) = color.rgba();
+        let hue = match hue(r, g, b) {
+            Some(hue) => hue,
+            None => return color,
+        };
+        let value = r.max(g).max(b) as f64 / 255.0;
+
+        let target = match self {
+            Palette::Standard => return color,
+            Palette::HighContrast => return vivid(hue, value.max(0.8), a),
+            Palette::Deuteranopia => match hue_band(hue) {
+                HueBand::Red => (213, 94, 0),       // vermillion
+                HueBand::Yellow => (240, 228, 66),
+                HueBand::Green => (0, 114, 178),    // blue
+                HueBand::Cyan => (86, 180, 233),    // sky blue
+                HueBand::Blue => (120, 120, 255),
+                HueBand::Purple => (204, 121, 167), // reddish purple
+            },
+            // Red looks dark to protanopes, so it becomes a bright orange
+            Palette::Protanopia => match hue_band(hue) {
+                HueBand::Red => (245, 140, 20),
+                HueBand::Yellow => (240, 228, 66),
+                HueBand::Green => (30, 120, 220),
+                HueBand::Cyan => (86, 180, 233),
+                HueBand::Blue => (130, 130, 255),
+                HueBand::Purple => (220, 150, 200),
+            },
+        };
+
+        // Keep the brightness of the original, so that dimmed colors stay dim
+        let (tr, tg, tb) = target;
+        Color::RGBA((tr as f64 * value) as u8, (tg as f64 * value) as u8,
+                    (tb as f64 * value) as u8, a)
+    }
+
+    /// Whether labels of the HUD are drawn over a dark box
+    pub fn backs_hud(self) -> bool {
+        self == Palette::HighContrast
+    }
+
+    /// How much the backgrounds are dimmed, as a color modulation
+    pub fn background_dimming(self) -> Color {
+        match self {
+            Palette::HighContrast => Color::RGB(90, 90, 90),
+            _ => Color::RGB(255, 255, 255),
+        }
+    }
+}
+
+enum HueBand {
+    Red,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+}
+
+fn hue_band(hue: f64) -> HueBand {
+    match hue {
+        h if h < 20.0 => HueBand::Red,
+        h if h < 75.0 => HueBand::Yellow,
+        h if h < 165.0 => HueBand::Green,
+        h if h < 210.0 => HueBand::Cyan,
+        h if h < 270.0 => HueBand::Blue,
+        h if h < 330.0 => HueBand::Purple,
+        _ => HueBand::Red,
+    }
+}
+
+// Return the hue of a color in degrees, or None if it is too close to gray
+// to have one
+fn hue(r: u8, g: u8, b: u8) -> Option<f64> {
+    let (r, g, b) = (r as f64, g as f64, b as f64);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    if delta < 50.0 {
+        return None;
+    }
+
+    let hue = if max == r { 60.0 * (((g - b) / delta) % 6.0) }
+              else if max == g { 60.0 * ((b - r) / delta + 2.0) }
+              else { 60.0 * ((r - g) / delta + 4.0) };
+    Some(if hue < 0.0 { hue + 360.0 } else { hue })
+}
+
+// Build the fully saturated color of the given hue and value
+fn vivid(hue: f64, value: f64, alpha: u8) -> Color {
+    let x = 1.0 - ((hue / 60.0) % 2.0 - 1.0).abs();
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    Color::RGBA((r * value * 255.0) as u8, (g * value * 255.0) as u8,
+                (b * value * 255.0) as u8, alpha)
+}
@@ -0,0 +1,75 @@
+// phi/parallel.rs
+
+//! Spreads the updates of many independent entities over every core, when
+//! the game is built with the "parallel" feature. Without it, or for too
+//! few entities, the updates run in a plain loop on the current thread.
+
+/// The fewest items worth handing over to other threads. Below it, waking
+/// up the thread pool costs more than it saves.
+///
+/// This value is a placeholder which has not been measured yet. It should
+/// be the crossover where the parallel updates start to win in the
+/// benchmark mode, which depends on the machine. To find it, compare the
+/// frame times reported by `--benchmark` with and without
+/// `--features parallel`, changing the number of bullets and asteroids of
+/// views/benchmark.rs. What matters is the size of the largest array of
+/// the scene, usually the particles left by the bullets.
+pub const MIN_PARALLEL_ITEMS: usize = 1024;
+
+/// Whether the updates may run in parallel in this build
+pub fn is_enabled() -> bool {
+    cfg!(feature = "parallel")
+}
+
+/// Call 'f' on every item of 'items'
+#[cfg(feature = "parallel")]
+pub fn for_each_mut<T, F>(items: &mut [T], f: F)
+    where T: Send, F: Fn(&mut T) + Sync
+{
+    use ::rayon::prelude::*;
+
+    if items.len() >= MIN_PARALLEL_ITEMS {
+        items.par_iter_mut().for_each(|item| f(item));
+    }
+    else {
+        for item in items {
+            f(item);
+        }
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn for_each_mut<T, F>(items: &mut [T], f: F)
+    where F: Fn(&mut T)
+{
+    for item in items {
+        f(item);
+    }
+}
+
+/// Call 'f' on every item of 'items' along with the item of 'with' at the
+/// same index
+#[cfg(feature = "parallel")]
+pub fn zip_for_each_mut<T, U, F>(items: &mut [T], with: &[U], f: F)
+    where T: Send, U: Sync, F: Fn(&mut T, &U) + Sync
+{
+    use ::rayon::prelude::*;
+
+    if items.len() >= MIN_PARALLEL_ITEMS {
+        items.par_iter_mut().zip(with.par_iter()).for_each(|(item, other)| f(item, other));
+    }
+    else {
+        for (item, other) in items.iter_mut().zip(with) {
+            f(item, other);
+        }
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+pub fn zip_for_each_mut<T, U, F>(items: &mut [T], with: &[U], f: F)
+    where F: Fn(&mut T, &U)
+{
+    for (item, other) in items.iter_mut().zip(with) {
+        f(item, other);
+    }
+}
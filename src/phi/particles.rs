@@ -0,0 +1,172 @@
+// phi/particles.rs
+
+use ::sdl2::pixels::Color;
+use ::sdl2::rect::Rect as SdlRect;
+use ::sdl2::render::{BlendMode, Renderer};
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::Blending;
+use ::phi::parallel;
+use ::phi::random::Distributions;
+
+/// The steps in which particles fade out, so that particles of the same
+/// color and about the same age can be drawn together
+const ALPHA_LEVELS: u32 = 16;
+
+/// A square of color moving in a straight line and fading away
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    pos: (f64, f64),
+    vel: (f64, f64),  // pixels/second
+
+    // Seconds left before the particle disappears, and its total lifetime
+    life: f64,
+    max_life: f64,
+
+    size: f64,
+    color: (u8, u8, u8),
+    blending: Blending,
+}
+
+/// Every particle alive in a scene, updated and rendered together
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+
+    // The share of the particles which emitters release, from 0 to 1
+    share: f64,
+}
+
+impl ParticleSystem {
+    pub fn new() -> ParticleSystem {
+        ParticleSystem {
+            particles: vec![],
+            share: 1.0,
+        }
+    }
+
+    /// Let emitters release only a share of their particles, from 0 to 1,
+    /// to lighten the scene
+    pub fn set_share(&mut self, share: f64) {
+        self.share = share;
+    }
+
+    /// Return the number of particles alive
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Move the particles and drop the ones whose time is up
+    pub fn update(&mut self, dt: f64) {
+        parallel::for_each_mut(&mut self.particles, |particle| {
+            particle.pos.0 += particle.vel.0 * dt;
+            particle.pos.1 += particle.vel.1 * dt;
+            particle.life -= dt;
+        });
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    /// Render the particles, which become more transparent as they age.
+    /// The particles are rendered one blending after the other, the
+    /// additive ones, which glow, last.
+    pub fn render(&self, renderer: &mut Renderer) {
+        for &blending in &[Blending::Normal, Blending::Multiply, Blending::Additive] {
+            renderer.set_blend_mode(blending.to_sdl());
+
+            // Particles of the same color and transparency are filled in one
+            // call; the sort is stable, so they keep their order otherwise
+            let mut squares: Vec<(Color, SdlRect)> = self.particles.iter()
+                .filter(|particle| particle.blending == blending)
+                .map(|particle| {
+                    let (r, g, b) = particle.color;
+                    let level = (ALPHA_LEVELS as f64 * particle.life / particle.max_life).ceil() as u32;
+                    let alpha = (255 * level.min(ALPHA_LEVELS) / ALPHA_LEVELS) as u8;
+                    (Color::RGBA(r, g, b, alpha), Rectangle {
+                        x: particle.pos.0 - particle.size / 2.0,
+                        y: particle.pos.1 - particle.size / 2.0,
+                        w: particle.size,
+                        h: particle.size,
+                    }.to_sdl().unwrap())
+                })
+                .collect();
+            squares.sort_by_key(|&(color, _)| color_key(color));
+
+            let mut start = 0;
+            while start < squares.len() {
+                let color = squares[start].0;
+                let end = squares[start..].iter()
+                    .position(|&(other, _)| other != color)
+                    .map_or(squares.len(), |count| start + count);
+
+                let rects: Vec<SdlRect> = squares[start..end].iter().map(|&(_, rect)| rect).collect();
+                renderer.set_draw_color(color);
+                renderer.fill_rects(&rects);
+                start = end;
+            }
+        }
+
+        renderer.set_blend_mode(BlendMode::None);
+    }
+}
+
+// Order colors by their components, to group the particles sharing one
+fn color_key(color: Color) -> u32 {
+    let (r, g, b, a) = color.rgba();
+    (r as u32) << 24 | (g as u32) << 16 | (b as u32) << 8 | a as u32
+}
+
+/// Describes the particles released by something, such as an engine or
+/// a bullet. An emitter holds no state of its own, so the same emitter
+/// may be attached to any number of entities, at any position.
+#[derive(Clone, Copy, Debug)]
+pub struct Emitter {
+    /// Particles released every second at full intensity
+    pub rate: f64,
+
+    /// Position of the emission point relative to the entity's position
+    pub offset: (f64, f64),
+
+    /// Average velocity of the particles, in pixels/second, and the
+    /// maximum random deviation from it along each axis
+    pub velocity: (f64, f64),
+    pub spread: f64,
+
+    /// Seconds during which a particle is visible
+    pub life: f64,
+
+    /// Side of the particles, in pixels
+    pub size: f64,
+
+    pub color: (u8, u8, u8),
+
+    /// How the particles combine with what is under them
+    pub blending: Blending,
+}
+
+impl Emitter {
+    /// Release the particles emitted during 'dt' seconds by an entity at
+    /// 'pos' into 'system'. The number of particles and their speed are
+    /// scaled by 'intensity', usually between 0 and 1.
+    pub fn emit(&self, system: &mut ParticleSystem, pos: (f64, f64), intensity: f64, dt: f64) {
+        // Fractions of particles are released at random, so that low rates
+        // still produce particles at high frame rates
+        let expected = self.rate * intensity * system.share * dt;
+        let mut rng = ::rand::thread_rng();
+        let mut count = expected.floor() as usize;
+        if rng.chance(expected.fract()) {
+            count += 1;
+        }
+
+        for _ in 0..count {
+            system.particles.push(Particle {
+                pos: (pos.0 + self.offset.0, pos.1 + self.offset.1),
+                vel: (rng.around(self.velocity.0 * intensity, self.spread),
+                      rng.around(self.velocity.1 * intensity, self.spread)),
+                life: self.life,
+                max_life: self.life,
+                size: self.size,
+                color: self.color,
+                blending: self.blending,
+            });
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// phi/path.rs
+
+/// A trajectory, followed from its start at progress 0 to its end at
+/// progress 1. Coordinates are in whatever space the caller chooses, for
+/// instance fractions of the screen size.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Path {
+    /// Straight lines between consecutive points, travelled at a
+    /// constant speed
+    Waypoints(Vec<(f64, f64)>),
+
+    /// Cubic Bezier curve from the first point to the last one
+    Bezier((f64, f64), (f64, f64), (f64, f64), (f64, f64)),
+
+    /// Straight line from 'from' to 'to', oscillating 'waves' times around
+    /// it with the given amplitude, perpendicularly to it
+    Sine { from: (f64, f64), to: (f64, f64), amplitude: f64, waves: f64 },
+}
+
+impl Path {
+    /// Return the point reached at 'progress', clamped to [0, 1].
+    pub fn point(&self, progress: f64) -> (f64, f64) {
+        let t = progress.max(0.0).min(1.0);
+
+        match *self {
+            Path::Waypoints(ref points) => waypoint(points, t),
+
+            Path::Bezier(p0, p1, p2, p3) => {
+                let u = 1.0 - t;
+                let a = u * u * u;
+                let b = 3.0 * u * u * t;
+                let c = 3.0 * u * t * t;
+                let d = t * t * t;
+                (a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                 a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1)
+            },
+
+            Path::Sine { from, to, amplitude, waves } => {
+                let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+                let len = (dx * dx + dy * dy).sqrt();
+                let offset = amplitude * (t * waves * 2.0 * ::std::f64::consts::PI).sin();
+                let (nx, ny) = if len > 0.0 { (-dy / len, dx / len) } else { (0.0, 0.0) };
+                (from.0 + dx * t + nx * offset,
+                 from.1 + dy * t + ny * offset)
+            },
+        }
+    }
+
+    /// Parse a path written as its kind followed by its parameters, with
+    /// points written as `x,y`:
+    ///
+    ///     waypoints x,y x,y ...
+    ///     bezier x,y x,y x,y x,y
+    ///     sine x,y x,y amplitude waves
+    pub fn parse(text: &str) -> Option<Path> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.is_empty() {
+            return None;
+        }
+
+        match (words[0], words.len()) {
+            ("waypoints", n) if n >= 3 => {
+                let points: Vec<_> = words[1..].iter().filter_map(|w| parse_point(w)).collect();
+                if points.len() == n - 1 { Some(Path::Waypoints(points)) } else { None }
+            },
+
+            ("bezier", 5) => {
+                match (parse_point(words[1]), parse_point(words[2]),
+                       parse_point(words[3]), parse_point(words[4])) {
+                    (Some(p0), Some(p1), Some(p2), Some(p3)) => Some(Path::Bezier(p0, p1, p2, p3)),
+                    _ => None,
+                }
+            },
+
+            ("sine", 5) => {
+                match (parse_point(words[1]), parse_point(words[2]),
+                       words[3].parse(), words[4].parse()) {
+                    (Some(from), Some(to), Ok(amplitude), Ok(waves)) => Some(Path::Sine {
+                        from: from,
+                        to: to,
+                        amplitude: amplitude,
+                        waves: waves,
+                    }),
+                    _ => None,
+                }
+            },
+
+            _ => None,
+        }
+    }
+}
+
+/// Parse a point written as `x,y`.
+pub fn parse_point(text: &str) -> Option<(f64, f64)> {
+    let mut coords = text.split(',').map(|c| c.trim().parse::<f64>());
+    match (coords.next(), coords.next(), coords.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Some((x, y)),
+        _ => None,
+    }
+}
+
+// Return the point at 't' along the polyline going through 'points'
+fn waypoint(points: &[(f64, f64)], t: f64) -> (f64, f64) {
+    if points.len() < 2 {
+        return points.first().cloned().unwrap_or((0.0, 0.0));
+    }
+
+    let lengths: Vec<f64> = points.windows(2).map(|pair| {
+        let (dx, dy) = (pair[1].0 - pair[0].0, pair[1].1 - pair[0].1);
+        (dx * dx + dy * dy).sqrt()
+    }).collect();
+    let total: f64 = lengths.iter().fold(0.0, |sum, len| sum + len);
+
+    // Find the segment containing the point at distance t * total
+    let mut remaining = t * total;
+    for (i, &len) in lengths.iter().enumerate() {
+        if remaining <= len && len > 0.0 {
+            let k = remaining / len;
+            let (a, b) = (points[i], points[i + 1]);
+            return (a.0 + (b.0 - a.0) * k, a.1 + (b.1 - a.1) * k);
+        }
+        remaining -= len;
+    }
+
+    points[points.len() - 1]
+}
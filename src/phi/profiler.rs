@@ -0,0 +1,81 @@
+// phi/profiler.rs
+
+use ::std::time::Instant;
+
+/// How much every new frame weighs in the average duration of a scope
+const SMOOTHING: f64 = 0.1;
+
+/// Measures how long named parts of every frame take. Scopes may be nested,
+/// and a scope entered several times during a frame adds up.
+pub struct Profiler {
+    epoch: Instant,
+
+    // The scopes entered but not left yet, with the time they started at
+    open: Vec<(&'static str, f64)>,
+
+    // The time spent in every scope during the current frame, and its
+    // average over the previous frames, in seconds, in order of first use
+    current: Vec<(&'static str, f64)>,
+    averages: Vec<(&'static str, f64)>,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler {
+            epoch: Instant::now(),
+            open: vec![],
+            current: vec![],
+            averages: vec![],
+        }
+    }
+
+    fn now(&self) -> f64 {
+        let elapsed = self.epoch.elapsed();
+        elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9
+    }
+
+    /// Start measuring the scope 'name'
+    pub fn begin(&mut self, name: &'static str) {
+        let now = self.now();
+        self.open.push((name, now));
+    }
+
+    /// Stop measuring the scope 'name', which must be the last one begun
+    pub fn end(&mut self, name: &'static str) {
+        let now = self.now();
+        match self.open.pop() {
+            Some((open, start)) if open == name => add(&mut self.current, name, now - start),
+            other => panic!("Profiler scope {} ended while {:?} was open", name, other),
+        }
+    }
+
+    /// Fold the measures of the frame which ends into the averages
+    pub fn end_frame(&mut self) {
+        // Scopes skipped during this frame count as instantaneous
+        for &mut (name, ref mut average) in &mut self.averages {
+            let duration = self.current.iter()
+                .find(|&&(other, _)| other == name)
+                .map_or(0.0, |&(_, duration)| duration);
+            *average += (duration - *average) * SMOOTHING;
+        }
+        for &(name, duration) in &self.current {
+            if !self.averages.iter().any(|&(other, _)| other == name) {
+                self.averages.push((name, duration));
+            }
+        }
+        self.current.clear();
+    }
+
+    /// Return the average time spent in every scope, in seconds
+    pub fn averages(&self) -> &[(&'static str, f64)] {
+        &self.averages
+    }
+}
+
+// Add 'duration' to the time spent in the scope 'name'
+fn add(scopes: &mut Vec<(&'static str, f64)>, name: &'static str, duration: f64) {
+    match scopes.iter_mut().find(|&&mut (other, _)| other == name) {
+        Some(&mut (_, ref mut total)) => *total += duration,
+        None => scopes.push((name, duration)),
+    }
+}
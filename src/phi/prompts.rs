@@ -0,0 +1,272 @@
+// phi/prompts.rs
+
+use ::sdl2::controller::Button;
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::controllers::{PadBindings, PadInput};
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::input_display::PAD_INPUTS;
+use ::phi::shapes;
+
+/// The optional spritesheet of the glyphs of the controller inputs, one
+/// frame per input in the order of PAD_INPUTS. Without it, prompts write the
+/// names of the buttons on keycaps, like keys.
+const PAD_GLYPHS_ASSET: &'static str = "pad_glyphs";
+
+/// The player whose controller bindings prompts show
+const PLAYER_ID: usize = 0;
+
+/// The colors of the keycaps, and the size of their text relative to the
+/// text around them
+const KEYCAP_COLOR: Color = Color::RGB(70, 70, 90);
+const KEYCAP_TEXT_COLOR: Color = Color::RGB(255, 255, 255);
+const KEYCAP_TEXT_SCALE: f64 = 0.7;
+
+/// What the player plays with, which prompts show the keys or the buttons
+/// of. It changes to whichever was used last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Device {
+    Keyboard,
+    Pad,
+}
+
+/// What prompts may ask the player to press, written `[confirm]`, `[back]`
+/// and so on in their text
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PromptAction {
+    Confirm,
+    Back,
+    Pause,
+    Fire,
+    NextWeapon,
+}
+
+impl PromptAction {
+    pub fn from_name(name: &str) -> Option<PromptAction> {
+        match name {
+            "confirm" => Some(PromptAction::Confirm),
+            "back" => Some(PromptAction::Back),
+            "pause" => Some(PromptAction::Pause),
+            "fire" => Some(PromptAction::Fire),
+            "next_weapon" => Some(PromptAction::NextWeapon),
+            _ => None,
+        }
+    }
+
+    /// The key of the name of the keyboard keys doing it in the string
+    /// tables
+    pub fn key_label_key(self) -> &'static str {
+        match self {
+            PromptAction::Confirm => "prompt.key_enter",
+            PromptAction::Back | PromptAction::Pause => "prompt.key_escape",
+            PromptAction::Fire => "prompt.key_space",
+            PromptAction::NextWeapon => "prompt.key_weapons",
+        }
+    }
+
+    /// The controller input doing it, with 'bindings'
+    pub fn pad_input(self, bindings: &PadBindings) -> PadInput {
+        match self {
+            PromptAction::Confirm => PadInput::Button(Button::A),
+            PromptAction::Back => PadInput::Button(Button::B),
+            PromptAction::Pause => bindings.pause,
+            PromptAction::Fire => bindings.fire,
+            PromptAction::NextWeapon => bindings.next_weapon,
+        }
+    }
+}
+
+/// A short name of a controller input, to write on a keycap
+fn pad_input_label(input: PadInput) -> &'static str {
+    use ::sdl2::controller::Axis;
+    match input {
+        PadInput::Button(Button::A) => "A",
+        PadInput::Button(Button::B) => "B",
+        PadInput::Button(Button::X) => "X",
+        PadInput::Button(Button::Y) => "Y",
+        PadInput::Button(Button::Back) => "Back",
+        PadInput::Button(Button::Guide) => "Guide",
+        PadInput::Button(Button::Start) => "Start",
+        PadInput::Button(Button::LeftStick) => "LS",
+        PadInput::Button(Button::RightStick) => "RS",
+        PadInput::Button(Button::LeftShoulder) => "LB",
+        PadInput::Button(Button::RightShoulder) => "RB",
+        PadInput::Button(Button::DPadUp) => "Up",
+        PadInput::Button(Button::DPadDown) => "Down",
+        PadInput::Button(Button::DPadLeft) => "Left",
+        PadInput::Button(Button::DPadRight) => "Right",
+        PadInput::Trigger(Axis::TriggerLeft) => "LT",
+        PadInput::Trigger(_) => "RT",
+    }
+}
+
+/// Find the first action of 'text', and return where its name starts and
+/// ends, brackets included. Brackets around anything else are left as text.
+fn next_action(text: &str) -> Option<(usize, usize, PromptAction)> {
+    text.match_indices('[').filter_map(|(start, _)| {
+        text[start..].find(']').and_then(|len| {
+            PromptAction::from_name(&text[start + 1..start + len])
+                .map(|action| (start, start + len + 1, action))
+        })
+    }).next()
+}
+
+// A piece of a prompt, laid out from left to right
+enum Part {
+    Text(Sprite),
+    // The name of a key or a button, drawn on a keycap
+    Keycap(Sprite),
+    // A glyph of the spritesheet, as high as the text
+    Icon(Sprite),
+}
+
+/// A line of text asking the player to press something, where `[confirm]`
+/// and the like are replaced by the key or the button doing it: the name
+/// of the key on a keycap while the player uses the keyboard, and the glyph
+/// of the button bound to it on the controller while they use a controller.
+/// The prompt is laid out again whenever either changes.
+pub struct Prompt {
+    text: String,
+    font: &'static str,
+    size: i32,
+    color: Color,
+
+    // The device and the bindings the parts were laid out for
+    shown_for: Option<(Device, PadBindings)>,
+    parts: Vec<Part>,
+    line_h: f64,
+}
+
+impl Prompt {
+    pub fn new(text: &str, font: &'static str, size: i32, color: Color) -> Prompt {
+        Prompt {
+            text: text.to_string(),
+            font: font,
+            size: size,
+            color: color,
+            shown_for: None,
+            parts: vec![],
+            line_h: 0.0,
+        }
+    }
+
+    /// The glyph of 'action' for 'device'
+    fn glyph(&self, phi: &mut Phi, action: PromptAction, device: Device,
+             bindings: &PadBindings) -> Option<Part> {
+        let keycap_size = ((self.size as f64) * KEYCAP_TEXT_SCALE).round() as i32;
+        match device {
+            Device::Keyboard => {
+                let name = phi.locale.text(action.key_label_key());
+                phi.ttf_str_sprite(&name, self.font, keycap_size, KEYCAP_TEXT_COLOR).map(Part::Keycap)
+            },
+            Device::Pad => {
+                let input = action.pad_input(bindings);
+                let icon = PAD_INPUTS.iter().position(|&known| known == input)
+                    .and_then(|frame| phi.load_frames(PAD_GLYPHS_ASSET)
+                        .and_then(|mut frames| if frame < frames.len() { Some(frames.swap_remove(frame)) } else { None }));
+                match icon {
+                    Some(icon) => Some(Part::Icon(icon)),
+                    None => phi.ttf_str_sprite(pad_input_label(input), self.font, keycap_size, KEYCAP_TEXT_COLOR)
+                        .map(Part::Keycap),
+                }
+            },
+        }
+    }
+
+    /// Lay the prompt out again if the device of the player or the bindings
+    /// of their controller changed
+    fn layout(&mut self, phi: &mut Phi) {
+        let shown_for = (phi.events.device, phi.pad_bindings(PLAYER_ID));
+        if self.shown_for == Some(shown_for) {
+            return;
+        }
+        let (device, bindings) = shown_for;
+
+        let mut parts = vec![];
+        let mut rest = self.text.clone();
+        while !rest.is_empty() {
+            // Cut the text before the next action, and the action itself
+            let (text, next) = match next_action(&rest) {
+                Some((start, end, action)) => (rest[..start].to_string(), Some((end, action))),
+                None => (rest.clone(), None),
+            };
+
+            if !text.is_empty() {
+                if let Some(sprite) = phi.ttf_str_sprite(&text, self.font, self.size, self.color) {
+                    parts.push(Part::Text(sprite));
+                }
+            }
+            match next {
+                Some((end, action)) => {
+                    if let Some(glyph) = self.glyph(phi, action, device, &bindings) {
+                        parts.push(glyph);
+                    }
+                    rest = rest[end..].to_string();
+                },
+                None => rest.clear(),
+            }
+        }
+
+        self.line_h = phi.text_size("A", self.font, self.size).map_or(0.0, |(_, h)| h);
+        self.parts = parts;
+        self.shown_for = Some(shown_for);
+    }
+
+    // The width of a part once drawn, for text 'line_h' pixels high and
+    // with keycaps padded by 'padding'
+    fn part_width(part: &Part, line_h: f64, padding: f64) -> f64 {
+        match *part {
+            Part::Text(ref sprite) => sprite.size().0,
+            Part::Keycap(ref sprite) => sprite.size().0 + padding * 2.0,
+            Part::Icon(ref sprite) => {
+                let (w, h) = sprite.size();
+                if h > 0.0 { w * line_h / h } else { w }
+            },
+        }
+    }
+
+    /// Return the size of the prompt once drawn
+    pub fn size(&mut self, phi: &mut Phi) -> (f64, f64) {
+        self.layout(phi);
+        let padding = phi.scaled(4.0);
+        let w: f64 = self.parts.iter().map(|part| Prompt::part_width(part, self.line_h, padding)).sum();
+        (w, self.line_h)
+    }
+
+    /// Draw the prompt with its top-left corner at (x, y)
+    pub fn render(&mut self, phi: &mut Phi, (x, y): (f64, f64)) {
+        self.layout(phi);
+        let padding = phi.scaled(4.0);
+        let line_h = self.line_h;
+
+        let mut x = x;
+        for part in &self.parts {
+            let w = Prompt::part_width(part, line_h, padding);
+            match *part {
+                Part::Text(ref sprite) => {
+                    let (w, h) = sprite.size();
+                    sprite.render(&mut phi.renderer, Rectangle { x: x, y: y + (line_h - h) / 2.0, w: w, h: h });
+                },
+                Part::Keycap(ref sprite) => {
+                    let (text_w, text_h) = sprite.size();
+                    let cap = Rectangle { x: x + padding / 2.0, y: y + padding / 2.0, w: w - padding, h: line_h - padding };
+                    if cap.w >= 1.0 && cap.h >= 1.0 {
+                        shapes::fill_rounded_rect(&mut phi.renderer, cap, padding, KEYCAP_COLOR);
+                    }
+                    sprite.render(&mut phi.renderer, Rectangle {
+                        x: x + (w - text_w) / 2.0,
+                        y: y + (line_h - text_h) / 2.0,
+                        w: text_w,
+                        h: text_h,
+                    });
+                },
+                Part::Icon(ref sprite) => {
+                    sprite.render(&mut phi.renderer, Rectangle { x: x, y: y, w: w, h: line_h });
+                },
+            }
+            x += w;
+        }
+    }
+}
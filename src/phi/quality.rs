@@ -0,0 +1,102 @@
+// phi/quality.rs
+
+use ::phi::settings::Settings;
+
+/// How much every new frame weighs in the average time spent on a frame
+const SMOOTHING: f64 = 0.1;
+
+/// The lowest quality, at which the fewest particles and background layers
+/// are drawn, without post effects
+const LOWEST_LEVEL: u32 = 3;
+
+/// Seconds the frames must stay over budget before the quality is lowered,
+/// and within the headroom before it is raised again. Raising it is slower,
+/// so that it does not go up and down every other second.
+const LOWER_AFTER: f64 = 0.5;
+const RAISE_AFTER: f64 = 3.0;
+
+/// Lowers how much is drawn while the frames take longer to update and
+/// draw than the budget of the settings, and restores it once there is
+/// headroom again. From the highest quality, every level:
+///
+/// 1. halves the particles;
+/// 2. drops the post effects, such as hit flashes and color washes;
+/// 3. halves the particles again, and keeps only the farthest background.
+pub struct Quality {
+    level: u32,
+
+    // The average seconds spent on a frame, presenting it excluded
+    average: f64,
+
+    // Seconds during which the frames have been over budget, or within
+    // the headroom
+    over: f64,
+    under: f64,
+}
+
+impl Quality {
+    pub fn new() -> Quality {
+        Quality {
+            level: 0,
+            average: 0.0,
+            over: 0.0,
+            under: 0.0,
+        }
+    }
+
+    /// Account for a frame which took 'work' seconds to update and draw,
+    /// and 'elapsed' seconds after the previous one
+    pub fn update(&mut self, settings: &Settings, work: f64, elapsed: f64) {
+        if !settings.adaptive_quality {
+            self.level = 0;
+            return;
+        }
+
+        self.average += (work - self.average) * SMOOTHING;
+        let budget = settings.frame_budget / 1000.0;
+        if self.average > budget {
+            self.over += elapsed;
+            self.under = 0.0;
+        }
+        else if self.average < budget * settings.quality_headroom {
+            self.under += elapsed;
+            self.over = 0.0;
+        }
+        else {
+            self.over = 0.0;
+            self.under = 0.0;
+        }
+
+        if self.over >= LOWER_AFTER && self.level < LOWEST_LEVEL {
+            self.level += 1;
+            self.over = 0.0;
+            info!("Frames take {:.1} ms, lowering the quality to level {}",
+                  self.average * 1000.0, self.level);
+        }
+        else if self.under >= RAISE_AFTER && self.level > 0 {
+            self.level -= 1;
+            self.under = 0.0;
+            info!("Frames take {:.1} ms, raising the quality to level {}",
+                  self.average * 1000.0, self.level);
+        }
+    }
+
+    /// Return the share of the particles which are emitted, from 0 to 1
+    pub fn particle_share(&self) -> f64 {
+        match self.level {
+            0 => 1.0,
+            1 | 2 => 0.5,
+            _ => 0.25,
+        }
+    }
+
+    /// Whether effects drawn over the scene, such as flashes, are drawn
+    pub fn post_effects(&self) -> bool {
+        self.level < 2
+    }
+
+    /// Whether the background layers other than the farthest one are drawn
+    pub fn all_backgrounds(&self) -> bool {
+        self.level < 3
+    }
+}
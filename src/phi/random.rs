@@ -0,0 +1,204 @@
+// phi/random.rs
+
+use ::rand::Rng;
+use ::std::f64::consts::PI;
+
+/// The distributions the game is tuned with, drawn from any generator.
+/// Whatever changes how a run unfolds draws from the game's seeded
+/// generator, so that replays play the same; effects which only change how
+/// it looks may draw from `rand::thread_rng()`.
+pub trait Distributions: Rng + Sized {
+    /// Return a number between 'min' and 'max', excluded
+    fn range_f64(&mut self, min: f64, max: f64) -> f64 {
+        min + self.gen::<f64>() * (max - min)
+    }
+
+    /// Return a number within 'spread' of 'center'
+    fn around(&mut self, center: f64, spread: f64) -> f64 {
+        self.range_f64(center - spread, center + spread)
+    }
+
+    /// Return true with a probability of 'probability', from 0 to 1
+    fn chance(&mut self, probability: f64) -> bool {
+        self.gen::<f64>() < probability
+    }
+
+    /// Return an index into a list of 'len' items, which must not be empty
+    fn index(&mut self, len: usize) -> usize {
+        self.gen_range(0, len)
+    }
+
+    /// Return an angle, in radians
+    fn angle(&mut self) -> f64 {
+        self.range_f64(0.0, 2.0 * PI)
+    }
+
+    /// Pick one of 'items' with a chance proportional to its weight, or
+    /// None if no item weighs anything
+    fn weighted<'a, T>(&mut self, items: &'a [(T, f64)]) -> Option<&'a T> {
+        let total: f64 = items.iter().map(|&(_, weight)| weight.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut roll = self.range_f64(0.0, total);
+        let mut picked = None;
+        for &(ref item, weight) in items {
+            if weight <= 0.0 {
+                continue;
+            }
+            picked = Some(item);
+            if roll < weight {
+                break;
+            }
+            roll -= weight;
+        }
+        picked
+    }
+
+    /// Return a number from the normal distribution of mean 'mean' and
+    /// standard deviation 'deviation'
+    fn normal(&mut self, mean: f64, deviation: f64) -> f64 {
+        // Box-Muller transform; the first number must not be 0
+        let u = 1.0 - self.gen::<f64>();
+        let v = self.gen::<f64>();
+        mean + deviation * (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+    }
+}
+
+impl<R: Rng> Distributions for R {}
+
+/// Goes off about every 'period' seconds, give or take 'jitter' seconds,
+/// so that what it spawns does not come in a steady beat
+#[derive(Clone)]
+pub struct JitterTimer {
+    period: f64,
+    jitter: f64,
+
+    // Seconds until the timer goes off
+    left: f64,
+}
+
+impl JitterTimer {
+    /// Create a timer going off for the first time after 'period' seconds
+    pub fn new(period: f64, jitter: f64) -> JitterTimer {
+        JitterTimer {
+            period: period,
+            jitter: jitter,
+            left: period,
+        }
+    }
+
+    /// Advance the timer by 'dt' seconds, and return whether it went off,
+    /// drawing the time until it goes off again from 'rng'
+    pub fn update<R: Rng>(&mut self, rng: &mut R, dt: f64) -> bool {
+        self.left -= dt;
+        if self.left > 0.0 {
+            return false;
+        }
+        self.left += rng.around(self.period, self.jitter).max(0.0);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rand::{SeedableRng, StdRng};
+
+    // Draws the given numbers in turn, over and over
+    struct Fixed {
+        draws: Vec<f64>,
+        next: usize,
+    }
+
+    impl Rng for Fixed {
+        fn next_u32(&mut self) -> u32 {
+            (self.next_f64() * 4_294_967_296.0) as u32
+        }
+
+        fn next_f64(&mut self) -> f64 {
+            let draw = self.draws[self.next % self.draws.len()];
+            self.next += 1;
+            draw
+        }
+    }
+
+    fn fixed(draws: &[f64]) -> Fixed {
+        Fixed { draws: draws.to_vec(), next: 0 }
+    }
+
+    fn seeded() -> StdRng {
+        StdRng::from_seed(&[451][..])
+    }
+
+    #[test]
+    fn ranges_stay_within_their_bounds() {
+        let mut rng = seeded();
+        for _ in 0..10_000 {
+            let number = rng.range_f64(-3.0, 5.0);
+            assert!(-3.0 <= number && number < 5.0);
+            let number = rng.around(10.0, 0.5);
+            assert!(9.5 <= number && number < 10.5);
+            let angle = rng.angle();
+            assert!(0.0 <= angle && angle < 2.0 * PI);
+            assert!(rng.index(7) < 7);
+        }
+        assert_eq!(fixed(&[0.25]).range_f64(2.0, 6.0), 3.0);
+    }
+
+    #[test]
+    fn chances_follow_their_probability() {
+        let mut rng = seeded();
+        assert!(!(0..1_000).any(|_| rng.chance(0.0)));
+        assert!((0..1_000).all(|_| rng.chance(1.0)));
+
+        let hits = (0..10_000).filter(|_| rng.chance(0.3)).count();
+        assert!(2_800 < hits && hits < 3_200);
+    }
+
+    #[test]
+    fn weights_set_the_odds() {
+        let items = [('a', 1.0), ('b', 0.0), ('c', 3.0), ('d', -2.0)];
+        assert_eq!(fixed(&[0.0]).weighted(&items), Some(&'a'));
+        assert_eq!(fixed(&[0.24]).weighted(&items), Some(&'a'));
+        assert_eq!(fixed(&[0.26]).weighted(&items), Some(&'c'));
+        assert_eq!(fixed(&[0.99]).weighted(&items), Some(&'c'));
+
+        let mut rng = seeded();
+        let picked_c = (0..10_000).filter(|_| rng.weighted(&items) == Some(&'c')).count();
+        assert!(7_200 < picked_c && picked_c < 7_800);
+    }
+
+    #[test]
+    fn nothing_weighs_nothing() {
+        let mut rng = seeded();
+        assert_eq!(rng.weighted::<char>(&[]), None);
+        assert_eq!(rng.weighted(&[('a', 0.0), ('b', -1.0)]), None);
+    }
+
+    #[test]
+    fn normal_numbers_gather_around_their_mean() {
+        let mut rng = seeded();
+        let numbers: Vec<f64> = (0..20_000).map(|_| rng.normal(5.0, 2.0)).collect();
+        let mean = numbers.iter().sum::<f64>() / numbers.len() as f64;
+        let variance = numbers.iter().map(|n| (n - mean) * (n - mean)).sum::<f64>()
+            / numbers.len() as f64;
+        assert!((mean - 5.0).abs() < 0.1);
+        assert!((variance.sqrt() - 2.0).abs() < 0.1);
+
+        // A first draw of 0 must not make an infinite number
+        assert!(fixed(&[0.0]).normal(0.0, 1.0).is_finite());
+    }
+
+    #[test]
+    fn timers_go_off_about_every_period() {
+        let mut rng = seeded();
+        let mut timer = JitterTimer::new(1.0, 0.25);
+        assert!(!timer.update(&mut rng, 0.9));
+        assert!(timer.update(&mut rng, 0.1));
+
+        let fired = (0..10_000).filter(|_| timer.update(&mut rng, 0.01)).count();
+        assert!(90 <= fired && fired <= 110);
+    }
+}
@@ -0,0 +1,261 @@
+// phi/settings.rs
+
+use ::phi::controllers::{DeadZone, PadBindings};
+use ::std::fs::File;
+use ::std::io::{self, BufRead, BufReader, Write};
+
+/// Where the player's settings are kept between sessions
+pub const SETTINGS_PATH: &'static str = "settings.cfg";
+
+/// The slowest the game may be set to run
+pub const MIN_GAME_SPEED: f64 = 0.7;
+
+/// User preferences, stored as `key = value` lines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Whether controllers may vibrate
+    pub vibration: bool,
+
+    /// Extra game controller mappings, in the format of the SDL game
+    /// controller database, for pads it does not know about
+    pub controller_mappings: Vec<String>,
+
+    /// How the buttons and sticks are used, by controller GUID
+    pub pad_bindings: Vec<(String, PadBindings)>,
+
+    /// How analog sticks are interpreted
+    pub stick_inner_dead_zone: f64,
+    pub stick_outer_dead_zone: f64,
+    pub stick_curve: f64,
+
+    /// Whether the ship fires toward the mouse cursor (or the right stick)
+    /// instead of straight ahead
+    pub mouse_aim: bool,
+
+    /// How strongly the screen shakes, from 0 (not at all) to 1
+    pub screen_shake: f64,
+
+    /// Whether the screen never shakes and the backgrounds stand still,
+    /// whatever `screen_shake` says
+    pub reduce_motion: bool,
+
+    /// Whether blinking banners stay lit and hit targets do not flash white
+    pub reduce_flashes: bool,
+
+    /// The speed of everything but the ship and the menus, from 0.7 to 1
+    pub game_speed: f64,
+
+    /// The name under which the player's runs enter the leaderboards
+    pub player_name: String,
+
+    /// Which messages are logged, such as `info` or
+    /// `warn,arcade_rs::views=debug`; see phi::logging
+    pub log_level: String,
+
+    /// The name of the visual theme; see phi::themes
+    pub theme: String,
+
+    /// The code of the language of the text, such as `en`; see phi::locale
+    pub language: String,
+
+    /// How much the text and menus are enlarged, or 0 to pick it from the
+    /// resolution of the display
+    pub ui_scale: f64,
+
+    /// The name of the palette of the colors, such as `deuteranopia`; see
+    /// phi::palette
+    pub palette: String,
+
+    /// Whether the last seconds of play are kept, to be saved as a clip
+    /// with F12; see phi::capture
+    pub clip_capture: bool,
+
+    /// Whether fewer particles, background layers and effects are drawn
+    /// while frames take longer than `frame_budget`; see phi::quality
+    pub adaptive_quality: bool,
+
+    /// The milliseconds a frame may take to update and draw
+    pub frame_budget: f64,
+
+    /// The share of the budget under which frames must stay for the
+    /// quality to be raised again, from 0 to 1
+    pub quality_headroom: f64,
+
+    /// Whether the debugging hotkeys work during the game: F4 to F8 for the
+    /// cheats, F9 to rewind it, F10 to step through it frame by frame and F11
+    /// to step
+    pub debug_tools: bool,
+
+    /// The wave practice starts at, whether it starts with the boss of that
+    /// wave, and the seed of its runs; see views::practice
+    pub practice_wave: u32,
+    pub practice_boss: bool,
+    pub practice_seed: u64,
+}
+
+impl Settings {
+    /// Create the default settings
+    pub fn new() -> Settings {
+        Settings {
+            vibration: true,
+            controller_mappings: vec![],
+            pad_bindings: vec![],
+            stick_inner_dead_zone: 0.2,
+            stick_outer_dead_zone: 0.95,
+            stick_curve: 1.5,
+            mouse_aim: false,
+            screen_shake: 1.0,
+            reduce_motion: false,
+            reduce_flashes: false,
+            game_speed: 1.0,
+            player_name: "Player".to_string(),
+            log_level: "info".to_string(),
+            theme: ::phi::themes::DEFAULT_THEME.to_string(),
+            language: ::phi::locale::DEFAULT_LANGUAGE.to_string(),
+            ui_scale: 0.0,
+            palette: "standard".to_string(),
+            clip_capture: true,
+            adaptive_quality: true,
+            frame_budget: 14.0,
+            quality_headroom: 0.6,
+            debug_tools: false,
+            practice_wave: 1,
+            practice_boss: false,
+            practice_seed: 0,
+        }
+    }
+
+    /// How strongly the screen shakes, from 0 (not at all) to 1, once
+    /// reduce_motion is taken into account
+    pub fn shake_scale(&self) -> f64 {
+        if self.reduce_motion { 0.0 }
+        else { self.screen_shake.max(0.0).min(1.0) }
+    }
+
+    /// The speed of the game, kept within the bounds it may be set to
+    pub fn game_speed(&self) -> f64 {
+        self.game_speed.max(MIN_GAME_SPEED).min(1.0)
+    }
+
+    /// Return the bindings of the controllers with the given GUID, or the
+    /// default ones if they were never changed
+    pub fn pad_bindings(&self, guid: &str) -> PadBindings {
+        self.pad_bindings.iter()
+            .find(|&&(ref known, _)| known == guid)
+            .map_or(PadBindings::default(), |&(_, bindings)| bindings)
+    }
+
+    pub fn set_pad_bindings(&mut self, guid: &str, bindings: PadBindings) {
+        match self.pad_bindings.iter().position(|&(ref known, _)| known == guid) {
+            Some(index) => self.pad_bindings[index].1 = bindings,
+            None => self.pad_bindings.push((guid.to_string(), bindings)),
+        }
+    }
+
+    /// Return the dead zone applied to analog sticks
+    pub fn dead_zone(&self) -> DeadZone {
+        DeadZone {
+            inner: self.stick_inner_dead_zone,
+            outer: self.stick_outer_dead_zone,
+            curve: self.stick_curve,
+        }
+    }
+
+    /// Read the settings stored at 'path'. Missing, unknown or malformed
+    /// entries are ignored, and the defaults are used in their place.
+    pub fn load(path: &str) -> Settings {
+        let mut settings = Settings::new();
+
+        if let Ok(file) = File::open(path) {
+            for line in BufReader::new(file).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+
+                let mut parts = line.splitn(2, '=');
+                if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                    settings.set(key.trim(), value.trim());
+                }
+            }
+        }
+
+        settings
+    }
+
+    /// Write the settings to 'path', so that they can be loaded back
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = try!(File::create(path));
+        try!(writeln!(file, "vibration = {}", self.vibration));
+        try!(writeln!(file, "stick_inner_dead_zone = {}", self.stick_inner_dead_zone));
+        try!(writeln!(file, "stick_outer_dead_zone = {}", self.stick_outer_dead_zone));
+        try!(writeln!(file, "stick_curve = {}", self.stick_curve));
+        try!(writeln!(file, "mouse_aim = {}", self.mouse_aim));
+        try!(writeln!(file, "screen_shake = {}", self.screen_shake));
+        try!(writeln!(file, "reduce_motion = {}", self.reduce_motion));
+        try!(writeln!(file, "reduce_flashes = {}", self.reduce_flashes));
+        try!(writeln!(file, "game_speed = {}", self.game_speed));
+        try!(writeln!(file, "player_name = {}", self.player_name));
+        try!(writeln!(file, "log_level = {}", self.log_level));
+        try!(writeln!(file, "theme = {}", self.theme));
+        try!(writeln!(file, "language = {}", self.language));
+        try!(writeln!(file, "ui_scale = {}", self.ui_scale));
+        try!(writeln!(file, "palette = {}", self.palette));
+        try!(writeln!(file, "clip_capture = {}", self.clip_capture));
+        try!(writeln!(file, "adaptive_quality = {}", self.adaptive_quality));
+        try!(writeln!(file, "frame_budget = {}", self.frame_budget));
+        try!(writeln!(file, "quality_headroom = {}", self.quality_headroom));
+        try!(writeln!(file, "debug_tools = {}", self.debug_tools));
+        try!(writeln!(file, "practice_wave = {}", self.practice_wave));
+        try!(writeln!(file, "practice_boss = {}", self.practice_boss));
+        try!(writeln!(file, "practice_seed = {}", self.practice_seed));
+        for mapping in &self.controller_mappings {
+            try!(writeln!(file, "controller_mapping = {}", mapping));
+        }
+        for &(ref guid, ref bindings) in &self.pad_bindings {
+            try!(writeln!(file, "pad_bindings = {}", bindings.to_setting(guid)));
+        }
+        Ok(())
+    }
+
+    // Assign the value of a single entry read from a settings file
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "vibration" => set_parsed(&mut self.vibration, value),
+            "stick_inner_dead_zone" => set_parsed(&mut self.stick_inner_dead_zone, value),
+            "stick_outer_dead_zone" => set_parsed(&mut self.stick_outer_dead_zone, value),
+            "stick_curve" => set_parsed(&mut self.stick_curve, value),
+            "mouse_aim" => set_parsed(&mut self.mouse_aim, value),
+            "screen_shake" => set_parsed(&mut self.screen_shake, value),
+            "reduce_motion" => set_parsed(&mut self.reduce_motion, value),
+            "reduce_flashes" => set_parsed(&mut self.reduce_flashes, value),
+            "game_speed" => set_parsed(&mut self.game_speed, value),
+            "player_name" => self.player_name = value.to_string(),
+            "log_level" => self.log_level = value.to_string(),
+            "theme" => self.theme = value.to_string(),
+            "language" => self.language = value.to_string(),
+            "ui_scale" => set_parsed(&mut self.ui_scale, value),
+            "palette" => self.palette = value.to_string(),
+            "clip_capture" => set_parsed(&mut self.clip_capture, value),
+            "adaptive_quality" => set_parsed(&mut self.adaptive_quality, value),
+            "frame_budget" => set_parsed(&mut self.frame_budget, value),
+            "quality_headroom" => set_parsed(&mut self.quality_headroom, value),
+            "debug_tools" => set_parsed(&mut self.debug_tools, value),
+            "practice_wave" => set_parsed(&mut self.practice_wave, value),
+            "practice_boss" => set_parsed(&mut self.practice_boss, value),
+            "practice_seed" => set_parsed(&mut self.practice_seed, value),
+            "controller_mapping" => self.controller_mappings.push(value.to_string()),
+            "pad_bindings" => if let Some((guid, bindings)) = PadBindings::parse(value) {
+                self.set_pad_bindings(&guid, bindings);
+            },
+            _ => {}
+        }
+    }
+}
+
+// Replace 'field' by 'value' if it can be parsed, otherwise keep it as is
+fn set_parsed<T: ::std::str::FromStr>(field: &mut T, value: &str) {
+    if let Ok(parsed) = value.parse() {
+        *field = parsed;
+    }
+}
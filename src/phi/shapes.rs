@@ -0,0 +1,111 @@
+// phi/shapes.rs
+
+use ::phi::data::Rectangle;
+use ::sdl2::pixels::Color;
+use ::sdl2::render::Renderer;
+
+/// The direction along which the colors of a gradient change
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gradient {
+    /// From the top to the bottom
+    Vertical,
+    /// From the left to the right
+    Horizontal,
+}
+
+/// Return the color 't' of the way from 'from' to 'to', 't' being between
+/// 0 and 1
+pub fn mix(from: Color, to: Color, t: f64) -> Color {
+    let (r1, g1, b1, a1) = from.rgba();
+    let (r2, g2, b2, a2) = to.rgba();
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Color::RGBA(channel(r1, r2), channel(g1, g2), channel(b1, b2), channel(a1, a2))
+}
+
+/// Fill 'rect' with colors going from 'from' to 'to' along 'gradient'
+pub fn fill_gradient(renderer: &mut Renderer, rect: Rectangle,
+                     from: Color, to: Color, gradient: Gradient) {
+    fill_rounded_gradient(renderer, rect, 0.0, from, to, gradient);
+}
+
+/// Fill 'rect' with 'color', its corners rounded with a radius of
+/// 'radius' pixels
+pub fn fill_rounded_rect(renderer: &mut Renderer, rect: Rectangle, radius: f64, color: Color) {
+    let radius = radius.max(0.0).min(rect.w.min(rect.h) / 2.0).floor();
+    renderer.set_draw_color(color);
+
+    // The band between the corners, then the rows of the corners, which
+    // are inset more and more toward the top and bottom edges
+    fill(renderer, Rectangle {
+        x: rect.x,
+        y: rect.y + radius,
+        w: rect.w,
+        h: rect.h - radius * 2.0,
+    });
+    for row in 0..radius as u32 {
+        let inset = corner_inset(radius, row as f64);
+        for &y in &[rect.y + row as f64, rect.y + rect.h - 1.0 - row as f64] {
+            fill(renderer, Rectangle {
+                x: rect.x + inset,
+                y: y,
+                w: rect.w - inset * 2.0,
+                h: 1.0,
+            });
+        }
+    }
+}
+
+/// Fill 'rect' with colors going from 'from' to 'to' along 'gradient', its
+/// corners rounded with a radius of 'radius' pixels. The gradient is drawn
+/// one line of pixels at a time.
+pub fn fill_rounded_gradient(renderer: &mut Renderer, rect: Rectangle, radius: f64,
+                             from: Color, to: Color, gradient: Gradient) {
+    if from == to {
+        return fill_rounded_rect(renderer, rect, radius, from);
+    }
+
+    let radius = radius.max(0.0).min(rect.w.min(rect.h) / 2.0).floor();
+    let length = match gradient {
+        Gradient::Vertical => rect.h,
+        Gradient::Horizontal => rect.w,
+    }.floor();
+
+    for line in 0..length as u32 {
+        let line = line as f64;
+        let t = if length > 1.0 { line / (length - 1.0) } else { 0.0 };
+        renderer.set_draw_color(mix(from, to, t));
+
+        // Lines within the corners are inset at both ends
+        let from_edge = line.min(length - 1.0 - line);
+        let inset = if from_edge < radius { corner_inset(radius, from_edge) } else { 0.0 };
+
+        fill(renderer, match gradient {
+            Gradient::Vertical => Rectangle {
+                x: rect.x + inset,
+                y: rect.y + line,
+                w: rect.w - inset * 2.0,
+                h: 1.0,
+            },
+            Gradient::Horizontal => Rectangle {
+                x: rect.x + line,
+                y: rect.y + inset,
+                w: 1.0,
+                h: rect.h - inset * 2.0,
+            },
+        });
+    }
+}
+
+/// Return how far from the sides the line 'line' pixels from the edge of a
+/// corner of radius 'radius' starts
+fn corner_inset(radius: f64, line: f64) -> f64 {
+    let dy = radius - line - 0.5;
+    (radius - (radius * radius - dy * dy).max(0.0).sqrt()).round()
+}
+
+/// Fill 'rect' with the current draw color, unless it is empty
+fn fill(renderer: &mut Renderer, rect: Rectangle) {
+    if rect.w >= 1.0 && rect.h >= 1.0 {
+        renderer.fill_rect(rect.to_sdl().unwrap());
+    }
+}
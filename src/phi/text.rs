@@ -0,0 +1,65 @@
+// phi/text.rs
+
+//! Layout of text whose width is known through a measuring function, such
+//! as `Phi::text_size`. Text is only ever split between characters, never
+//! inside the UTF-8 encoding of one.
+
+/// Appended to text shortened to fit in some width
+const ELLIPSIS: &'static str = "...";
+
+/// Break 'text' into lines no wider than 'max_width', between words when
+/// possible, and keep the line breaks it already has.
+pub fn wrap<F>(text: &str, max_width: f64, mut measure: F) -> Vec<String>
+        where F: FnMut(&str) -> f64 {
+    let mut lines = vec![];
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_string() }
+                            else { format!("{} {}", line, word) };
+            if measure(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(line);
+            }
+
+            // A word too wide for a line of its own is broken anywhere
+            line = String::new();
+            for ch in word.chars() {
+                line.push(ch);
+                if measure(&line) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(line);
+                    line = ch.to_string();
+                }
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Shorten 'text' so that it fits in 'max_width', ending it with an
+/// ellipsis if anything had to be removed.
+pub fn ellipsize<F>(text: &str, max_width: f64, mut measure: F) -> String
+        where F: FnMut(&str) -> f64 {
+    if measure(text) <= max_width {
+        return text.to_string();
+    }
+
+    let mut shortened = text.to_string();
+    while shortened.pop().is_some() {
+        let candidate = format!("{}{}", shortened.trim_right(), ELLIPSIS);
+        if measure(&candidate) <= max_width {
+            return candidate;
+        }
+    }
+    ELLIPSIS.to_string()
+}
@@ -0,0 +1,108 @@
+// phi/themes.rs
+
+use ::phi::assets::{self, unquote};
+use ::sdl2::pixels::Color;
+use ::std::collections::HashMap;
+
+pub const THEMES_PATH: &'static str = "assets/themes.toml";
+
+/// The theme used when none is chosen or the chosen one does not exist
+pub const DEFAULT_THEME: &'static str = "classic";
+
+/// A visual variation of the game. A theme replaces some assets of the
+/// manifest by others, and may recolor the bullets and tint the backgrounds.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    pub name: String,
+
+    /// The wave a player must have reached once before picking the theme
+    pub unlock_wave: u32,
+
+    pub bullet_color: Option<Color>,
+    pub background_tint: Option<Color>,
+
+    // The paths replacing those of the manifest, by asset name
+    overrides: HashMap<String, String>,
+}
+
+impl Theme {
+    /// The look of the game as the manifest describes it
+    pub fn default() -> Theme {
+        Theme {
+            name: DEFAULT_THEME.to_string(),
+            unlock_wave: 0,
+            bullet_color: None,
+            background_tint: None,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Return the path replacing that of the asset 'name', if any
+    pub fn path(&self, name: &str) -> Option<&str> {
+        self.overrides.get(name).map(|path| &path[..])
+    }
+}
+
+/// Parse a color written as `[r, g, b]`
+pub fn parse_color(value: &str) -> Option<Color> {
+    if !value.starts_with('[') || !value.ends_with(']') {
+        return None;
+    }
+
+    let channels: Vec<u8> = value[1..value.len() - 1].split(',')
+        .filter_map(|channel| channel.trim().parse().ok())
+        .collect();
+    if channels.len() == 3 {
+        Some(Color::RGB(channels[0], channels[1], channels[2]))
+    }
+    else {
+        None
+    }
+}
+
+/// Read the themes defined at 'path'. Every theme is a table named after
+/// it, holding the optional `unlock_wave` integer, the `bullet_color` and
+/// `background_tint` colors written `[r, g, b]`, and any number of
+/// `asset_name = "path"` lines. The default theme always comes first.
+pub fn load_themes(path: &str) -> Vec<Theme> {
+    let mut themes = vec![Theme::default()];
+
+    let tables = match assets::read_tables(path) {
+        Some(tables) => tables,
+        None => {
+            warn!("Could not open the themes {}", path);
+            return themes;
+        }
+    };
+
+    for (name, values) in tables {
+        let mut theme = Theme {
+            name: name.clone(),
+            ..Theme::default()
+        };
+
+        for (key, value) in values {
+            let valid = match &key[..] {
+                "unlock_wave" => value.parse().ok().map(|wave| theme.unlock_wave = wave).is_some(),
+                "bullet_color" => parse_color(&value).map(|color| theme.bullet_color = Some(color)).is_some(),
+                "background_tint" => parse_color(&value).map(|color| theme.background_tint = Some(color)).is_some(),
+                _ => unquote(&value).map(|asset| {
+                    theme.overrides.insert(key.clone(), asset.to_string());
+                }).is_some(),
+            };
+            if !valid {
+                warn!("Ignoring {} = {} for the theme {} in {}", key, value, name, path);
+            }
+        }
+
+        // A table for the default theme amends it
+        if name == DEFAULT_THEME {
+            themes[0] = theme;
+        }
+        else {
+            themes.push(theme);
+        }
+    }
+
+    themes
+}
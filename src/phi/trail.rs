@@ -0,0 +1,108 @@
+// phi/trail.rs
+
+use ::std::collections::VecDeque;
+use ::sdl2::pixels::Color;
+use ::sdl2::render::{BlendMode, Renderer};
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+
+/// How much of its size the oldest afterimage keeps
+const MIN_SCALE: f64 = 0.5;
+
+/// How opaque the newest afterimage is
+const MAX_ALPHA: f64 = 0.6;
+
+/// The afterimages left behind by something moving fast: the places it was
+/// at over the last moments, which fade and shrink as they age. A trail
+/// records them only while it is active, and lets the ones it has fade out
+/// once it is not anymore.
+#[derive(Clone)]
+pub struct Trail {
+    // Where the entity was, and the seconds since, oldest first
+    images: VecDeque<(Rectangle, f64)>,
+
+    // Seconds between two afterimages, and seconds an afterimage lasts
+    spacing: f64,
+    life: f64,
+
+    // Seconds since the last afterimage was recorded
+    since_last: f64,
+    active: bool,
+}
+
+impl Trail {
+    /// Create an inactive trail recording an afterimage every 'spacing'
+    /// seconds, each lasting 'life' seconds
+    pub fn new(spacing: f64, life: f64) -> Trail {
+        Trail {
+            images: VecDeque::new(),
+            spacing: spacing,
+            life: life,
+            since_last: spacing,
+            active: false,
+        }
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.images.clear();
+    }
+
+    /// Age the afterimages by 'dt' seconds, and record one at 'rect' if
+    /// the trail is active and the last one is far enough in the past
+    pub fn update(&mut self, rect: Rectangle, dt: f64) {
+        for image in &mut self.images {
+            image.1 += dt;
+        }
+        while self.images.front().map_or(false, |&(_, age)| age >= self.life) {
+            self.images.pop_front();
+        }
+
+        self.since_last += dt;
+        if self.active && self.since_last >= self.spacing {
+            self.images.push_back((rect, 0.0));
+            self.since_last = 0.0;
+        }
+    }
+
+    /// Return every afterimage, oldest first, shrunk after its age, along
+    /// with its opacity from 0 to 1
+    fn afterimages(&self) -> Vec<(Rectangle, f64)> {
+        self.images.iter().map(|&(rect, age)| {
+            let fresh = 1.0 - age / self.life;
+            (rect.scale_about_center(MIN_SCALE + (1.0 - MIN_SCALE) * fresh), MAX_ALPHA * fresh)
+        }).collect()
+    }
+
+    /// Render the afterimages as copies of 'sprite'
+    pub fn render_sprite(&self, renderer: &mut Renderer, sprite: &Sprite) {
+        for (rect, alpha) in self.afterimages() {
+            if rect.w >= 1.0 && rect.h >= 1.0 {
+                sprite.set_alpha_mod((alpha * 255.0) as u8);
+                sprite.render(renderer, rect);
+            }
+        }
+        sprite.set_alpha_mod(255);
+    }
+
+    /// Render the afterimages as rectangles filled with 'color'
+    pub fn render_fill(&self, renderer: &mut Renderer, color: Color) {
+        let (r, g, b) = color.rgb();
+        renderer.set_blend_mode(BlendMode::Blend);
+        for (rect, alpha) in self.afterimages() {
+            if rect.w >= 1.0 && rect.h >= 1.0 {
+                renderer.set_draw_color(Color::RGBA(r, g, b, (alpha * 255.0) as u8));
+                renderer.fill_rect(rect.to_sdl().unwrap());
+            }
+        }
+        renderer.set_blend_mode(BlendMode::None);
+    }
+}
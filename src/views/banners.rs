@@ -0,0 +1,148 @@
+// views/banners.rs
+
+use ::std::collections::VecDeque;
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::bus::Announcement;
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+
+/// Seconds a banner takes to slide in, and to slide out
+const SLIDE_TIME: f64 = 0.35;
+
+/// The most announcements waiting for their turn; past this, new ones are
+/// dropped
+const MAX_QUEUED: usize = 8;
+
+/// Times per second an urgent banner blinks
+const BLINK_RATE: f64 = 2.0;
+
+/// The banner shown, and the seconds since it started sliding in
+struct Banner {
+    announcement: Announcement,
+    label: Option<Sprite>,
+    time: f64,
+}
+
+/// Announcements shown one at a time in a band across the middle of the
+/// screen. A banner opens from its middle line while its text slides in
+/// from the right, then slides out to the left as the band closes.
+pub struct Banners {
+    queue: VecDeque<Announcement>,
+    current: Option<Banner>,
+}
+
+impl Banners {
+    pub fn new() -> Banners {
+        Banners {
+            queue: VecDeque::new(),
+            current: None,
+        }
+    }
+
+    /// Show 'announcement' once the banners queued before it are gone. An
+    /// announcement already waiting is not queued twice.
+    pub fn announce(&mut self, announcement: Announcement) {
+        if self.queue.contains(&announcement) {
+            return;
+        }
+        if self.queue.len() >= MAX_QUEUED {
+            warn!("Too many announcements are waiting, dropping \"{}\"", announcement.text);
+            return;
+        }
+        self.queue.push_back(announcement);
+    }
+
+    /// Take the banner shown and the waiting ones away
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.current = None;
+    }
+
+    /// Advance the banner shown by 'dt' seconds, and show the next one once
+    /// it is gone. Return the sound to play as a banner slides in, if any.
+    pub fn update(&mut self, phi: &mut Phi, dt: f64) -> Option<String> {
+        if let Some(ref mut banner) = self.current {
+            banner.time += dt;
+        }
+        if self.current.as_ref().map_or(false, |banner| banner.time >= banner.announcement.duration) {
+            self.current = None;
+        }
+
+        if self.current.is_some() {
+            return None;
+        }
+        let announcement = match self.queue.pop_front() {
+            Some(announcement) => announcement,
+            None => return None,
+        };
+
+        let (size, color) = if announcement.urgent { (64, Color::RGB(230, 40, 40)) }
+                            else { (48, Color::RGB(255, 255, 255)) };
+        let color = phi.palette_color(color);
+        let label = phi.ttf_str_sprite(&announcement.text, "menu_font", size, color);
+        let sound = announcement.sound.clone();
+        self.current = Some(Banner {
+            announcement: announcement,
+            label: label,
+            time: 0.0,
+        });
+        sound
+    }
+
+    /// Render the banner shown, if any
+    pub fn render(&self, phi: &mut Phi) {
+        let banner = match self.current {
+            Some(ref banner) => banner,
+            None => return,
+        };
+        let label = match banner.label {
+            Some(ref label) => label,
+            None => return,
+        };
+
+        // Urgent banners blink, unless flashes are reduced
+        let urgent = banner.announcement.urgent;
+        if urgent && !phi.settings.reduce_flashes && (banner.time * BLINK_RATE * 2.0) as u32 % 2 == 1 {
+            return;
+        }
+
+        // How far the banner slid in or out, eased so that it slows down
+        // as it settles
+        let duration = banner.announcement.duration;
+        let shown = (banner.time / SLIDE_TIME)
+            .min((duration - banner.time) / SLIDE_TIME)
+            .max(0.0).min(1.0);
+        let ease = 1.0 - (1.0 - shown) * (1.0 - shown);
+
+        let (win_w, win_h) = phi.output_size();
+        let (w, h) = label.size();
+        let margin = phi.scaled(10.0);
+
+        let band_h = ((h + margin * 2.0) * ease).floor();
+        if band_h >= 1.0 {
+            let back = if urgent { Color::RGB(60, 10, 10) } else { Color::RGB(20, 20, 40) };
+            phi.renderer.set_draw_color(back);
+            phi.renderer.fill_rect(Rectangle {
+                x: 0.0,
+                y: ((win_h - band_h) / 2.0).floor(),
+                w: win_w,
+                h: band_h,
+            }.to_sdl().unwrap());
+        }
+
+        let slide = if phi.settings.reduce_motion { 0.0 }
+                    else { (1.0 - ease) * (win_w + w) / 2.0 };
+        let x = if banner.time < duration / 2.0 { (win_w - w) / 2.0 + slide }
+                else { (win_w - w) / 2.0 - slide };
+
+        label.set_alpha_mod((ease * 255.0) as u8);
+        label.render(&mut phi.renderer, Rectangle {
+            x: x,
+            y: (win_h - h) / 2.0,
+            w: w,
+            h: h,
+        });
+    }
+}
@@ -0,0 +1,191 @@
+// views/benchmark.rs
+
+use ::rand::{SeedableRng, StdRng};
+use ::sdl2::pixels::Color;
+use ::std::env;
+use ::std::time::{Duration, Instant};
+
+use ::phi::{Phi, View, ViewAction};
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, SpriteBatch};
+use ::phi::parallel;
+use ::views::game::GameView;
+use ::views::shared::Backgrounds;
+
+/// The option which starts the benchmark, optionally followed by its
+/// duration in seconds, as in `--benchmark=30`
+const BENCHMARK_OPTION: &'static str = "--benchmark";
+const DEFAULT_DURATION: f64 = 20.0;
+
+/// The scene is always the same, and kept this busy
+const SEED: usize = 0x5eed;
+const BULLETS: usize = 2000;
+const ASTEROIDS: usize = 500;
+
+/// The sprite copies drawn at the end, alternating between two textures,
+/// to compare drawing them one at a time with drawing them in a batch
+const BATCH_COPIES: usize = 10_000;
+const BATCH_SIDE: f64 = 32.0;
+
+/// Return how long the benchmark should run if it was asked for on the
+/// command line.
+pub fn duration_from_args() -> Option<f64> {
+    env::args()
+        .find(|arg| arg == BENCHMARK_OPTION || arg.starts_with(&format!("{}=", BENCHMARK_OPTION)))
+        .map(|arg| arg[BENCHMARK_OPTION.len()..].trim_left_matches('=')
+             .parse().unwrap_or(DEFAULT_DURATION))
+}
+
+/// Plays a heavy scene as fast as possible for a fixed time, then prints
+/// statistics about the frame times and quits.
+pub struct BenchmarkView {
+    game: GameView,
+    rng: StdRng,
+
+    duration: f64,
+    total_time: f64,
+
+    // The adaptive quality of the settings, turned off while running so
+    // that the scene stays the same
+    adaptive_quality: bool,
+
+    // The start of the current frame, and the duration of every frame so
+    // far, in seconds
+    frame_start: Option<Instant>,
+    frame_times: Vec<f64>,
+
+    // The seconds taken to draw BATCH_COPIES sprites one at a time, then
+    // through a SpriteBatch, once measured
+    batching: Option<(f64, f64)>,
+}
+
+// Return a duration in seconds
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9
+}
+
+impl BenchmarkView {
+    pub fn new(phi: &mut Phi, duration: f64) -> BenchmarkView {
+        // Not GameView::new, whose seed is random and which lets the ghost
+        // of the player's best run fly along
+        let backgrounds = Backgrounds::new(phi);
+        BenchmarkView {
+            game: GameView::new_with_seed(phi, backgrounds, SEED as u64),
+            rng: StdRng::from_seed(&[SEED]),
+            duration: duration,
+            total_time: 0.0,
+            adaptive_quality: phi.settings.adaptive_quality,
+            frame_start: None,
+            frame_times: vec![],
+            batching: None,
+        }
+    }
+
+    // Time drawing the same sprites with and without a SpriteBatch. The
+    // copies alternate between two textures, which a batch draws one after
+    // the other. Nothing is presented, so only the time spent submitting
+    // the copies is measured.
+    fn measure_batching(&mut self, phi: &mut Phi) {
+        let sprites = match (phi.load_sprite("player_ship"), phi.load_sprite("asteroid_sheet")) {
+            (Some(ship), Some(asteroids)) => [ship, asteroids],
+            _ => {
+                warn!("Could not load the sprites to measure batching with");
+                return;
+            },
+        };
+
+        let (win_w, win_h) = phi.output_size();
+        let dest = |i: usize| Rectangle {
+            x: (i * 37) as f64 % (win_w - BATCH_SIDE).max(1.0),
+            y: (i * 53) as f64 % (win_h - BATCH_SIDE).max(1.0),
+            w: BATCH_SIDE,
+            h: BATCH_SIDE,
+        };
+
+        let start = Instant::now();
+        for i in 0..BATCH_COPIES {
+            sprites[i % 2].render(&mut phi.renderer, dest(i));
+        }
+        let direct = seconds(start.elapsed());
+
+        let start = Instant::now();
+        let mut batch = SpriteBatch::with_capacity(BATCH_COPIES);
+        for i in 0..BATCH_COPIES {
+            batch.push(&sprites[i % 2], dest(i));
+        }
+        batch.flush(&mut phi.renderer);
+        let batched = seconds(start.elapsed());
+
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+        self.batching = Some((direct, batched));
+    }
+
+    fn report(&mut self) {
+        if self.frame_times.is_empty() {
+            println!("Benchmark: no frame was rendered");
+            return;
+        }
+
+        self.frame_times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let count = self.frame_times.len();
+        let average = self.frame_times.iter().fold(0.0, |sum, time| sum + time) / count as f64;
+        let percentile = |p: f64| self.frame_times[((count - 1) as f64 * p).round() as usize];
+
+        println!("Benchmark: {} frames in {:.1} s ({} bullets, {} asteroids)",
+                 count, self.total_time, BULLETS, ASTEROIDS);
+        if parallel::is_enabled() {
+            println!("  parallel updates from {} entities", parallel::MIN_PARALLEL_ITEMS);
+        }
+        else {
+            println!("  sequential updates");
+        }
+        println!("  average {:.2} ms ({:.0} FPS)", average * 1000.0, 1.0 / average);
+        println!("  p50 {:.2} ms, p90 {:.2} ms, p99 {:.2} ms, max {:.2} ms",
+                 percentile(0.5) * 1000.0, percentile(0.9) * 1000.0,
+                 percentile(0.99) * 1000.0, self.frame_times[count - 1] * 1000.0);
+        if let Some((direct, batched)) = self.batching {
+            println!("  {} sprites: {:.2} ms one at a time, {:.2} ms batched",
+                     BATCH_COPIES, direct * 1000.0, batched * 1000.0);
+        }
+    }
+}
+
+impl View for BenchmarkView {
+    fn resume(&mut self, phi: &mut Phi) {
+        info!("Running the benchmark for {} seconds", self.duration);
+        phi.limit_fps = false;
+        self.adaptive_quality = phi.settings.adaptive_quality;
+        phi.settings.adaptive_quality = false;
+        self.game.resume(phi);
+    }
+
+    fn pause(&mut self, phi: &mut Phi) {
+        self.game.pause(phi);
+        phi.limit_fps = true;
+        phi.settings.adaptive_quality = self.adaptive_quality;
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        // Measure the whole frame, presenting it included
+        let now = Instant::now();
+        if let Some(start) = self.frame_start {
+            self.frame_times.push(seconds(now.duration_since(start)));
+        }
+        self.frame_start = Some(now);
+
+        self.total_time += elapsed;
+        if self.total_time >= self.duration || phi.events.now.quit {
+            self.measure_batching(phi);
+            self.report();
+            return ViewAction::Quit;
+        }
+
+        self.game.fill_benchmark_scene(phi, &mut self.rng, BULLETS, ASTEROIDS);
+        match self.game.render(phi, elapsed) {
+            // Do not leave the scene for the menu or the game over screen
+            ViewAction::ChangeView(_) | ViewAction::PushView(_) => ViewAction::None,
+            action => action,
+        }
+    }
+}
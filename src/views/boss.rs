@@ -0,0 +1,407 @@
+// views/boss.rs
+
+use ::sdl2::pixels::Color;
+use ::sdl2::render::BlendMode;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::gfx::Blending;
+use ::phi::shapes;
+use ::views::hazards::fill_circle;
+use ::views::hud::Bar;
+use ::views::shared::HIT_FLASH;
+
+/// Pixels travelled by the boss every second while it enters the screen
+const BOSS_ENTRY_SPEED: f64 = 80.0;
+
+/// Distance between the right edge of the screen and the boss once it
+/// is in place
+const BOSS_MARGIN: f64 = 200.0;
+
+const BOSS_BULLET_SPEED: f64 = 200.0;
+const BOSS_BULLET_SIDE: f64 = 8.0;
+
+/// Seconds between two shots of a turret, and between two volleys of the
+/// core once the turrets are gone
+const TURRET_RELOAD: f64 = 1.2;
+const CORE_RELOAD: f64 = 1.5;
+
+/// Seconds during which the player is warned before the boss enters
+pub const WARNING_DURATION: f64 = 3.0;
+
+/// The points won by destroying a part, and the whole boss
+const PART_SCORE: u32 = 500;
+pub const BOSS_SCORE: u32 = 5_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PartKind {
+    /// Fires at the player
+    Turret,
+
+    /// Protects the core as long as any of them stands
+    ShieldGenerator,
+
+    /// Destroying it destroys the boss
+    Core,
+}
+
+impl PartKind {
+    /// Return the color of the part, with its brightness multiplied by
+    /// 'brightness'
+    fn color(self, brightness: f64) -> Color {
+        let (r, g, b) = match self {
+            PartKind::Turret => (200.0, 120.0, 40.0),
+            PartKind::ShieldGenerator => (60.0, 140.0, 230.0),
+            PartKind::Core => (230.0, 40.0, 40.0),
+        };
+        Color::RGB((r * brightness) as u8, (g * brightness) as u8, (b * brightness) as u8)
+    }
+}
+
+/// A piece of the boss with its own hitbox and hit points
+#[derive(Clone, Debug)]
+pub struct Part {
+    pub kind: PartKind,
+
+    // Relative to the top-left corner of the boss
+    offset: Rectangle,
+
+    pub hp: u32,
+    max_hp: u32,
+
+    // Seconds before the part fires again
+    reload: f64,
+
+    // Seconds during which the part flashes after a hit
+    flash: f64,
+
+    // The hit points left, as shown at the top of the screen
+    health: Bar,
+}
+
+impl Part {
+    fn new(kind: PartKind, offset: Rectangle, hp: u32) -> Part {
+        Part {
+            kind: kind,
+            offset: offset,
+            hp: hp,
+            max_hp: hp,
+            reload: TURRET_RELOAD,
+            flash: 0.0,
+            health: Bar::new(kind.color(1.0)).with_background(kind.color(0.25)),
+        }
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.hp == 0
+    }
+}
+
+/// A bullet fired by an enemy, which ends the game when it hits the ship
+#[derive(Clone)]
+pub struct EnemyBullet {
+    pub rect: Rectangle,
+    vel: (f64, f64),
+}
+
+impl EnemyBullet {
+    /// Create a bullet centered on 'pos' flying toward 'target'
+    pub fn aimed(pos: (f64, f64), target: (f64, f64)) -> EnemyBullet {
+        let (dx, dy) = (target.0 - pos.0, target.1 - pos.1);
+        let dist = (dx * dx + dy * dy).sqrt();
+        let dir = if dist > 0.0 { (dx / dist, dy / dist) } else { (-1.0, 0.0) };
+        EnemyBullet::with_direction(pos, dir)
+    }
+
+    /// Create a bullet centered on 'pos' flying along the unit vector 'dir'
+    pub fn with_direction(pos: (f64, f64), dir: (f64, f64)) -> EnemyBullet {
+        EnemyBullet {
+            rect: Rectangle {
+                x: 0.0,
+                y: 0.0,
+                w: BOSS_BULLET_SIDE,
+                h: BOSS_BULLET_SIDE,
+            }.with_center(pos),
+            vel: (dir.0 * BOSS_BULLET_SPEED, dir.1 * BOSS_BULLET_SPEED),
+        }
+    }
+
+    /// Make the bullet fly 'factor' times as fast
+    pub fn speed_up(&mut self, factor: f64) {
+        self.vel = (self.vel.0 * factor, self.vel.1 * factor);
+    }
+
+    /// Move the bullet
+    pub fn update(&mut self, dt: f64) {
+        self.rect.x += self.vel.0 * dt;
+        self.rect.y += self.vel.1 * dt;
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        let color = phi.palette_color(Color::RGB(255, 90, 200));  // pink
+        phi.renderer.set_draw_color(color);
+        phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
+    }
+}
+
+/// A large enemy made of destructible parts. Its turrets fire at the
+/// player; once they are gone, the core fires volleys itself. The core
+/// can only be hit after every shield generator has been destroyed.
+#[derive(Clone)]
+pub struct Boss {
+    // The bounding box of the whole boss
+    rect: Rectangle,
+    parts: Vec<Part>,
+
+    // Seconds since the boss appeared, used for its movement
+    total_time: f64,
+
+    // Seconds left before the boss starts entering the screen
+    warning: f64,
+}
+
+impl Boss {
+    /// Create a boss entering from the right of the screen
+    pub fn new(phi: &mut Phi) -> Boss {
+        let (w, h) = phi.output_size();
+        let part = |x, y, w, h| Rectangle { x: x, y: y, w: w, h: h };
+
+        Boss {
+            rect: Rectangle {
+                x: w,
+                y: (h - 240.0) / 2.0,
+                w: 160.0,
+                h: 240.0,
+            },
+            parts: vec![
+                Part::new(PartKind::Turret, part(0.0, 20.0, 40.0, 30.0), 8),
+                Part::new(PartKind::Turret, part(0.0, 190.0, 40.0, 30.0), 8),
+                Part::new(PartKind::ShieldGenerator, part(90.0, 0.0, 50.0, 50.0), 12),
+                Part::new(PartKind::ShieldGenerator, part(90.0, 190.0, 50.0, 50.0), 12),
+                Part::new(PartKind::Core, part(40.0, 80.0, 80.0, 80.0), 30),
+            ],
+            total_time: 0.0,
+            warning: WARNING_DURATION,
+        }
+    }
+
+    /// Whether the player is being warned that the boss is coming
+    pub fn is_warning(&self) -> bool {
+        self.warning > 0.0
+    }
+
+    /// Return the bounding box of every part still standing, in screen
+    /// coordinates
+    pub fn hitboxes(&self) -> Vec<Rectangle> {
+        self.parts.iter()
+            .filter(|part| !part.is_destroyed())
+            .map(|part| self.part_rect(part))
+            .collect()
+    }
+
+    fn part_rect(&self, part: &Part) -> Rectangle {
+        Rectangle {
+            x: self.rect.x + part.offset.x,
+            y: self.rect.y + part.offset.y,
+            ..part.offset
+        }
+    }
+
+    fn is_shielded(&self) -> bool {
+        self.parts.iter()
+            .any(|part| part.kind == PartKind::ShieldGenerator && !part.is_destroyed())
+    }
+
+    fn has_turrets(&self) -> bool {
+        self.parts.iter()
+            .any(|part| part.kind == PartKind::Turret && !part.is_destroyed())
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.parts.iter().any(|part| part.kind == PartKind::Core && part.is_destroyed())
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        self.rect.center()
+    }
+
+    /// Whether a bullet at 'rect' would hit a part still standing
+    pub fn is_hit_by(&self, rect: Rectangle) -> bool {
+        self.parts.iter()
+            .any(|part| !part.is_destroyed() && self.part_rect(part).overlaps(rect))
+    }
+
+    /// Apply a bullet hit dealing 'damage' to the part under 'rect', if any
+    /// can be hit. Return None if the bullet missed, otherwise the damage
+    /// actually dealt and the points it won.
+    pub fn hit(&mut self, rect: Rectangle, damage: u32) -> Option<(u32, u32)> {
+        let shielded = self.is_shielded();
+        let boss_rect = self.rect;
+
+        for part in &mut self.parts {
+            let part_rect = Rectangle {
+                x: boss_rect.x + part.offset.x,
+                y: boss_rect.y + part.offset.y,
+                ..part.offset
+            };
+
+            if part.is_destroyed() || !part_rect.overlaps(rect) {
+                continue;
+            }
+
+            // The shield absorbs the bullets aimed at the core
+            if part.kind == PartKind::Core && shielded {
+                return Some((0, 0));
+            }
+
+            let dealt = damage.min(part.hp);
+            part.hp -= dealt;
+            part.flash = HIT_FLASH;
+            part.health.set(part.hp as f64 / part.max_hp as f64);
+            return Some((dealt, if part.is_destroyed() { PART_SCORE } else { 0 }));
+        }
+
+        None
+    }
+
+    /// Move the boss and let it fire at 'target'. Return the bullets it
+    /// fired.
+    pub fn update(&mut self, phi: &mut Phi, target: (f64, f64), dt: f64) -> Vec<EnemyBullet> {
+        if self.is_warning() {
+            self.warning -= dt;
+            return vec![];
+        }
+
+        let (w, h) = phi.output_size();
+        self.total_time += dt;
+        for part in &mut self.parts {
+            part.flash -= dt;
+            part.health.update(dt);
+        }
+
+        // Enter the screen, then hover up and down
+        let rest_x = w - BOSS_MARGIN - self.rect.w;
+        if self.rect.x > rest_x {
+            self.rect.x = (self.rect.x - BOSS_ENTRY_SPEED * dt).max(rest_x);
+            return vec![];
+        }
+        self.rect.y = (h - self.rect.h) / 2.0 + (self.total_time * 0.7).sin() * h * 0.2;
+
+        // The turrets aim at the player; without them, the core fires
+        // volleys straight ahead
+        let mut bullets = vec![];
+        let has_turrets = self.has_turrets();
+        let boss_rect = self.rect;
+
+        for part in &mut self.parts {
+            if part.is_destroyed() {
+                continue;
+            }
+
+            let firing = match part.kind {
+                PartKind::Turret => true,
+                PartKind::Core => !has_turrets,
+                PartKind::ShieldGenerator => false,
+            };
+            if !firing {
+                continue;
+            }
+
+            part.reload -= dt;
+            if part.reload > 0.0 {
+                continue;
+            }
+
+            let center = Rectangle {
+                x: boss_rect.x + part.offset.x,
+                y: boss_rect.y + part.offset.y,
+                ..part.offset
+            }.center();
+
+            match part.kind {
+                PartKind::Turret => {
+                    part.reload = TURRET_RELOAD;
+                    bullets.push(EnemyBullet::aimed(center, target));
+                },
+                _ => {
+                    part.reload = CORE_RELOAD;
+                    for i in -2..3 {
+                        let angle = ::std::f64::consts::PI + i as f64 * 0.25;
+                        bullets.push(EnemyBullet::with_direction(center, (angle.cos(), angle.sin())));
+                    }
+                },
+            }
+        }
+
+        bullets
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        // The hull holding the parts together
+        phi.renderer.set_draw_color(Color::RGB(60, 60, 80));
+        phi.renderer.fill_rect(self.rect.inflate(-20.0, -10.0).to_sdl().unwrap());
+
+        let shielded = self.is_shielded();
+
+        for part in &self.parts {
+            if part.is_destroyed() {
+                continue;
+            }
+
+            // Parts get darker as they are damaged
+            let health = part.hp as f64 / part.max_hp as f64;
+            let color = match part.kind {
+                _ if part.flash > 0.0 && !phi.settings.reduce_flashes && phi.quality.post_effects() => Color::RGB(255, 255, 255),
+                PartKind::Core if shielded => Color::RGB(80, 80, 120),
+                kind => phi.palette_color(kind.color(0.4 + 0.6 * health)),
+            };
+
+            phi.renderer.set_draw_color(color);
+            phi.renderer.fill_rect(self.part_rect(part).to_sdl().unwrap());
+
+            // The shield wraps the core in a faint, pulsing bubble of light
+            if part.kind == PartKind::Core && shielded {
+                let rect = self.part_rect(part);
+                let pulse = 0.9 + 0.1 * (self.total_time * 4.0).sin();
+                let radius = (rect.w.max(rect.h) * 0.75 + 8.0) * pulse;
+                phi.renderer.set_blend_mode(Blending::Additive.to_sdl());
+                phi.renderer.set_draw_color(Color::RGBA(60, 110, 230, 70));
+                fill_circle(phi, rect.center(), radius);
+                phi.renderer.set_blend_mode(BlendMode::None);
+            }
+        }
+    }
+
+    /// Render the health of the boss across the top of the screen, with one
+    /// segment per part, sized after the part's hit points
+    pub fn render_health(&self, phi: &mut Phi) {
+        let gap = phi.scaled(4.0);
+        let border = phi.scaled(3.0);
+
+        let (w, _) = phi.output_size();
+        let bar = Rectangle {
+            x: w * 0.25,
+            y: phi.scaled(14.0),
+            w: w * 0.5,
+            h: phi.scaled(12.0),
+        };
+
+        shapes::fill_rounded_rect(&mut phi.renderer, bar.inflate(border, border),
+                                  border, Color::RGB(30, 30, 40));
+
+        let total_hp: u32 = self.parts.iter().map(|part| part.max_hp).sum();
+        let usable_w = bar.w - gap * (self.parts.len() - 1) as f64;
+        let mut x = bar.x;
+
+        for part in &self.parts {
+            let segment_w = usable_w * part.max_hp as f64 / total_hp as f64;
+            part.health.render(phi, Rectangle {
+                x: x,
+                y: bar.y,
+                w: segment_w,
+                h: bar.h,
+            });
+            x += segment_w + gap;
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// views/bullets.rs
+
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::parallel;
+
+/// The trajectory a bullet of the player follows, and how it looks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BulletKind {
+    /// Fired straight ahead by the default cannon
+    Rect,
+
+    /// Waves up and down around its path
+    Sine { amplitude: f64, angular_vel: f64 },
+
+    /// Follows a vertical trajectory described by:
+    ///     a * ( (t / b)^3 - (t / b)^2)
+    /// where 'a' influences the height and 'b' the width
+    Divergent { a: f64, b: f64 },
+
+    /// Fired in any direction, toward the crosshair or by the drones
+    Aimed,
+}
+
+impl BulletKind {
+    /// Return how far the bullet is displaced vertically from its path
+    /// 't' seconds after being fired
+    fn offset(&self, t: f64) -> f64 {
+        match *self {
+            BulletKind::Sine { amplitude, angular_vel } =>
+                amplitude * f64::sin(angular_vel * t),
+            BulletKind::Divergent { a, b } =>
+                a * ((t / b).powi(3) - (t / b).powi(2)),
+            BulletKind::Rect | BulletKind::Aimed => 0.0,
+        }
+    }
+
+    /// Return the color of the bullet, unless the theme overrides it
+    fn default_color(&self) -> Color {
+        match *self {
+            BulletKind::Rect => Color::RGB(230, 230, 30),            // yellow
+            BulletKind::Sine { .. } => Color::RGB(30, 230, 30),      // greenish
+            BulletKind::Divergent { .. } => Color::RGB(230, 30, 30), // reddish
+            BulletKind::Aimed => Color::RGB(30, 230, 230),           // cyan
+        }
+    }
+
+    /// Whether the bullet briefly freezes the game when it hits
+    pub fn is_heavy(&self) -> bool {
+        match *self {
+            BulletKind::Divergent { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// A bullet as it is fired, before it is added to the others
+#[derive(Clone, Copy, Debug)]
+pub struct Bullet {
+    pub kind: BulletKind,
+
+    /// Where the bullet starts
+    pub rect: Rectangle,
+
+    /// The velocity of the bullet along its path, in pixels/second
+    pub vel: (f64, f64),
+}
+
+/// The bullets of the player, each property of which is stored in its own
+/// array so that they are all updated in one tight loop
+#[derive(Clone)]
+pub struct Bullets {
+    kinds: Vec<BulletKind>,
+
+    // Where every bullet would be if it followed a straight path, the
+    // velocity along that path, which external forces such as gravity
+    // wells change, and the size of the bullet
+    pos: Vec<(f64, f64)>,
+    vel: Vec<(f64, f64)>,
+    size: Vec<(f64, f64)>,
+
+    // Seconds since every bullet was fired
+    time: Vec<f64>,
+}
+
+impl Bullets {
+    pub fn new() -> Bullets {
+        Bullets {
+            kinds: vec![],
+            pos: vec![],
+            vel: vec![],
+            size: vec![],
+            time: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.kinds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    pub fn push(&mut self, bullet: Bullet) {
+        self.kinds.push(bullet.kind);
+        self.pos.push((bullet.rect.x, bullet.rect.y));
+        self.vel.push(bullet.vel);
+        self.size.push((bullet.rect.w, bullet.rect.h));
+        self.time.push(0.0);
+    }
+
+    /// Move the bullets out of 'bullets' and add them to the others
+    pub fn append(&mut self, bullets: &mut Vec<Bullet>) {
+        for bullet in bullets.drain(..) {
+            self.push(bullet);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.kinds.clear();
+        self.pos.clear();
+        self.vel.clear();
+        self.size.clear();
+        self.time.clear();
+    }
+
+    /// Move every bullet along its trajectory
+    pub fn update(&mut self, dt: f64) {
+        parallel::zip_for_each_mut(&mut self.pos, &self.vel, |pos, vel| {
+            pos.0 += vel.0 * dt;
+            pos.1 += vel.1 * dt;
+        });
+        parallel::for_each_mut(&mut self.time, |time| *time += dt);
+    }
+
+    /// Accelerate every bullet by the result of 'accel', in
+    /// pixels/second², for 'dt' seconds. 'accel' is given the center of
+    /// the bullet.
+    pub fn accelerate<F: Fn((f64, f64)) -> (f64, f64)>(&mut self, accel: F, dt: f64) {
+        for index in 0..self.len() {
+            let (ax, ay) = accel(self.rect(index).center());
+            self.vel[index].0 += ax * dt;
+            self.vel[index].1 += ay * dt;
+        }
+    }
+
+    /// Return the bounding box of the bullet at 'index'
+    pub fn rect(&self, index: usize) -> Rectangle {
+        let (x, y) = self.pos[index];
+        let (w, h) = self.size[index];
+        Rectangle {
+            x: x,
+            y: y + self.kinds[index].offset(self.time[index]),
+            w: w,
+            h: h,
+        }
+    }
+
+    pub fn kind(&self, index: usize) -> BulletKind {
+        self.kinds[index]
+    }
+
+    /// Keep only the bullets whose bounding box satisfies 'predicate'
+    pub fn retain<F: FnMut(Rectangle) -> bool>(&mut self, mut predicate: F) {
+        let kept: Vec<bool> = (0..self.len()).map(|index| predicate(self.rect(index))).collect();
+        self.keep(&kept);
+    }
+
+    /// Keep only the bullets at the indices where 'kept' is true, in order
+    pub fn keep(&mut self, kept: &[bool]) {
+        let mut next = 0;
+        for index in 0..self.len() {
+            if kept[index] {
+                self.kinds.swap(next, index);
+                self.pos.swap(next, index);
+                self.vel.swap(next, index);
+                self.size.swap(next, index);
+                self.time.swap(next, index);
+                next += 1;
+            }
+        }
+
+        self.kinds.truncate(next);
+        self.pos.truncate(next);
+        self.vel.truncate(next);
+        self.size.truncate(next);
+        self.time.truncate(next);
+    }
+
+    /// Render the bullets which overlap 'visible'
+    pub fn render(&self, phi: &mut Phi, visible: Rectangle) {
+        let theme_color = phi.assets.theme().bullet_color;
+        for index in 0..self.len() {
+            let rect = self.rect(index);
+            if !visible.overlaps(rect) {
+                continue;
+            }
+
+            let color = phi.palette_color(theme_color.unwrap_or(self.kinds[index].default_color()));
+            phi.renderer.set_draw_color(color);
+            phi.renderer.fill_rect(rect.to_sdl().unwrap());
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// views/combo.rs
+
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+
+/// Seconds without a kill after which the combo is lost
+const COMBO_DECAY: f64 = 3.0;
+
+/// The number of kills in a row from which the score is multiplied by 2,
+/// 3, 4, and so on
+const COMBO_TIERS: [u32; 3] = [5, 15, 30];
+
+/// Counts the kills made in quick succession, and multiplies the points
+/// they are worth as the count grows
+#[derive(Clone)]
+pub struct Combo {
+    kills: u32,
+
+    // Seconds left before the combo is lost
+    timer: f64,
+}
+
+impl Combo {
+    pub fn new() -> Combo {
+        Combo {
+            kills: 0,
+            timer: 0.0,
+        }
+    }
+
+    pub fn kills(&self) -> u32 {
+        self.kills
+    }
+
+    /// Return the number of tiers the combo reached
+    pub fn tier(&self) -> usize {
+        COMBO_TIERS.iter().filter(|&&threshold| self.kills >= threshold).count()
+    }
+
+    pub fn multiplier(&self) -> u32 {
+        self.tier() as u32 + 1
+    }
+
+    /// Count a kill worth 'points'. Return the points actually won, after
+    /// the multiplier of the combo (including this kill) is applied.
+    pub fn kill(&mut self, points: u32) -> u32 {
+        self.kills += 1;
+        self.timer = COMBO_DECAY;
+        points * self.multiplier()
+    }
+
+    /// Lose the combo, for instance when the ship is hit
+    pub fn reset(&mut self) {
+        self.kills = 0;
+        self.timer = 0.0;
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        if self.kills > 0 {
+            self.timer -= dt;
+            if self.timer <= 0.0 {
+                self.reset();
+            }
+        }
+    }
+
+    /// Render the time left before the combo is lost, as a bar under 'label'
+    /// whose color reflects the tier
+    pub fn render_meter(&self, phi: &mut Phi, label: Rectangle) {
+        if self.kills == 0 {
+            return;
+        }
+
+        let color = match self.tier() {
+            0 => Color::RGB(200, 200, 200),
+            1 => Color::RGB(90, 220, 120),
+            2 => Color::RGB(240, 200, 40),
+            _ => Color::RGB(240, 80, 40),
+        };
+
+        let (gap, height) = (phi.scaled(2.0), phi.scaled(4.0));
+        let color = phi.palette_color(color);
+        phi.renderer.set_draw_color(color);
+        phi.renderer.fill_rect(Rectangle {
+            x: label.x,
+            y: label.y + label.h + gap,
+            w: (label.w * self.timer / COMBO_DECAY).max(1.0),
+            h: height.max(1.0),
+        }.to_sdl().unwrap());
+    }
+}
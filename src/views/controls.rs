@@ -0,0 +1,234 @@
+// views/controls.rs
+
+use ::sdl2::pixels::Color;
+
+use ::phi::controllers::{PadAction, PadBindings};
+use ::phi::data::Rectangle;
+use ::phi::focus::Focus;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::settings::SETTINGS_PATH;
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+
+use ::views::shared::{self, Backgrounds};
+
+/// The player whose controller is set up
+const PLAYER_ID: usize = 0;
+
+const ROW_H: f64 = 40.0;
+
+/// A line of the controls screen
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    Bind(PadAction),
+    SwapSticks,
+    InvertY,
+}
+
+/// Lets the player bind the buttons of their controller to the actions of
+/// the game, and choose how its sticks are used. Bindings are saved for the
+/// model of the controller plugged in.
+pub struct ControlsView {
+    backgrounds: Backgrounds,
+
+    rows: Vec<Row>,
+    focus: Focus,
+
+    // The action waiting for a button to be pressed, if any
+    binding: Option<PadAction>,
+
+    title: Sprite,
+    labels: Vec<Sprite>,
+
+    // Shown instead of the rows while no controller is plugged in
+    no_controller: Sprite,
+}
+
+impl ControlsView {
+    pub fn new_with_backgrounds(phi: &mut Phi, backgrounds: Backgrounds) -> ControlsView {
+        let mut rows: Vec<Row> = PadAction::all().into_iter().map(Row::Bind).collect();
+        rows.push(Row::SwapSticks);
+        rows.push(Row::InvertY);
+
+        let title = phi.locale.text("controls.title");
+        let no_controller = phi.locale.text("controls.no_controller");
+
+        let mut view = ControlsView {
+            backgrounds: backgrounds,
+            focus: Focus::new(rows.len()),
+            rows: rows,
+            binding: None,
+            title: phi.ttf_str_sprite(&title, "menu_font", 32, Color::RGB(255, 255, 255)).unwrap(),
+            labels: vec![],
+            no_controller: phi.ttf_str_sprite(&no_controller, "menu_font", 20,
+                                              Color::RGB(180, 180, 180)).unwrap(),
+        };
+        view.refresh(phi);
+        view
+    }
+
+    /// Render the rows again, after a binding changed or another row was
+    /// selected
+    fn refresh(&mut self, phi: &mut Phi) {
+        let bindings = phi.pad_bindings(PLAYER_ID);
+        let on = phi.locale.text("controls.on");
+        let off = phi.locale.text("controls.off");
+
+        self.labels = self.rows.iter().enumerate().map(|(i, &row)| {
+            let text = match row {
+                Row::Bind(action) if self.binding == Some(action) => {
+                    let action = phi.locale.text(action.label_key());
+                    phi.locale.format("controls.waiting", &[&action])
+                },
+                Row::Bind(action) => {
+                    let action = phi.locale.text(action.label_key());
+                    phi.locale.format("controls.binding", &[&action, &bindings.input(action).name()])
+                },
+                Row::SwapSticks => {
+                    let state = if bindings.swap_sticks { &on } else { &off };
+                    phi.locale.format("controls.swap_sticks", &[state])
+                },
+                Row::InvertY => {
+                    let state = if bindings.invert_y { &on } else { &off };
+                    phi.locale.format("controls.invert_y", &[state])
+                },
+            };
+            let color = if i == self.focus.index() { Color::RGB(240, 200, 40) }
+                        else { Color::RGB(220, 220, 220) };
+            phi.ttf_str_sprite(&text, "menu_font", 24, color).unwrap()
+        }).collect();
+    }
+
+    /// Change the bindings of the controller plugged in, and save them
+    fn update_bindings<F: FnOnce(&mut PadBindings)>(phi: &mut Phi, change: F) {
+        let guid = match phi.events.controllers.guid(PLAYER_ID) {
+            Some(guid) => guid,
+            None => return,
+        };
+
+        let mut bindings = phi.settings.pad_bindings(&guid);
+        change(&mut bindings);
+        phi.settings.set_pad_bindings(&guid, bindings);
+        if let Err(err) = phi.settings.save(SETTINGS_PATH) {
+            warn!("Could not save the settings: {}", err);
+        }
+    }
+}
+
+impl View for ControlsView {
+    fn resume(&mut self, phi: &mut Phi) {
+        shared::show_menu_cursor(phi);
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        let connected = phi.events.controllers.is_connected(PLAYER_ID);
+        let mut changed = phi.events.now.controller_connected.is_some()
+            || phi.events.now.controller_disconnected.is_some();
+
+        if let Some(action) = self.binding {
+            // The next button pressed on the controller is bound to the
+            // action, unless the player gives up
+            let pressed = phi.events.now.pad_inputs.iter()
+                .find(|&&(player, _)| player == PLAYER_ID)
+                .map(|&(_, input)| input);
+
+            if let Some(input) = pressed {
+                ControlsView::update_bindings(phi, |bindings| bindings.bind(action, input));
+                self.binding = None;
+                changed = true;
+            }
+            else if phi.events.now.key_escape == Some(true) || !connected {
+                self.binding = None;
+                changed = true;
+            }
+        }
+        else {
+            if self.focus.cancelled(phi) {
+                return ViewAction::ChangeView(Box::new(
+                    ::views::main_menu::MainMenuView::options(
+                        phi, self.backgrounds.clone())));
+            }
+
+            if self.focus.navigate(phi) {
+                changed = true;
+            }
+
+            if connected && self.focus.activated(phi) {
+                match self.rows[self.focus.index()] {
+                    Row::Bind(action) => self.binding = Some(action),
+                    Row::SwapSticks => ControlsView::update_bindings(
+                        phi, |bindings| bindings.swap_sticks = !bindings.swap_sticks),
+                    Row::InvertY => ControlsView::update_bindings(
+                        phi, |bindings| bindings.invert_y = !bindings.invert_y),
+                }
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.refresh(phi);
+        }
+
+        // Clear the screen . . .
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.backgrounds.back.render(&mut phi.renderer, elapsed);
+        self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+        self.backgrounds.front.render(&mut phi.renderer, elapsed);
+
+        // Render the title, then the rows under it
+        let (win_w, win_h) = phi.output_size();
+        let row_h = phi.scaled(ROW_H);
+        let top = (win_h - row_h * (self.rows.len() + 1) as f64) / 2.0;
+
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle {
+            x: (win_w - w) / 2.0,
+            y: top,
+            w: w,
+            h: h,
+        });
+
+        if !connected {
+            self.focus.set_layout(vec![], (0.0, 0.0), None);
+            let (w, h) = self.no_controller.size();
+            self.no_controller.render(&mut phi.renderer, Rectangle {
+                x: (win_w - w) / 2.0,
+                y: top + row_h,
+                w: w,
+                h: h,
+            });
+            return ViewAction::None;
+        }
+
+        // The mouse picks a row anywhere on its line, as wide as the widest
+        let rows_w = self.labels.iter().map(|label| label.size().0).fold(0.0, f64::max);
+        self.focus.set_layout((0..self.labels.len()).map(|i| Rectangle {
+            x: (win_w - rows_w) / 2.0,
+            y: top + row_h * (i + 1) as f64,
+            w: rows_w,
+            h: row_h,
+        }).collect(), (0.0, 0.0), None);
+
+        for (i, label) in self.labels.iter().enumerate() {
+            let (w, h) = label.size();
+            label.render(&mut phi.renderer, Rectangle {
+                x: (win_w - w) / 2.0,
+                y: top + row_h * (i + 1) as f64 + (row_h - h) / 2.0,
+                w: w,
+                h: h,
+            });
+        }
+
+        ViewAction::None
+    }
+}
@@ -0,0 +1,166 @@
+// views/credits.rs
+
+use ::std::fs::File;
+use ::std::io::{BufRead, BufReader};
+
+use ::sdl2::controller::Button;
+use ::sdl2::pixels::Color;
+
+use ::phi::controllers::PadInput;
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{Phi, View, ViewAction};
+
+use ::views::shared::Backgrounds;
+use ::views::splash::{self, ATTRIBUTION_PATH};
+
+pub const CREDITS_PATH: &'static str = "assets/credits.txt";
+
+/// The pixels the credits move up every second, and the height of an empty
+/// line, before the UI scale is applied
+const SCROLL_SPEED: f64 = 50.0;
+const SPACE_H: f64 = 30.0;
+
+/// A line of the credits
+#[derive(Clone, Debug, PartialEq)]
+pub enum Line {
+    /// The text of the string tables under the key, as a title
+    Heading(String),
+
+    /// Text shown as it is, such as the name of an author
+    Name(String),
+
+    /// An empty line
+    Space,
+}
+
+/// Read the credits at 'path'. Every line holds a line of the credits:
+///
+/// * `heading KEY`
+/// * `name TEXT`
+/// * `attribution`, for the credits of the assets, which the splash screen
+///   shows as well
+/// * `space`
+///
+/// Empty lines and lines starting with `#` are ignored.
+pub fn load_credits(path: &str) -> Vec<Line> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => {
+            warn!("Could not open the credits in {}", path);
+            return vec![];
+        }
+    };
+
+    let mut lines = vec![];
+
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, ' ');
+        let (key, value) = (parts.next().unwrap_or(""), parts.next().unwrap_or("").trim());
+        match key {
+            "heading" if !value.is_empty() => lines.push(Line::Heading(value.to_string())),
+            "name" if !value.is_empty() => lines.push(Line::Name(value.to_string())),
+            "attribution" => lines.extend(splash::load_attribution(ATTRIBUTION_PATH).into_iter().map(Line::Name)),
+            "space" => lines.push(Line::Space),
+            _ => warn!("Ignoring malformed line in {}: {}", path, line),
+        }
+    }
+
+    lines
+}
+
+/// Rolls the credits up the screen over the backgrounds, then goes back to
+/// the main menu. Return, Escape, or the A or B button of the controller
+/// leave early.
+pub struct CreditsView {
+    backgrounds: Backgrounds,
+
+    // The lines, None for empty ones, and how far they moved up
+    lines: Vec<Option<Sprite>>,
+    scroll: f64,
+}
+
+impl CreditsView {
+    pub fn new_with_backgrounds(phi: &mut Phi, backgrounds: Backgrounds) -> CreditsView {
+        let lines = load_credits(CREDITS_PATH).into_iter().map(|line| match line {
+            Line::Heading(key) => {
+                let text = phi.locale.text(&key);
+                phi.ttf_str_sprite(&text, "menu_font", 32, Color::RGB(240, 200, 40))
+            },
+            Line::Name(text) => phi.ttf_str_sprite(&text, "menu_font", 20, Color::RGB(230, 230, 230)),
+            Line::Space => None,
+        }).collect();
+
+        CreditsView {
+            backgrounds: backgrounds,
+            lines: lines,
+            scroll: 0.0,
+        }
+    }
+}
+
+impl View for CreditsView {
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        let (win_w, win_h) = phi.output_size();
+        let space_h = phi.scaled(SPACE_H);
+        let total_h: f64 = self.lines.iter()
+            .map(|line| line.as_ref().map_or(space_h, |sprite| sprite.size().1))
+            .sum();
+
+        // The credits start below the screen, and are over once the last
+        // line left it at the top
+        self.scroll += phi.scaled(SCROLL_SPEED) * elapsed;
+        let skipped = phi.events.now.key_return == Some(true) || phi.events.now.key_escape == Some(true)
+                      || phi.events.pad_pressed(0, PadInput::Button(Button::A))
+                      || phi.events.pad_pressed(0, PadInput::Button(Button::B));
+        if skipped || self.scroll > win_h + total_h {
+            return ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())));
+        }
+
+        // Clear the screen . . .
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.backgrounds.back.render(&mut phi.renderer, elapsed);
+        self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+        self.backgrounds.front.render(&mut phi.renderer, elapsed);
+
+        // Render the lines which are on screen
+        let mut y = win_h - self.scroll;
+        for line in &self.lines {
+            match *line {
+                Some(ref sprite) => {
+                    let (w, h) = sprite.size();
+                    if y + h > 0.0 && y < win_h {
+                        sprite.render(&mut phi.renderer, Rectangle {
+                            x: (win_w - w) / 2.0,
+                            y: y,
+                            w: w,
+                            h: h,
+                        });
+                    }
+                    y += h;
+                },
+                None => y += space_h,
+            }
+        }
+
+        ViewAction::None
+    }
+}
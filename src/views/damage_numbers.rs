@@ -0,0 +1,137 @@
+// views/damage_numbers.rs
+
+use ::std::fmt::Write;
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::bitmap_font::BitmapFont;
+
+/// The characters of the damage numbers
+const GLYPHS: &'static str = "0123456789!";
+
+/// The most numbers shown at once; past this, the oldest one is reused
+const POOL_SIZE: usize = 48;
+
+/// Seconds a number stays on screen, the last of which it fades out during
+const LIFETIME: f64 = 0.8;
+const FADE: f64 = 0.3;
+
+/// How far numbers rise over their lifetime, in pixels. They start fast
+/// and slow down.
+const RISE: f64 = 36.0;
+
+/// The size of the numbers relative to the glyphs. Crits are drawn at the
+/// size of the glyphs, and pop in from larger still.
+const NORMAL_SCALE: f64 = 0.65;
+const CRIT_POP_SCALE: f64 = 1.6;
+const CRIT_POP_TIME: f64 = 0.12;
+
+const NORMAL_COLOR: Color = Color::RGB(255, 255, 255);
+const CRIT_COLOR: Color = Color::RGB(255, 200, 40);
+
+/// A number of the pool; it is free once its lifetime is over
+struct DamageNumber {
+    text: String,
+    pos: (f64, f64),
+    age: f64,
+    crit: bool,
+}
+
+impl DamageNumber {
+    fn is_free(&self) -> bool {
+        self.age >= LIFETIME
+    }
+}
+
+/// The damage dealt by the player, shown where it was dealt as numbers
+/// rising and fading out. The numbers are drawn from a bitmap font and
+/// kept in a fixed pool, so that dozens of them per second allocate nothing
+/// and upload no texture.
+pub struct DamageNumbers {
+    font: Option<BitmapFont>,
+    numbers: Vec<DamageNumber>,
+}
+
+impl DamageNumbers {
+    pub fn new(phi: &mut Phi) -> DamageNumbers {
+        let font = phi.bitmap_font(GLYPHS, "menu_font", 28);
+        if font.is_none() {
+            warn!("Could not render the font of the damage numbers, they will not be shown");
+        }
+
+        DamageNumbers {
+            font: font,
+            numbers: (0..POOL_SIZE).map(|_| DamageNumber {
+                text: String::with_capacity(8),
+                pos: (0.0, 0.0),
+                age: LIFETIME,
+                crit: false,
+            }).collect(),
+        }
+    }
+
+    /// Show 'amount' of damage rising from 'pos', styled as a critical hit
+    /// if 'crit'
+    pub fn spawn(&mut self, pos: (f64, f64), amount: u32, crit: bool) {
+        // Take a free number, or the oldest one if they are all shown
+        let free = self.numbers.iter().position(DamageNumber::is_free);
+        let number = match free {
+            Some(index) => &mut self.numbers[index],
+            None => self.numbers.iter_mut()
+                .max_by(|a, b| a.age.partial_cmp(&b.age).unwrap())
+                .unwrap(),
+        };
+
+        number.text.clear();
+        let _ = write!(number.text, "{}", amount);
+        if crit {
+            number.text.push('!');
+        }
+        number.pos = pos;
+        number.age = 0.0;
+        number.crit = crit;
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        for number in self.numbers.iter_mut().filter(|number| !number.is_free()) {
+            number.age = (number.age + dt).min(LIFETIME);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for number in &mut self.numbers {
+            number.age = LIFETIME;
+        }
+    }
+
+    /// Render the numbers, centered on where the damage was dealt
+    pub fn render(&self, phi: &mut Phi) {
+        let font = match self.font {
+            Some(ref font) => font,
+            None => return,
+        };
+        let rise = phi.scaled(RISE);
+
+        for number in self.numbers.iter().filter(|number| !number.is_free()) {
+            let t = number.age / LIFETIME;
+            let alpha = ((LIFETIME - number.age) / FADE).min(1.0);
+            let (scale, color) = if number.crit {
+                let pop = (1.0 - number.age / CRIT_POP_TIME).max(0.0);
+                (1.0 + (CRIT_POP_SCALE - 1.0) * pop, CRIT_COLOR)
+            }
+            else {
+                (NORMAL_SCALE, NORMAL_COLOR)
+            };
+
+            let (r, g, b) = phi.palette_color(color).rgb();
+            font.set_color(Color::RGBA(r, g, b, (alpha * 255.0) as u8));
+
+            let (w, h) = font.text_size(&number.text, scale);
+            let offset = rise * (1.0 - (1.0 - t) * (1.0 - t));
+            font.render(&mut phi.renderer, &number.text,
+                        (number.pos.0 - w / 2.0, number.pos.1 - h / 2.0 - offset), scale);
+        }
+
+        font.set_color(Color::RGBA(255, 255, 255, 255));
+    }
+}
@@ -0,0 +1,220 @@
+// views/determinism.rs
+
+use ::rand::{Rng, SeedableRng, StdRng};
+use ::std::env;
+
+use ::phi::{Phi, View, ViewAction};
+use ::phi::random::Distributions;
+use ::views::game::GameView;
+use ::views::leaderboard::GameMode;
+use ::views::replay::{Input, Replay};
+use ::views::shared::Backgrounds;
+
+/// The option which starts the check, optionally followed by the replay
+/// whose frames both runs play, as in `--check-determinism=replays/run.txt`
+const CHECK_OPTION: &'static str = "--check-determinism";
+
+/// Without a replay, both runs play a script of random frames of a fixed
+/// duration, drawn from this seed, which the runs are also played with
+const SEED: u64 = 0x5eed;
+const SCRIPT_FRAMES: usize = 60 * 180;
+const FRAME_TIME: f64 = 1.0 / 60.0;
+
+/// Frames the script holds the same steering and cannons for
+const SCRIPT_STEP: usize = 30;
+
+/// Frames between two comparisons of the state of both runs
+const CHECK_INTERVAL: usize = 60;
+
+/// Frames played by both runs before drawing one, so that the check does
+/// not take as long as the runs would
+const FRAMES_PER_RENDER: usize = 300;
+
+/// Return, if the check was asked for on the command line, the path of the
+/// replay to play, or None to play a script of random frames.
+pub fn replay_from_args() -> Option<Option<String>> {
+    env::args()
+        .find(|arg| arg == CHECK_OPTION || arg.starts_with(&format!("{}=", CHECK_OPTION)))
+        .map(|arg| {
+            let path = arg[CHECK_OPTION.len()..].trim_left_matches('=');
+            if path.is_empty() { None } else { Some(path.to_string()) }
+        })
+}
+
+/// Return a script of random frames, the same every time
+fn script() -> Vec<Input> {
+    let mut rng = StdRng::from_seed(&[SEED as usize]);
+    let mut frames = Vec::with_capacity(SCRIPT_FRAMES);
+    let mut input = Input {
+        elapsed: FRAME_TIME,
+        game_speed: 1.0,
+        movement: (0.0, 0.0),
+        cannon: 1,
+        fire: false,
+        aim: None,
+        // Always continue after a game over, so that the runs go on
+        confirm: true,
+        restart: false,
+    };
+
+    for frame in 0..SCRIPT_FRAMES {
+        if frame % SCRIPT_STEP == 0 {
+            input.movement = (rng.range_f64(-1.0, 1.0), rng.range_f64(-1.0, 1.0));
+            input.cannon = rng.gen_range(1, 4);
+        }
+        input.fire = rng.chance(0.8);
+        input.restart = rng.chance(0.0005);
+        frames.push(input.clone());
+    }
+    frames
+}
+
+/// Plays two runs from the same seed and the same frames, side by side,
+/// and compares their state at regular intervals. Replays, and any play
+/// over the network, only work if a run always unfolds the same from the
+/// same seed and frames. If both runs ever differ, the check logs where as
+/// an error; otherwise it logs how many times the runs were compared. It
+/// quits either way.
+pub struct DeterminismView {
+    first: GameView,
+    second: GameView,
+
+    frames: Vec<Input>,
+    next: usize,
+    checks: usize,
+}
+
+impl DeterminismView {
+    pub fn new(phi: &mut Phi, replay: Option<String>) -> DeterminismView {
+        let (seed, mode, drones, frames) = match replay.as_ref().and_then(|path| Replay::load(path)) {
+            Some(replay) => {
+                if replay.size != phi.output_size() {
+                    warn!("The replay was recorded in a {}x{} window, and may end early in this one",
+                          replay.size.0, replay.size.1);
+                }
+                (replay.entry.seed, replay.mode, replay.drones, replay.frames)
+            },
+            None => {
+                if let Some(path) = replay {
+                    warn!("Checking with a script of random frames instead of {}", path);
+                }
+                (SEED, GameMode::Arcade, 0, script())
+            },
+        };
+
+        let first_backgrounds = Backgrounds::new(phi);
+        let second_backgrounds = Backgrounds::new(phi);
+        DeterminismView {
+            first: GameView::new_with_mode(phi, first_backgrounds, seed, mode, drones),
+            second: GameView::new_with_mode(phi, second_backgrounds, seed, mode, drones),
+            frames: frames,
+            next: 0,
+            checks: 0,
+        }
+    }
+
+    /// Compare the state of both runs, and return the first line of their
+    /// state which differs, if any
+    fn compare(&mut self) -> Result<(), String> {
+        let first = self.first.dump_state();
+        let second = self.second.dump_state();
+        if first == second {
+            self.checks += 1;
+            return Ok(());
+        }
+
+        let mut first_lines = first.lines();
+        let mut second_lines = second.lines();
+        loop {
+            match (first_lines.next(), second_lines.next()) {
+                (Some(a), Some(b)) if a == b => continue,
+                (a, b) => return Err(format!("\n  first run:  {}\n  second run: {}",
+                                             a.unwrap_or("(nothing)"), b.unwrap_or("(nothing)"))),
+            }
+        }
+    }
+
+    /// Play the next frame of both runs, comparing them when due. Return,
+    /// once the check is over, whether it passed, or where the runs differ.
+    fn step(&mut self, phi: &mut Phi) -> Option<Result<(), String>> {
+        if self.next >= self.frames.len() {
+            return Some(self.compare());
+        }
+
+        let input = self.frames[self.next].clone();
+        self.next += 1;
+        let first_over = self.first.simulate(phi, &input);
+        let second_over = self.second.simulate(phi, &input);
+
+        if first_over || second_over || self.next % CHECK_INTERVAL == 0 {
+            if let Err(difference) = self.compare() {
+                return Some(Err(difference));
+            }
+        }
+        if first_over != second_over {
+            return Some(Err(format!("only the {} run is over",
+                                    if first_over { "first" } else { "second" })));
+        }
+        if first_over { Some(Ok(())) } else { None }
+    }
+}
+
+impl View for DeterminismView {
+    fn resume(&mut self, phi: &mut Phi) {
+        info!("Checking that {} frames play the same twice", self.frames.len());
+        phi.limit_fps = false;
+    }
+
+    fn pause(&mut self, phi: &mut Phi) {
+        phi.limit_fps = true;
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        for _ in 0..FRAMES_PER_RENDER {
+            match self.step(phi) {
+                Some(Ok(())) => {
+                    info!("Determinism check passed: {} frames, compared {} times",
+                          self.next, self.checks);
+                    return ViewAction::Quit;
+                },
+                Some(Err(difference)) => {
+                    error!("Determinism check failed at frame {} of {}: {}",
+                           self.next, self.frames.len(), difference);
+                    return ViewAction::Quit;
+                },
+                None => {},
+            }
+        }
+
+        // Show how the first run is going
+        self.first.draw_frame(phi, elapsed);
+        ViewAction::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The frames of the script played by the test, to keep it short
+    const TEST_FRAMES: usize = 60 * 30;
+
+    #[test]
+    fn runs_play_the_same() {
+        ::phi::headless(|phi| {
+            let mut check = DeterminismView::new(phi, None);
+            check.frames.truncate(TEST_FRAMES);
+
+            let mut outcome = None;
+            while outcome.is_none() {
+                outcome = check.step(phi);
+            }
+            assert_eq!(outcome, Some(Ok(())));
+            assert!(check.checks >= TEST_FRAMES / CHECK_INTERVAL);
+        });
+    }
+}
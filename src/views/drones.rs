@@ -0,0 +1,115 @@
+// views/drones.rs
+
+use ::std::collections::VecDeque;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::gfx::{AnimatedSprite, Renderable};
+
+/// The maximum number of drones escorting the ship
+const MAX_DRONES: usize = 2;
+
+/// The number of frames by which every drone lags behind the one before
+/// it (the first one lags behind the ship itself)
+const DRONE_SPACING: usize = 10;
+
+const DRONE_W: f64 = 22.0;
+const DRONE_H: f64 = 20.0;
+
+/// Small ships following the player's path. Every drone fires along with
+/// the ship and absorbs one hit before being destroyed.
+#[derive(Clone)]
+pub struct Drones {
+    sprite: AnimatedSprite,
+
+    // The latest positions of the center of the ship, most recent first
+    history: VecDeque<(f64, f64)>,
+
+    // The number of drones escorting the ship
+    count: usize,
+}
+
+impl Drones {
+    pub fn new(sprite: AnimatedSprite) -> Drones {
+        Drones {
+            sprite: sprite,
+            history: VecDeque::with_capacity(MAX_DRONES * DRONE_SPACING + 1),
+            count: 0,
+        }
+    }
+
+    /// Add a drone to the escort. Return false if it is already full.
+    pub fn add(&mut self) -> bool {
+        if self.count < MAX_DRONES {
+            self.count += 1;
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    /// Destroy every drone of the escort
+    pub fn clear(&mut self) {
+        self.count = 0;
+    }
+
+    /// Return the number of drones escorting the ship
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Make the escort 'count' drones strong, as many as it can hold
+    pub fn set_count(&mut self, count: usize) {
+        self.count = count.min(MAX_DRONES);
+    }
+
+    /// Record the position of the ship, which the drones will reach in the
+    /// coming frames
+    pub fn follow(&mut self, ship_center: (f64, f64), dt: f64) {
+        self.history.push_front(ship_center);
+        self.history.truncate(MAX_DRONES * DRONE_SPACING + 1);
+        self.sprite.add_time(dt);
+    }
+
+    /// Return the centers of the drones
+    pub fn positions(&self) -> Vec<(f64, f64)> {
+        if self.history.is_empty() {
+            return vec![];
+        }
+
+        // Until the ship has moved long enough, the drones bunch up at the
+        // oldest known position
+        let oldest = self.history.len() - 1;
+        (1..self.count + 1)
+            .map(|i| self.history[::std::cmp::min(i * DRONE_SPACING, oldest)])
+            .collect()
+    }
+
+    fn hitboxes(&self) -> Vec<Rectangle> {
+        self.positions().into_iter().map(|center| Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: DRONE_W,
+            h: DRONE_H,
+        }.with_center(center)).collect()
+    }
+
+    /// If a drone touches 'rect', destroy the last drone of the escort and
+    /// return true; the drones behind the one hit move up to fill the gap.
+    pub fn absorb(&mut self, rect: Rectangle) -> bool {
+        if self.hitboxes().iter().any(|hitbox| hitbox.overlaps(rect)) {
+            self.count -= 1;
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        for hitbox in self.hitboxes() {
+            self.sprite.render(&mut phi.renderer, hitbox);
+        }
+    }
+}
@@ -0,0 +1,554 @@
+// views/editor.rs
+
+use ::std::env;
+
+use ::sdl2::keyboard::Keycode;
+use ::sdl2::pixels::Color;
+use ::sdl2::render::BlendMode;
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+
+use ::views::hazards::{self, HazardSpawn, Level};
+use ::views::shared::{self, Backgrounds};
+
+/// The option which opens the level editor, optionally followed by the
+/// level to edit, as in `--editor=assets/level.txt`
+const EDITOR_OPTION: &'static str = "--editor";
+
+/// Horizontal pixels for every second of the level
+const PIXELS_PER_SECOND: f64 = 12.0;
+
+/// Seconds of the level between two lines of the grid
+const GRID_SECONDS: f64 = 5.0;
+
+/// How far the arrows move the selected hazard, in seconds and in
+/// fractions of the screen, and scroll the level or the preview when
+/// nothing is selected
+const TIME_STEP: f64 = 0.5;
+const HEIGHT_STEP: f64 = 0.05;
+const SCROLL_STEP: f64 = 5.0;
+
+/// The pieces of debris of a new field
+const DEBRIS_COUNT: usize = 8;
+
+/// The extent of a field of debris, see hazards::Debris::field
+const DEBRIS_SPREAD: f64 = 150.0;
+
+const MARKER_SIDE: f64 = 10.0;
+
+/// Return the level to edit if the editor was asked for on the command
+/// line, the one of the game if no path was given
+pub fn path_from_args() -> Option<String> {
+    env::args()
+        .find(|arg| arg == EDITOR_OPTION || arg.starts_with(&format!("{}=", EDITOR_OPTION)))
+        .map(|arg| match arg[EDITOR_OPTION.len()..].trim_left_matches('=') {
+            "" => hazards::LEVEL_PATH.to_string(),
+            path => path.to_string(),
+        })
+}
+
+/// The kinds of hazards which may be placed
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Mine,
+    Debris,
+    Well,
+}
+
+impl Kind {
+    fn all() -> Vec<Kind> {
+        vec![Kind::Mine, Kind::Debris, Kind::Well]
+    }
+
+    fn of(spawn: &HazardSpawn) -> Kind {
+        match *spawn {
+            HazardSpawn::Mine { .. } => Kind::Mine,
+            HazardSpawn::Debris { .. } => Kind::Debris,
+            HazardSpawn::Well { .. } => Kind::Well,
+        }
+    }
+
+    /// A new hazard of this kind at height 'y'
+    fn spawn(self, y: f64) -> HazardSpawn {
+        match self {
+            Kind::Mine => HazardSpawn::Mine { y: y },
+            Kind::Debris => HazardSpawn::Debris { y: y, count: DEBRIS_COUNT },
+            Kind::Well => HazardSpawn::Well { y: y },
+        }
+    }
+
+    fn label_key(self) -> &'static str {
+        match self {
+            Kind::Mine => "editor.mine",
+            Kind::Debris => "editor.debris",
+            Kind::Well => "editor.well",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Kind::Mine => Color::RGB(230, 60, 40),
+            Kind::Debris => Color::RGB(170, 170, 170),
+            Kind::Well => Color::RGB(150, 80, 230),
+        }
+    }
+}
+
+/// Return the height of a hazard, in fractions of the screen
+fn height(spawn: &HazardSpawn) -> f64 {
+    match *spawn {
+        HazardSpawn::Mine { y } | HazardSpawn::Debris { y, .. } | HazardSpawn::Well { y } => y,
+    }
+}
+
+/// Return 'spawn' moved to the height 'y'
+fn with_height(spawn: &HazardSpawn, y: f64) -> HazardSpawn {
+    match *spawn {
+        HazardSpawn::Mine { .. } => HazardSpawn::Mine { y: y },
+        HazardSpawn::Debris { count, .. } => HazardSpawn::Debris { y: y, count: count },
+        HazardSpawn::Well { .. } => HazardSpawn::Well { y: y },
+    }
+}
+
+/// Lets the hazards of a level be placed on a timeline, with the level's
+/// backgrounds behind them, and previewed as they would appear in the game
+/// at any time of the level.
+pub struct EditorView {
+    path: String,
+    level: Level,
+    modified: bool,
+
+    backgrounds: Backgrounds,
+
+    // The kind of hazard placed by clicking, and the hazard being edited
+    kind: Kind,
+    selected: Option<usize>,
+
+    // The time of the level at the left of the screen
+    scroll: f64,
+
+    // The time shown by the preview while it is open, and whether it plays
+    preview: Option<f64>,
+    playing: bool,
+
+    // The status line, along with its text to tell when it changed, and
+    // the help of the editor and of the preview
+    status: Sprite,
+    status_text: String,
+    help: Sprite,
+    preview_help: Sprite,
+}
+
+impl EditorView {
+    pub fn new(phi: &mut Phi, path: &str) -> EditorView {
+        let level = Level::load(path);
+        let backgrounds = Backgrounds::load(
+            phi, level.background.as_ref().map_or(shared::DEFAULT_BACKGROUND, |name| &name[..]));
+
+        let help = phi.locale.text("editor.help");
+        let preview_help = phi.locale.text("editor.preview_help");
+
+        let mut view = EditorView {
+            path: path.to_string(),
+            level: level,
+            modified: false,
+            backgrounds: backgrounds,
+            kind: Kind::Mine,
+            selected: None,
+            scroll: 0.0,
+            preview: None,
+            playing: false,
+            status: phi.ttf_str_sprite(" ", "menu_font", 18, Color::RGB(255, 255, 255)).unwrap(),
+            status_text: String::new(),
+            help: phi.ttf_str_sprite(&help, "menu_font", 16, Color::RGB(200, 200, 200)).unwrap(),
+            preview_help: phi.ttf_str_sprite(&preview_help, "menu_font", 16,
+                                             Color::RGB(200, 200, 200)).unwrap(),
+        };
+        view.refresh(phi);
+        view
+    }
+
+    /// The name of the set of backgrounds the level is played over
+    fn background_name(&self) -> String {
+        self.level.background.clone().unwrap_or_else(|| shared::DEFAULT_BACKGROUND.to_string())
+    }
+
+    /// Render the status line again if what it shows changed
+    fn refresh(&mut self, phi: &mut Phi) {
+        let text = match self.preview {
+            Some(time) => phi.locale.format("editor.preview", &[&format!("{:.1}", time)]),
+            None => {
+                let kind = phi.locale.text(self.kind.label_key());
+                let background = self.background_name();
+                let modified = if self.modified { "*" } else { "" };
+                let mut text = phi.locale.format(
+                    "editor.status", &[&self.path, &modified, &kind, &background]);
+
+                if let Some(i) = self.selected {
+                    let (time, ref spawn) = self.level.spawns[i];
+                    let kind = phi.locale.text(Kind::of(spawn).label_key());
+                    let percent = (height(spawn) * 100.0).round();
+                    text.push_str("  |  ");
+                    text.push_str(&match *spawn {
+                        HazardSpawn::Debris { count, .. } => phi.locale.format(
+                            "editor.selected_debris", &[&kind, &time, &percent, &count]),
+                        _ => phi.locale.format("editor.selected", &[&kind, &time, &percent]),
+                    });
+                }
+                text
+            },
+        };
+
+        if text != self.status_text {
+            self.status = phi.ttf_str_sprite(&text, "menu_font", 18, Color::RGB(255, 255, 255)).unwrap();
+            self.status_text = text;
+        }
+    }
+
+    /// Return the horizontal position of the time 'time' of the level
+    fn time_to_x(&self, phi: &Phi, time: f64) -> f64 {
+        (time - self.scroll) * phi.scaled(PIXELS_PER_SECOND)
+    }
+
+    /// Return the time of the level and the height at a point of the
+    /// screen, rounded so that the level file stays readable
+    fn point_to_spawn(&self, phi: &Phi, (x, y): (f64, f64)) -> (f64, f64) {
+        let (_, win_h) = phi.output_size();
+        let time = self.scroll + x / phi.scaled(PIXELS_PER_SECOND);
+        ((time.max(0.0) * 10.0).round() / 10.0,
+         ((y / win_h).max(0.0).min(1.0) * 100.0).round() / 100.0)
+    }
+
+    /// Return where the marker of a hazard is drawn
+    fn marker(&self, phi: &Phi, &(time, ref spawn): &(f64, HazardSpawn)) -> Rectangle {
+        let (_, win_h) = phi.output_size();
+        let side = phi.scaled(MARKER_SIDE);
+        Rectangle {
+            x: self.time_to_x(phi, time) - side / 2.0,
+            y: height(spawn) * win_h - side / 2.0,
+            w: side,
+            h: side,
+        }
+    }
+
+    /// Change the selected hazard, then keep the hazards sorted and the same
+    /// one selected
+    fn change_selected<F: FnOnce(&mut (f64, HazardSpawn))>(&mut self, change: F) {
+        let i = match self.selected {
+            Some(i) => i,
+            None => return,
+        };
+
+        change(&mut self.level.spawns[i]);
+        let changed = self.level.spawns[i].clone();
+        self.level.sort();
+        self.selected = self.level.spawns.iter().position(|spawn| *spawn == changed);
+        self.modified = true;
+    }
+
+    /// Handle the input of the editor, as opposed to that of the preview
+    fn edit(&mut self, phi: &mut Phi) {
+        if phi.events.now.mouse_left == Some(true) {
+            // Select the hazard under the cursor, or place a new one
+            let mouse = phi.events.mouse;
+            let clicked = self.level.spawns.iter()
+                .position(|spawn| self.marker(phi, spawn).inflate(2.0, 2.0).contains_point(mouse));
+
+            self.selected = match clicked {
+                Some(i) => Some(i),
+                None => {
+                    let (time, y) = self.point_to_spawn(phi, mouse);
+                    let spawn = (time, self.kind.spawn(y));
+                    self.level.spawns.push(spawn.clone());
+                    self.level.sort();
+                    self.modified = true;
+                    self.level.spawns.iter().position(|other| *other == spawn)
+                },
+            };
+        }
+
+        if phi.events.now.mouse_right == Some(true) || phi.events.pressed(Keycode::Delete)
+                || phi.events.now.key_backspace == Some(true) {
+            if let Some(i) = self.selected.take() {
+                self.level.spawns.remove(i);
+                self.modified = true;
+            }
+        }
+
+        if phi.events.pressed(Keycode::Tab) {
+            let kinds = Kind::all();
+            let current = kinds.iter().position(|&kind| kind == self.kind).unwrap_or(0);
+            self.kind = kinds[(current + 1) % kinds.len()];
+        }
+
+        // The arrows move the selected hazard, or scroll the level
+        let repeat = (phi.events.now.repeat.key_left, phi.events.now.repeat.key_right,
+                      phi.events.now.repeat.key_up, phi.events.now.repeat.key_down);
+        if self.selected.is_some() {
+            let dt = match repeat {
+                (true, false, _, _) => -TIME_STEP,
+                (false, true, _, _) => TIME_STEP,
+                _ => 0.0,
+            };
+            let dy = match repeat {
+                (_, _, true, false) => -HEIGHT_STEP,
+                (_, _, false, true) => HEIGHT_STEP,
+                _ => 0.0,
+            };
+            if dt != 0.0 || dy != 0.0 {
+                self.change_selected(|&mut (ref mut time, ref mut spawn)| {
+                    *time = (*time + dt).max(0.0);
+                    let y = ((height(spawn) + dy).max(0.0).min(1.0) * 100.0).round() / 100.0;
+                    *spawn = with_height(spawn, y);
+                });
+            }
+
+            let count = if phi.events.pressed(Keycode::Equals) || phi.events.pressed(Keycode::KpPlus) { 1 }
+                        else if phi.events.pressed(Keycode::Minus) || phi.events.pressed(Keycode::KpMinus) { -1 }
+                        else { 0 };
+            if count != 0 {
+                self.change_selected(|&mut (_, ref mut spawn)| {
+                    if let HazardSpawn::Debris { count: ref mut pieces, .. } = *spawn {
+                        *pieces = (*pieces as i32 + count).max(1) as usize;
+                    }
+                });
+            }
+        }
+        else {
+            match repeat {
+                (true, false, _, _) => self.scroll = (self.scroll - SCROLL_STEP).max(0.0),
+                (false, true, _, _) => self.scroll += SCROLL_STEP,
+                _ => {},
+            }
+        }
+
+        // Play the level over the next set of backgrounds
+        if phi.events.pressed(Keycode::B) {
+            let sets = shared::load_background_sets(shared::BACKGROUNDS_PATH);
+            let current = self.level.background.as_ref()
+                .and_then(|name| sets.iter().position(|set| set.name == *name))
+                .unwrap_or(0);
+            let name = sets[(current + 1) % sets.len()].name.clone();
+            self.backgrounds = Backgrounds::load(phi, &name);
+            self.level.background = Some(name);
+            self.modified = true;
+        }
+
+        if phi.events.pressed(Keycode::S) {
+            match self.level.save(&self.path) {
+                Ok(()) => {
+                    info!("Saved the level {}", self.path);
+                    self.modified = false;
+                },
+                Err(err) => warn!("Could not save the level {}: {}", self.path, err),
+            }
+        }
+
+        // Forget the changes made since the level was last saved
+        if phi.events.pressed(Keycode::R) {
+            self.level = Level::load(&self.path);
+            self.selected = None;
+            self.modified = false;
+        }
+    }
+
+    /// Handle the input of the preview, which shows the game at a time of
+    /// the level and may play it from there
+    fn scrub(&mut self, phi: &mut Phi, elapsed: f64) {
+        let mut time = self.preview.unwrap_or(0.0);
+
+        if phi.events.now.key_space == Some(true) {
+            self.playing = !self.playing;
+        }
+        if self.playing {
+            time += elapsed;
+        }
+
+        if phi.events.now.repeat.key_left {
+            time = (time - TIME_STEP).max(0.0);
+        }
+        if phi.events.now.repeat.key_right {
+            time += TIME_STEP;
+        }
+
+        self.preview = Some(time);
+    }
+
+    /// Render the grid of the timeline and the marker of every hazard
+    fn draw_timeline(&self, phi: &mut Phi) {
+        let (win_w, win_h) = phi.output_size();
+
+        phi.renderer.set_blend_mode(BlendMode::Blend);
+        phi.renderer.set_draw_color(Color::RGBA(0, 0, 0, 120));
+        phi.renderer.fill_rect(Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: win_w,
+            h: win_h,
+        }.to_sdl().unwrap());
+
+        // A line every few seconds, and at every quarter of the height
+        phi.renderer.set_draw_color(Color::RGBA(255, 255, 255, 50));
+        let first = (self.scroll / GRID_SECONDS).ceil() * GRID_SECONDS;
+        let mut time = first;
+        while self.time_to_x(phi, time) < win_w {
+            let x = self.time_to_x(phi, time);
+            phi.renderer.fill_rect(Rectangle { x: x, y: 0.0, w: 1.0, h: win_h }.to_sdl().unwrap());
+            time += GRID_SECONDS;
+        }
+        for quarter in 1..4 {
+            let y = win_h * quarter as f64 / 4.0;
+            phi.renderer.fill_rect(Rectangle { x: 0.0, y: y, w: win_w, h: 1.0 }.to_sdl().unwrap());
+        }
+        phi.renderer.set_blend_mode(BlendMode::None);
+
+        for (i, spawn) in self.level.spawns.iter().enumerate() {
+            let marker = self.marker(phi, spawn);
+            if marker.x + marker.w < 0.0 || marker.x > win_w {
+                continue;
+            }
+
+            if self.selected == Some(i) {
+                phi.renderer.set_draw_color(Color::RGB(240, 200, 40));
+                phi.renderer.fill_rect(marker.inflate(3.0, 3.0).to_sdl().unwrap());
+            }
+            phi.renderer.set_draw_color(Kind::of(&spawn.1).color());
+            phi.renderer.fill_rect(marker.to_sdl().unwrap());
+        }
+    }
+
+    /// Render the hazards on the screen at the time 'time' of the level, as
+    /// they drift in from the right
+    fn draw_preview(&self, phi: &mut Phi, time: f64) {
+        let (win_w, win_h) = phi.output_size();
+
+        for &(start, ref spawn) in self.level.spawns.iter().filter(|&&(start, _)| start <= time) {
+            let drift = (time - start) * hazards::DRIFT_SPEED;
+            let y = height(spawn) * win_h;
+            let rect = match *spawn {
+                HazardSpawn::Mine { .. } => Rectangle {
+                    x: win_w - drift,
+                    y: y - hazards::MINE_SIDE / 2.0,
+                    w: hazards::MINE_SIDE,
+                    h: hazards::MINE_SIDE,
+                },
+                // Pieces of debris are scattered at random; show where they
+                // may be
+                HazardSpawn::Debris { .. } => Rectangle {
+                    x: win_w - drift,
+                    y: y - DEBRIS_SPREAD / 2.0,
+                    w: DEBRIS_SPREAD + hazards::DEBRIS_SIDE,
+                    h: DEBRIS_SPREAD,
+                },
+                HazardSpawn::Well { .. } => Rectangle {
+                    x: win_w + hazards::WELL_RADIUS - drift - MARKER_SIDE,
+                    y: y - MARKER_SIDE,
+                    w: MARKER_SIDE * 2.0,
+                    h: MARKER_SIDE * 2.0,
+                },
+            };
+            if rect.x + rect.w < 0.0 || rect.x > win_w {
+                continue;
+            }
+
+            let (r, g, b) = Kind::of(spawn).color().rgb();
+            phi.renderer.set_blend_mode(BlendMode::Blend);
+            phi.renderer.set_draw_color(Color::RGBA(r, g, b, 160));
+            phi.renderer.fill_rect(rect.to_sdl().unwrap());
+            phi.renderer.set_blend_mode(BlendMode::None);
+        }
+    }
+}
+
+impl View for EditorView {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.3,
+            rate: 12.0,
+        }));
+
+        // The backgrounds may have been tuned in the meantime
+        let name = self.background_name();
+        self.backgrounds = Backgrounds::load(phi, &name);
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        if phi.events.now.key_escape == Some(true) {
+            if self.preview.is_some() {
+                self.preview = None;
+                self.playing = false;
+            }
+            else {
+                if self.modified {
+                    warn!("Leaving the editor without saving {}", self.path);
+                }
+                return ViewAction::ChangeView(Box::new(
+                    ::views::main_menu::MainMenuView::new_with_backgrounds(
+                        phi, self.backgrounds.clone())));
+            }
+        }
+        else if self.preview.is_none() && phi.events.pressed(Keycode::G) {
+            let name = self.background_name();
+            return ViewAction::PushView(Box::new(
+                ::views::parallax::ParallaxView::new(phi, &name)));
+        }
+        else if phi.events.pressed(Keycode::P) {
+            self.preview = match self.preview {
+                Some(_) => None,
+                None => Some(self.scroll),
+            };
+            self.playing = false;
+        }
+        else if self.preview.is_some() {
+            self.scrub(phi, elapsed);
+        }
+        else {
+            self.edit(phi);
+        }
+        self.refresh(phi);
+
+        // Clear the screen . . .
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // The backgrounds only move while the preview plays
+        let scrolled = if self.playing { elapsed } else { 0.0 };
+        self.backgrounds.back.render(&mut phi.renderer, scrolled);
+        self.backgrounds.middle.render(&mut phi.renderer, scrolled);
+
+        match self.preview {
+            Some(time) => self.draw_preview(phi, time),
+            None => self.draw_timeline(phi),
+        }
+
+        self.backgrounds.front.render(&mut phi.renderer, scrolled);
+
+        // Render the status line at the top, and the help at the bottom
+        let (_, win_h) = phi.output_size();
+        let margin = phi.scaled(10.0);
+        let (w, h) = self.status.size();
+        self.status.render(&mut phi.renderer, Rectangle {
+            x: margin,
+            y: margin,
+            w: w,
+            h: h,
+        });
+
+        let help = if self.preview.is_some() { &self.preview_help } else { &self.help };
+        let (w, h) = help.size();
+        help.render(&mut phi.renderer, Rectangle {
+            x: margin,
+            y: win_h - h - margin,
+            w: w,
+            h: h,
+        });
+
+        ViewAction::None
+    }
+}
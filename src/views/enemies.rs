@@ -0,0 +1,277 @@
+// views/enemies.rs
+
+use ::std::fs::File;
+use ::std::io::{BufRead, BufReader};
+use ::std::rc::Rc;
+
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::gfx::Sprite;
+use ::phi::path::{parse_point, Path};
+use ::views::scripting::Scripts;
+
+pub const FORMATIONS_PATH: &'static str = "assets/formations.txt";
+
+/// The points won by shooting down an enemy ship
+pub const ENEMY_SCORE: u32 = 150;
+
+/// A ship of a formation, inside it
+#[derive(Clone, Debug)]
+pub struct Member {
+    // Seconds between the appearance of the formation and that of the ship
+    pub delay: f64,
+
+    // Where the ship flies relative to the path, in fractions of the screen
+    pub offset: (f64, f64),
+}
+
+/// How the ships of a formation move
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trajectory {
+    Path(Path),
+
+    /// A function of the gameplay scripts, see views::scripting
+    Script(String),
+}
+
+impl Trajectory {
+    /// Parse a trajectory: either `script NAME` or a path (see Path::parse)
+    pub fn parse(text: &str) -> Option<Trajectory> {
+        let text = text.trim();
+        if text.starts_with("script ") {
+            Some(Trajectory::Script(text["script ".len()..].trim().to_string()))
+        }
+        else {
+            Path::parse(text).map(Trajectory::Path)
+        }
+    }
+
+    /// Return the point reached at 'progress', in [0, 1]
+    pub fn point(&self, scripts: &Scripts, progress: f64) -> Option<(f64, f64)> {
+        match *self {
+            Trajectory::Path(ref path) => Some(path.point(progress)),
+            Trajectory::Script(ref name) => scripts.point(name, progress),
+        }
+    }
+}
+
+/// A group of enemies flying along a same path
+#[derive(Clone, Debug)]
+pub struct Formation {
+    pub name: String,
+    pub path: Rc<Trajectory>,
+
+    // The time, in seconds, it takes every member to follow the path
+    pub duration: f64,
+
+    pub members: Vec<Member>,
+}
+
+impl Formation {
+    /// Create the ships of the formation, which will appear as their
+    /// delay runs out
+    pub fn spawn(&self, sprite: &Sprite) -> Vec<Enemy> {
+        self.members.iter().map(|member| Enemy {
+            sprite: sprite.clone(),
+            path: self.path.clone(),
+            offset: member.offset,
+            duration: self.duration,
+            time: -member.delay,
+            rect: Rectangle {
+                x: 0.0,
+                y: 0.0,
+                w: sprite.size().0,
+                h: sprite.size().1,
+            },
+            on_path: false,
+            destroyed: false,
+        }).collect()
+    }
+
+    /// Create the ships of the formation, moved so that its path starts at
+    /// 'start', in pixels of a window of size 'size'
+    pub fn spawn_from(&self, sprite: &Sprite, scripts: &Scripts,
+                      start: (f64, f64), size: (f64, f64)) -> Vec<Enemy> {
+        let (sx, sy) = self.path.point(scripts, 0.0).unwrap_or((0.0, 0.0));
+        let shift = (start.0 / size.0 - sx, start.1 / size.1 - sy);
+
+        let mut enemies = self.spawn(sprite);
+        for enemy in &mut enemies {
+            enemy.offset.0 += shift.0;
+            enemy.offset.1 += shift.1;
+        }
+        enemies
+    }
+}
+
+/// Read the formations defined in the file at 'path'. Malformed ones are
+/// reported and skipped.
+///
+/// A formation starts with a `formation NAME` line followed by a `path`
+/// line (see `Trajectory::parse`), a `duration SECONDS` line, and one
+/// `member DELAY X,Y` line per ship. Empty lines and lines starting
+/// with `#` are ignored.
+pub fn load_formations(path: &str) -> Vec<Formation> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => {
+            warn!("Could not open the formations in {}", path);
+            return vec![];
+        }
+    };
+
+    let mut formations = vec![];
+    let mut current: Option<Formation> = None;
+
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, ' ');
+        let (key, value) = (parts.next().unwrap_or(""), parts.next().unwrap_or("").trim());
+
+        if key == "formation" {
+            formations.extend(current.take().and_then(check_formation));
+            current = Some(Formation {
+                name: value.to_string(),
+                path: Rc::new(Trajectory::Path(Path::Waypoints(vec![]))),
+                duration: 0.0,
+                members: vec![],
+            });
+            continue;
+        }
+
+        let formation = match current.as_mut() {
+            Some(formation) => formation,
+            None => {
+                warn!("Ignoring line outside of a formation: {}", line);
+                continue;
+            }
+        };
+
+        let parsed = match key {
+            "path" => Trajectory::parse(value).map(|path| formation.path = Rc::new(path)),
+            "duration" => value.parse().ok().map(|duration| formation.duration = duration),
+            "member" => {
+                let mut words = value.split_whitespace();
+                match (words.next().and_then(|w| w.parse().ok()),
+                       words.next().and_then(parse_point)) {
+                    (Some(delay), Some(offset)) => Some(formation.members.push(Member {
+                        delay: delay,
+                        offset: offset,
+                    })),
+                    _ => None,
+                }
+            },
+            _ => None,
+        };
+
+        if parsed.is_none() {
+            warn!("Ignoring malformed line in formation {}: {}", formation.name, line);
+        }
+    }
+
+    formations.extend(current.and_then(check_formation));
+    formations
+}
+
+// Keep a formation only if it is complete
+fn check_formation(formation: Formation) -> Option<Formation> {
+    if formation.duration <= 0.0 || formation.members.is_empty()
+            || *formation.path == Trajectory::Path(Path::Waypoints(vec![])) {
+        warn!("Ignoring incomplete formation {}", formation.name);
+        None
+    }
+    else if !cfg!(feature = "scripting") {
+        // Scripted formations cannot fly without the scripts
+        match *formation.path {
+            Trajectory::Script(_) => None,
+            _ => Some(formation),
+        }
+    }
+    else {
+        Some(formation)
+    }
+}
+
+/// An enemy ship, flying along the path of its formation
+#[derive(Clone)]
+pub struct Enemy {
+    sprite: Sprite,
+    path: Rc<Trajectory>,
+    offset: (f64, f64),
+    duration: f64,
+
+    // Seconds since the ship entered its path; negative while it waits
+    time: f64,
+
+    rect: Rectangle,
+
+    // Whether 'rect' has been moved onto the path yet; until then, it
+    // still lies in the top-left corner where the ship was spawned
+    on_path: bool,
+
+    pub destroyed: bool,
+}
+
+impl Enemy {
+    /// Move the ship along its path. Return None once it has reached the
+    /// end of its path, or has been destroyed.
+    pub fn update(mut self, phi: &mut Phi, scripts: &Scripts, dt: f64) -> Option<Enemy> {
+        self.time += dt;
+        if self.destroyed || self.time > self.duration {
+            return None;
+        }
+        if self.time < 0.0 {
+            return Some(self);
+        }
+
+        // Ships whose script fails are dropped
+        let (w, h) = phi.output_size();
+        let (px, py) = match self.path.point(scripts, self.time / self.duration) {
+            Some(point) => point,
+            None => return None,
+        };
+        self.rect = self.rect.with_center(((px + self.offset.0) * w,
+                                           (py + self.offset.1) * h));
+        self.on_path = true;
+        Some(self)
+    }
+
+    /// Whether the ship has entered its path, and can be seen and hit
+    pub fn is_active(&self) -> bool {
+        self.time >= 0.0 && self.on_path && !self.destroyed
+    }
+
+    pub fn render(&self, phi: &mut Phi, debug: bool) {
+        if !self.is_active() {
+            return;
+        }
+
+        if debug {
+            phi.renderer.set_draw_color(Color::RGB(200, 50, 50));
+            phi.renderer.fill_rect(self.hitbox().to_sdl().unwrap());
+        }
+
+        // Enemies use the player's ship, facing the other way
+        self.sprite.render_flipped(&mut phi.renderer, self.rect, true, false);
+    }
+
+    /// Return the area in which the ship is drawn
+    pub fn rect(&self) -> Rectangle {
+        self.rect
+    }
+
+    /// Return the area in which the ship can be hit
+    pub fn hitbox(&self) -> Rectangle {
+        self.rect.scale_about_center(0.7)
+    }
+}
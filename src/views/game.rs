@@ -1,14 +1,48 @@
 // views/game.rs
 
+use ::std::cell::Cell;
+use ::std::collections::VecDeque;
+use ::std::rc::Rc;
+
 use ::rand;
+use ::rand::{Rng, SeedableRng, StdRng};
 
+use ::sdl2::controller::Button;
 use ::sdl2::pixels::Color;
+use ::sdl2::render::BlendMode;
 
 use ::phi::{Phi, View, ViewAction};
-use ::phi::data::Rectangle;
-use ::phi::gfx::{Renderable, Sprite, AnimatedSprite};
-
-use ::views::shared::Backgrounds;
+use ::phi::bus::{Announcement, GameEvent};
+use ::phi::camera::{Camera, Shake};
+use ::phi::controllers::PadInput;
+use ::phi::data::{Polygon, Rectangle};
+use ::phi::gfx::{self, AnimatedSprite, Blending, Cursor, Renderable, Sprite};
+use ::phi::layers::{Layer, RenderQueue};
+use ::phi::parallel;
+use ::phi::particles::{Emitter, ParticleSystem};
+use ::phi::prompts::Prompt;
+use ::phi::random::{Distributions, JitterTimer};
+use ::phi::trail::Trail;
+
+use ::views::banners::Banners;
+use ::views::boss::{self, Boss, EnemyBullet};
+use ::views::bullets::{Bullet, BulletKind, Bullets};
+use ::views::combo::Combo;
+use ::views::damage_numbers::DamageNumbers;
+use ::views::drones::Drones;
+use ::views::enemies::{self, Enemy, Formation};
+use ::views::hazards::{self, Debris, GravityWell, HazardSpawn, Level, Mine, SpawnKind};
+use ::views::hud::Bar;
+use ::views::leaderboard::{self, Entry, GameMode, Leaderboard, SortKey};
+use ::views::pickups::{self, Pickup, PickupKind};
+use ::views::practice::Practice;
+use ::views::progress::{Progress, PROGRESS_PATH};
+use ::views::replay::{self, Ghost, Input, Replay};
+use ::views::scripting::{self, Scripts};
+use ::views::shared::{self, play_sound, Backgrounds, HIT_FLASH};
+use ::views::stats::WaveStats;
+use ::views::terrain::Terrain;
+use ::views::timeline::{self, Action, Spawn, Timeline};
 
 // CONSTANTS . . .
 
@@ -17,9 +51,143 @@ const DEBUG: bool = false;
 /// Pixels travelled by a player ship every second, when it is moving
 const PLAYER_SPEED: f64 = 180.0;
 
+/// Seconds during which the world freezes when a heavy bullet hits, which
+/// lasts about two frames
+const HIT_STOP: f64 = 0.033;
+
+/// The chance that a bullet hitting something deals a critical hit, and
+/// how many times the usual damage it then deals
+const CRIT_CHANCE: f64 = 0.08;
+const CRIT_DAMAGE: u32 = 2;
+
+/// The shakes of the screen when something explodes, when the boss fires,
+/// and when the ship is hit
+const EXPLOSION_SHAKE: Shake = Shake { magnitude: 8.0, frequency: 25.0, decay: 6.0 };
+const BOSS_FIRE_SHAKE: Shake = Shake { magnitude: 2.5, frequency: 30.0, decay: 12.0 };
+const SHIP_HIT_SHAKE: Shake = Shake { magnitude: 6.0, frequency: 20.0, decay: 8.0 };
+
+/// The speed of the world, relative to the ship, during bullet time
+const BULLET_TIME_SCALE: f64 = 0.5;
+
+/// How much the scene is dimmed while the boss enters
+const BOSS_INTRO_DIM: f64 = 0.35;
+
+/// Seconds during which the banners announcing a new wave and a new high
+/// score stay on screen
+const WAVE_BANNER_DURATION: f64 = 2.0;
+const HIGH_SCORE_BANNER_DURATION: f64 = 2.5;
+
+/// Seconds during which the summary of a wave stays on screen
+const SUMMARY_DURATION: f64 = 4.0;
+
+/// The wave whose boss is the last of the campaign: destroying it plays the
+/// ending, and the credits roll once the run is over
+pub const FINAL_WAVE: u32 = 5;
+
+/// How much harder New Game+ is: the spawn table of the level is picked
+/// from this much more often, and the bullets of the boss fly this much
+/// faster
+const PLUS_SPAWN_RATE: f64 = 1.5;
+const PLUS_BULLET_SPEED: f64 = 1.3;
+
+/// The number of times the player may continue after a game over
+const MAX_CONTINUES: u32 = 3;
+
+/// Seconds the player has to accept to continue
+const CONTINUE_COUNTDOWN: f64 = 9.0;
+
+/// Seconds of play between two snapshots of the world, and how many are
+/// kept to rewind the game to when debugging it
+const REWIND_INTERVAL: f64 = 1.0;
+const REWIND_SNAPSHOTS: usize = 30;
+
+/// What the debugging cheats bring in at the mouse cursor, before the
+/// formations of the level, and how long they show what they do
+const DEBUG_SPAWNS: [&'static str; 4] = ["asteroid", "mine", "gravity well", "pickup"];
+const DEBUG_BANNER_DURATION: f64 = 1.5;
+
+/// Seconds the game moves on by at every step while stepping through it
+/// frame by frame when debugging it
+const STEP_TIME: f64 = 1.0 / 60.0;
+
+/// Seconds between two picks from the spawn table of the level, give or
+/// take the jitter
+const SPAWN_INTERVAL: f64 = 2.0;
+const SPAWN_JITTER: f64 = 1.0;
+
+/// How opaque the ghost of the best run is, and its tint
+const GHOST_ALPHA: u8 = 90;
+const GHOST_COLOR: Color = Color::RGB(140, 200, 255);
+
+/// How far under the ship the wingman of a game for two starts, and its
+/// tint, which tells it apart from the ship
+const WINGMAN_GAP: f64 = 80.0;
+const WINGMAN_COLOR: Color = Color::RGB(150, 255, 170);
+
+/// The player whose controller drives the ship
+const PLAYER_ID: usize = 0;
+
+/// The name of the ship, as recorded on the leaderboards
+const SHIP_NAME: &'static str = "Phi";
+
+/// The ship's spritesheet in the asset manifest
+const SHIP_ASSET: &'static str = "player_ship";
 const SHIP_W: f64 = 43.0;
 const SHIP_H: f64 = 39.0;
 
+/// The ship starts on the left, halfway down the screen, clear of the
+/// terrain
+const SHIP_START_X: f64 = 64.0;
+
+/// The number of hits the ship takes before the game is over
+const SHIP_HP: u32 = 3;
+
+/// Seconds during which the ship cannot be hit again after a hit
+const SHIP_INVULNERABILITY: f64 = 1.5;
+
+/// The optional spritesheets shown as the ship gets damaged, after one hit,
+/// two hits, and so on; laid out like SHIP_ASSET
+const SHIP_DAMAGED_ASSETS: [&'static str; 2] = [
+    "player_ship_damaged1",
+    "player_ship_damaged2",
+];
+
+/// The flames behind the ship's engine, relative to its top-left corner
+const EXHAUST: Emitter = Emitter {
+    rate: 90.0,
+    offset: (4.0, SHIP_H / 2.0),
+    velocity: (-160.0, 0.0),
+    spread: 25.0,
+    life: 0.3,
+    size: 3.0,
+    color: (255, 160, 60),
+    blending: Blending::Additive,
+};
+
+/// The smoke escaping from a damaged ship, relative to its top-left corner
+const SMOKE: Emitter = Emitter {
+    rate: 30.0,
+    offset: (SHIP_W / 2.0, SHIP_H / 2.0),
+    velocity: (-60.0, -20.0),
+    spread: 15.0,
+    life: 0.8,
+    size: 5.0,
+    color: (110, 110, 110),
+    blending: Blending::Normal,
+};
+
+/// The sparks left behind by a bullet, relative to its center
+const BULLET_TRAIL: Emitter = Emitter {
+    rate: 40.0,
+    offset: (0.0, 0.0),
+    velocity: (0.0, 0.0),
+    spread: 8.0,
+    life: 0.15,
+    size: 2.0,
+    color: (230, 230, 160),
+    blending: Blending::Additive,
+};
+
 // DATA TYPES . . .
 
 #[derive(Clone, Copy)]
@@ -35,338 +203,989 @@ enum ShipFrame {
     DownSlow = 8,
 }
 
+impl ShipFrame {
+    /// Return the frame of a ship which moved by (dx, dy)
+    fn moving(dx: f64, dy: f64) -> ShipFrame {
+        if      dx == 0.0 && dy < 0.0  { ShipFrame::UpNorm }
+        else if dx > 0.0  && dy < 0.0  { ShipFrame::UpFast }
+        else if dx < 0.0  && dy < 0.0  { ShipFrame::UpSlow }
+        else if dx == 0.0 && dy == 0.0 { ShipFrame::MidNorm }
+        else if dx > 0.0  && dy == 0.0 { ShipFrame::MidFast }
+        else if dx < 0.0  && dy == 0.0 { ShipFrame::MidSlow }
+        else if dx == 0.0 && dy > 0.0  { ShipFrame::DownNorm }
+        else if dx > 0.0  && dy > 0.0  { ShipFrame::DownFast }
+        else if dx < 0.0  && dy > 0.0  { ShipFrame::DownSlow }
+        else { unreachable!() }
+    }
+}
+
 const BULLET_SPEED: f64 = 240.0;  // pixels/second
 const BULLET_W: f64 = 8.0;
 const BULLET_H: f64 = 4.0;
 
-#[derive(Clone, Copy)]
+/// Distance between the ship and the crosshair when aiming with a stick
+const CROSSHAIR_DISTANCE: f64 = 120.0;
+const CROSSHAIR_SIDE: f64 = 16.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum CannonType {
     RectBullet,
     SineBullet { amplitude: f64, angular_vel: f64 },
     DivergentBullet { a: f64, b: f64 },
 }
 
-trait Bullet {
-    fn update(self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>>;
-    fn render(&self, phi: &mut Phi);
-    fn rect(&self) -> Rectangle;
+/// Return the cannons picked by the number 'cannon', from 1 to 3
+fn cannon_type(cannon: u8) -> CannonType {
+    match cannon {
+        1 => CannonType::RectBullet,
+        2 => CannonType::SineBullet {
+            amplitude: 10.0,
+            angular_vel: 15.0,
+        },
+        _ => CannonType::DivergentBullet {
+            a: 100.0,
+            b: 1.2,
+        },
+    }
 }
 
-#[derive(Clone, Copy)]
-struct RectBullet {
-    rect: Rectangle,
+/// Return how far the gravity wells pull a ship centered on 'center' in
+/// 'elapsed' seconds
+fn wells_pull(wells: &[GravityWell], center: (f64, f64), elapsed: f64) -> (f64, f64) {
+    let (mut pull_x, mut pull_y) = (0.0, 0.0);
+    for well in wells {
+        let (px, py) = well.ship_pull(center);
+        pull_x += px * elapsed;
+        pull_y += py * elapsed;
+    }
+    (pull_x, pull_y)
+}
+
+/// Return the unit vector pointing from 'from' to 'to', or straight ahead
+/// if both are the same point
+fn direction(from: (f64, f64), to: (f64, f64)) -> (f64, f64) {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let dist = (dx * dx + dy * dy).sqrt();
+    if dist > 0.0 { (dx / dist, dy / dist) }
+    else { (1.0, 0.0) }
 }
 
-struct SineBullet {
-    //? Notice that the bounding box isn't stored, it is computed
-    pos_x: f64,
-    origin_y: f64,
-    amplitude: f64,
-    angular_vel: f64,
-    total_time: f64,
+/// Roll the damage a bullet deals as it hits, and whether the hit is
+/// critical
+fn roll_damage(rng: &mut StdRng) -> (u32, bool) {
+    if rng.chance(CRIT_CHANCE) { (CRIT_DAMAGE, true) }
+    else { (1, false) }
 }
 
-/// Bullet which follows a vertical trajectory described by:
-///     a * ( (t / b)^3 - (t / b)^2)
-struct DivergentBullet {
-    pos_x: f64,
-    origin_y: f64,
-    a: f64,  // Influences bullet height
-    b: f64,  // Influences bullet width
-    total_time: f64,
+/// Draw a dark box behind the HUD label at 'dest', if the palette wants
+/// the HUD to stand out from the game
+fn back_hud_label(phi: &mut Phi, dest: Rectangle) {
+    if phi.palette().backs_hud() {
+        let border = phi.scaled(4.0);
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.fill_rect(dest.inflate(border, border).to_sdl().unwrap());
+    }
 }
 
-impl Bullet for RectBullet {
+#[derive(Clone)]
+struct Ship {
 
-    /// Update the bullet; delete it if it leaves the screen.
-    /// Return Some(updated_bullet) or None
-    fn update(mut self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>> {
-        let (w, _) = phi.output_size();
-        self.rect.x += BULLET_SPEED * dt;
+    rect: Rectangle,
+    sprites: Vec<Sprite>,
+    current: ShipFrame,
 
-        // If the bullet has left the screen, delete it
-        if self.rect.x > w {
-            None
-        }
-        else {
-            Some(self)
-        }
-    }
+    // The frames shown after every hit, if their spritesheet exists
+    damaged_sprites: Vec<Option<Vec<Sprite>>>,
 
-    /// Render the bullet to the screen
-    fn render(&self, phi: &mut Phi) {
-        phi.renderer.set_draw_color(Color::RGB(230, 230, 30));  // yellow
-        phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
-    }
+    hp: u32,
 
-    /// Return the bullet's bounding box
-    fn rect(&self) -> Rectangle {
-        self.rect
-    }
-}
+    // Seconds before the ship can be hit again
+    invulnerable: f64,
 
-impl Bullet for SineBullet {
+    cannon: CannonType,
 
-    /// Update the bullet; delete it if it leaves the screen.
-    /// Return Some(updated_bullet) or None
-    fn update(mut self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>> {
-        self.total_time += dt;
-        self.pos_x += BULLET_SPEED * dt;
+    // The polygons making up the hitbox of every frame, relative to the
+    // frame; None if the spritesheet comes without hitboxes
+    hitboxes: Option<Vec<Vec<Polygon>>>,
 
-        let (w, _) = phi.output_size();
+    // The afterimages the ship leaves while it outpaces the world
+    trail: Trail,
+}
 
-        // If the bullet has left the screen, delete it
-        if self.rect().x > w {
-            None
+impl Ship {
+    /// Return the frame to render, which shows how damaged the ship is
+    fn sprite(&self) -> &Sprite {
+        let damage = (SHIP_HP - self.hp) as usize;
+        if damage > 0 {
+            if let Some(&Some(ref sprites)) = self.damaged_sprites.get(damage - 1) {
+                return &sprites[self.current as usize];
+            }
         }
-        else {
-            Some(self)
+        &self.sprites[self.current as usize]
+    }
+
+    /// Lose one hit point, unless the ship was hit moments ago. Return
+    /// whether the ship is destroyed.
+    fn hit(&mut self) -> bool {
+        if self.invulnerable <= 0.0 {
+            self.hp -= 1;
+            self.invulnerable = SHIP_INVULNERABILITY;
         }
+        self.hp == 0
     }
 
-    /// Render the bullet to the screen
-    fn render(&self, phi: &mut Phi) {
-        phi.renderer.set_draw_color(Color::RGB(30, 230, 30));  // greenish
-        phi.renderer.fill_rect(self.rect().to_sdl().unwrap());
+    /// Whether the ship touches 'rect'. Use the hitboxes of the current
+    /// frame if there are some, and the bounding box of the ship otherwise.
+    fn collides_with(&self, rect: Rectangle) -> bool {
+        match self.hitboxes {
+            Some(ref hitboxes) => hitboxes[self.current as usize].iter()
+                .any(|hitbox| hitbox.translate(self.rect.x, self.rect.y).overlaps_rect(rect)),
+            None => self.rect.overlaps(rect),
+        }
     }
 
-    /// Return the bullet's bounding box
-    fn rect(&self) -> Rectangle {
-        let dy = self.amplitude * f64::sin(self.angular_vel * self.total_time);
-        Rectangle {
-            x: self.pos_x,
-            y: self.origin_y + dy,
-            w: BULLET_W,
-            h: BULLET_H,
+    /// Emit the flames of the engine of the ship, which moved by (dx, dy)
+    /// in 'elapsed' seconds, and its smoke if it is damaged
+    fn emit_engine(&self, particles: &mut ParticleSystem, (dx, dy): (f64, f64), elapsed: f64) {
+        // The engine burns brighter as the ship moves faster
+        let speed = if elapsed > 0.0 { (dx * dx + dy * dy).sqrt() / (PLAYER_SPEED * elapsed) }
+                    else { 0.0 };
+        EXHAUST.emit(particles, (self.rect.x, self.rect.y), 0.3 + 0.7 * speed.min(1.0), elapsed);
+
+        // A damaged ship smokes, more so as it takes more hits
+        let damage = (SHIP_HP - self.hp) as f64 / (SHIP_HP - 1) as f64;
+        if damage > 0.0 {
+            SMOKE.emit(particles, (self.rect.x, self.rect.y), damage, elapsed);
         }
     }
-}
 
-impl Bullet for DivergentBullet {
+    fn spawn_bullets(&self) -> Vec<Bullet> {
+        let cannons_x = self.rect.x + 30.0;
+        let cannon1_y = self.rect.y + 6.0;
+        let cannon2_y = self.rect.y + SHIP_H - 10.0;
 
-    /// Update the bullet; delete it if it leaves the screen.
-    /// Return Some(updated_bullet) or None
-    fn update(mut self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>> {
-        self.total_time += dt;
-        self.pos_x += BULLET_SPEED * dt;
+        // Create one bullet at the tip of each cannon; divergent bullets
+        // part ways
+        let (kind1, kind2) = match self.cannon {
+            CannonType::RectBullet =>
+                (BulletKind::Rect, BulletKind::Rect),
+            CannonType::SineBullet { amplitude, angular_vel } => {
+                let kind = BulletKind::Sine { amplitude: amplitude, angular_vel: angular_vel };
+                (kind, kind)
+            },
+            CannonType::DivergentBullet { a, b } =>
+                (BulletKind::Divergent { a: -a, b: b }, BulletKind::Divergent { a: a, b: b }),
+        };
 
-        let (w, h) = phi.output_size();
+        vec![(kind1, cannon1_y), (kind2, cannon2_y)].into_iter().map(|(kind, cannon_y)| {
+            Bullet {
+                kind: kind,
+                rect: Rectangle {
+                    x: cannons_x,
+                    y: cannon_y,
+                    w: BULLET_W,
+                    h: BULLET_H,
+                },
+                vel: (BULLET_SPEED, 0.0),
+            }
+        }).collect()
+    }
 
-        // If the bullet has left the screen, delete it
-        let rect = self.rect();
-        if rect.x > w || rect.x < 0.0 ||
-           rect.y > h || rect.y < 0.0 {
-            None
-        }
-        else {
-            Some(self)
-        }
+    /// Return the point between the tips of the cannons
+    fn cannons_center(&self) -> (f64, f64) {
+        (self.rect.x + 30.0, self.rect.y + SHIP_H / 2.0 - 2.0)
     }
 
-    /// Render the bullet to the screen
-    fn render(&self, phi: &mut Phi) {
-        phi.renderer.set_draw_color(Color::RGB(230, 30, 30));  // reddish
-        phi.renderer.fill_rect(self.rect().to_sdl().unwrap());
+    /// Create one bullet at the tip of each cannon, both flying in the
+    /// direction of the unit vector 'dir'
+    fn spawn_aimed_bullets(&self, dir: (f64, f64)) -> Vec<Bullet> {
+        let cannons_x = self.rect.x + 30.0;
+        let cannon1_y = self.rect.y + 6.0;
+        let cannon2_y = self.rect.y + SHIP_H - 10.0;
+
+        vec![cannon1_y, cannon2_y].into_iter().map(|cannon_y| {
+            Bullet {
+                kind: BulletKind::Aimed,
+                rect: Rectangle {
+                    x: cannons_x,
+                    y: cannon_y,
+                    w: BULLET_W,
+                    h: BULLET_W,
+                },
+                vel: (dir.0 * BULLET_SPEED, dir.1 * BULLET_SPEED),
+            }
+        }).collect()
     }
+}
 
-    /// Return the bullet's bounding box
-    fn rect(&self) -> Rectangle {
-        let dy = self.a *
-                ((self.total_time / self.b).powi(3) -
-                 (self.total_time / self.b).powi(2));
-        Rectangle {
-            x: self.pos_x,
-            y: self.origin_y + dy,
+/// Return the timer of the picks from the spawn table of the level, which
+/// comes around more often in New Game+
+fn spawn_timer(mode: GameMode) -> JitterTimer {
+    let rate = if mode == GameMode::NewGamePlus { PLUS_SPAWN_RATE } else { 1.0 };
+    JitterTimer::new(SPAWN_INTERVAL / rate, SPAWN_JITTER / rate)
+}
+
+/// Create the bullet fired by a drone centered on 'center', flying in the
+/// direction of the unit vector 'dir'
+fn drone_bullet(center: (f64, f64), dir: (f64, f64)) -> Bullet {
+    Bullet {
+        kind: BulletKind::Aimed,
+        rect: Rectangle {
+            x: 0.0,
+            y: 0.0,
             w: BULLET_W,
             h: BULLET_H,
-        }
+        }.with_center(center),
+        vel: (dir.0 * BULLET_SPEED, dir.1 * BULLET_SPEED),
     }
 }
 
-struct Ship {
 
-    rect: Rectangle,
-    sprites: Vec<Sprite>,
-    current: ShipFrame,
+/// Where the player starts over when they restart the wave or continue
+/// after a game over, saved at the start of every wave
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Checkpoint {
+    wave: u32,
+    score: u32,
+
+    // The state of the ship: its hit points, its escort and its cannons
+    hp: u32,
+    drones: usize,
     cannon: CannonType,
-}
 
-impl Ship {
-    fn spawn_bullets(&self) -> Vec<Box<Bullet>> {
-        let cannons_x = self.rect.x + 30.0;
-        let cannon1_y = self.rect.y + 6.0;
-        let cannon2_y = self.rect.y + SHIP_H - 10.0;
+    // The hit points of the wingman, in a game for two
+    wingman_hp: u32,
+}
 
-        // Create one bullet at the tip of each cannon
-        match self.cannon {
-            CannonType::RectBullet =>
-                vec![
-                    Box::new(RectBullet {
-                        rect: Rectangle {
-                            x : cannons_x,
-                            y : cannon1_y,
-                            w : BULLET_W,
-                            h : BULLET_H,
-                        }
-                    }),
-                    Box::new(RectBullet {
-                        rect: Rectangle {
-                            x : cannons_x,
-                            y : cannon2_y,
-                            w : BULLET_W,
-                            h : BULLET_H,
-                        }
-                    }),
-                ],
-            CannonType::SineBullet { amplitude, angular_vel } =>
-                vec![
-                    Box::new(SineBullet {
-                        pos_x: cannons_x,
-                        origin_y: cannon1_y,
-                        amplitude: amplitude,
-                        angular_vel: angular_vel,
-                        total_time: 0.0,
-                    }),
-                    Box::new(SineBullet {
-                        pos_x: cannons_x,
-                        origin_y: cannon2_y,
-                        amplitude: amplitude,
-                        angular_vel: angular_vel,
-                        total_time: 0.0,
-                    }),
-                ],
-            CannonType::DivergentBullet { a, b } =>
-                vec![
-                    Box::new(DivergentBullet {
-                        pos_x: cannons_x,
-                        origin_y: cannon1_y,
-                        a: -a,
-                        b: b,
-                        total_time: 0.0,
-                    }),
-                    Box::new(DivergentBullet {
-                        pos_x: cannons_x,
-                        origin_y: cannon2_y,
-                        a: a,
-                        b: b,
-                        total_time: 0.0,
-                    }),
-                ]
-        }
-    }
+/// Where a timeline flies the ship, and the seconds left to get there
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Autopilot {
+    target: (f64, f64),
+    time_left: f64,
 }
 
+/// Everything that decides how the run unfolds from a given frame on, which
+/// the debugging tools rewind the game to. What only shows, such as the
+/// particles and the banners, is left as it is.
+#[derive(Clone)]
+struct WorldState {
+    // The number of frames recorded when the snapshot was taken
+    frame: usize,
+
+    player: Ship,
+    wingman: Option<Ship>,
+    drones: Drones,
+    bullets: Bullets,
+    asteroids: Vec<Asteroid>,
+    enemies: Vec<Enemy>,
+    spawn_timer: JitterTimer,
+    terrain_scroll: Option<f64>,
+    level_time: f64,
+    next_hazard: usize,
+    mines: Vec<Mine>,
+    debris: Vec<Debris>,
+    wells: Vec<GravityWell>,
+    timeline: Option<Timeline>,
+    ending: bool,
+    autopilot: Option<Autopilot>,
+    boss: Option<Boss>,
+    enemy_bullets: Vec<EnemyBullet>,
+    pickups: Vec<Pickup>,
+    magnet_time: f64,
+    bullet_time: f64,
+    score: u32,
+    rng: StdRng,
+    wave: u32,
+    wave_stats: WaveStats,
+    combo: Combo,
+    hit_stop: f64,
+    checkpoint: Checkpoint,
+    continues_left: u32,
+}
 
 // VIEW DEFINITIONS . . .
 
 pub struct GameView {
     player: Ship,
-    bullets: Vec<Box<Bullet>>,
+
+    // The ship of the second player, in a game for two
+    wingman: Option<Ship>,
+
+    drones: Drones,
+    bullets: Bullets,
     asteroids: Vec<Asteroid>,
     asteroid_factory: AsteroidFactory,
+    enemies: Vec<Enemy>,
+    enemy_sprite: Sprite,
+    formations: Vec<Formation>,
+    scripts: Scripts,
+
+    // The mode the run is played in, which decides how hard it is, and the
+    // drones the ship started with, recorded along with the run
+    mode: GameMode,
+    start_drones: usize,
+
+    // The level, the time elapsed in it, and the index of the next hazard
+    // to appear, and when to pick from its spawn table next
+    level: Level,
+    spawn_timer: JitterTimer,
+
+    terrain: Option<Terrain>,
+    level_time: f64,
+    next_hazard: usize,
+    mines: Vec<Mine>,
+    debris: Vec<Debris>,
+    wells: Vec<GravityWell>,
+
+    // The timeline playing, such as the introduction of the level or the
+    // entrance of the boss, and the caption it shows with the seconds it
+    // stays on screen
+    timeline: Option<Timeline>,
+    caption: Option<(Sprite, f64)>,
+
+    // Whether the final boss was destroyed, so that the run ends with the
+    // ending, and where the ending flies the ship meanwhile
+    ending: bool,
+    autopilot: Option<Autopilot>,
+
+    // The boss guarding the end of the level, and the bullets fired at
+    // the player
+    boss: Option<Boss>,
+    enemy_bullets: Vec<EnemyBullet>,
+
+    pickups: Vec<Pickup>,
+    particles: ParticleSystem,
+    damage_numbers: DamageNumbers,
+
+    // Seconds before the magnet stops attracting the pickups, and before
+    // bullet time ends
+    magnet_time: f64,
+    bullet_time: f64,
+
+    // The gauges of the HUD: the ship's hit points, and the time left
+    // before the magnet stops
+    health_bar: Bar,
+    magnet_bar: Bar,
+
     backgrounds: Backgrounds,
+    camera: Camera,
+
+    score: u32,
+    score_label: Sprite,
+
+    // Identifies the run on the leaderboard, and seeds every random number
+    // the game draws, so that the run plays the same when watched again
+    seed: u64,
+    rng: StdRng,
+
+    // The input of every frame, and the size of the window when the run
+    // started, saved along with the run at game over
+    frames: Vec<Input>,
+    window_size: (f64, f64),
+
+    // The seconds the run lasted so far, and where the ship was every
+    // POSITION_INTERVAL seconds, saved with the run for it to be shown as
+    // a ghost later
+    run_time: f64,
+    positions: Vec<(f64, (f64, f64))>,
+
+    // The best run of the player in this mode, flying along
+    ghost: Option<Ghost>,
+
+    // The game speed of the settings, as of the last frame
+    game_speed: f64,
+
+    // The current wave, starting at 1, how accurate the player is during
+    // it, and the summary of the previous wave with the seconds it stays
+    // on screen
+    wave: u32,
+    wave_stats: WaveStats,
+    summary: Option<(Sprite, f64)>,
+
+    // The announcements shown across the screen, and the best score of the
+    // leaderboard until the player beats it
+    banners: Banners,
+    best_score: Option<u32>,
+
+    // The combo, and the number of kills its label shows
+    combo: Combo,
+    combo_shown: u32,
+    combo_label: Option<Sprite>,
+
+    // Where the player aims when aiming with the mouse, and whether the
+    // crosshair replaced the mouse cursor, so that it is only drawn with
+    // the scene while aiming with the stick
+    crosshair: Option<(f64, f64)>,
+    crosshair_cursor: bool,
+
+    // Seconds during which the world stays frozen after a heavy hit. Only
+    // set when handling GameEvent::BulletHit, whatever the bullet hit.
+    hit_stop: f64,
+
+    // The start of the current wave, the continues the player has left,
+    // and, after a game over, the seconds left to continue along with
+    // the prompt showing them
+    checkpoint: Checkpoint,
+    continues_left: u32,
+    continue_countdown: Option<f64>,
+    continue_label: Option<(u32, Prompt)>,
+
+    // Set by the pause menu when the player chooses to restart the wave
+    restart_requested: Rc<Cell<bool>>,
+
+    // The latest snapshots of the world, oldest first, and the seconds of
+    // play before the next one, while the debugging tools are on
+    snapshots: VecDeque<WorldState>,
+    snapshot_timer: f64,
+
+    // Whether the game only moves on a frame at a time, while the debugging
+    // tools are on, and the label telling so along with the frame it shows
+    stepping: bool,
+    step_label: Option<(usize, Sprite)>,
+
+    // The debugging cheats: whether nothing harms the ship, what F6 brings
+    // in, and whether any cheat was used, which keeps the run off the
+    // leaderboard
+    god_mode: bool,
+    debug_spawn: usize,
+    cheated: bool,
+
+    // What practice shows, during practice, where the ship is never lost
+    // for good and the run is neither ranked nor saved
+    practice: Option<Practice>,
+
+    // Whether the player's controller was unplugged during the game
+    controller_lost: bool,
+    controller_lost_label: Sprite,
 }
 
 impl GameView {
 
-    #[allow(dead_code)]
     pub fn new(phi: &mut Phi) -> GameView {
-        let bg = Backgrounds::new(&mut phi.renderer);
+        let bg = Backgrounds::new(phi);
         GameView::new_with_backgrounds(phi, bg)
     }
 
     pub fn new_with_backgrounds(phi: &mut Phi, backgrounds: Backgrounds) -> GameView {
+        let mut view = GameView::new_with_seed(phi, backgrounds, ::rand::random());
+        view.load_ghost(phi);
+        view
+    }
+
+    /// Start a run of New Game+, with the escort the ship cleared the
+    /// campaign with
+    pub fn new_plus(phi: &mut Phi, backgrounds: Backgrounds) -> GameView {
+        let drones = Progress::load(PROGRESS_PATH).drones;
+        let mut view = GameView::new_with_mode(phi, backgrounds, ::rand::random(), GameMode::NewGamePlus, drones);
+        view.load_ghost(phi);
+        view
+    }
+
+    /// Start practicing the wave picked in the settings, from its start or
+    /// right at its boss, with the seed picked there
+    pub fn new_practice(phi: &mut Phi, backgrounds: Backgrounds) -> GameView {
+        let wave = phi.settings.practice_wave.max(1).min(FINAL_WAVE);
+        let boss = phi.settings.practice_boss;
+        let seed = phi.settings.practice_seed;
+
+        let mut view = GameView::new_with_seed(phi, backgrounds, seed);
+        view.practice = Some(Practice::new(boss));
+        view.best_score = None;
+        view.wave = wave;
+        view.checkpoint = view.snapshot();
+        view.restore_checkpoint(phi);
+        view
+    }
+
+    /// Let the best run of the player in this mode fly along, if it was
+    /// saved with the positions of the ship
+    fn load_ghost(&mut self, phi: &Phi) {
+        let best = Leaderboard::load(self.mode).sorted(SortKey::Score).into_iter()
+            .find(|entry| entry.name == phi.settings.player_name);
+        self.ghost = best.and_then(|entry| Ghost::load(&replay::path_of(&entry)));
+    }
+
+    /// Start the run of 'replay' over, to watch it again
+    pub fn from_replay(phi: &mut Phi, backgrounds: Backgrounds, replay: &Replay) -> GameView {
+        GameView::new_with_mode(phi, backgrounds, replay.entry.seed, replay.mode, replay.drones)
+    }
+
+    /// Start a run for two players whose random numbers all come from
+    /// 'seed'. The second player flies a wingman, tinted apart from the
+    /// ship; simulate_pair plays the run with the inputs of both, and
+    /// losing either ship costs the wave.
+    pub fn new_pair(phi: &mut Phi, backgrounds: Backgrounds, seed: u64) -> GameView {
+        let mut view = GameView::new_with_seed(phi, backgrounds, seed);
+        let mut wingman = view.player.clone();
+        wingman.rect.y += WINGMAN_GAP;
+        view.wingman = Some(wingman);
+        view.best_score = None;
+        view
+    }
+
+    /// Start an arcade run whose random numbers all come from 'seed'
+    pub fn new_with_seed(phi: &mut Phi, backgrounds: Backgrounds, seed: u64) -> GameView {
+        GameView::new_with_mode(phi, backgrounds, seed, GameMode::Arcade, 0)
+    }
+
+    /// Start a run of 'mode' whose random numbers all come from 'seed', with
+    /// 'drones' escorting the ship
+    pub fn new_with_mode(phi: &mut Phi, backgrounds: Backgrounds, seed: u64,
+                         mode: GameMode, drones: usize) -> GameView {
 
         // Load the texture from the filesystem
-        let spritesheet = Sprite::load(&mut phi.renderer, "assets/spaceship.png").unwrap();
-        let mut sprites = Vec::with_capacity(9);
-        for y in 0..3 {
-            for x in 0..3 {
-                sprites.push(spritesheet.region(Rectangle {
-                    w: SHIP_W,
-                    h: SHIP_H,
-                    x: SHIP_W * x as f64,
-                    y: SHIP_H * y as f64,
-                }).unwrap());
-            }
-        }
+        let sprites = phi.load_frames(SHIP_ASSET).unwrap();
+        let damaged_sprites = SHIP_DAMAGED_ASSETS.iter()
+            .map(|name| phi.load_frames(name))
+            .collect();
+
+        let enemy_sprite = sprites[ShipFrame::MidNorm as usize].clone();
+
+        // Drones are small copies of the ship with a flickering engine
+        let drone_sprite = AnimatedSprite::new_with_fps(vec![
+            sprites[ShipFrame::MidNorm as usize].clone(),
+            sprites[ShipFrame::MidFast as usize].clone(),
+            sprites[ShipFrame::MidNorm as usize].clone(),
+            sprites[ShipFrame::MidSlow as usize].clone(),
+        ], 12.0);
+
+        // The level may be played over other backgrounds than the menu's
+        let level = Level::load(hazards::LEVEL_PATH);
+        let backgrounds = match level.background {
+            Some(ref name) if *name != backgrounds.name => Backgrounds::load(phi, name),
+            _ => backgrounds,
+        };
 
-        GameView {
+        let controller_lost = phi.locale.text("game.controller_lost");
+        let best_score = Leaderboard::load(mode).best_score();
+        let best_score = if best_score > 0 { Some(best_score) } else { None };
+
+        let mut view = GameView {
             player: Ship {
                 rect: Rectangle {
-                    x: 64.0,
-                    y: 64.0,
+                    x: SHIP_START_X,
+                    y: (phi.output_size().1 - SHIP_H) / 2.0,
                     w: SHIP_W,
                     h: SHIP_H,
                 },
                 sprites: sprites,
                 current: ShipFrame::MidNorm,
+                damaged_sprites: damaged_sprites,
+                hp: SHIP_HP,
+                invulnerable: 0.0,
                 cannon: CannonType::RectBullet,
+                hitboxes: gfx::load_hitboxes(phi.assets.path(SHIP_ASSET), 9),
+                trail: Trail::new(0.04, 0.25),
             },
 
-            bullets: vec![],
+            wingman: None,
+
+            drones: Drones::new(drone_sprite),
+
+            bullets: Bullets::new(),
 
             asteroids: vec![],
 
             asteroid_factory: Asteroid::factory(phi),
 
+            enemies: vec![],
+            enemy_sprite: enemy_sprite,
+            formations: enemies::load_formations(enemies::FORMATIONS_PATH),
+            scripts: Scripts::load(scripting::SCRIPTS_DIR),
+
+            terrain: level.tilemap.as_ref().and_then(|path| Terrain::load(phi, path)),
+            level: level,
+            mode: mode,
+            start_drones: drones,
+            spawn_timer: spawn_timer(mode),
+            level_time: 0.0,
+            next_hazard: 0,
+            mines: vec![],
+            debris: vec![],
+            wells: vec![],
+
+            timeline: Some(Timeline::load(timeline::INTRO_PATH)),
+            caption: None,
+            ending: false,
+            autopilot: None,
+
+            boss: None,
+            enemy_bullets: vec![],
+
+            pickups: vec![],
+            particles: ParticleSystem::new(),
+            damage_numbers: DamageNumbers::new(phi),
+            magnet_time: 0.0,
+            health_bar: Bar::new(Color::RGB(70, 210, 90))
+                .with_threshold(0.7, Color::RGB(240, 200, 40))
+                .with_threshold(0.4, Color::RGB(230, 50, 40)),
+            magnet_bar: Bar::new(pickups::MAGNET_COLOR).without_ghost(),
+            bullet_time: 0.0,
+
             backgrounds: backgrounds,
+            camera: Camera::new(),
+
+            score: 0,
+            score_label: GameView::score_label(phi, 0),
+
+            seed: seed,
+            rng: StdRng::from_seed(&[seed as usize, (seed >> 32) as usize][..]),
+
+            frames: vec![],
+            window_size: phi.output_size(),
+            run_time: 0.0,
+            positions: vec![],
+            ghost: None,
+            game_speed: phi.settings.game_speed(),
+
+            wave: 1,
+            wave_stats: WaveStats::default(),
+            summary: None,
+
+            banners: Banners::new(),
+            best_score: best_score,
+
+            combo: Combo::new(),
+            combo_shown: 0,
+            combo_label: None,
+
+            crosshair: None,
+            crosshair_cursor: false,
+
+            hit_stop: 0.0,
+
+            checkpoint: Checkpoint {
+                wave: 1,
+                score: 0,
+                hp: SHIP_HP,
+                drones: drones,
+                cannon: CannonType::RectBullet,
+                wingman_hp: SHIP_HP,
+            },
+            continues_left: MAX_CONTINUES,
+            continue_countdown: None,
+            continue_label: None,
+            restart_requested: Rc::new(Cell::new(false)),
+
+            snapshots: VecDeque::with_capacity(REWIND_SNAPSHOTS),
+            snapshot_timer: 0.0,
+
+            stepping: false,
+            step_label: None,
+
+            god_mode: false,
+            debug_spawn: 0,
+            cheated: false,
+
+            practice: None,
+
+            controller_lost: false,
+            controller_lost_label: phi.ttf_str_sprite(
+                &controller_lost, "menu_font", 32,
+                Color::RGB(255, 255, 255)).unwrap(),
+        };
+        view.drones.set_count(drones);
+        view.announce_wave(phi);
+        view
+    }
+
+    /// Top the scene up to 'bullets' bullets and 'asteroids' asteroids,
+    /// placed with 'rng', and keep the ship from being destroyed. Used to
+    /// put a steady load on the game when benchmarking it.
+    pub fn fill_benchmark_scene<R: Rng>(&mut self, phi: &mut Phi, rng: &mut R,
+                                        bullets: usize, asteroids: usize) {
+        let (w, h) = phi.output_size();
+
+        while self.bullets.len() < bullets {
+            let angle = rng.angle();
+            self.bullets.push(Bullet {
+                kind: BulletKind::Rect,
+                rect: Rectangle {
+                    x: rng.range_f64(0.0, w),
+                    y: rng.range_f64(0.0, h),
+                    w: BULLET_W,
+                    h: BULLET_H,
+                },
+                vel: (angle.cos() * BULLET_SPEED, angle.sin() * BULLET_SPEED),
+            });
+        }
+
+        while self.asteroids.len() < asteroids {
+            let size = AsteroidSize::random(rng);
+            let center = (rng.range_f64(0.0, w), rng.range_f64(0.0, h));
+            self.asteroids.push(self.asteroid_factory.spawn(rng, size, center, 0.0));
         }
+
+        self.player.invulnerable = SHIP_INVULNERABILITY;
     }
 }
 
 impl View for GameView {
 
+    fn resume(&mut self, phi: &mut Phi) {
+        // The crosshair replaces the system cursor when aiming with the mouse
+        if phi.settings.mouse_aim {
+            let cursor = Cursor::crosshair(&phi.renderer, CROSSHAIR_SIDE as u32, Color::RGB(230, 230, 230));
+            match cursor {
+                Some(cursor) => {
+                    phi.set_cursor(Some(cursor));
+                    self.crosshair_cursor = true;
+                },
+                None => phi.show_system_cursor(false),
+            }
+        }
+    }
+
+    fn pause(&mut self, phi: &mut Phi) {
+        self.crosshair_cursor = false;
+        phi.set_title_status(None);
+    }
+
+    fn draw_frozen(&mut self, phi: &mut Phi) {
+        self.draw(phi, 0.0);
+    }
+
     fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
 
         if phi.events.now.quit {
             return ViewAction::Quit;
         }
-        if phi.events.now.key_escape == Some(true) {
-            return ViewAction::ChangeView(Box::new(
-                ::views::main_menu::MainMenuView::new_with_backgrounds(
-                    phi, self.backgrounds.clone())));
+        let pause = phi.pad_bindings(PLAYER_ID).pause;
+        if phi.events.now.key_escape == Some(true) || phi.events.pad_pressed(PLAYER_ID, pause) {
+            return ViewAction::PushView(Box::new(
+                ::views::pause::PauseView::new(phi, self.restart_requested.clone(),
+                                               self.continue_countdown.is_none())));
+        }
+
+        // Freeze the game while the player's controller is unplugged
+        if phi.events.now.controller_disconnected == Some(PLAYER_ID) {
+            self.controller_lost = true;
+            let paused = phi.locale.text("title.paused");
+            phi.set_title_status(Some(&paused));
+        }
+        if phi.events.now.controller_connected == Some(PLAYER_ID) {
+            self.controller_lost = false;
+            phi.set_title_status(None);
+        }
+
+        if self.controller_lost {
+            self.draw(phi, 0.0);
+            self.draw_controller_lost(phi);
+            return ViewAction::None;
+        }
+
+        // While stepping, the game holds still until asked for the next
+        // frame, which lasts a fixed time whatever the frame rate
+        let mut elapsed = elapsed;
+        let stepping = phi.settings.debug_tools && self.stepping;
+        if phi.settings.debug_tools {
+            self.debug_cheats(phi);
+            if phi.events.now.key_f9 == Some(true) {
+                self.rewind(phi);
+            }
+            if phi.events.now.key_f10 == Some(true) {
+                self.stepping = !self.stepping;
+            }
+            if stepping {
+                if phi.events.now.key_f11 != Some(true) {
+                    self.draw_frame(phi, 0.0);
+                    self.draw_step_indicator(phi);
+                    return ViewAction::None;
+                }
+                elapsed = STEP_TIME;
+            }
+            if self.continue_countdown.is_none() {
+                self.take_snapshot(elapsed);
+            }
+        }
+
+        // Every frame the game runs is recorded, so that it can be played
+        // back from the same seed
+        let input = self.read_input(phi, elapsed);
+        self.frames.push(input.clone());
+        if self.simulate(phi, &input) {
+            return self.game_over(phi);
+        }
+        self.draw_frame(phi, elapsed);
+        if stepping {
+            self.draw_step_indicator(phi);
+        }
+
+        ViewAction::None
+    }
+
+}
+
+impl GameView {
+
+    /// Run a frame of the game with the player's 'input', without drawing
+    /// it, and return whether the game is over
+    pub fn simulate(&mut self, phi: &mut Phi, input: &Input) -> bool {
+        self.simulate_with(phi, input, None)
+    }
+
+    /// Run a frame of a game for two as simulate does, with the 'partner'
+    /// input of the player flying the wingman
+    pub fn simulate_pair(&mut self, phi: &mut Phi, input: &Input, partner: &Input) -> bool {
+        self.simulate_with(phi, input, Some(partner))
+    }
+
+    // Run a frame with the input of every player
+    fn simulate_with(&mut self, phi: &mut Phi, input: &Input, partner: Option<&Input>) -> bool {
+        // Each frame only reacts to its own events, even when several are
+        // simulated before the bus is cleared, as when seeking a replay
+        phi.bus.clear();
+        self.game_speed = input.game_speed;
+
+        // Keep where the ship is every now and then, for the run to be
+        // shown as a ghost
+        let sampled = (self.run_time / replay::POSITION_INTERVAL).floor();
+        self.run_time += input.elapsed;
+        if (self.run_time / replay::POSITION_INTERVAL).floor() > sampled {
+            self.positions.push((self.run_time, (self.player.rect.x, self.player.rect.y)));
+        }
+
+        if let Some(countdown) = self.continue_countdown {
+            // Offer to continue from the checkpoint until the time is up
+            let countdown = countdown - input.elapsed;
+            if countdown <= 0.0 {
+                return true;
+            }
+
+            if input.confirm || partner.map_or(false, |partner| partner.confirm) {
+                self.continue_from_checkpoint(phi);
+            }
+            else {
+                self.continue_countdown = Some(countdown);
+            }
+            false
+        }
+        else if self.hit_stop > 0.0 {
+            // Hold the frame on the impact
+            self.hit_stop -= input.elapsed;
+            false
+        }
+        else {
+            if input.restart {
+                self.restore_checkpoint(phi);
+            }
+
+            phi.profiler.begin("update");
+            let over = self.update(phi, input, partner);
+            phi.profiler.end("update");
+            over
+        }
+    }
+
+    /// Draw the frame last simulated; the backgrounds scroll by 'elapsed'
+    /// seconds
+    pub fn draw_frame(&mut self, phi: &mut Phi, elapsed: f64) {
+        if let Some(countdown) = self.continue_countdown {
+            self.draw(phi, 0.0);
+            self.draw_continue(phi, countdown);
+        }
+        else if self.hit_stop > 0.0 {
+            self.draw(phi, 0.0);
+        }
+        else {
+            let world_dt = elapsed * self.time_scale();
+            phi.profiler.begin("draw");
+            self.draw(phi, world_dt);
+            phi.profiler.end("draw");
         }
+    }
 
-        // Change the player's cannons
+    /// Read what the player does during a frame lasting 'elapsed' seconds
+    fn read_input(&self, phi: &Phi, elapsed: f64) -> Input {
+        let bindings = phi.pad_bindings(PLAYER_ID);
+
+        // Change the player's cannons, directly with the keyboard or in turn
+        // with the controller
+        let mut cannon = match self.player.cannon {
+            CannonType::RectBullet => 1,
+            CannonType::SineBullet { .. } => 2,
+            CannonType::DivergentBullet { .. } => 3,
+        };
+        if phi.events.pad_pressed(PLAYER_ID, bindings.next_weapon) {
+            cannon = cannon % 3 + 1;
+        }
         if phi.events.now.key_1 == Some(true) {
-            self.player.cannon = CannonType::RectBullet;
+            cannon = 1;
         }
         if phi.events.now.key_2 == Some(true) {
-            self.player.cannon = CannonType::SineBullet {
-                amplitude: 10.0,
-                angular_vel: 15.0,
-            };
+            cannon = 2;
         }
-
         if phi.events.now.key_3 == Some(true) {
-            self.player.cannon = CannonType::DivergentBullet {
-                a: 100.0,
-                b: 1.2,
-            };
+            cannon = 3;
         }
 
-        // Move the player's ship
-
+        // Steer with the keyboard, as fast diagonally as straight ahead
         let diagonal =  (phi.events.key_up ^ phi.events.key_down)
                      && (phi.events.key_left ^ phi.events.key_right);
 
         let moved = if diagonal { 1.0 / 2.0f64.sqrt() }
-                    else { 1.0 }
-                    * PLAYER_SPEED * elapsed;
+                    else { 1.0 };
 
-        let dx = match (phi.events.key_left, phi.events.key_right) {
+        let mut x = match (phi.events.key_left, phi.events.key_right) {
             (true, true) | (false, false) => 0.0,
             (true, false) => -moved,
             (false, true) =>  moved,
         };
 
-        let dy = match (phi.events.key_up, phi.events.key_down) {
+        let mut y = match (phi.events.key_up, phi.events.key_down) {
             (true, true) | (false, false) => 0.0,
             (true, false) => -moved,
             (false, true) =>  moved,
         };
 
+        // A tilted analog stick takes over the keyboard, with a speed
+        // proportional to how far it is tilted
+        if let Some(stick) = bindings.move_stick(&phi.events.controllers, PLAYER_ID) {
+            let (sx, sy) = phi.settings.dead_zone().apply(stick);
+            if sx != 0.0 || sy != 0.0 {
+                x = sx;
+                y = sy;
+            }
+        }
+
+        let pad_fire = phi.events.pad_pressed(PLAYER_ID, bindings.fire);
+        let fire = phi.events.now.key_space == Some(true) || pad_fire
+                   || (phi.settings.mouse_aim && phi.events.now.mouse_left == Some(true));
+
+        Input {
+            elapsed: elapsed,
+            game_speed: phi.settings.game_speed(),
+            movement: (x, y),
+            cannon: cannon,
+            fire: fire,
+            aim: if phi.settings.mouse_aim { Some(self.aim_target(phi)) } else { None },
+            confirm: phi.events.now.key_return == Some(true) || phi.events.now.key_space == Some(true)
+                     || phi.events.pad_pressed(PLAYER_ID, PadInput::Button(Button::A)),
+            restart: self.restart_requested.replace(false),
+        }
+    }
+
+    /// Move every entity and handle the input of every player, and return
+    /// whether the game is over
+    fn update(&mut self, phi: &mut Phi, input: &Input, partner: Option<&Input>) -> bool {
+        let elapsed = input.elapsed;
+
+        self.player.cannon = cannon_type(input.cannon);
+
+        // Move the player's ship, unless a timeline flies it
+        let (dx, dy) = match self.autopilot {
+            Some(ref mut autopilot) => {
+                let (win_w, win_h) = phi.output_size();
+                let share = if autopilot.time_left > elapsed { elapsed / autopilot.time_left } else { 1.0 };
+                autopilot.time_left = (autopilot.time_left - elapsed).max(0.0);
+                let rect = self.player.rect;
+                ((autopilot.target.0 * win_w - rect.w / 2.0 - rect.x) * share,
+                 (autopilot.target.1 * win_h - rect.h / 2.0 - rect.y) * share)
+            },
+            None => (input.movement.0 * PLAYER_SPEED * elapsed,
+                     input.movement.1 * PLAYER_SPEED * elapsed),
+        };
+
         // Create a bounding box - limit width to 70% of maximum
         let moveable_region = Rectangle {
             x: 0f64,
@@ -375,236 +1194,1776 @@ impl View for GameView {
             h: phi.output_size().1,
         };
 
-        self.player.rect.x += dx;
-        self.player.rect.y += dy;
-        self.player.rect =
-            self.player.rect.move_inside(moveable_region).unwrap();
+        // Gravity wells pull the ship toward them, without changing the
+        // way it is shown as moving
+        let (pull_x, pull_y) = wells_pull(&self.wells, self.player.rect.center(), elapsed);
+
+        if self.autopilot.is_some() {
+            self.player.rect.x += dx;
+            self.player.rect.y += dy;
+        }
+        else {
+            self.player.rect.x += dx + pull_x;
+            self.player.rect.y += dy + pull_y;
+            self.player.rect = self.player.rect.clamp_inside(moveable_region);
+        }
+        self.drones.follow(self.player.rect.center(), elapsed);
+
+        // During bullet time, the ship outpaces everything and leaves
+        // afterimages behind
+        self.player.trail.set_active(self.bullet_time > 0.0);
+        self.player.trail.update(self.player.rect, elapsed);
+
+        self.player.emit_engine(&mut self.particles, (dx, dy), elapsed);
+        self.player.invulnerable -= elapsed;
+
+        // The wingman of a game for two is steered by the other player,
+        // even while a timeline flies the ship
+        if let (Some(wingman), Some(partner)) = (self.wingman.as_mut(), partner) {
+            let (dx, dy) = (partner.movement.0 * PLAYER_SPEED * elapsed,
+                            partner.movement.1 * PLAYER_SPEED * elapsed);
+            let (pull_x, pull_y) = wells_pull(&self.wells, wingman.rect.center(), elapsed);
+            wingman.cannon = cannon_type(partner.cannon);
+            wingman.rect.x += dx + pull_x;
+            wingman.rect.y += dy + pull_y;
+            wingman.rect = wingman.rect.clamp_inside(moveable_region);
+            wingman.current = ShipFrame::moving(dx, dy);
+            wingman.trail.set_active(self.bullet_time > 0.0);
+            wingman.trail.update(wingman.rect, elapsed);
+            wingman.emit_engine(&mut self.particles, (dx, dy), elapsed);
+            wingman.invulnerable -= elapsed;
+        }
+
+        // Everything but the ship slows down during bullet time
+        self.bullet_time = (self.bullet_time - elapsed).max(0.0);
+        let world_dt = elapsed * self.time_scale();
 
         // Select the appropriate sprite of the ship to show
-        self.player.current =
-            if      dx == 0.0 && dy < 0.0  { ShipFrame::UpNorm }
-            else if dx > 0.0  && dy < 0.0  { ShipFrame::UpFast }
-            else if dx < 0.0  && dy < 0.0  { ShipFrame::UpSlow }
-            else if dx == 0.0 && dy == 0.0 { ShipFrame::MidNorm }
-            else if dx > 0.0  && dy == 0.0 { ShipFrame::MidFast }
-            else if dx < 0.0  && dy == 0.0 { ShipFrame::MidSlow }
-            else if dx == 0.0 && dy > 0.0  { ShipFrame::DownNorm }
-            else if dx > 0.0  && dy > 0.0  { ShipFrame::DownFast }
-            else if dx < 0.0  && dy > 0.0  { ShipFrame::DownSlow }
-            else { unreachable!() };
+        self.player.current = ShipFrame::moving(dx, dy);
 
         // Update the bullets
-        let old_bullets = ::std::mem::replace(&mut self.bullets, vec![]);
-        self.bullets = old_bullets.into_iter()
-            .filter_map(|bullet| bullet.update(phi, elapsed)).collect();
+        self.bullets.update(world_dt);
+        for index in 0..self.bullets.len() {
+            BULLET_TRAIL.emit(&mut self.particles, self.bullets.rect(index).center(), 1.0, world_dt);
+        }
+        phi.profiler.begin("particles");
+        self.particles.set_share(phi.quality.particle_share());
+        self.particles.update(world_dt);
+        phi.profiler.end("particles");
 
         // Update the asteroid
-        self.asteroids = ::std::mem::replace(&mut self.asteroids, vec![])
+        Asteroid::update_all(&mut self.asteroids, world_dt);
+
+        // Update the enemies, with the latest version of their scripts
+        self.scripts.update(elapsed);
+        let scripts = &self.scripts;
+        self.enemies = ::std::mem::replace(&mut self.enemies, vec![])
             .into_iter()
-            .filter_map(|asteroid| asteroid.update(elapsed))
+            .filter_map(|enemy| enemy.update(phi, scripts, world_dt))
             .collect();
 
-        // Allow the player to shoot after the bullets are updated
-        // so that the new bullets appear at the tips of the cannons.
-        if phi.events.now.key_space == Some(true) {
-            self.bullets.append(&mut self.player.spawn_bullets());
+        // Update the hazards, and let gravity wells attract bullets and
+        // asteroids
+        for well in &mut self.wells {
+            well.update(world_dt);
         }
-
-        // Randomly create an asteroid about once every 100 frames,
-        // i.e. about every 2 seconds
-        if ::rand::random::<usize>() % 100 == 0 {
-            self.asteroids.push(self.asteroid_factory.random(phi));
+        for well in &self.wells {
+            self.bullets.accelerate(|pos| well.acceleration(pos), world_dt);
+            for asteroid in &mut self.asteroids {
+                let (ax, ay) = well.acceleration(asteroid.rect.center());
+                asteroid.accelerate(ax, ay, world_dt);
+            }
         }
 
-        println!("{} asteroids", self.asteroids.len());
-
-        // Clear the screen . . .
+        let ship = self.player.rect;
+        self.mines = ::std::mem::replace(&mut self.mines, vec![])
+            .into_iter()
+            .filter_map(|mine| mine.update(ship, world_dt))
+            .collect();
+        self.debris = ::std::mem::replace(&mut self.debris, vec![])
+            .into_iter()
+            .filter_map(|debris| debris.update(world_dt))
+            .collect();
 
-        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
-        phi.renderer.clear();
+        // The terrain drifts along with the hazards
+        if let Some(ref mut terrain) = self.terrain {
+            terrain.update(phi, world_dt);
+        }
 
-        // Render the backgrounds . . .
-        self.backgrounds.back.render(&mut phi.renderer, elapsed);
-        self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+        // Collect the pickups either ship flies into, or which the magnet
+        // brings to the ship. The gauge shows the ship closest to being
+        // destroyed, since losing either costs the wave.
+        self.magnet_time = (self.magnet_time - elapsed).max(0.0);
+        self.magnet_bar.set(self.magnet_time / pickups::MAGNET_DURATION);
+        let hp = self.wingman.as_ref().map_or(self.player.hp, |wingman| wingman.hp.min(self.player.hp));
+        self.health_bar.set(hp as f64 / SHIP_HP as f64);
+        self.health_bar.update(elapsed);
+        if self.magnet_time > 0.0 {
+            let target = self.player.rect.center();
+            for pickup in &mut self.pickups {
+                pickup.attract(target, world_dt);
+            }
+        }
+        for pickup in &mut self.pickups {
+            pickup.update(world_dt);
+        }
+        let (collected, pickups): (Vec<Pickup>, Vec<Pickup>) = {
+            let player = &self.player;
+            let wingman = &self.wingman;
+            ::std::mem::replace(&mut self.pickups, vec![])
+                .into_iter()
+                .partition(|pickup| player.collides_with(pickup.rect())
+                           || wingman.as_ref().map_or(false, |wingman| wingman.collides_with(pickup.rect())))
+        };
+        self.pickups = pickups;
+        for pickup in collected {
+            match pickup.kind {
+                PickupKind::Drone => { self.drones.add(); },
+                PickupKind::Magnet => self.magnet_time = pickups::MAGNET_DURATION,
+                PickupKind::BulletTime => self.bullet_time = pickups::BULLET_TIME_DURATION,
+            }
+        }
+
+        // Update the boss, which fires at the player
+        if let Some(ref mut boss) = self.boss {
+            if boss.is_warning() {
+                phi.overlay.dim(BOSS_INTRO_DIM);
+            }
+            let target = self.player.rect.center();
+            let mut fired = boss.update(phi, target, world_dt);
+            if self.mode == GameMode::NewGamePlus {
+                for bullet in &mut fired {
+                    bullet.speed_up(PLUS_BULLET_SPEED);
+                }
+            }
+            if !fired.is_empty() {
+                self.camera.shake(BOSS_FIRE_SHAKE);
+            }
+            self.enemy_bullets.append(&mut fired);
+        }
+        for bullet in &mut self.enemy_bullets {
+            bullet.update(world_dt);
+        }
+
+        // Forget what went too far past the edges of the screen to matter
+        // again. Everything uses the same margins, so that nothing vanishes
+        // while partly visible or still in the reach of a gravity well.
+        let size = phi.output_size();
+        self.bullets.retain(|rect| !shared::is_despawned(rect, size));
+        self.enemy_bullets.retain(|bullet| !shared::is_despawned(bullet.rect, size));
+        self.asteroids.retain(|asteroid| !shared::is_despawned(asteroid.rect(), size));
+        self.wells.retain(|well| !shared::is_despawned(well.reach(), size));
+        self.mines.retain(|mine| !shared::is_despawned(mine.bounds(), size));
+        self.debris.retain(|debris| !shared::is_despawned(debris.hitbox(), size));
+        self.pickups.retain(|pickup| !shared::is_despawned(pickup.rect(), size));
+
+        // Handle bullets hitting asteroids, enemies and hazards; every
+        // bullet hits at most one of them
+        phi.profiler.begin("collision");
+        let screen = Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: size.0,
+            h: size.1,
+        };
+        let mut kept = Vec::with_capacity(self.bullets.len());
+        for index in 0..self.bullets.len() {
+            let rect = self.bullets.rect(index);
+            let hit = GameEvent::BulletHit { pos: rect.center(), heavy: self.bullets.kind(index).is_heavy() };
+            kept.push(false);
+
+            // Bullets past the edges of the screen fly on until they are
+            // removed, but cannot hit what the player does not see
+            if !screen.overlaps(rect) {
+                kept[index] = true;
+                continue;
+            }
+
+            // The terrain stops bullets, without it counting as a hit
+            if self.terrain.as_ref().map_or(false, |terrain| terrain.overlaps(rect)) {
+                continue;
+            }
 
-        // Render the bounding box (for debugging) . . .\
-        if DEBUG
+            if let Some(asteroid) = self.asteroids.iter_mut()
+                    .find(|asteroid| asteroid.hp > 0 && asteroid.hitbox().overlaps(rect)) {
+                let (damage, crit) = roll_damage(&mut self.rng);
+                asteroid.hp = asteroid.hp.saturating_sub(damage);
+                asteroid.flash = HIT_FLASH;
+                phi.bus.push(hit);
+                phi.bus.push(GameEvent::Damage { pos: rect.center(), amount: damage, crit: crit });
+                continue;
+            }
+
+            if let Some(enemy) = self.enemies.iter_mut()
+                    .find(|enemy| enemy.is_active() && enemy.hitbox().overlaps(rect)) {
+                let (damage, crit) = roll_damage(&mut self.rng);
+                enemy.destroyed = true;
+                let pos = enemy.hitbox().center();
+                phi.bus.push(hit);
+                phi.bus.push(GameEvent::Damage { pos: pos, amount: damage, crit: crit });
+                phi.bus.push(GameEvent::EnemyDestroyed { pos: pos, score: enemies::ENEMY_SCORE });
+                if let Some(pickup) = Pickup::random_drop(&mut self.rng, pos) {
+                    self.pickups.push(pickup);
+                }
+                continue;
+            }
+
+            if let Some(mine) = self.mines.iter_mut()
+                    .find(|mine| mine.hitbox().map_or(false, |hitbox| hitbox.overlaps(rect))) {
+                mine.detonate();
+                phi.bus.push(hit);
+                phi.bus.push(GameEvent::HazardDestroyed { pos: mine.center(), score: hazards::MINE_SCORE });
+                continue;
+            }
+
+            if let Some(debris) = self.debris.iter_mut()
+                    .find(|debris| !debris.destroyed && debris.hitbox().overlaps(rect)) {
+                debris.destroyed = true;
+                phi.bus.push(hit);
+                phi.bus.push(GameEvent::HazardDestroyed {
+                    pos: debris.hitbox().center(),
+                    score: hazards::DEBRIS_SCORE,
+                });
+                continue;
+            }
+
+            if self.boss.as_ref().map_or(false, |boss| boss.is_hit_by(rect)) {
+                let (damage, crit) = roll_damage(&mut self.rng);
+                let (dealt, points) = self.boss.as_mut().unwrap().hit(rect, damage).unwrap_or((0, 0));
+
+                // Only destroyed parts count as kills
+                phi.bus.push(hit);
+                if dealt > 0 {
+                    phi.bus.push(GameEvent::Damage { pos: rect.center(), amount: dealt, crit: crit });
+                }
+                if points > 0 {
+                    phi.bus.push(GameEvent::BossPartDestroyed { pos: rect.center(), score: points });
+                }
+                continue;
+            }
+
+            kept[index] = true;
+        }
+        self.bullets.keep(&kept);
+        phi.profiler.end("collision");
+
+        // Destroying the boss ends the wave; see handle_events
+        if self.boss.as_ref().map_or(false, |boss| boss.is_destroyed()) {
+            let pos = self.boss.as_ref().unwrap().center();
+            self.boss = None;
+            self.enemy_bullets.clear();
+            phi.bus.push(GameEvent::BossDestroyed { pos: pos, score: boss::BOSS_SCORE });
+        }
+
+        // The terrain stops the bullets of the boss, and the enemies which
+        // crash into it are destroyed, though not by the player
+        if let Some(ref terrain) = self.terrain {
+            self.enemy_bullets.retain(|bullet| !terrain.overlaps(bullet.rect));
+            for enemy in &mut self.enemies {
+                if enemy.is_active() && terrain.overlaps(enemy.hitbox()) {
+                    enemy.destroyed = true;
+                }
+            }
+        }
+
+        // Drones absorb the bullets, asteroids and enemies they run into,
+        // and are destroyed by them
         {
-            phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-            phi.renderer.fill_rect(self.player.rect.to_sdl().unwrap());
+            let drones = &mut self.drones;
+            self.enemy_bullets.retain(|bullet| !drones.absorb(bullet.rect));
+        }
+        for asteroid in &mut self.asteroids {
+            if asteroid.hp > 0 && self.drones.absorb(asteroid.hitbox()) {
+                asteroid.hp -= 1;
+            }
+        }
+        for enemy in &mut self.enemies {
+            if enemy.is_active() && self.drones.absorb(enemy.hitbox()) {
+                enemy.destroyed = true;
+                phi.bus.push(GameEvent::EnemyDestroyed {
+                    pos: enemy.hitbox().center(),
+                    score: enemies::ENEMY_SCORE,
+                });
+            }
+        }
+
+        // Explosions destroy the asteroids and enemies they reach
+        for mine in self.mines.iter().filter(|mine| mine.just_exploded()) {
+            phi.bus.push(GameEvent::MineExploded { pos: mine.center() });
+        }
+        for (center, radius) in self.mines.iter().filter_map(|mine| mine.blast()) {
+            for asteroid in &mut self.asteroids {
+                if asteroid.hitbox().overlaps_circle(center, radius) {
+                    asteroid.hp = 0;
+                }
+            }
+            for enemy in &mut self.enemies {
+                if enemy.is_active() && enemy.hitbox().overlaps_circle(center, radius) {
+                    enemy.destroyed = true;
+                    phi.bus.push(GameEvent::EnemyDestroyed {
+                        pos: enemy.hitbox().center(),
+                        score: enemies::ENEMY_SCORE,
+                    });
+                }
+            }
+        }
+
+        // Destroyed asteroids break into smaller ones
+        let mut pieces = vec![];
+        for asteroid in self.asteroids.iter().filter(|asteroid| asteroid.hp == 0) {
+            phi.bus.push(GameEvent::AsteroidDestroyed {
+                pos: asteroid.hitbox().center(),
+                score: asteroid.size.score(),
+            });
+            pieces.append(&mut self.asteroid_factory.split(&mut self.rng, asteroid));
+        }
+        self.asteroids.retain(|asteroid| asteroid.hp > 0);
+        self.asteroids.append(&mut pieces);
+
+        // Allow the player to shoot after the bullets are updated
+        // so that the new bullets appear at the tips of the cannons.
+        let bullets_before = self.bullets.len();
+        let fire = input.fire && self.autopilot.is_none();
+        self.crosshair = input.aim;
+        if let Some(aim) = input.aim {
+            if fire {
+                let dir = direction(self.player.cannons_center(), aim);
+                self.bullets.append(&mut self.player.spawn_aimed_bullets(dir));
+
+                // Every drone aims at the crosshair on its own
+                for center in self.drones.positions() {
+                    self.bullets.push(drone_bullet(center, direction(center, aim)));
+                }
+            }
+        }
+        else if fire {
+            self.bullets.append(&mut self.player.spawn_bullets());
+            for center in self.drones.positions() {
+                self.bullets.push(drone_bullet(center, (1.0, 0.0)));
+            }
+        }
+        if let (Some(wingman), Some(partner)) = (self.wingman.as_ref(), partner) {
+            if partner.fire && self.autopilot.is_none() {
+                let mut bullets = match partner.aim {
+                    Some(aim) => wingman.spawn_aimed_bullets(direction(wingman.cannons_center(), aim)),
+                    None => wingman.spawn_bullets(),
+                };
+                self.bullets.append(&mut bullets);
+            }
+        }
+        let fired = (self.bullets.len() - bullets_before) as u32;
+        if fired > 0 {
+            phi.bus.push(GameEvent::ShotsFired { count: fired });
+        }
+
+        // Take the summary of the last wave away after a while
+        if let Some((_, ref mut time_left)) = self.summary {
+            *time_left -= elapsed;
+        }
+        if self.summary.as_ref().map_or(false, |&(_, time_left)| time_left <= 0.0) {
+            self.summary = None;
+        }
+
+        // Take the caption of the timeline away after a while
+        if let Some((_, ref mut time_left)) = self.caption {
+            *time_left -= elapsed;
+        }
+        if self.caption.as_ref().map_or(false, |&(_, time_left)| time_left <= 0.0) {
+            self.caption = None;
         }
 
-        // Render the ship . . .
-        self.player.sprites[self.player.current as usize]
-            .render(&mut phi.renderer, self.player.rect);
+        if let Some(sound) = self.banners.update(phi, elapsed) {
+            play_sound(&sound);
+        }
+        if let Some(ref mut practice) = self.practice {
+            practice.update(phi, elapsed);
+        }
+
+        // While a timeline plays, only it brings things into the game
+        self.run_timeline(phi, world_dt);
+        let scripted = self.timeline.is_some();
+        if self.ending && !scripted {
+            return true;
+        }
+
+        // Bring in the hazards of the level as their time comes. Once they
+        // all appeared, the boss makes its entrance, and the level starts
+        // over after it is destroyed.
+        if self.boss.is_none() && !scripted {
+            self.level_time += world_dt;
+        }
+        while self.next_hazard < self.level.spawns.len()
+                && self.level.spawns[self.next_hazard].0 <= self.level_time {
+            let hazard = self.level.spawns[self.next_hazard].1.clone();
+            self.spawn(phi, Spawn::Hazard(hazard));
+            self.next_hazard += 1;
+        }
+        if self.next_hazard == self.level.spawns.len() && !self.level.spawns.is_empty() {
+            self.timeline = Some(GameView::boss_entrance());
+            self.level_time = 0.0;
+            self.next_hazard = 0;
+        }
+
+        // Bring in something from the spawn table of the level every few
+        // seconds
+        if !scripted && self.spawn_timer.update(&mut self.rng, world_dt) {
+            self.spawn_random(phi);
+        }
+
+        trace!("{} asteroids", self.asteroids.len());
+
+        self.camera.update(elapsed);
+
+        // The ships are damaged by whatever they hit, and the game is over
+        // once either is destroyed
+        let harmless = self.god_mode || self.ending;
+        let hit = !harmless && self.is_hit(&self.player);
+        let vulnerable = self.player.invulnerable <= 0.0;
+        let mut destroyed = hit && self.player.hit();
+        if hit && vulnerable {
+            phi.bus.push(GameEvent::ShipHit { pos: self.player.rect.center(), hp: self.player.hp });
+        }
+
+        let wingman_hit = !harmless && self.wingman.as_ref().map_or(false, |wingman| self.is_hit(wingman));
+        if let Some(ref mut wingman) = self.wingman {
+            if wingman_hit {
+                let vulnerable = wingman.invulnerable <= 0.0;
+                destroyed = wingman.hit() || destroyed;
+                if vulnerable {
+                    phi.bus.push(GameEvent::ShipHit { pos: wingman.rect.center(), hp: wingman.hp });
+                }
+            }
+        }
+
+        self.handle_events(phi, world_dt);
+
+        if destroyed {
+            // Practice goes on with a new ship where the old one was lost
+            if let Some(ref mut practice) = self.practice {
+                practice.died();
+                self.player.hp = SHIP_HP;
+                self.player.invulnerable = SHIP_INVULNERABILITY;
+                return false;
+            }
+
+            if self.continues_left > 0 {
+                self.continue_countdown = Some(CONTINUE_COUNTDOWN);
+                return false;
+            }
 
-        // Render the bullets
-        for bullet in &self.bullets {
-            bullet.render(phi);
+            return true;
         }
 
-        // Render the asteroids
+        false
+    }
+
+    /// Whether 'ship' hits an asteroid, an enemy, a hazard, the terrain, or
+    /// the boss and its bullets
+    fn is_hit(&self, ship: &Ship) -> bool {
+        let boss_hitboxes = self.boss.as_ref().map_or(vec![], |boss| boss.hitboxes());
+        let terrain_tiles = self.terrain.as_ref().map_or(vec![], |terrain| terrain.solid_tiles(ship.rect));
+        self.asteroids.iter().any(|asteroid| ship.collides_with(asteroid.hitbox()))
+                || self.enemies.iter().any(|enemy| enemy.is_active()
+                                                   && ship.collides_with(enemy.hitbox()))
+                || self.debris.iter().any(|debris| ship.collides_with(debris.hitbox()))
+                || self.mines.iter().filter_map(|mine| mine.blast())
+                       .any(|(center, radius)| ship.rect.overlaps_circle(center, radius))
+                || self.enemy_bullets.iter().any(|bullet| ship.collides_with(bullet.rect))
+                || boss_hitboxes.iter().any(|&hitbox| ship.collides_with(hitbox))
+                || terrain_tiles.iter().any(|&tile| ship.collides_with(tile))
+    }
+
+    /// Record the run on the leaderboard, save it to be watched again, and
+    /// go back to the main menu, or roll the credits if the run ended with
+    /// the ending of the campaign
+    fn game_over(&mut self, phi: &mut Phi) -> ViewAction {
+        if self.cheated {
+            info!("The run used debugging cheats, and is neither ranked nor saved");
+            return self.leave(phi);
+        }
+        if self.practice.is_some() {
+            // Practice is neither ranked nor saved either
+            return self.leave(phi);
+        }
+
+        if self.ending {
+            let mut progress = Progress::load(PROGRESS_PATH);
+            progress.cleared = true;
+            progress.drones = self.drones.count();
+            if let Err(err) = progress.save(PROGRESS_PATH) {
+                warn!("Could not save the progress: {}", err);
+            }
+        }
+
+        let entry = Entry {
+            name: phi.settings.player_name.clone(),
+            score: self.score,
+            wave: self.wave,
+            ship: SHIP_NAME.to_string(),
+            date: leaderboard::now(),
+            seed: self.seed,
+            speed: (self.frames.iter().map(|input| input.game_speed).fold(1.0, f64::min) * 100.0).round() as u32,
+        };
+
+        let mut board = Leaderboard::load(self.mode);
+        board.add(entry.clone());
+        if let Err(err) = board.save() {
+            warn!("Could not save the leaderboard: {}", err);
+        }
+
+        let replay = Replay {
+            entry: entry,
+            mode: self.mode,
+            drones: self.start_drones,
+            size: self.window_size,
+            frames: ::std::mem::replace(&mut self.frames, vec![]),
+            positions: ::std::mem::replace(&mut self.positions, vec![]),
+        };
+        match replay.save() {
+            Ok(path) => info!("Saved the replay {}", path),
+            Err(err) => warn!("Could not save the replay: {}", err),
+        }
+
+        self.leave(phi)
+    }
+
+    /// Go on to the credits after the ending, otherwise back to the main
+    /// menu
+    fn leave(&self, phi: &mut Phi) -> ViewAction {
+        if self.ending {
+            ViewAction::ChangeView(Box::new(
+                ::views::credits::CreditsView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())))
+        }
+        else {
+            ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())))
+        }
+    }
+
+    /// Start the current wave over with a new ship, as it was at the last
+    /// checkpoint, using one of the player's continues
+    fn continue_from_checkpoint(&mut self, phi: &mut Phi) {
+        self.continues_left -= 1;
+        self.continue_countdown = None;
+        self.continue_label = None;
+
+        self.restore_checkpoint(phi);
+        self.player.hp = SHIP_HP;
+        if let Some(ref mut wingman) = self.wingman {
+            wingman.hp = SHIP_HP;
+        }
+    }
+
+    /// React to the debugging cheats: F4 toggles god mode, F5 picks what F6
+    /// brings in at the mouse cursor, F7 clears the screen and F8 skips to
+    /// the next wave. They change the world outside of the recorded frames,
+    /// so a run using them would not play back the same.
+    fn debug_cheats(&mut self, phi: &mut Phi) {
+        let pressed = |key: Option<bool>| key == Some(true);
+        let (god_mode, pick, spawn, clear, skip) = {
+            let now = &phi.events.now;
+            (pressed(now.key_f4), pressed(now.key_f5), pressed(now.key_f6),
+             pressed(now.key_f7), pressed(now.key_f8))
+        };
+        if god_mode || spawn || clear || skip {
+            self.cheated = true;
+        }
+
+        if god_mode {
+            self.god_mode = !self.god_mode;
+            let text = if self.god_mode { "God mode on" } else { "God mode off" };
+            self.banners.announce(Announcement::new(text, DEBUG_BANNER_DURATION));
+        }
+
+        if pick {
+            self.debug_spawn = (self.debug_spawn + 1) % (DEBUG_SPAWNS.len() + self.formations.len());
+            let text = format!("F6 spawns: {}", self.debug_spawn_name());
+            self.banners.announce(Announcement::new(&text, DEBUG_BANNER_DURATION));
+        }
+
+        if spawn {
+            let cursor = phi.events.mouse_world();
+            self.debug_spawn_at(phi, cursor);
+        }
+
+        if clear {
+            self.asteroids.clear();
+            self.enemies.clear();
+            self.mines.clear();
+            self.debris.clear();
+            self.wells.clear();
+            self.boss = None;
+            self.enemy_bullets.clear();
+        }
+
+        if skip {
+            // Start the next wave from a checkpoint, as if this one was won
+            self.clear_wave(phi);
+            self.restore_checkpoint(phi);
+        }
+    }
+
+    /// Return the name of what F6 brings in
+    fn debug_spawn_name(&self) -> String {
+        match DEBUG_SPAWNS.get(self.debug_spawn) {
+            Some(name) => name.to_string(),
+            None => format!("formation {}", self.formations[self.debug_spawn - DEBUG_SPAWNS.len()].name),
+        }
+    }
+
+    /// Bring in what F6 brings in, at 'center'
+    fn debug_spawn_at(&mut self, phi: &mut Phi, center: (f64, f64)) {
+        match self.debug_spawn {
+            0 => {
+                let asteroid = self.asteroid_factory.spawn(&mut self.rng, AsteroidSize::Large, center, 0.0);
+                self.asteroids.push(asteroid);
+            },
+            1 => {
+                let mut mine = Mine::new(phi, 0.0);
+                mine.move_to(center);
+                self.mines.push(mine);
+            },
+            2 => {
+                let mut well = GravityWell::new(phi, 0.0);
+                well.move_to(center);
+                self.wells.push(well);
+            },
+            3 => self.pickups.push(Pickup::random(&mut self.rng, center)),
+            index => {
+                let size = phi.output_size();
+                let formation = &self.formations[index - DEBUG_SPAWNS.len()];
+                self.enemies.append(&mut formation.spawn_from(
+                    &self.enemy_sprite, &self.scripts, center, size));
+            },
+        }
+    }
+
+    /// Take a snapshot of the world once 'elapsed' seconds more of play
+    /// make it time to, forgetting the oldest one if there are too many
+    fn take_snapshot(&mut self, elapsed: f64) {
+        self.snapshot_timer -= elapsed;
+        if self.snapshot_timer > 0.0 {
+            return;
+        }
+        self.snapshot_timer = REWIND_INTERVAL;
+
+        if self.snapshots.len() >= REWIND_SNAPSHOTS {
+            self.snapshots.pop_front();
+        }
+        let state = WorldState {
+            frame: self.frames.len(),
+            player: self.player.clone(),
+            wingman: self.wingman.clone(),
+            drones: self.drones.clone(),
+            bullets: self.bullets.clone(),
+            asteroids: self.asteroids.clone(),
+            enemies: self.enemies.clone(),
+            spawn_timer: self.spawn_timer.clone(),
+            terrain_scroll: self.terrain.as_ref().map(|terrain| terrain.scroll()),
+            level_time: self.level_time,
+            next_hazard: self.next_hazard,
+            mines: self.mines.clone(),
+            debris: self.debris.clone(),
+            wells: self.wells.clone(),
+            timeline: self.timeline.clone(),
+            ending: self.ending,
+            autopilot: self.autopilot,
+            boss: self.boss.clone(),
+            enemy_bullets: self.enemy_bullets.clone(),
+            pickups: self.pickups.clone(),
+            magnet_time: self.magnet_time,
+            bullet_time: self.bullet_time,
+            score: self.score,
+            rng: self.rng.clone(),
+            wave: self.wave,
+            wave_stats: self.wave_stats,
+            combo: self.combo.clone(),
+            hit_stop: self.hit_stop,
+            checkpoint: self.checkpoint,
+            continues_left: self.continues_left,
+        };
+        self.snapshots.push_back(state);
+    }
+
+    /// Put the world back as it was at the latest snapshot, and forget the
+    /// frames played since, so that the run still plays back the same
+    fn rewind(&mut self, phi: &mut Phi) {
+        let state = match self.snapshots.pop_back() {
+            Some(state) => state,
+            None => {
+                info!("There is no snapshot to rewind the game to");
+                return;
+            }
+        };
+        info!("Rewinding the game by {} frames", self.frames.len() - state.frame);
+
+        self.frames.truncate(state.frame);
+        self.run_time = self.frames.iter().map(|input| input.elapsed).sum();
+        let run_time = self.run_time;
+        self.positions.retain(|&(time, _)| time <= run_time);
+        self.player = state.player;
+        self.wingman = state.wingman;
+        self.drones = state.drones;
+        self.bullets = state.bullets;
+        self.asteroids = state.asteroids;
+        self.enemies = state.enemies;
+        self.spawn_timer = state.spawn_timer;
+        if let (Some(terrain), Some(scroll)) = (self.terrain.as_mut(), state.terrain_scroll) {
+            terrain.set_scroll(scroll);
+        }
+        self.level_time = state.level_time;
+        self.next_hazard = state.next_hazard;
+        self.mines = state.mines;
+        self.debris = state.debris;
+        self.wells = state.wells;
+        self.timeline = state.timeline;
+        self.ending = state.ending;
+        self.autopilot = state.autopilot;
+        self.boss = state.boss;
+        self.enemy_bullets = state.enemy_bullets;
+        self.pickups = state.pickups;
+        self.magnet_time = state.magnet_time;
+        self.bullet_time = state.bullet_time;
+        self.score = state.score;
+        self.score_label = GameView::score_label(phi, self.score);
+        self.rng = state.rng;
+        self.wave = state.wave;
+        self.wave_stats = state.wave_stats;
+        self.combo = state.combo;
+        self.hit_stop = state.hit_stop;
+        self.checkpoint = state.checkpoint;
+        self.continues_left = state.continues_left;
+        self.continue_countdown = None;
+        self.continue_label = None;
+        self.snapshot_timer = REWIND_INTERVAL;
+    }
+
+    /// Return the state of the world which decides how the run unfolds as
+    /// text, one line per entity. Floats are written with as many digits as
+    /// it takes to read them back exactly, so that two runs only give the
+    /// same text if they are in the very same state.
+    pub fn dump_state(&self) -> String {
+        let mut dump = String::new();
+        dump.push_str(&format!("frame {} wave {} score {} time {} hazard {}\n",
+                               self.frames.len(), self.wave, self.score,
+                               self.level_time, self.next_hazard));
+        dump.push_str(&format!("ship {:?} hp {} invulnerable {} cannon {:?} drones {}\n",
+                               self.player.rect, self.player.hp, self.player.invulnerable,
+                               self.player.cannon, self.drones.count()));
+        if let Some(ref wingman) = self.wingman {
+            dump.push_str(&format!("wingman {:?} hp {} invulnerable {} cannon {:?}\n",
+                                   wingman.rect, wingman.hp, wingman.invulnerable, wingman.cannon));
+        }
+        dump.push_str(&format!("checkpoint {:?} continues {} hit_stop {} combo {}\n",
+                               self.checkpoint, self.continues_left, self.hit_stop,
+                               self.combo.kills()));
+        dump.push_str(&format!("magnet {} bullet_time {} terrain {:?}\n",
+                               self.magnet_time, self.bullet_time,
+                               self.terrain.as_ref().map(|terrain| terrain.scroll())));
+
+        for index in 0..self.bullets.len() {
+            dump.push_str(&format!("bullet {:?}\n", self.bullets.rect(index)));
+        }
         for asteroid in &self.asteroids {
-            asteroid.render(phi);
+            dump.push_str(&format!("asteroid {:?} vel {} {} hp {}\n",
+                                   asteroid.rect, asteroid.vel, asteroid.vel_y, asteroid.hp));
+        }
+        for enemy in &self.enemies {
+            dump.push_str(&format!("enemy {:?}\n", enemy.rect()));
+        }
+        for mine in &self.mines {
+            dump.push_str(&format!("mine {:?} {:?}\n", mine.bounds(), mine.hitbox()));
+        }
+        for debris in &self.debris {
+            dump.push_str(&format!("debris {:?}\n", debris.hitbox()));
+        }
+        for well in &self.wells {
+            dump.push_str(&format!("well {:?}\n", well.reach()));
+        }
+        if let Some(ref boss) = self.boss {
+            for part in boss.hitboxes() {
+                dump.push_str(&format!("boss {:?}\n", part));
+            }
+        }
+        for bullet in &self.enemy_bullets {
+            dump.push_str(&format!("enemy_bullet {:?}\n", bullet.rect));
+        }
+        for pickup in &self.pickups {
+            dump.push_str(&format!("pickup {:?} {:?}\n", pickup.kind, pickup.rect()));
         }
 
-        // Render the foreground . . .
-        self.backgrounds.front.render(&mut phi.renderer, elapsed);
+        // The next number the generator gives tells whether both runs drew
+        // as many numbers from it
+        dump.push_str(&format!("rng {}\n", self.rng.clone().gen::<u64>()));
+        dump
+    }
 
-        ViewAction::None
+    /// Return the state of the run from which the current wave may be
+    /// started over
+    fn snapshot(&self) -> Checkpoint {
+        Checkpoint {
+            wave: self.wave,
+            score: self.score,
+            hp: self.player.hp,
+            drones: self.drones.count(),
+            cannon: self.player.cannon,
+            wingman_hp: self.wingman.as_ref().map_or(SHIP_HP, |wingman| wingman.hp),
+        }
     }
 
-}
+    /// Empty the world and start the current wave over, with the ship and
+    /// the score as they were at the last checkpoint
+    fn restore_checkpoint(&mut self, phi: &mut Phi) {
+        self.player.hp = self.checkpoint.hp;
+        self.player.cannon = self.checkpoint.cannon;
+        self.player.invulnerable = SHIP_INVULNERABILITY;
+        self.player.rect.x = SHIP_START_X;
+        self.player.trail.clear();
+        self.player.rect.y = (phi.output_size().1 - SHIP_H) / 2.0;
+        self.drones.set_count(self.checkpoint.drones);
+        if let Some(ref mut wingman) = self.wingman {
+            wingman.hp = self.checkpoint.wingman_hp;
+            wingman.invulnerable = SHIP_INVULNERABILITY;
+            wingman.rect.x = SHIP_START_X;
+            wingman.rect.y = self.player.rect.y + WINGMAN_GAP;
+            wingman.trail.clear();
+        }
 
-const ASTEROID_PATH: &'static str = "assets/asteroid.png";
-const ASTEROIDS_WIDE: usize = 21;
-const ASTEROIDS_HIGH: usize = 7;
-const ASTEROIDS_TOTAL: usize = ASTEROIDS_WIDE * ASTEROIDS_HIGH - 4;
-const ASTEROID_SIDE: f64 = 96.0;
+        self.bullets.clear();
+        self.asteroids.clear();
+        self.enemies.clear();
+        self.mines.clear();
+        self.debris.clear();
+        self.wells.clear();
+        self.boss = None;
+        self.enemy_bullets.clear();
+        self.pickups.clear();
+        self.damage_numbers.clear();
+        self.banners.clear();
+        self.announce_wave(phi);
+        self.magnet_time = 0.0;
+        self.bullet_time = 0.0;
+
+        self.level_time = 0.0;
+        self.next_hazard = 0;
+        if let Some(ref mut terrain) = self.terrain {
+            terrain.reset();
+        }
+        self.spawn_timer = spawn_timer(self.mode);
+        self.timeline = None;
+        self.caption = None;
+        self.ending = false;
+        self.autopilot = None;
+        self.camera.move_to(0.0, 0.0, 0.0);
+
+        // Practicing a boss starts over at its entrance
+        if self.practice.as_ref().map_or(false, |practice| practice.at_boss()) {
+            self.timeline = Some(GameView::boss_entrance());
+        }
 
-pub struct Asteroid {
-    sprite: AnimatedSprite,
-    rect: Rectangle,
-    vel: f64,
-}
+        // Starting over costs the combo and the points won since the
+        // checkpoint
+        self.wave = self.checkpoint.wave;
+        self.wave_stats = WaveStats::default();
+        self.score = self.checkpoint.score;
+        self.score_label = GameView::score_label(phi, self.score);
+        self.combo.reset();
+    }
 
-impl Asteroid {
+    /// React to the events of the frame: count the points and the kills of
+    /// the combo, update the HUD and the statistics of the wave, shake the
+    /// screen and the controller
+    fn handle_events(&mut self, phi: &mut Phi, world_dt: f64) {
+        let score_before = self.score;
+        let events = phi.bus.events().to_vec();
+
+        for event in events {
+            let score = event.score();
+            if score > 0 {
+                self.score += self.combo.kill(score);
+            }
 
-    fn factory(phi: &mut Phi) -> AsteroidFactory {
-        // Read the asteroids image from the filesystem
-        // and construct an animated sprite from it.
-        let asteroid_spritesheet =
-                Sprite::load(&mut phi.renderer, ASTEROID_PATH).unwrap();
-        let mut asteroid_sprites = Vec::with_capacity(ASTEROIDS_TOTAL);
-
-        for yth in 0..ASTEROIDS_HIGH {
-            for xth in 0..ASTEROIDS_WIDE {
-                // Omit the 4 missing asteroid sprites at the end
-                if ASTEROIDS_WIDE * yth + xth >= ASTEROIDS_TOTAL {
-                    break;
-                }
+            match event {
+                GameEvent::BulletHit { heavy, .. } => {
+                    self.wave_stats.shots_hit += 1;
+                    if heavy {
+                        self.hit_stop = HIT_STOP;
+                    }
+                },
+                GameEvent::ShotsFired { count } => {
+                    self.wave_stats.shots_fired += count;
+                },
+                GameEvent::Damage { pos, amount, crit } => {
+                    self.damage_numbers.spawn(pos, amount, crit);
+                    if let Some(ref mut practice) = self.practice {
+                        practice.dealt(amount);
+                    }
+                },
+                GameEvent::Announce(announcement) => {
+                    self.banners.announce(announcement);
+                },
+                GameEvent::MineExploded { .. } => {
+                    self.camera.shake(EXPLOSION_SHAKE);
+                },
+                GameEvent::BossDestroyed { .. } => {
+                    self.camera.shake(Shake { magnitude: 16.0, ..EXPLOSION_SHAKE });
+                    phi.overlay.flash(Color::RGBA(255, 255, 255, 200), 0.4);
+                    phi.rumble(PLAYER_ID, 1.0, 0.6);
+                    // The final boss ends the campaign, the others the
+                    // level, but practice goes on
+                    if self.wave >= FINAL_WAVE && self.practice.is_none() {
+                        self.ending = true;
+                        self.timeline = Some(Timeline::load(timeline::FINAL_PATH));
+                    }
+                    else {
+                        self.timeline = Some(Timeline::load(timeline::ENDING_PATH));
+                    }
+                    self.clear_wave(phi);
+                },
+                GameEvent::ShipHit { .. } => {
+                    self.camera.shake(SHIP_HIT_SHAKE);
+                    phi.overlay.flash(Color::RGBA(230, 30, 30, 110), 0.25);
+                    self.combo.reset();
+                    phi.rumble(PLAYER_ID, 0.6, 0.25);
+                },
+                _ => {},
+            }
+        }
 
-                asteroid_sprites.push(
-                    asteroid_spritesheet.region(Rectangle {
-                        w: ASTEROID_SIDE,
-                        h: ASTEROID_SIDE,
-                        x: ASTEROID_SIDE * xth as f64,
-                        y: ASTEROID_SIDE * yth as f64,
-                    }).unwrap());
+        if self.score != score_before {
+            self.score_label = GameView::score_label(phi, self.score);
+        }
+
+        // Beating the best run of the leaderboard is announced once
+        if self.best_score.map_or(false, |best| self.score > best) {
+            self.best_score = None;
+            let text = phi.locale.text("game.new_high_score");
+            self.banners.announce(Announcement::new(&text, HIGH_SCORE_BANNER_DURATION)
+                .with_sound("high_score"));
+        }
+
+        self.damage_numbers.update(world_dt);
+
+        // Kills in quick succession multiply the points they are worth
+        self.combo.update(world_dt);
+        if self.combo.kills() != self.combo_shown {
+            self.combo_shown = self.combo.kills();
+            self.combo_label = GameView::combo_label(phi, &self.combo);
+        }
+    }
+
+    /// Run the actions of the current timeline which are due after 'dt'
+    /// seconds, and forget the timeline once it is over
+    fn run_timeline(&mut self, phi: &mut Phi, dt: f64) {
+        let actions = match self.timeline {
+            Some(ref mut timeline) => timeline.update(dt),
+            None => return,
+        };
+
+        for action in actions {
+            match action {
+                // Waits are handled by the timeline itself
+                Action::Wait(_) => {},
+                Action::Spawn(spawn) => self.spawn(phi, spawn),
+                Action::Text { key, duration } => {
+                    let text = phi.locale.text(&key);
+                    self.caption = phi.ttf_str_sprite(
+                        &text, "menu_font", 32,
+                        Color::RGB(255, 255, 255)).map(|sprite| (sprite, duration));
+                },
+                Action::Camera { x, y, duration } => {
+                    if !phi.settings.reduce_motion {
+                        self.camera.move_to(x, y, duration);
+                    }
+                },
+                Action::Ship { x, y, duration } => {
+                    self.autopilot = Some(Autopilot { target: (x, y), time_left: duration });
+                },
+                Action::Sound(name) => play_sound(&name),
             }
         }
 
-        // Return the data required to build an asteroid
-        AsteroidFactory {
-            sprite: AnimatedSprite::new_with_fps(asteroid_sprites, 1.0),
+        if self.timeline.as_ref().map_or(false, |timeline| timeline.is_finished()) {
+            self.timeline = None;
         }
     }
 
-    /*
-    pub fn new(phi: &mut Phi) -> Asteroid {
-        let (w, h) = phi.output_size();
+    /// Bring something into the game, from the level or a timeline
+    fn spawn(&mut self, phi: &mut Phi, spawn: Spawn) {
+        match spawn {
+            Spawn::Hazard(HazardSpawn::Mine { y }) =>
+                self.mines.push(Mine::new(phi, y)),
+            Spawn::Hazard(HazardSpawn::Debris { y, count }) =>
+                self.debris.append(&mut Debris::field(phi, &mut self.rng, y, count)),
+            Spawn::Hazard(HazardSpawn::Well { y }) =>
+                self.wells.push(GravityWell::new(phi, y)),
+            Spawn::Asteroid =>
+                self.asteroids.push(self.asteroid_factory.random(phi, &mut self.rng)),
+            Spawn::Formation(name) => {
+                let formation = match name {
+                    Some(ref name) => self.formations.iter().find(|formation| formation.name == *name),
+                    None if self.formations.is_empty() => None,
+                    None => self.formations.get(self.rng.index(self.formations.len())),
+                };
+                match formation {
+                    Some(formation) => self.enemies.append(&mut formation.spawn(&self.enemy_sprite)),
+                    None => {
+                        if let Some(ref name) = name {
+                            warn!("There is no formation called {}", name);
+                        }
+                    },
+                }
+            },
+            Spawn::Boss => {
+                let text = phi.locale.text("game.boss_approaching");
+                self.banners.announce(Announcement::new(&text, boss::WARNING_DURATION).urgent());
+                self.boss = Some(Boss::new(phi));
+            },
+        }
+    }
 
-        let mut asteroid =
-            Asteroid {
-                sprite: Asteroid::get_sprite(phi, 1.0),
-                rect: Rectangle {
-                    w: ASTEROID_SIDE,
-                    h: ASTEROID_SIDE,
+    /// Bring in what the spawn table of the level picks for the current wave
+    fn spawn_random(&mut self, phi: &mut Phi) {
+        match self.level.pick_spawn(&mut self.rng, self.wave) {
+            Some(SpawnKind::Asteroid) => self.spawn(phi, Spawn::Asteroid),
+            Some(SpawnKind::Formation) => self.spawn(phi, Spawn::Formation(None)),
+            Some(SpawnKind::Pickup) => {
+                // Pickups float in from the right, away from the edges
+                let (w, h) = phi.output_size();
+                let y = self.rng.range_f64(h * 0.1, h * 0.9);
+                self.pickups.push(Pickup::random(&mut self.rng, (w + pickups::PICKUP_SIDE, y)));
+            },
+            None => {},
+        }
+    }
+
+    /// Return the timeline bringing the boss in, which spawns it right away
+    /// if the one of the assets does not
+    fn boss_entrance() -> Timeline {
+        let entrance = Timeline::load(timeline::BOSS_PATH);
+        if entrance.spawns_boss() {
+            entrance
+        }
+        else {
+            warn!("The timeline {} does not spawn the boss", timeline::BOSS_PATH);
+            Timeline::new(vec![Action::Spawn(Spawn::Boss)])
+        }
+    }
+
+    /// End the current wave, with a bonus for accuracy, and start the next
+    /// one from a new checkpoint
+    fn clear_wave(&mut self, phi: &mut Phi) {
+        self.score += self.wave_stats.bonus();
+        let summary = self.wave_stats.summary(&phi.locale, self.wave);
+        self.summary = phi.ttf_str_sprite(
+            &summary, "menu_font", 28,
+            Color::RGB(255, 255, 255)).map(|sprite| (sprite, SUMMARY_DURATION));
+        self.wave += 1;
+        self.wave_stats = WaveStats::default();
+        self.announce_wave(phi);
+        self.checkpoint = self.snapshot();
+    }
+
+    /// Announce the number of the current wave
+    fn announce_wave(&mut self, phi: &mut Phi) {
+        let text = phi.locale.format("game.wave", &[&self.wave]);
+        self.banners.announce(Announcement::new(&text, WAVE_BANNER_DURATION));
+    }
+
+    /// Return the speed at which everything but the ship moves: slowed down
+    /// during bullet time, and by the game speed of the settings
+    fn time_scale(&self) -> f64 {
+        let scale = if self.bullet_time > 0.0 { BULLET_TIME_SCALE } else { 1.0 };
+        scale * self.game_speed
+    }
+
+    /// Render the score shown in the corner of the screen
+    fn score_label(phi: &mut Phi, score: u32) -> Sprite {
+        let text = phi.locale.format("game.score", &[&score]);
+        phi.ttf_str_sprite(&text, "menu_font", 24, Color::RGB(255, 255, 255)).unwrap()
+    }
+
+    /// Render the kills of the combo and its multiplier, if there is a
+    /// combo going on
+    fn combo_label(phi: &mut Phi, combo: &Combo) -> Option<Sprite> {
+        if combo.kills() == 0 {
+            return None;
+        }
+
+        let text = phi.locale.format("game.combo", &[&combo.kills(), &combo.multiplier()]);
+        phi.ttf_str_sprite(&text, "menu_font", 20, Color::RGB(255, 255, 255))
+    }
+
+    /// Return the point the player is aiming at: in the direction of the
+    /// right stick if it is tilted, otherwise the point of the scene under
+    /// the mouse cursor
+    fn aim_target(&self, phi: &Phi) -> (f64, f64) {
+        self.stick_target(phi).unwrap_or_else(|| phi.events.mouse_world())
+    }
+
+    // Return the point in the direction of the right stick, if it is tilted
+    fn stick_target(&self, phi: &Phi) -> Option<(f64, f64)> {
+        let bindings = phi.pad_bindings(PLAYER_ID);
+        if let Some(stick) = bindings.aim_stick(&phi.events.controllers, PLAYER_ID) {
+            let (sx, sy) = phi.settings.dead_zone().apply(stick);
+            if sx != 0.0 || sy != 0.0 {
+                let (cx, cy) = self.player.cannons_center();
+                let norm = (sx * sx + sy * sy).sqrt();
+                return Some((cx + sx / norm * CROSSHAIR_DISTANCE,
+                             cy + sy / norm * CROSSHAIR_DISTANCE));
+            }
+        }
+
+        None
+    }
+
+    /// Render the scene; the backgrounds scroll by 'elapsed' seconds
+    fn draw(&mut self, phi: &mut Phi, elapsed: f64) {
+
+        // Clear the screen . . .
+
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // At the lowest quality, the nearer backgrounds keep scrolling but
+        // are not drawn
+        self.backgrounds.back.scroll(elapsed);
+        self.backgrounds.middle.scroll(elapsed);
+        self.backgrounds.front.scroll(elapsed);
+        let all_backgrounds = phi.quality.all_backgrounds();
+
+        // Skip whatever lies entirely outside of the screen
+        let shake_scale = phi.settings.shake_scale();
+        let visible = self.camera.visible_area(shake_scale, phi.output_size());
+
+        // The mouse aims at the point of the scene under it, wherever the
+        // camera moved the scene
+        let offset = (-visible.x, -visible.y);
+        phi.events.set_world_offset(offset);
+
+        // Everything registers what it draws in its layer, and the queue
+        // draws the layers from the back to the front, shaking those of the
+        // world
+        let view = &*self;
+        let mut queue = RenderQueue::new();
+
+        // The backgrounds, the terrain, and the gravity wells behind
+        // everything they attract
+        queue.push(Layer::Background, move |phi| view.backgrounds.back.draw(&mut phi.renderer));
+        if all_backgrounds {
+            queue.push(Layer::Background, move |phi| view.backgrounds.middle.draw(&mut phi.renderer));
+        }
+        if let Some(ref terrain) = view.terrain {
+            queue.push(Layer::Background, move |phi| terrain.render(phi));
+        }
+        for well in view.wells.iter().filter(|well| visible.overlaps(well.reach())) {
+            queue.push(Layer::Background, move |phi| well.render(phi));
+        }
+
+        // The particles, behind the ship and what it fights
+        queue.push_at(Layer::Entities, -1, move |phi| {
+            phi.profiler.begin("particles");
+            view.particles.render(&mut phi.renderer);
+            phi.profiler.end("particles");
+        });
+
+        // The bounding box of the ship (for debugging)
+        if DEBUG {
+            queue.push_at(Layer::Entities, -1, move |phi| {
+                phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
+                phi.renderer.fill_rect(view.player.rect.to_sdl().unwrap());
+            });
+        }
+
+        // The ghost of the best run, under everything the player flies
+        if let Some(pos) = view.ghost.as_ref().and_then(|ghost| ghost.position(view.run_time)) {
+            queue.push(Layer::Entities, move |phi| {
+                let sprite = &view.player.sprites[ShipFrame::MidNorm as usize];
+                sprite.set_alpha_mod(GHOST_ALPHA);
+                sprite.set_color_mod(GHOST_COLOR);
+                sprite.render(&mut phi.renderer, Rectangle { x: pos.0, y: pos.1, ..view.player.rect });
+                sprite.set_alpha_mod(255);
+                sprite.set_color_mod(Color::RGB(255, 255, 255));
+            });
+        }
+
+        // The ship's afterimages, then the ship, blinking while it cannot
+        // be hit, with the glow of its engine behind it, and its drones
+        if !view.player.trail.is_empty() {
+            queue.push(Layer::Entities, move |phi| {
+                view.player.trail.render_sprite(&mut phi.renderer, view.player.sprite());
+            });
+        }
+        if view.player.invulnerable <= 0.0 || (view.player.invulnerable * 10.0) as u32 % 2 == 0 {
+            queue.push(Layer::Entities, move |phi| {
+                let rect = view.player.rect;
+                phi.renderer.set_blend_mode(Blending::Additive.to_sdl());
+                phi.renderer.set_draw_color(Color::RGBA(255, 140, 50, 110));
+                hazards::fill_circle(phi, (rect.x + EXHAUST.offset.0, rect.y + EXHAUST.offset.1), 8.0);
+                phi.renderer.set_blend_mode(BlendMode::None);
+
+                view.player.sprite().render(&mut phi.renderer, rect);
+            });
+        }
+
+        // The wingman of a game for two likewise, tinted to tell it apart
+        if let Some(ref wingman) = view.wingman {
+            if !wingman.trail.is_empty() {
+                queue.push(Layer::Entities, move |phi| {
+                    wingman.trail.render_sprite(&mut phi.renderer, wingman.sprite());
+                });
+            }
+            if wingman.invulnerable <= 0.0 || (wingman.invulnerable * 10.0) as u32 % 2 == 0 {
+                queue.push(Layer::Entities, move |phi| {
+                    let sprite = wingman.sprite();
+                    sprite.set_color_mod(WINGMAN_COLOR);
+                    sprite.render(&mut phi.renderer, wingman.rect);
+                    sprite.set_color_mod(Color::RGB(255, 255, 255));
+                });
+            }
+        }
+        queue.push(Layer::Entities, move |phi| view.drones.render(phi));
+
+        for asteroid in view.asteroids.iter().filter(|asteroid| visible.overlaps(asteroid.rect())) {
+            queue.push(Layer::Entities, move |phi| asteroid.render(phi));
+        }
+        for enemy in view.enemies.iter().filter(|enemy| visible.overlaps(enemy.rect())) {
+            queue.push(Layer::Entities, move |phi| enemy.render(phi, DEBUG));
+        }
+        for debris in view.debris.iter().filter(|debris| visible.overlaps(debris.hitbox())) {
+            queue.push(Layer::Entities, move |phi| debris.render(phi));
+        }
+        for mine in view.mines.iter().filter(|mine| visible.overlaps(mine.bounds())) {
+            queue.push(Layer::Entities, move |phi| mine.render(phi));
+        }
+        for pickup in view.pickups.iter().filter(|pickup| visible.overlaps(pickup.rect())) {
+            queue.push(Layer::Entities, move |phi| pickup.render(phi));
+        }
+        if let Some(ref boss) = view.boss {
+            queue.push(Layer::Entities, move |phi| boss.render(phi));
+        }
+
+        // The bullets of the player, then the boss's
+        queue.push(Layer::Projectiles, move |phi| view.bullets.render(phi, visible));
+        for bullet in view.enemy_bullets.iter().filter(|bullet| visible.overlaps(bullet.rect)) {
+            queue.push(Layer::Projectiles, move |phi| bullet.render(phi));
+        }
+
+        // The foreground, and the damage dealt, over everything it was
+        // dealt to
+        if all_backgrounds {
+            queue.push(Layer::Effects, move |phi| view.backgrounds.front.draw(&mut phi.renderer));
+        }
+        queue.push(Layer::Effects, move |phi| view.damage_numbers.render(phi));
+
+        // Wash the colors out during bullet time. The renderer cannot
+        // desaturate, so blend the scene with gray instead.
+        if view.bullet_time > 0.0 && phi.quality.post_effects() {
+            queue.push(Layer::Screen, |phi| {
+                let (w, h) = phi.output_size();
+                phi.renderer.set_blend_mode(BlendMode::Blend);
+                phi.renderer.set_draw_color(Color::RGBA(110, 110, 110, 90));
+                phi.renderer.fill_rect(Rectangle {
                     x: 0.0,
                     y: 0.0,
-                },
-                vel: 0.0,
+                    w: w,
+                    h: h,
+                }.to_sdl().unwrap());
+                phi.renderer.set_blend_mode(BlendMode::None);
+            });
+        }
+
+        // The dimming and flashes asked for by the game, or the views over
+        // it, under the HUD
+        queue.push_at(Layer::Screen, 1, |phi| {
+            let (w, h) = phi.output_size();
+            let reduce_flashes = phi.settings.reduce_flashes;
+            phi.overlay.render(&mut phi.renderer, Rectangle {
+                x: 0.0,
+                y: 0.0,
+                w: w,
+                h: h,
+            }, reduce_flashes);
+        });
+
+        // The score, with the magnet's timer under it
+        let margin = phi.scaled(10.0);
+        let (_, score_h) = view.score_label.size();
+        queue.push(Layer::Hud, move |phi| {
+            let (w, h) = view.score_label.size();
+            let dest = Rectangle {
+                x: margin,
+                y: margin,
+                w: w,
+                h: h,
             };
-        asteroid.reset(phi);
-        asteroid
-    }
+            back_hud_label(phi, dest);
+            view.score_label.render(&mut phi.renderer, dest);
+        });
+        if view.magnet_time > 0.0 {
+            queue.push(Layer::Hud, move |phi| {
+                let icon = Rectangle {
+                    x: margin,
+                    y: margin * 2.0 + score_h,
+                    w: phi.scaled(12.0),
+                    h: phi.scaled(12.0),
+                };
+                let timer_w = phi.scaled(80.0);
+                let color = phi.palette_color(pickups::MAGNET_COLOR);
+                phi.renderer.set_draw_color(color);
+                phi.renderer.fill_rect(icon.to_sdl().unwrap());
+                view.magnet_bar.render(phi, Rectangle {
+                    x: icon.x + icon.w + icon.w / 2.0,
+                    y: icon.y + icon.h / 4.0,
+                    w: timer_w,
+                    h: icon.h / 2.0,
+                });
+            });
+        }
 
-    pub fn get_sprite(phi: &mut Phi, fps: f64) -> AnimatedSprite {
-        let asteroid_spritesheet = Sprite::load(&mut phi.renderer, ASTEROID_PATH).unwrap();
-        let mut asteroid_sprites = Vec::with_capacity(ASTEROIDS_TOTAL);
+        // What practice shows, under the score
+        if let Some(label) = view.practice.as_ref().and_then(|practice| practice.label()) {
+            queue.push(Layer::Hud, move |phi| {
+                let (w, h) = label.size();
+                let dest = Rectangle {
+                    x: margin,
+                    y: margin * 3.0 + score_h + phi.scaled(12.0),
+                    w: w,
+                    h: h,
+                };
+                back_hud_label(phi, dest);
+                label.render(&mut phi.renderer, dest);
+            });
+        }
 
-        for yth in 0..ASTEROIDS_HIGH {
-            for xth in 0..ASTEROIDS_WIDE {
-                // Omit the 4 missing asteroid sprites at the end
-                if ASTEROIDS_WIDE * yth + xth >= ASTEROIDS_TOTAL {
-                    break;
-                }
+        // The combo in the top-right corner
+        if let Some(ref label) = view.combo_label {
+            queue.push(Layer::Hud, move |phi| {
+                let (win_w, _) = phi.output_size();
+                let (label_w, label_h) = label.size();
+                let dest = Rectangle {
+                    x: win_w - label_w - margin,
+                    y: margin,
+                    w: label_w,
+                    h: label_h,
+                };
+                back_hud_label(phi, dest);
+                label.render(&mut phi.renderer, dest);
+                view.combo.render_meter(phi, dest);
+            });
+        }
 
-                asteroid_sprites.push(
-                    asteroid_spritesheet.region(Rectangle {
-                        w: ASTEROID_SIDE,
-                        h: ASTEROID_SIDE,
-                        x: ASTEROID_SIDE * xth as f64,
-                        y: ASTEROID_SIDE * yth as f64,
-                    }).unwrap());
+        // The ship's hit points in the bottom-left corner
+        queue.push(Layer::Hud, move |phi| {
+            let (_, win_h) = phi.output_size();
+            let bar_h = phi.scaled(10.0);
+            let bar_w = phi.scaled(120.0);
+            view.health_bar.render(phi, Rectangle {
+                x: margin,
+                y: win_h - margin - bar_h,
+                w: bar_w,
+                h: bar_h,
+            });
+        });
+
+        // The boss's health, once it stops warning the player
+        if let Some(ref boss) = view.boss {
+            if !boss.is_warning() {
+                queue.push(Layer::Hud, move |phi| boss.render_health(phi));
             }
         }
-        AnimatedSprite::new_with_fps(asteroid_sprites, fps)
+
+        // The summary of the last wave, and the caption of the timeline
+        if let Some((ref summary, _)) = view.summary {
+            queue.push(Layer::Hud, move |phi| {
+                let (win_w, win_h) = phi.output_size();
+                let (w, h) = summary.size();
+                summary.render(&mut phi.renderer, Rectangle {
+                    x: (win_w - w) / 2.0,
+                    y: win_h / 3.0 - h / 2.0,
+                    w: w,
+                    h: h,
+                });
+            });
+        }
+        if let Some((ref caption, _)) = view.caption {
+            queue.push(Layer::Hud, move |phi| {
+                let (win_w, win_h) = phi.output_size();
+                let (w, h) = caption.size();
+                caption.render(&mut phi.renderer, Rectangle {
+                    x: (win_w - w) / 2.0,
+                    y: win_h * 2.0 / 3.0 - h / 2.0,
+                    w: w,
+                    h: h,
+                });
+            });
+        }
+
+        // The announcements over the middle of the screen
+        queue.push(Layer::Hud, move |phi| view.banners.render(phi));
+
+        // The crosshair, over everything, where the point it aims at is
+        // shown on screen, unless it follows the mouse as its cursor
+        let cursor_aims = view.crosshair_cursor && view.stick_target(phi).is_none();
+        if let (Some((x, y)), false) = (view.crosshair, cursor_aims) {
+            let (x, y) = (x + offset.0, y + offset.1);
+            queue.push(Layer::Overlay, move |phi| {
+                phi.renderer.set_draw_color(Color::RGB(230, 230, 230));
+                phi.renderer.fill_rect(Rectangle {
+                    x: x - CROSSHAIR_SIDE / 2.0,
+                    y: y - 1.0,
+                    w: CROSSHAIR_SIDE,
+                    h: 2.0,
+                }.to_sdl().unwrap());
+                phi.renderer.fill_rect(Rectangle {
+                    x: x - 1.0,
+                    y: y - CROSSHAIR_SIDE / 2.0,
+                    w: 2.0,
+                    h: CROSSHAIR_SIDE,
+                }.to_sdl().unwrap());
+            });
+        }
+
+        queue.draw(phi, &view.camera);
     }
 
-    fn reset(&mut self, phi: &mut Phi) {
-        let (w, h) = phi.output_size();
+    /// Ask the player whether they want to continue, with the seconds left
+    /// to answer
+    fn draw_continue(&mut self, phi: &mut Phi, countdown: f64) {
+        let seconds = countdown.ceil() as u32;
+        if self.continue_label.as_ref().map_or(true, |&(shown, _)| shown != seconds) {
+            let text = phi.locale.format("game.continue", &[&seconds, &self.continues_left]);
+            self.continue_label = Some((seconds, Prompt::new(&text, "menu_font", 32, Color::RGB(255, 255, 255))));
+        }
 
-        // Get FPS in [10.0, 30.0)
-        self.sprite.set_fps(::rand::random::<f64>().abs() * 20.0 + 10.0);
+        if let Some((_, ref mut label)) = self.continue_label {
+            let (win_w, win_h) = phi.output_size();
+            let (w, h) = label.size(phi);
 
-        self.rect = Rectangle {
-            w: ASTEROID_SIDE,
-            h: ASTEROID_SIDE,
-            x: w,
-            y: ::rand::random::<f64>().abs() * (h - ASTEROID_SIDE),
-        };
+            let margin = phi.scaled(10.0);
+
+            phi.renderer.set_draw_color(Color::RGB(15, 15, 70));
+            phi.renderer.fill_rect(Rectangle {
+                w: w + margin * 4.0,
+                h: h + margin * 2.0,
+                x: (win_w - w) / 2.0 - margin * 2.0,
+                y: (win_h - h) / 2.0 - margin,
+            }.to_sdl().unwrap());
 
-        // Velocity in [50.0, 150)
-        self.vel = rand::random::<f64>().abs() * 100.0 + 50.0;
+            label.render(phi, ((win_w - w) / 2.0, (win_h - h) / 2.0));
+        }
     }
-    */
 
-    pub fn update(mut self, dt: f64) -> Option<Asteroid> {
-        self.rect.x -= dt * self.vel;
-        self.sprite.add_time(dt);
+    /// Tell that the game is stepped through frame by frame, and which frame
+    /// it shows
+    fn draw_step_indicator(&mut self, phi: &mut Phi) {
+        let frame = self.frames.len();
+        if self.step_label.as_ref().map_or(true, |&(shown, _)| shown != frame) {
+            self.step_label = phi.ttf_str_sprite(
+                &format!("STEP  frame {}  (F11: next, F10: resume)", frame),
+                "menu_font", 16, Color::RGB(255, 220, 80))
+                .map(|sprite| (frame, sprite));
+        }
 
-        if self.rect.x <= -ASTEROID_SIDE {
-            None
-        } else {
-            Some(self)
+        if let Some((_, ref label)) = self.step_label {
+            let (_, win_h) = phi.output_size();
+            let (w, h) = label.size();
+            let margin = phi.scaled(6.0);
+
+            phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+            phi.renderer.fill_rect(Rectangle {
+                w: w + margin * 2.0,
+                h: h + margin * 2.0,
+                x: 0.0,
+                y: win_h - h - margin * 2.0,
+            }.to_sdl().unwrap());
+
+            label.render(&mut phi.renderer, Rectangle {
+                w: w,
+                h: h,
+                x: margin,
+                y: win_h - h - margin,
+            });
         }
     }
 
+    /// Tell the player to plug their controller back in
+    fn draw_controller_lost(&self, phi: &mut Phi) {
+        let (win_w, win_h) = phi.output_size();
+        let (w, h) = self.controller_lost_label.size();
+        let margin = phi.scaled(10.0);
+
+        phi.renderer.set_draw_color(Color::RGB(70, 15, 70));
+        phi.renderer.fill_rect(Rectangle {
+            w: w + margin * 4.0,
+            h: h + margin * 2.0,
+            x: (win_w - w) / 2.0 - margin * 2.0,
+            y: (win_h - h) / 2.0 - margin,
+        }.to_sdl().unwrap());
+
+        self.controller_lost_label.render(&mut phi.renderer, Rectangle {
+            w: w,
+            h: h,
+            x: (win_w - w) / 2.0,
+            y: (win_h - h) / 2.0,
+        });
+    }
+}
+
+/// The asteroids' spritesheet in the asset manifest
+const ASTEROID_ASSET: &'static str = "asteroid_sheet";
+/// The animation of the asteroids, in assets/asteroid.anim
+const ASTEROID_ANIMATION: &'static str = "spin";
+const ASTEROID_SIDE: f64 = 96.0;
+
+/// Vertical speed of the pieces of a destroyed asteroid, in pixels/second
+const ASTEROID_SPLIT_SPEED: f64 = 40.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AsteroidSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl AsteroidSize {
+    /// Pick a size at random; large asteroids are the most common, as
+    /// they split into the smaller ones
+    fn random<R: Rng>(rng: &mut R) -> AsteroidSize {
+        *rng.weighted(&[
+            (AsteroidSize::Large, 0.5),
+            (AsteroidSize::Medium, 0.3),
+            (AsteroidSize::Small, 0.2),
+        ]).unwrap()
+    }
+
+    /// The size of the rendered asteroid relative to its sprite
+    fn scale(self) -> f64 {
+        match self {
+            AsteroidSize::Small  => 0.5,
+            AsteroidSize::Medium => 0.75,
+            AsteroidSize::Large  => 1.0,
+        }
+    }
+
+    /// The number of bullets required to destroy the asteroid
+    fn hp(self) -> u32 {
+        match self {
+            AsteroidSize::Small  => 1,
+            AsteroidSize::Medium => 2,
+            AsteroidSize::Large  => 4,
+        }
+    }
+
+    /// The range, in pixels/second, of the horizontal speed of the asteroid
+    fn speed_range(self) -> (f64, f64) {
+        match self {
+            AsteroidSize::Small  => (100.0, 200.0),
+            AsteroidSize::Medium => (75.0, 175.0),
+            AsteroidSize::Large  => (50.0, 150.0),
+        }
+    }
+
+    /// The points won by destroying the asteroid; smaller ones are
+    /// faster and harder to hit
+    fn score(self) -> u32 {
+        match self {
+            AsteroidSize::Small  => 100,
+            AsteroidSize::Medium => 50,
+            AsteroidSize::Large  => 20,
+        }
+    }
+
+    /// The size of the two pieces the asteroid breaks into when destroyed
+    fn split(self) -> Option<AsteroidSize> {
+        match self {
+            AsteroidSize::Small  => None,
+            AsteroidSize::Medium => Some(AsteroidSize::Small),
+            AsteroidSize::Large  => Some(AsteroidSize::Medium),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Asteroid {
+    sprite: AnimatedSprite,
+    rect: Rectangle,
+    vel: f64,
+    vel_y: f64,
+    size: AsteroidSize,
+    hp: u32,
+
+    // Seconds during which the asteroid flashes after a hit
+    flash: f64,
+}
+
+impl Asteroid {
+
+    fn factory(phi: &mut Phi) -> AsteroidFactory {
+        // Read the frames of the asteroids from the filesystem, and the
+        // animation going through them from its sidecar file.
+        let animations = phi.load_animations(ASTEROID_ASSET).unwrap();
+        let mut sprite = animations.sprite(ASTEROID_ANIMATION).unwrap();
+        let fps = sprite.fps();
+
+        // Every frame is hit within the bounding box of its polygons
+        if let Some(frames) = gfx::load_hitboxes(phi.assets.path(ASTEROID_ASSET), animations.frame_count()) {
+            let hitboxes: Vec<Rectangle> = frames.iter().map(|polygons| {
+                polygons.iter()
+                    .map(|polygon| polygon.bounding_box())
+                    .fold(None, |hitbox: Option<Rectangle>, rect| match hitbox {
+                        Some(hitbox) => Some(hitbox.union(rect)),
+                        None => Some(rect),
+                    })
+                    .unwrap_or(Rectangle {
+                        x: 0.0,
+                        y: 0.0,
+                        w: ASTEROID_SIDE,
+                        h: ASTEROID_SIDE,
+                    })
+            }).collect();
+            sprite.set_hitboxes(animations.get(ASTEROID_ANIMATION).unwrap().frames.iter()
+                .map(|&frame| hitboxes[frame])
+                .collect());
+        }
+
+        // Return the data required to build an asteroid
+        AsteroidFactory {
+            sprite: sprite,
+            fps: fps,
+        }
+    }
+
+    /// Update every asteroid of 'asteroids'. Their sprites share textures,
+    /// which must stay on this thread, so only their positions may be
+    /// updated in parallel.
+    pub fn update_all(asteroids: &mut [Asteroid], dt: f64) {
+        // Gathering the positions costs an allocation every frame, which is
+        // only worth it when they are then spread over the thread pool
+        if !parallel::is_enabled() || asteroids.len() < parallel::MIN_PARALLEL_ITEMS {
+            for asteroid in asteroids {
+                asteroid.sprite.add_time(dt);
+                asteroid.flash -= dt;
+                asteroid.rect.x -= dt * asteroid.vel;
+                asteroid.rect.y += dt * asteroid.vel_y;
+            }
+            return;
+        }
+
+        let mut moving: Vec<(&mut Rectangle, (f64, f64))> = asteroids.iter_mut()
+            .map(|asteroid| {
+                asteroid.sprite.add_time(dt);
+                asteroid.flash -= dt;
+                (&mut asteroid.rect, (asteroid.vel, asteroid.vel_y))
+            })
+            .collect();
+
+        parallel::for_each_mut(&mut moving, |item| {
+            let (vel, vel_y) = item.1;
+            item.0.x -= dt * vel;
+            item.0.y += dt * vel_y;
+        });
+    }
+
+    /// Accelerate the asteroid by (ax, ay), in pixels/second², for 'dt'
+    /// seconds
+    fn accelerate(&mut self, ax: f64, ay: f64, dt: f64) {
+        // 'vel' points to the left
+        self.vel -= ax * dt;
+        self.vel_y += ay * dt;
+    }
+
     pub fn render(&self, phi: &mut Phi) {
         if DEBUG {
             // Render the bounding box
             phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
             phi.renderer.fill_rect(self.rect().to_sdl().unwrap());
         }
-        self.sprite.render(&mut phi.renderer, self.rect);
+        if self.flash > 0.0 && !phi.settings.reduce_flashes && phi.quality.post_effects() {
+            self.sprite.render_flash(&mut phi.renderer, self.rect);
+        }
+        else {
+            self.sprite.render(&mut phi.renderer, self.rect);
+        }
     }
 
     fn rect(&self) -> Rectangle {
         self.rect
     }
+
+    /// Return the area in which the asteroid can be hit, which follows
+    /// the silhouette of the current frame as the rock rotates.
+    fn hitbox(&self) -> Rectangle {
+        match self.sprite.current_hitbox() {
+            Some(frame_hitbox) => {
+                let scale = self.rect.w / ASTEROID_SIDE;
+                Rectangle {
+                    x: self.rect.x + frame_hitbox.x * scale,
+                    y: self.rect.y + frame_hitbox.y * scale,
+                    w: frame_hitbox.w * scale,
+                    h: frame_hitbox.h * scale,
+                }
+            },
+            // The sprite leaves a margin around the rock itself
+            None => self.rect.scale_about_center(0.7),
+        }
+    }
 }
 
 struct AsteroidFactory {
     sprite: AnimatedSprite,
+
+    // The frame rate of the animation, around which every asteroid spins
+    // at its own speed
+    fps: f64,
 }
 
 impl AsteroidFactory {
-    fn random(&self, phi: &mut Phi) -> Asteroid {
+    /// Create an asteroid of a random size entering from the right
+    fn random<R: Rng>(&self, phi: &mut Phi, rng: &mut R) -> Asteroid {
         let (w, h) = phi.output_size();
+        let size = AsteroidSize::random(rng);
+        let side = ASTEROID_SIDE * size.scale();
+        let y = rng.range_f64(side / 2.0, h - side / 2.0);
+
+        self.spawn(rng, size, (w + side / 2.0, y), 0.0)
+    }
+
+    /// Create an asteroid of the given size centered on 'center', moving
+    /// left at a random speed and vertically at 'vel_y'
+    fn spawn<R: Rng>(&self, rng: &mut R, size: AsteroidSize, center: (f64, f64), vel_y: f64) -> Asteroid {
+        let side = ASTEROID_SIDE * size.scale();
+        let (min_speed, max_speed) = size.speed_range();
 
         let mut sprite = self.sprite.clone();
-        sprite.set_fps(::rand::random::<f64>().abs() * 20.0 + 10.0);
+        sprite.set_fps(self.fps * rng.range_f64(0.5, 1.5));
 
         Asteroid {
             sprite: sprite,
             rect: Rectangle {
-                w: ASTEROID_SIDE,
-                h: ASTEROID_SIDE,
-                x: w,
-                y: ::rand::random::<f64>().abs() * (h - ASTEROID_SIDE),
-            },
-            vel: ::rand::random::<f64>().abs() * 100.0 + 50.0,
+                w: side,
+                h: side,
+                x: 0.0,
+                y: 0.0,
+            }.with_center(center),
+            vel: rng.range_f64(min_speed, max_speed),
+            vel_y: vel_y,
+            size: size,
+            hp: size.hp(),
+            flash: 0.0,
+        }
+    }
+
+    /// Return the pieces 'asteroid' breaks into when destroyed
+    fn split<R: Rng>(&self, rng: &mut R, asteroid: &Asteroid) -> Vec<Asteroid> {
+        match asteroid.size.split() {
+            Some(size) => vec![
+                self.spawn(rng, size, asteroid.rect.center(), -ASTEROID_SPLIT_SPEED),
+                self.spawn(rng, size, asteroid.rect.center(),  ASTEROID_SPLIT_SPEED),
+            ],
+            None => vec![],
         }
     }
 }
@@ -0,0 +1,566 @@
+// views/hazards.rs
+
+use ::std::fs::File;
+use ::std::io::{self, BufRead, BufReader, Write};
+
+use ::rand::Rng;
+use ::sdl2::pixels::Color;
+use ::sdl2::render::BlendMode;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::gfx::Blending;
+use ::phi::random::Distributions;
+
+pub const LEVEL_PATH: &'static str = "assets/level.txt";
+
+/// Pixels travelled to the left every second by mines and debris, which
+/// drift with the middle background
+pub const DRIFT_SPEED: f64 = 40.0;
+
+pub const MINE_SIDE: f64 = 20.0;
+
+/// Distance from the ship at which a mine starts its fuse
+const MINE_TRIGGER_RADIUS: f64 = 110.0;
+
+/// Seconds between a mine being triggered and its explosion
+const MINE_FUSE: f64 = 0.6;
+
+const EXPLOSION_RADIUS: f64 = 130.0;
+
+/// Seconds during which an explosion hurts what it touches
+const EXPLOSION_DURATION: f64 = 0.35;
+
+pub const DEBRIS_SIDE: f64 = 14.0;
+
+/// Distance beyond which gravity wells stop attracting
+pub const WELL_RADIUS: f64 = 220.0;
+
+/// Acceleration, in pixels/second², at the center of a gravity well
+const WELL_STRENGTH: f64 = 600.0;
+
+/// Ratio between the pull a gravity well exerts on the ship and its
+/// acceleration, as the ship has no momentum of its own
+const WELL_SHIP_PULL: f64 = 0.15;
+
+/// The points won by shooting a mine or a piece of debris
+pub const MINE_SCORE: u32 = 40;
+pub const DEBRIS_SCORE: u32 = 10;
+
+/// The appearance of hazards, read from the level file
+#[derive(Clone, Debug, PartialEq)]
+pub enum HazardSpawn {
+    /// A mine, at a height given in fractions of the screen
+    Mine { y: f64 },
+
+    /// 'count' pieces of debris scattered around a height given in
+    /// fractions of the screen
+    Debris { y: f64, count: usize },
+
+    /// A gravity well, at a height given in fractions of the screen
+    Well { y: f64 },
+}
+
+/// What the game may bring in at random between the hazards of a level
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpawnKind {
+    /// An asteroid of a random size
+    Asteroid,
+
+    /// A random formation of enemies
+    Formation,
+
+    /// A pickup of a random kind
+    Pickup,
+}
+
+impl SpawnKind {
+    fn parse(name: &str) -> Option<SpawnKind> {
+        match name {
+            "asteroid" => Some(SpawnKind::Asteroid),
+            "formation" => Some(SpawnKind::Formation),
+            "pickup" => Some(SpawnKind::Pickup),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SpawnKind::Asteroid => "asteroid",
+            SpawnKind::Formation => "formation",
+            SpawnKind::Pickup => "pickup",
+        }
+    }
+}
+
+/// An entry of the spawn table of a level: what may be brought in, how
+/// often compared to the other entries, and from which wave on
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnChance {
+    pub kind: SpawnKind,
+    pub weight: f64,
+    pub min_wave: u32,
+}
+
+/// The spawn table of the levels which do not have their own
+fn default_spawn_table() -> Vec<SpawnChance> {
+    vec![
+        SpawnChance { kind: SpawnKind::Asteroid, weight: 3.0, min_wave: 1 },
+        SpawnChance { kind: SpawnKind::Formation, weight: 1.0, min_wave: 1 },
+    ]
+}
+
+/// A level: the set of backgrounds it is played over, the hazards
+/// appearing in it, and what is brought in at random between them
+#[derive(Clone, Debug, PartialEq)]
+pub struct Level {
+    /// The name of the background set, see shared::Backgrounds::load, or
+    /// None to keep the backgrounds of the menu
+    pub background: Option<String>,
+
+    /// The Tiled map drawn as the terrain of the level, if any; see
+    /// phi::gfx::Tilemap
+    pub tilemap: Option<String>,
+
+    /// The hazards, sorted by the time, in seconds since the start of the
+    /// level, at which they occur
+    pub spawns: Vec<(f64, HazardSpawn)>,
+
+    /// The random spawns, one of which is picked by weight every time the
+    /// game brings something in
+    pub spawn_table: Vec<SpawnChance>,
+}
+
+impl Level {
+    /// Read the level at 'path'.
+    ///
+    /// Every line reads `TIME mine Y`, `TIME debris Y COUNT`, `TIME well Y`,
+    /// `background NAME`, `tilemap PATH`, or `spawn KIND WEIGHT [MIN_WAVE]`
+    /// to add an entry to the spawn table. Empty lines and lines starting
+    /// with `#` are ignored. A level without a `spawn` line gets the
+    /// default spawn table.
+    pub fn load(path: &str) -> Level {
+        let mut level = Level {
+            background: None,
+            tilemap: None,
+            spawns: vec![],
+            spawn_table: vec![],
+        };
+
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => {
+                warn!("Could not open the level {}", path);
+                level.spawn_table = default_spawn_table();
+                return level;
+            }
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match (words.get(0).cloned(), words.len()) {
+                (Some("background"), 2) => {
+                    level.background = Some(words[1].to_string());
+                    continue;
+                },
+                (Some("tilemap"), 2) => {
+                    level.tilemap = Some(words[1].to_string());
+                    continue;
+                },
+                (Some("spawn"), 3) | (Some("spawn"), 4) => {
+                    let min_wave = words.get(3).map_or(Ok(1), |wave| wave.parse());
+                    match (SpawnKind::parse(words[1]), words[2].parse::<f64>(), min_wave) {
+                        (Some(kind), Ok(weight), Ok(min_wave)) if weight >= 0.0 =>
+                            level.spawn_table.push(SpawnChance {
+                                kind: kind,
+                                weight: weight,
+                                min_wave: min_wave,
+                            }),
+                        _ => warn!("Ignoring malformed line in {}: {}", path, line),
+                    }
+                    continue;
+                },
+                _ => {},
+            }
+
+            let time = words.get(0).and_then(|w| w.parse::<f64>().ok());
+            let spawn = match (time, words.get(1).cloned(), words.len()) {
+                (Some(time), Some("mine"), 3) => words[2].parse().ok()
+                    .map(|y| (time, HazardSpawn::Mine { y: y })),
+                (Some(time), Some("well"), 3) => words[2].parse().ok()
+                    .map(|y| (time, HazardSpawn::Well { y: y })),
+                (Some(time), Some("debris"), 4) => match (words[2].parse(), words[3].parse()) {
+                    (Ok(y), Ok(count)) => Some((time, HazardSpawn::Debris { y: y, count: count })),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            match spawn {
+                Some(spawn) => level.spawns.push(spawn),
+                None => warn!("Ignoring malformed line in {}: {}", path, line),
+            }
+        }
+
+        if level.spawn_table.is_empty() {
+            level.spawn_table = default_spawn_table();
+        }
+        level.sort();
+        level
+    }
+
+    /// Write the level to 'path', in the format read by `load`
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = try!(File::create(path));
+        try!(writeln!(file, "# Hazards of the level, see views::hazards::Level::load. The level may be"));
+        try!(writeln!(file, "# edited with `--editor`."));
+        try!(writeln!(file, "#"));
+        try!(writeln!(file, "# Every line reads `TIME mine Y`, `TIME debris Y COUNT` or `TIME well Y`,"));
+        try!(writeln!(file, "# where TIME is in seconds since the start of the level and Y is a height"));
+        try!(writeln!(file, "# in fractions of the screen. The level starts over once its last hazard"));
+        try!(writeln!(file, "# appeared. A `background NAME` line picks a set of assets/backgrounds.toml,"));
+        try!(writeln!(file, "# and a `tilemap PATH` line the Tiled map drawn as the terrain."));
+        try!(writeln!(file, "#"));
+        try!(writeln!(file, "# Between the hazards, the game brings in something at random every few"));
+        try!(writeln!(file, "# seconds. Every `spawn KIND WEIGHT [MIN_WAVE]` line adds an asteroid,"));
+        try!(writeln!(file, "# formation or pickup to what it picks from, with a chance proportional"));
+        try!(writeln!(file, "# to WEIGHT, from the wave MIN_WAVE on."));
+        try!(writeln!(file, ""));
+
+        if let Some(ref background) = self.background {
+            try!(writeln!(file, "background {}", background));
+        }
+        if let Some(ref tilemap) = self.tilemap {
+            try!(writeln!(file, "tilemap {}", tilemap));
+        }
+        if self.background.is_some() || self.tilemap.is_some() {
+            try!(writeln!(file, ""));
+        }
+
+        for chance in &self.spawn_table {
+            try!(writeln!(file, "spawn {} {} {}", chance.kind.name(), chance.weight, chance.min_wave));
+        }
+        if !self.spawn_table.is_empty() {
+            try!(writeln!(file, ""));
+        }
+
+        for &(time, ref spawn) in &self.spawns {
+            match *spawn {
+                HazardSpawn::Mine { y } => try!(writeln!(file, "{} mine {}", time, y)),
+                HazardSpawn::Debris { y, count } => try!(writeln!(file, "{} debris {} {}", time, y, count)),
+                HazardSpawn::Well { y } => try!(writeln!(file, "{} well {}", time, y)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Put the hazards back in the order of their times, after some moved
+    pub fn sort(&mut self) {
+        self.spawns.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(::std::cmp::Ordering::Equal));
+    }
+
+    /// Pick what to bring in at random during 'wave' from the spawn table,
+    /// or None if nothing may appear yet
+    pub fn pick_spawn<R: Rng>(&self, rng: &mut R, wave: u32) -> Option<SpawnKind> {
+        let chances: Vec<(SpawnKind, f64)> = self.spawn_table.iter()
+            .filter(|chance| chance.min_wave <= wave)
+            .map(|chance| (chance.kind, chance.weight))
+            .collect();
+        rng.weighted(&chances).cloned()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MineState {
+    Idle,
+
+    /// Seconds before the explosion
+    Fuse(f64),
+
+    /// Seconds for which the explosion has been going on
+    Exploding(f64),
+}
+
+/// A proximity mine, which explodes shortly after the ship comes near
+/// and destroys everything around it
+#[derive(Clone)]
+pub struct Mine {
+    rect: Rectangle,
+    state: MineState,
+}
+
+impl Mine {
+    /// Create a mine entering the screen from the right at height 'y'
+    pub fn new(phi: &mut Phi, y: f64) -> Mine {
+        let (w, h) = phi.output_size();
+        Mine {
+            rect: Rectangle {
+                x: w,
+                y: y * h - MINE_SIDE / 2.0,
+                w: MINE_SIDE,
+                h: MINE_SIDE,
+            },
+            state: MineState::Idle,
+        }
+    }
+
+    /// Drift, and start the fuse when 'ship' comes close. Return None once
+    /// the mine has exploded.
+    pub fn update(mut self, ship: Rectangle, dt: f64) -> Option<Mine> {
+        self.rect.x -= DRIFT_SPEED * dt;
+
+        self.state = match self.state {
+            MineState::Idle if self.rect.overlaps_circle(ship.center(), MINE_TRIGGER_RADIUS) =>
+                MineState::Fuse(MINE_FUSE),
+            MineState::Idle => MineState::Idle,
+            MineState::Fuse(left) if left - dt <= 0.0 => MineState::Exploding(0.0),
+            MineState::Fuse(left) => MineState::Fuse(left - dt),
+            MineState::Exploding(time) if time + dt > EXPLOSION_DURATION => return None,
+            MineState::Exploding(time) => MineState::Exploding(time + dt),
+        };
+
+        Some(self)
+    }
+
+    /// Make the mine explode right away, for instance when it is shot
+    pub fn detonate(&mut self) {
+        if !self.is_exploding() {
+            self.state = MineState::Exploding(0.0);
+        }
+    }
+
+    pub fn is_exploding(&self) -> bool {
+        match self.state {
+            MineState::Exploding(_) => true,
+            _ => false,
+        }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        self.rect.center()
+    }
+
+    pub fn move_to(&mut self, center: (f64, f64)) {
+        self.rect = self.rect.with_center(center);
+    }
+
+    /// Whether the mine exploded during the last frame
+    pub fn just_exploded(&self) -> bool {
+        match self.state {
+            MineState::Exploding(time) => time == 0.0,
+            _ => false,
+        }
+    }
+
+    /// Return the area the mine covers on screen, its explosion included
+    pub fn bounds(&self) -> Rectangle {
+        match self.blast() {
+            Some((center, radius)) => self.rect.inflate(radius, radius)
+                .with_center(center),
+            None => self.rect,
+        }
+    }
+
+    /// Return the area in which the mine can be shot, unless it exploded
+    pub fn hitbox(&self) -> Option<Rectangle> {
+        if self.is_exploding() { None } else { Some(self.rect) }
+    }
+
+    /// Return the center and radius of the explosion, while it lasts
+    pub fn blast(&self) -> Option<((f64, f64), f64)> {
+        match self.state {
+            MineState::Exploding(time) => {
+                // The blast expands quickly to its full size
+                let growth = (time / (EXPLOSION_DURATION * 0.3)).min(1.0);
+                Some((self.rect.center(), EXPLOSION_RADIUS * growth))
+            },
+            _ => None,
+        }
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        match self.state {
+            MineState::Idle => {
+                phi.renderer.set_draw_color(Color::RGB(120, 120, 130));
+                phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
+            },
+            MineState::Fuse(left) => {
+                // Blink faster and faster as the explosion nears
+                let blink = (left * 20.0) as u32 % 2 == 0;
+                let color = if blink { phi.palette_color(Color::RGB(230, 30, 30)) }
+                            else { Color::RGB(120, 120, 130) };
+                phi.renderer.set_draw_color(color);
+                phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
+            },
+            MineState::Exploding(_) => {
+                if let Some((center, radius)) = self.blast() {
+                    let color = phi.palette_color(Color::RGB(250, 160, 40));
+                    phi.renderer.set_draw_color(color);
+                    fill_circle(phi, center, radius);
+
+                    // The core of the blast glows white hot
+                    phi.renderer.set_blend_mode(Blending::Additive.to_sdl());
+                    phi.renderer.set_draw_color(Color::RGBA(255, 230, 170, 200));
+                    fill_circle(phi, center, radius * 0.5);
+                    phi.renderer.set_blend_mode(BlendMode::None);
+                }
+            },
+        }
+    }
+}
+
+/// A slowly drifting piece of debris, which a single bullet destroys
+#[derive(Clone)]
+pub struct Debris {
+    rect: Rectangle,
+    vel_y: f64,
+    pub destroyed: bool,
+}
+
+impl Debris {
+    /// Create a field of 'count' pieces of debris entering the screen from
+    /// the right around height 'y', scattered with 'rng'
+    pub fn field<R: Rng>(phi: &mut Phi, rng: &mut R, y: f64, count: usize) -> Vec<Debris> {
+        let (w, h) = phi.output_size();
+
+        (0..count).map(|_| Debris {
+            rect: Rectangle {
+                x: rng.range_f64(w, w + 150.0),
+                y: rng.around(y * h, 75.0),
+                w: DEBRIS_SIDE,
+                h: DEBRIS_SIDE,
+            },
+            vel_y: rng.around(0.0, 10.0),
+            destroyed: false,
+        }).collect()
+    }
+
+    /// Drift. Return None once destroyed.
+    pub fn update(mut self, dt: f64) -> Option<Debris> {
+        self.rect.x -= DRIFT_SPEED * dt;
+        self.rect.y += self.vel_y * dt;
+
+        if self.destroyed {
+            None
+        }
+        else {
+            Some(self)
+        }
+    }
+
+    pub fn hitbox(&self) -> Rectangle {
+        self.rect
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        phi.renderer.set_draw_color(Color::RGB(90, 80, 70));
+        phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
+    }
+}
+
+/// Fill a disc with the current draw color, one horizontal line at a time
+pub fn fill_circle(phi: &mut Phi, (cx, cy): (f64, f64), radius: f64) {
+    let mut dy = -radius;
+    while dy <= radius {
+        // The rows at the very top and bottom may be too thin to draw
+        let half = (radius * radius - dy * dy).max(0.0).sqrt();
+        if half >= 0.5 {
+            phi.renderer.fill_rect(Rectangle {
+                x: cx - half,
+                y: cy + dy,
+                w: half * 2.0,
+                h: 2.0,
+            }.to_sdl().unwrap());
+        }
+        dy += 2.0;
+    }
+}
+
+/// A gravity well, attracting the ship, bullets and asteroids around it
+#[derive(Clone)]
+pub struct GravityWell {
+    center: (f64, f64),
+    radius: f64,
+    strength: f64,
+
+    // Used to animate the well
+    total_time: f64,
+}
+
+impl GravityWell {
+    /// Create a well entering the screen from the right at height 'y'
+    pub fn new(phi: &mut Phi, y: f64) -> GravityWell {
+        let (w, h) = phi.output_size();
+        GravityWell {
+            center: (w + WELL_RADIUS, y * h),
+            radius: WELL_RADIUS,
+            strength: WELL_STRENGTH,
+            total_time: 0.0,
+        }
+    }
+
+    pub fn move_to(&mut self, center: (f64, f64)) {
+        self.center = center;
+    }
+
+    /// Drift
+    pub fn update(&mut self, dt: f64) {
+        self.center.0 -= DRIFT_SPEED * dt;
+        self.total_time += dt;
+    }
+
+    /// Return the square around the area in which the well attracts
+    /// things
+    pub fn reach(&self) -> Rectangle {
+        Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: self.radius * 2.0,
+            h: self.radius * 2.0,
+        }.with_center(self.center)
+    }
+
+    /// Return the acceleration, in pixels/second², of something at 'pos'.
+    /// It points toward the center and decreases quadratically to zero at
+    /// the edge of the well.
+    pub fn acceleration(&self, pos: (f64, f64)) -> (f64, f64) {
+        let (dx, dy) = (self.center.0 - pos.0, self.center.1 - pos.1);
+        let dist = (dx * dx + dy * dy).sqrt();
+        if dist >= self.radius || dist == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let falloff = (1.0 - dist / self.radius).powi(2);
+        let accel = self.strength * falloff;
+        (dx / dist * accel, dy / dist * accel)
+    }
+
+    /// Return the displacement, in pixels/second, of the ship at 'pos'
+    pub fn ship_pull(&self, pos: (f64, f64)) -> (f64, f64) {
+        let (ax, ay) = self.acceleration(pos);
+        (ax * WELL_SHIP_PULL, ay * WELL_SHIP_PULL)
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        // Rings pulsing toward the center
+        let pulse = (self.total_time * 0.8).fract();
+        for ring in 0..3 {
+            let k = 1.0 - ((ring as f64 + pulse) / 3.0);
+            let shade = (40.0 + 60.0 * (1.0 - k)) as u8;
+            phi.renderer.set_draw_color(Color::RGB(shade / 2, 0, shade));
+            fill_circle(phi, self.center, self.radius * k * 0.5);
+        }
+
+        phi.renderer.set_draw_color(Color::RGB(10, 0, 20));
+        fill_circle(phi, self.center, 12.0);
+    }
+}
@@ -0,0 +1,377 @@
+// views/high_scores.rs
+
+use ::sdl2::keyboard::Keycode;
+use ::sdl2::pixels::Color;
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+
+use ::views::leaderboard::{Entry, Filter, GameMode, Leaderboard, SortKey};
+use ::views::shared::{self, cycle, Backgrounds};
+
+/// The number of runs shown at once
+const ROWS_SHOWN: usize = 10;
+
+/// The size of the cells, before the UI scale is applied
+const ROW_H: f64 = 30.0;
+const COLUMN_W: f64 = 130.0;
+
+/// The tabs above the table, each picking what the runs shown are
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tab {
+    Mode,
+    Difficulty,
+    Ship,
+    Sort,
+}
+
+impl Tab {
+    fn all() -> Vec<Tab> {
+        vec![Tab::Mode, Tab::Difficulty, Tab::Ship, Tab::Sort]
+    }
+
+    /// Return the key of the name of the tab, in phi::locale
+    fn label_key(self) -> &'static str {
+        match self {
+            Tab::Mode => "high_scores.mode",
+            Tab::Difficulty => "high_scores.difficulty",
+            Tab::Ship => "high_scores.ship",
+            Tab::Sort => "high_scores.sort",
+        }
+    }
+}
+
+/// Shows the best runs of every game mode. Up and down pick a tab, and left
+/// and right what it shows: the game mode, the difficulty and the ship of
+/// the runs, or the column by which they are sorted. Below the tabs, up and
+/// down pick a run, whose score C copies, and whose seed S copies.
+pub struct HighScoresView {
+    backgrounds: Backgrounds,
+
+    modes: Vec<GameMode>,
+    mode: usize,
+    sort: usize,
+    filter: Filter,
+
+    // The tab or the run picked, the tabs coming first
+    focus: usize,
+
+    // The game speeds and the ships of the runs of the mode, which the
+    // filter picks from
+    speeds: Vec<u32>,
+    ships: Vec<String>,
+
+    // The runs shown
+    entries: Vec<Entry>,
+
+    // The title, the tabs, the headers of the columns, and the cells of
+    // every row, or the message shown in their place when no run is, and
+    // what can be done with the run picked
+    title: Sprite,
+    tabs: Vec<Sprite>,
+    headers: Vec<Sprite>,
+    rows: Vec<Vec<Sprite>>,
+    empty: Option<Sprite>,
+    hint: Option<Sprite>,
+}
+
+impl HighScoresView {
+    pub fn new_with_backgrounds(phi: &mut Phi, backgrounds: Backgrounds) -> HighScoresView {
+        let modes = GameMode::all();
+        let mut view = HighScoresView {
+            backgrounds: backgrounds,
+            title: HighScoresView::title(phi, modes[0]),
+            modes: modes,
+            mode: 0,
+            sort: 1,  // By score
+            filter: Filter::default(),
+            focus: 0,
+            speeds: vec![],
+            ships: vec![],
+            entries: vec![],
+            tabs: vec![],
+            headers: vec![],
+            rows: vec![],
+            empty: None,
+            hint: None,
+        };
+        view.refresh(phi);
+        view
+    }
+
+    fn title(phi: &mut Phi, mode: GameMode) -> Sprite {
+        let text = phi.locale.format("high_scores.title", &[&phi.locale.text(mode.name_key())]);
+        phi.ttf_str_sprite(&text, "menu_font", 32, Color::RGB(255, 255, 255)).unwrap()
+    }
+
+    // Return the tab picked, if a run is not
+    fn tab(&self) -> Option<usize> {
+        if self.focus < Tab::all().len() { Some(self.focus) } else { None }
+    }
+
+    // Return the run picked, if a tab is not
+    fn entry(&self) -> Option<&Entry> {
+        self.focus.checked_sub(Tab::all().len()).and_then(|row| self.entries.get(row))
+    }
+
+    /// Show the next value of the tab picked, or the previous one if 'step'
+    /// is negative. The difficulty and the ship go through every one of the
+    /// runs, after any.
+    fn change(&mut self, step: i32) {
+        let tab = match self.tab() {
+            Some(tab) => tab,
+            None => return,
+        };
+        match Tab::all()[tab] {
+            Tab::Mode => self.mode = cycle(self.mode, self.modes.len(), step),
+            Tab::Difficulty => {
+                let current = self.filter.speed
+                    .and_then(|speed| self.speeds.iter().position(|&other| other == speed))
+                    .map_or(0, |index| index + 1);
+                let next = cycle(current, self.speeds.len() + 1, step);
+                self.filter.speed = if next == 0 { None } else { Some(self.speeds[next - 1]) };
+            },
+            Tab::Ship => {
+                let current = self.filter.ship.as_ref()
+                    .and_then(|ship| self.ships.iter().position(|other| other == ship))
+                    .map_or(0, |index| index + 1);
+                let next = cycle(current, self.ships.len() + 1, step);
+                self.filter.ship = if next == 0 { None } else { Some(self.ships[next - 1].clone()) };
+            },
+            Tab::Sort => self.sort = cycle(self.sort, SortKey::all().len(), step),
+        }
+    }
+
+    /// Render the labels of the table again, after the tabs changed
+    fn refresh(&mut self, phi: &mut Phi) {
+        let mode = self.modes[self.mode];
+        let keys = SortKey::all();
+        let sort = keys[self.sort];
+        let board = Leaderboard::load(mode);
+
+        // A filter the runs of another mode do not allow lets any run through
+        self.speeds = board.speeds();
+        self.ships = board.ships();
+        if self.filter.speed.map_or(false, |speed| !self.speeds.contains(&speed)) {
+            self.filter.speed = None;
+        }
+        if self.filter.ship.as_ref().map_or(false, |ship| !self.ships.contains(ship)) {
+            self.filter.ship = None;
+        }
+
+        self.title = HighScoresView::title(phi, mode);
+
+        let any = phi.locale.text("high_scores.any");
+        let tabs: Vec<Sprite> = Tab::all().into_iter().enumerate().map(|(i, tab)| {
+            let value = match tab {
+                Tab::Mode => phi.locale.text(mode.name_key()),
+                Tab::Difficulty => self.filter.speed
+                    .map_or(any.clone(), |speed| phi.locale.format("menu.game_speed_value", &[&speed])),
+                Tab::Ship => self.filter.ship.clone().unwrap_or(any.clone()),
+                Tab::Sort => phi.locale.text(sort.label_key()),
+            };
+            let (key, color) = if Some(i) == self.tab() { ("high_scores.tab_selected", Color::RGB(240, 200, 40)) }
+                               else { ("high_scores.tab", Color::RGB(180, 180, 180)) };
+            let text = phi.locale.format(key, &[&phi.locale.text(tab.label_key()), &value]);
+            phi.ttf_str_sprite(&text, "menu_font", 18, color).unwrap()
+        }).collect();
+        self.tabs = tabs;
+
+        self.headers = keys.iter().map(|&key| {
+            let color = if key == sort { Color::RGB(240, 200, 40) }
+                        else { Color::RGB(180, 180, 180) };
+            let label = phi.locale.text(key.label_key());
+            phi.ttf_str_sprite(&label, "menu_font", 20, color).unwrap()
+        }).collect();
+
+        let entries: Vec<_> = board.sorted(sort).into_iter()
+            .filter(|entry| self.filter.matches(entry))
+            .collect();
+        self.entries = entries.iter().take(ROWS_SHOWN).cloned().collect();
+        self.rows = self.entries.iter().map(|entry| {
+            keys.iter().map(|&key| {
+                // Rendering an empty string fails, so keep a space instead,
+                // and long names are cut short to stay in their column
+                let column_w = phi.scaled(COLUMN_W - 10.0);
+                let value = phi.fit_text(&key.value(entry), "menu_font", 18, column_w);
+                let text = if value.is_empty() { " ".to_string() } else { value };
+                phi.ttf_str_sprite(&text, "menu_font", 18,
+                                   Color::RGB(230, 230, 230)).unwrap()
+            }).collect()
+        }).collect();
+
+        // Tell apart a mode never played from filters leaving no run
+        self.empty = None;
+        if entries.is_empty() {
+            let key = if board.is_empty() { "high_scores.empty" } else { "high_scores.no_match" };
+            let text = phi.locale.text(key);
+            self.empty = phi.ttf_str_sprite(&text, "menu_font", 20, Color::RGB(200, 200, 200));
+        }
+
+        // The run picked may be gone with the filters
+        self.focus = self.focus.min(Tab::all().len() + self.entries.len() - 1);
+        self.hint = None;
+        if self.entry().is_some() {
+            self.show_hint(phi, "high_scores.copy_hint");
+        }
+    }
+
+    // Show the text 'key' under the table
+    fn show_hint(&mut self, phi: &mut Phi, key: &str) {
+        let text = phi.locale.text(key);
+        self.hint = phi.ttf_str_sprite(&text, "menu_font", 16, Color::RGB(200, 200, 200));
+    }
+
+    // Put 'text' in the clipboard, and tell whether it worked
+    fn copy(&mut self, phi: &mut Phi, text: &str) {
+        let key = if phi.set_clipboard_text(text) { "high_scores.copied" }
+                  else { "high_scores.copy_failed" };
+        self.show_hint(phi, key);
+    }
+}
+
+impl View for HighScoresView {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+        if phi.events.now.key_escape == Some(true) || phi.events.now.key_return == Some(true) {
+            return ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())));
+        }
+
+        // Pick a tab, and what it shows, or a run, wrapping around
+        let items = Tab::all().len() + self.entries.len();
+        let mut changed = false;
+        if phi.events.now.repeat.key_left {
+            self.change(-1);
+            changed = true;
+        }
+        if phi.events.now.repeat.key_right {
+            self.change(1);
+            changed = true;
+        }
+        if phi.events.now.repeat.key_up {
+            self.focus = cycle(self.focus, items, -1);
+            changed = true;
+        }
+        if phi.events.now.repeat.key_down {
+            self.focus = cycle(self.focus, items, 1);
+            changed = true;
+        }
+        if changed {
+            self.refresh(phi);
+        }
+
+        // Copy the score or the seed of the run picked, the seed written as
+        // the table shows it
+        let copied = self.entry().and_then(|entry| {
+            if phi.events.pressed(Keycode::C) { Some(entry.score.to_string()) }
+            else if phi.events.pressed(Keycode::S) { Some(SortKey::Seed.value(entry)) }
+            else { None }
+        });
+        if let Some(text) = copied {
+            self.copy(phi, &text);
+        }
+
+        // Clear the screen . . .
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.backgrounds.back.render(&mut phi.renderer, elapsed);
+        self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+        self.backgrounds.front.render(&mut phi.renderer, elapsed);
+
+        // Render the box which holds the table
+        let (win_w, win_h) = phi.output_size();
+        let (row_h, column_w) = (phi.scaled(ROW_H), phi.scaled(COLUMN_W));
+        let padding = phi.scaled(20.0);
+        let cell_w = column_w - phi.scaled(10.0);
+        let table_w = column_w * self.headers.len() as f64;
+        let table_h = row_h * (ROWS_SHOWN + 4) as f64;
+        let left = (win_w - table_w) / 2.0;
+        let top = (win_h - table_h) / 2.0;
+
+        shared::render_menu_box(phi, Rectangle {
+            x: left - padding,
+            y: top - padding,
+            w: table_w + padding * 2.0,
+            h: table_h + padding * 2.0,
+        }, Color::RGB(80, 20, 80));
+
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle {
+            x: (win_w - w) / 2.0,
+            y: top,
+            w: w,
+            h: h,
+        });
+
+        // Render the tabs side by side, under the title
+        let tab_w = table_w / self.tabs.len() as f64;
+        for (i, tab) in self.tabs.iter().enumerate() {
+            let (w, h) = tab.size();
+            tab.render(&mut phi.renderer, Rectangle {
+                x: left + tab_w * i as f64 + (tab_w - w.min(tab_w)) / 2.0,
+                y: top + row_h + (row_h - h) / 2.0,
+                w: w.min(tab_w),
+                h: h,
+            });
+        }
+
+        // Highlight the run picked
+        if let Some(row) = self.focus.checked_sub(Tab::all().len()) {
+            phi.renderer.set_draw_color(Color::RGB(130, 50, 130));
+            phi.renderer.fill_rect(Rectangle {
+                x: left - padding / 2.0,
+                y: top + row_h * (row + 3) as f64,
+                w: table_w + padding,
+                h: row_h,
+            }.to_sdl().unwrap());
+        }
+
+        // Render the table, one row under the other, or why it is empty
+        let lines = ::std::iter::once(&self.headers).chain(self.rows.iter());
+        for (i, cells) in lines.enumerate() {
+            for (j, cell) in cells.iter().enumerate() {
+                let (w, h) = cell.size();
+                cell.render(&mut phi.renderer, Rectangle {
+                    x: left + column_w * j as f64,
+                    y: top + row_h * (i + 2) as f64 + (row_h - h) / 2.0,
+                    w: w.min(cell_w),
+                    h: h,
+                });
+            }
+        }
+        if let Some(ref empty) = self.empty {
+            let (w, h) = empty.size();
+            empty.render(&mut phi.renderer, Rectangle {
+                x: (win_w - w) / 2.0,
+                y: top + row_h * 3.0 + (row_h * ROWS_SHOWN as f64 - h) / 2.0,
+                w: w,
+                h: h,
+            });
+        }
+        if let Some(ref hint) = self.hint {
+            let (w, h) = hint.size();
+            hint.render(&mut phi.renderer, Rectangle {
+                x: (win_w - w) / 2.0,
+                y: top + row_h * (ROWS_SHOWN + 3) as f64 + (row_h - h) / 2.0,
+                w: w,
+                h: h,
+            });
+        }
+
+        ViewAction::None
+    }
+}
@@ -0,0 +1,128 @@
+// views/hud.rs
+
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::shapes::{self, Gradient};
+
+/// Seconds the ghost of a bar takes to catch up with its value
+const GHOST_CATCH_UP: f64 = 0.5;
+
+/// A horizontal gauge of the HUD, such as a health bar. When its value
+/// drops, the part lost stays visible as a pale "ghost" which shrinks to
+/// the new value over half a second, so that the player sees how much
+/// they lost.
+#[derive(Clone, Debug)]
+pub struct Bar {
+    // The value shown, from 0 to 1, and where the ghost ends
+    value: f64,
+    ghost: f64,
+
+    // Where the ghost ended when the value last dropped, and the seconds
+    // since then
+    ghost_from: f64,
+    since_drop: f64,
+
+    // Whether the ghost is shown; it is not for timers, which drop a bit
+    // every frame
+    trails: bool,
+
+    // The color of the fill, and the colors it takes once the value is
+    // below some thresholds, the lowest threshold last
+    color: Color,
+    thresholds: Vec<(f64, Color)>,
+
+    background: Color,
+}
+
+impl Bar {
+    /// Create a full bar filled with 'color'
+    pub fn new(color: Color) -> Bar {
+        Bar {
+            value: 1.0,
+            ghost: 1.0,
+            ghost_from: 1.0,
+            since_drop: GHOST_CATCH_UP,
+            trails: true,
+            color: color,
+            thresholds: vec![],
+            background: Color::RGB(30, 30, 40),
+        }
+    }
+
+    /// Fill the bar with 'color' once its value is below 'below'
+    pub fn with_threshold(mut self, below: f64, color: Color) -> Bar {
+        self.thresholds.push((below, color));
+        self.thresholds.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        self
+    }
+
+    /// Fill the empty part of the bar with 'color'
+    pub fn with_background(mut self, color: Color) -> Bar {
+        self.background = color;
+        self
+    }
+
+    /// Never show the ghost
+    pub fn without_ghost(mut self) -> Bar {
+        self.trails = false;
+        self
+    }
+
+    /// Show 'value', from 0 to 1. The ghost trails behind when it drops,
+    /// but not when it rises.
+    pub fn set(&mut self, value: f64) {
+        let value = value.max(0.0).min(1.0);
+        if !self.trails {
+            self.ghost = value;
+        }
+        else if value < self.value {
+            self.ghost_from = self.ghost.max(self.value);
+            self.since_drop = 0.0;
+        }
+        else if value > self.ghost {
+            self.ghost = value;
+        }
+        self.value = value;
+    }
+
+    /// Let the ghost catch up with the value
+    pub fn update(&mut self, dt: f64) {
+        if !self.trails {
+            return;
+        }
+        self.since_drop = (self.since_drop + dt).min(GHOST_CATCH_UP);
+        let t = self.since_drop / GHOST_CATCH_UP;
+        self.ghost = (self.ghost_from + (self.value - self.ghost_from) * t).max(self.value);
+    }
+
+    /// Return the color of the fill at the current value
+    fn fill_color(&self) -> Color {
+        self.thresholds.iter()
+            .filter(|&&(below, _)| self.value < below)
+            .last()
+            .map_or(self.color, |&(_, color)| color)
+    }
+
+    /// Render the bar over 'rect', lit from above
+    pub fn render(&self, phi: &mut Phi, rect: Rectangle) {
+        let radius = (rect.h / 3.0).floor();
+        let color = phi.palette_color(self.fill_color());
+        let background = self.background;
+        shapes::fill_rounded_rect(&mut phi.renderer, rect, radius, background);
+
+        let ghost_w = (rect.w * self.ghost).floor();
+        if ghost_w > rect.w * self.value {
+            let ghost = shapes::mix(color, Color::RGB(255, 255, 255), 0.6);
+            shapes::fill_rounded_rect(&mut phi.renderer, Rectangle { w: ghost_w, ..rect }, radius, ghost);
+        }
+
+        let fill_w = (rect.w * self.value).floor();
+        if fill_w >= 1.0 {
+            let light = shapes::mix(color, Color::RGB(255, 255, 255), 0.4);
+            shapes::fill_rounded_gradient(&mut phi.renderer, Rectangle { w: fill_w, ..rect },
+                                          radius, light, color, Gradient::Vertical);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// views/lan.rs
+
+use ::sdl2::pixels::Color;
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::Renderable;
+use ::phi::locale::Locale;
+use ::phi::net::{self, Session};
+use ::phi::{Phi, View, ViewAction};
+
+use ::views::game::GameView;
+use ::views::replay::Input;
+use ::views::shared::Backgrounds;
+
+/// The address offered when joining a game
+const DEFAULT_ADDRESS: &'static str = "127.0.0.1:7878";
+
+/// Seconds simulated by every frame; the simulation must not depend on
+/// the time it takes to render, or both players would drift apart
+const STEP: f64 = 1.0 / 60.0;
+
+/// The number of frames between reading the local input and simulating
+/// it, which hides the latency of the network
+const INPUT_DELAY: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Host,
+    Join,
+}
+
+/// A run played by two players, each on their own computer: the host flies
+/// the ship and the other player its wingman. The host waits for the other
+/// player, who types the host's address, and picks the seed of the run.
+/// Both computers simulate the run in lockstep from the same inputs, so
+/// their windows must be the same size, as with replays. The run is
+/// neither ranked nor saved.
+pub struct LanView {
+    role: Role,
+    backgrounds: Backgrounds,
+
+    // The address typed by the joining player
+    address: String,
+
+    session: Option<Session>,
+    error: Option<String>,
+
+    // The next frame to simulate, and the next one to send the local
+    // input for
+    frame: u32,
+    sent: u32,
+
+    // The cannons picked by the local player, from 1 to 3
+    cannon: u8,
+
+    // The run, once both players know its seed
+    game: Option<GameView>,
+}
+
+impl LanView {
+    pub fn new_with_backgrounds(_phi: &mut Phi, backgrounds: Backgrounds, role: Role) -> LanView {
+        let mut view = LanView {
+            role: role,
+            backgrounds: backgrounds,
+            address: DEFAULT_ADDRESS.to_string(),
+            session: None,
+            error: None,
+            frame: 0,
+            sent: 0,
+            cannon: 1,
+            game: None,
+        };
+
+        if role == Role::Host {
+            view.connect(Session::host(net::DEFAULT_PORT, ::rand::random()));
+        }
+        view
+    }
+
+    fn connect(&mut self, session: ::std::io::Result<Session>) {
+        match session {
+            Ok(session) => {
+                self.session = Some(session);
+                self.error = None;
+            },
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    /// Exchange the inputs with the other player, and simulate the next
+    /// frame of the run once both are known. Return whether the run is
+    /// over.
+    fn update(&mut self, phi: &mut Phi) -> bool {
+        self.cannon = match (phi.events.now.key_1, phi.events.now.key_2, phi.events.now.key_3) {
+            (Some(true), _, _) => 1,
+            (_, Some(true), _) => 2,
+            (_, _, Some(true)) => 3,
+            _ => self.cannon,
+        };
+        let local = local_input(phi, self.cannon);
+        let frame = self.frame;
+
+        let inputs = match self.session {
+            Some(ref mut session) => {
+                session.poll();
+                if !session.is_connected() {
+                    return false;
+                }
+
+                // The run starts once both players know its seed
+                if self.game.is_none() {
+                    match session.seed() {
+                        Some(seed) => self.game = Some(GameView::new_pair(phi, self.backgrounds.clone(), seed)),
+                        None => return false,
+                    }
+                }
+
+                while self.sent <= frame + INPUT_DELAY {
+                    session.send_input(self.sent, local);
+                    self.sent += 1;
+                }
+
+                match (session.local_input(frame), session.remote_input(frame)) {
+                    (Some(local), Some(remote)) => match self.role {
+                        Role::Host => [local, remote],
+                        Role::Join => [remote, local],
+                    },
+                    // Wait for the other player
+                    _ => return false,
+                }
+            },
+            None => return false,
+        };
+
+        self.frame += 1;
+        match self.game {
+            Some(ref mut game) => game.simulate_pair(phi, &game_input(inputs[0]), &game_input(inputs[1])),
+            None => false,
+        }
+    }
+
+    /// Return the line telling the player what is going on
+    fn status(&self, locale: &Locale) -> String {
+        if let Some(ref error) = self.error {
+            return locale.format("lan.error", &[error]);
+        }
+
+        match self.session {
+            None => locale.format("lan.address", &[&self.address]),
+            Some(ref session) if !session.is_connected() => match self.role {
+                Role::Host => locale.format("lan.waiting", &[&net::DEFAULT_PORT]),
+                Role::Join => locale.format("lan.joining", &[&self.address]),
+            },
+            Some(ref session) => match session.latency() {
+                Some(latency) => locale.format("lan.latency", &[&(latency * 1000.0).round()]),
+                None => locale.text("lan.connected"),
+            },
+        }
+    }
+}
+
+/// Read the buttons of the local player, who picked the cannons 'cannon'
+fn local_input(phi: &Phi, cannon: u8) -> net::Input {
+    let mut input = (cannon as net::Input) << net::INPUT_CANNON_SHIFT;
+    if phi.events.key_up { input |= net::INPUT_UP; }
+    if phi.events.key_down { input |= net::INPUT_DOWN; }
+    if phi.events.key_left { input |= net::INPUT_LEFT; }
+    if phi.events.key_right { input |= net::INPUT_RIGHT; }
+    if phi.events.now.key_space == Some(true) { input |= net::INPUT_FIRE; }
+    if phi.events.now.key_return == Some(true) || phi.events.now.key_space == Some(true) {
+        input |= net::INPUT_CONFIRM;
+    }
+    input
+}
+
+/// Return what the 'buttons' of a player do during a frame of the run
+fn game_input(buttons: net::Input) -> Input {
+    let held = |button: net::Input| buttons & button != 0;
+    let axis = |neg: net::Input, pos: net::Input| match (held(neg), held(pos)) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => 0.0,
+    };
+
+    // As fast diagonally as straight ahead, as with the keyboard
+    let (x, y) = (axis(net::INPUT_LEFT, net::INPUT_RIGHT), axis(net::INPUT_UP, net::INPUT_DOWN));
+    let moved = if x != 0.0 && y != 0.0 { 1.0 / 2.0f64.sqrt() } else { 1.0 };
+
+    Input {
+        elapsed: STEP,
+        game_speed: 1.0,
+        movement: (x * moved, y * moved),
+        cannon: ((buttons & net::INPUT_CANNON) >> net::INPUT_CANNON_SHIFT).max(1) as u8,
+        fire: held(net::INPUT_FIRE),
+        aim: None,
+        confirm: held(net::INPUT_CONFIRM),
+        restart: false,
+    }
+}
+
+impl View for LanView {
+    fn resume(&mut self, phi: &mut Phi) {
+        if self.role == Role::Join {
+            phi.start_text_input();
+        }
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+        if phi.events.now.key_escape == Some(true) {
+            return ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())));
+        }
+
+        // Let the joining player type the address of the host
+        if self.session.is_none() && self.role == Role::Join {
+            self.address.push_str(&phi.events.now.text);
+            if phi.events.now.key_backspace == Some(true) {
+                self.address.pop();
+            }
+            if phi.events.now.key_return == Some(true) {
+                phi.stop_text_input();
+                let session = Session::join(&self.address);
+                self.connect(session);
+                if self.error.is_some() {
+                    phi.start_text_input();
+                }
+            }
+        }
+        else if self.update(phi) {
+            return ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())));
+        }
+
+        match self.game {
+            Some(ref mut game) => game.draw_frame(phi, elapsed),
+            None => {
+                // Clear the screen . . .
+                phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+                phi.renderer.clear();
+
+                self.backgrounds.back.render(&mut phi.renderer, elapsed);
+                self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+                self.backgrounds.front.render(&mut phi.renderer, elapsed);
+            },
+        }
+
+        // Render the status, centered at the top, clear of the score . . .
+        let status = self.status(&phi.locale);
+        if let Some(label) = phi.ttf_str_sprite(&status, "menu_font", 24,
+                                                Color::RGB(255, 255, 255)) {
+            let (w, h) = label.size();
+            let (win_w, _) = phi.output_size();
+            label.render(&mut phi.renderer, Rectangle {
+                x: (win_w - w) / 2.0,
+                y: 10.0,
+                w: w,
+                h: h,
+            });
+        }
+
+        ViewAction::None
+    }
+}
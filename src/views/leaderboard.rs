@@ -0,0 +1,372 @@
+// views/leaderboard.rs
+
+use ::std::cmp::Ordering;
+use ::std::fs::{self, File};
+use ::std::io::{self, BufRead, BufReader, Write};
+use ::std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the leaderboards are kept, one file per game mode
+pub const LEADERBOARD_DIR: &'static str = "scores";
+
+/// The number of entries kept on every leaderboard
+const MAX_ENTRIES: usize = 100;
+
+/// The ways the game can be played, each with its own leaderboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GameMode {
+    /// Waves follow each other until the player runs out of continues
+    Arcade,
+
+    /// The waves again once the campaign was cleared, harder, with the
+    /// escort the ship ended the campaign with
+    NewGamePlus,
+}
+
+impl GameMode {
+    pub fn all() -> Vec<GameMode> {
+        vec![GameMode::Arcade, GameMode::NewGamePlus]
+    }
+
+    /// Return the mode called 'id', as written by 'id'
+    pub fn from_id(id: &str) -> Option<GameMode> {
+        GameMode::all().into_iter().find(|mode| mode.id() == id)
+    }
+
+    /// Return the name of the mode in the files of the game
+    pub fn id(self) -> &'static str {
+        match self {
+            GameMode::Arcade => "arcade",
+            GameMode::NewGamePlus => "new_game_plus",
+        }
+    }
+
+    /// Return the key of the name shown to the player, in phi::locale
+    pub fn name_key(self) -> &'static str {
+        match self {
+            GameMode::Arcade => "mode.arcade",
+            GameMode::NewGamePlus => "mode.new_game_plus",
+        }
+    }
+
+    /// Return the path of the mode's leaderboard
+    fn path(self) -> String {
+        format!("{}/{}.txt", LEADERBOARD_DIR, self.id())
+    }
+}
+
+/// A finished run
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+
+    /// The wave the player was in when the run ended
+    pub wave: u32,
+
+    /// The name of the ship the player flew
+    pub ship: String,
+
+    /// When the run ended, in seconds since the Unix epoch
+    pub date: u64,
+
+    /// The seed the run was played with
+    pub seed: u64,
+
+    /// The game speed the run was played at, in percent: the slowest it was
+    /// set to during the run, which makes it easier
+    pub speed: u32,
+}
+
+impl Entry {
+    /// Read an entry written by 'to_line'. Entries written before the game
+    /// speed was kept have no such field, and were played at full speed.
+    pub fn parse(line: &str) -> Option<Entry> {
+        let fields: Vec<&str> = line.splitn(7, '\t').collect();
+        if fields.len() == 7 {
+            if let Ok(speed) = fields[4].parse() {
+                return Entry::parse_fields(&fields[..4], speed, fields[5], fields[6]);
+            }
+        }
+
+        let fields: Vec<&str> = line.splitn(6, '\t').collect();
+        if fields.len() != 6 {
+            return None;
+        }
+        Entry::parse_fields(&fields[..4], 100, fields[4], fields[5])
+    }
+
+    // Read an entry from the score, wave, date and seed in 'numbers', along
+    // with the rest of its fields
+    fn parse_fields(numbers: &[&str], speed: u32, ship: &str, name: &str) -> Option<Entry> {
+        match (numbers[0].parse(), numbers[1].parse(), numbers[2].parse(), numbers[3].parse()) {
+            (Ok(score), Ok(wave), Ok(date), Ok(seed)) => Some(Entry {
+                score: score,
+                wave: wave,
+                date: date,
+                seed: seed,
+                speed: speed,
+                ship: ship.to_string(),
+                name: name.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return the entry as a line of tab-separated fields. The name comes
+    /// last, so that it may contain anything but a line break.
+    pub fn to_line(&self) -> String {
+        format!("{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                self.score, self.wave, self.date, self.seed, self.speed,
+                self.ship.replace('\t', " "), self.name.replace('\n', " "))
+    }
+
+    /// Return the day the run ended, as YYYY-MM-DD
+    pub fn date_string(&self) -> String {
+        // Convert days since the epoch to a date of the proleptic Gregorian
+        // calendar, working in 400-year eras which start on March 1st
+        let days = (self.date / 86_400) as i64 + 719_468;
+        let era = days / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524
+                           - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// The columns by which a leaderboard can be sorted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortKey {
+    Name,
+    Score,
+    Wave,
+    Ship,
+    Date,
+    Seed,
+}
+
+impl SortKey {
+    pub fn all() -> Vec<SortKey> {
+        vec![SortKey::Name, SortKey::Score, SortKey::Wave,
+             SortKey::Ship, SortKey::Date, SortKey::Seed]
+    }
+
+    /// Return the key of the title of the column, in phi::locale
+    pub fn label_key(self) -> &'static str {
+        match self {
+            SortKey::Name => "column.name",
+            SortKey::Score => "column.score",
+            SortKey::Wave => "column.wave",
+            SortKey::Ship => "column.ship",
+            SortKey::Date => "column.date",
+            SortKey::Seed => "column.seed",
+        }
+    }
+
+    /// Return the value of the column for 'entry'
+    pub fn value(self, entry: &Entry) -> String {
+        match self {
+            SortKey::Name => entry.name.clone(),
+            SortKey::Score => entry.score.to_string(),
+            SortKey::Wave => entry.wave.to_string(),
+            SortKey::Ship => entry.ship.clone(),
+            SortKey::Date => entry.date_string(),
+            SortKey::Seed => format!("{:016x}", entry.seed),
+        }
+    }
+
+    /// Compare two entries along the column. Text is sorted alphabetically,
+    /// numbers from the highest to the lowest and dates from the latest.
+    fn compare(self, a: &Entry, b: &Entry) -> Ordering {
+        match self {
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::Score => b.score.cmp(&a.score),
+            SortKey::Wave => b.wave.cmp(&a.wave),
+            SortKey::Ship => a.ship.cmp(&b.ship),
+            SortKey::Date => b.date.cmp(&a.date),
+            SortKey::Seed => a.seed.cmp(&b.seed),
+        }
+    }
+}
+
+/// Which runs of a leaderboard are shown; None lets every run through
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    /// The game speed of the runs, in percent, which stands for how hard
+    /// they were
+    pub speed: Option<u32>,
+
+    /// The name of the ship flown
+    pub ship: Option<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.speed.map_or(true, |speed| entry.speed == speed)
+            && self.ship.as_ref().map_or(true, |ship| entry.ship == *ship)
+    }
+}
+
+/// The best runs of a game mode
+pub struct Leaderboard {
+    pub mode: GameMode,
+
+    // Sorted by decreasing score
+    entries: Vec<Entry>,
+}
+
+impl Leaderboard {
+    /// Read the leaderboard of 'mode'. Malformed lines are ignored, and
+    /// the leaderboard is empty if it was never saved.
+    pub fn load(mode: GameMode) -> Leaderboard {
+        let mut entries = vec![];
+
+        if let Ok(file) = File::open(mode.path()) {
+            for line in BufReader::new(file).lines() {
+                match line {
+                    Ok(line) => entries.extend(Entry::parse(&line)),
+                    Err(_) => break,
+                }
+            }
+        }
+
+        let mut leaderboard = Leaderboard {
+            mode: mode,
+            entries: vec![],
+        };
+        for entry in entries {
+            leaderboard.add(entry);
+        }
+        leaderboard
+    }
+
+    /// Write the leaderboard, so that it can be loaded back
+    pub fn save(&self) -> io::Result<()> {
+        try!(fs::create_dir_all(LEADERBOARD_DIR));
+        let mut file = try!(File::create(self.mode.path()));
+        for entry in &self.entries {
+            try!(writeln!(file, "{}", entry.to_line()));
+        }
+        Ok(())
+    }
+
+    /// Add a run to the leaderboard, unless it is too low to be kept
+    pub fn add(&mut self, entry: Entry) {
+        let index = self.entries.iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(index, entry);
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    /// Return the highest score of any kept run
+    pub fn best_score(&self) -> u32 {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    /// Return the furthest wave any kept run reached
+    pub fn best_wave(&self) -> u32 {
+        self.entries.iter().map(|entry| entry.wave).max().unwrap_or(0)
+    }
+
+    /// Return the entries sorted along 'key'; entries which compare equal
+    /// stay sorted by score
+    pub fn sorted(&self, key: SortKey) -> Vec<Entry> {
+        let mut entries = self.entries.clone();
+        entries.sort_by(|a, b| key.compare(a, b));
+        entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return every game speed the kept runs were played at, from the
+    /// fastest
+    pub fn speeds(&self) -> Vec<u32> {
+        let mut speeds: Vec<u32> = self.entries.iter().map(|entry| entry.speed).collect();
+        speeds.sort_by(|a, b| b.cmp(a));
+        speeds.dedup();
+        speeds
+    }
+
+    /// Return the name of every ship the kept runs were flown with
+    pub fn ships(&self) -> Vec<String> {
+        let mut ships: Vec<String> = self.entries.iter().map(|entry| entry.ship.clone()).collect();
+        ships.sort();
+        ships.dedup();
+        ships
+    }
+}
+
+/// Return the current time, in seconds since the Unix epoch
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(date: u64) -> Entry {
+        Entry {
+            name: "Ace".to_string(),
+            score: 12_500,
+            wave: 7,
+            ship: "Falcon".to_string(),
+            date: date,
+            seed: 0xdead_beef,
+            speed: 80,
+        }
+    }
+
+    #[test]
+    fn entries_are_read_back() {
+        let entry = entry(1_700_000_000);
+        assert_eq!(Entry::parse(&entry.to_line()), Some(entry));
+    }
+
+    #[test]
+    fn names_keep_their_tabs() {
+        let mut entry = entry(0);
+        entry.name = "Top\tGun".to_string();
+        assert_eq!(Entry::parse(&entry.to_line()), Some(entry));
+    }
+
+    #[test]
+    fn old_entries_were_played_at_full_speed() {
+        let parsed = Entry::parse("12500\t7\t0\t3735928559\tFalcon\tAce").unwrap();
+        assert_eq!(parsed, Entry { speed: 100, ..entry(0) });
+
+        // The name of an old entry may contain a tab too
+        let parsed = Entry::parse("12500\t7\t0\t3735928559\tFalcon\tTop\tGun").unwrap();
+        assert_eq!(parsed.speed, 100);
+        assert_eq!(parsed.name, "Top\tGun");
+    }
+
+    #[test]
+    fn malformed_entries_are_ignored() {
+        assert_eq!(Entry::parse(""), None);
+        assert_eq!(Entry::parse("12500\t7\t0\t3735928559\tFalcon"), None);
+        assert_eq!(Entry::parse("lots\t7\t0\t3735928559\t80\tFalcon\tAce"), None);
+        assert_eq!(Entry::parse("12500\t-7\t0\t3735928559\tFalcon\tAce"), None);
+    }
+
+    #[test]
+    fn dates_follow_the_gregorian_calendar() {
+        assert_eq!(entry(0).date_string(), "1970-01-01");
+        assert_eq!(entry(86_399).date_string(), "1970-01-01");
+        assert_eq!(entry(951_782_400).date_string(), "2000-02-29");
+        assert_eq!(entry(951_868_800).date_string(), "2000-03-01");
+        assert_eq!(entry(1_700_000_000).date_string(), "2023-11-14");
+        assert_eq!(entry(1_735_603_200).date_string(), "2024-12-31");
+        assert_eq!(entry(1_735_689_600).date_string(), "2025-01-01");
+    }
+}
@@ -0,0 +1,97 @@
+// views/loading.rs
+
+use ::sdl2::pixels::Color;
+
+use ::phi::data::Rectangle;
+use ::phi::{Phi, View, ViewAction};
+
+/// An asset loaded before the game starts
+enum Preload {
+    Texture(&'static str),
+    Font(&'static str, i32),
+}
+
+/// Everything the menu and the game need right away. Loading it upfront
+/// keeps entering a view from stalling on the filesystem.
+const PRELOADS: [Preload; 13] = [
+    Preload::Texture("star_back"),
+    Preload::Texture("star_middle"),
+    Preload::Texture("star_front"),
+    Preload::Texture("player_ship"),
+    Preload::Texture("asteroid_sheet"),
+    Preload::Font("menu_font", 12),
+    Preload::Font("menu_font", 18),
+    Preload::Font("menu_font", 20),
+    Preload::Font("menu_font", 24),
+    Preload::Font("menu_font", 28),
+    Preload::Font("menu_font", 32),
+    Preload::Font("menu_font", 40),
+    Preload::Font("menu_font", 64),
+];
+
+const BAR_W: f64 = 400.0;
+const BAR_H: f64 = 20.0;
+
+/// Loads one asset of PRELOADS every frame while showing the progress,
+/// then moves on to the splash screen.
+pub struct LoadingView {
+    loaded: usize,
+}
+
+impl LoadingView {
+    pub fn new() -> LoadingView {
+        LoadingView {
+            loaded: 0,
+        }
+    }
+}
+
+impl View for LoadingView {
+    fn render(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        if self.loaded == PRELOADS.len() {
+            return ViewAction::ChangeView(Box::new(
+                ::views::splash::SplashView::new(phi)));
+        }
+
+        // Missing assets are reported, and dealt with by the views using them
+        match PRELOADS[self.loaded] {
+            Preload::Texture(path) => {
+                let renderer = &phi.renderer;
+                phi.assets.preload(renderer, path);
+            },
+            Preload::Font(path, size) => {
+                phi.preload_font(path, size);
+            },
+        }
+        self.loaded += 1;
+
+        // Render the progress bar . . .
+        let (win_w, win_h) = phi.output_size();
+        let progress = self.loaded as f64 / PRELOADS.len() as f64;
+        let (bar_w, bar_h) = (phi.scaled(BAR_W), phi.scaled(BAR_H));
+        let bar = Rectangle {
+            x: (win_w - bar_w) / 2.0,
+            y: (win_h - bar_h) / 2.0,
+            w: bar_w,
+            h: bar_h,
+        };
+
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        phi.renderer.set_draw_color(Color::RGB(70, 15, 70));
+        let border = phi.scaled(3.0);
+        phi.renderer.fill_rect(bar.inflate(border, border).to_sdl().unwrap());
+        phi.renderer.set_draw_color(Color::RGB(140, 30, 140));
+        phi.renderer.fill_rect(Rectangle {
+            w: (bar_w * progress).max(1.0),
+            ..bar
+        }.to_sdl().unwrap());
+
+        ViewAction::None
+    }
+}
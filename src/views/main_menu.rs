@@ -1,90 +1,486 @@
 // views/main_menu.rs
 
 use ::phi::data::Rectangle;
+use ::phi::focus::Focus;
 use ::phi::gfx::{Renderable, Sprite};
-use ::phi::{Phi, View, ViewAction};
+use ::phi::locale::{self, Locale};
+use ::phi::palette::Palette;
+use ::phi::settings::SETTINGS_PATH;
+use ::phi::shapes;
+use ::phi::themes::{self, Theme};
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
 use ::sdl2::pixels::Color;
+use ::std::fmt::Display;
 
-use views::shared::Backgrounds;
+use views::leaderboard::{GameMode, Leaderboard};
+use views::game::FINAL_WAVE;
+use views::progress::{Progress, PROGRESS_PATH};
+use views::replay;
+use views::shared::{self, cycle, Backgrounds, DISABLED_COLOR, REFUSED_SOUND};
+
+/// The positions of the entries opening the options, and the controls
+/// within them
+const OPTIONS_ACTION: usize = 7;
+const CONTROLS_ACTION: usize = 3;
+
+/// The UI scales the menu cycles through, where 0 follows the display
+const UI_SCALES: [f64; 4] = [0.0, 1.0, 1.5, 2.0];
+
+/// The game speeds the menu cycles through, in tenths of the full speed,
+/// from the slowest the settings allow
+const GAME_SPEEDS: [u32; 4] = [7, 8, 9, 10];
+
+/// What happens when an action is chosen
+enum Choice {
+    // Leave the menu for the view returned
+    Run(Box<Fn(&mut Phi, Backgrounds) -> ViewAction>),
+
+    // Change a setting by some steps, -1 with Left, 1 with Right or when
+    // chosen; the menus are then built again to show it
+    Change(Box<Fn(&mut Phi, i32)>),
+
+    // Open a menu nested in this one, or go back to the menu this one is
+    // nested in
+    Open(Menu),
+    Back,
+}
 
 struct Action {
-    // The function that should be executed if that action if chosen
-    func: Box<Fn(&mut Phi, Backgrounds) -> ViewAction>,
+    // The text of the label, in the player's language, and the current
+    // value of the setting it changes, if any
+    label: String,
+    value: Option<String>,
 
-    // The sprite that is rendered when the user does not focus on this actions label.
-    idle_sprite: Sprite,
+    choice: Choice,
 
-    // The sprite that is rendered when the user focuses a label with the directional keys.
-    hover_sprite: Sprite,
+    // Whether the action may be chosen; otherwise it is greyed out and skipped.
+    enabled: bool,
 }
 
 impl Action {
-    fn new (phi: &mut Phi, label: &'static str, func: Box<Fn(&mut Phi, Backgrounds) -> ViewAction>) -> Action {
+    // The label is the string 'key' of the player's language, formatted with 'args'
+    fn new(phi: &Phi, key: &str, args: &[&Display], choice: Choice) -> Action {
         Action {
-            func: func,
-            idle_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 32, Color::RGB(220, 220, 220)).unwrap(),
-            hover_sprite: phi.ttf_str_sprite(label, "assets/belligerent.ttf", 32, Color::RGB(255, 255, 255)).unwrap(),
+            label: phi.locale.format(key, args),
+            value: None,
+            choice: choice,
+            enabled: true,
         }
     }
+
+    // Show 'value' after the label
+    fn with_value(mut self, value: String) -> Action {
+        self.value = Some(value);
+        self
+    }
+
+    // Let the action be chosen only if 'enabled'
+    fn enabled(mut self, enabled: bool) -> Action {
+        self.enabled = enabled;
+        self
+    }
 }
 
-pub struct MainMenuView {
+/// A list of actions, some of which open other menus
+struct Menu {
+    title: String,
     actions: Vec<Action>,
-    selected: i8,
+}
+
+impl Menu {
+    fn new(phi: &Phi, title_key: &str, actions: Vec<Action>) -> Menu {
+        Menu {
+            title: phi.locale.text(title_key),
+            actions: actions,
+        }
+    }
+}
+
+/// A text of the menu, drawn idle, selected, or disabled
+struct Label {
+    idle: Sprite,
+    hover: Sprite,
+    disabled: Sprite,
+}
+
+impl Label {
+    fn new(phi: &mut Phi, text: &str) -> Label {
+        Label {
+            idle: phi.ttf_str_sprite(text, "menu_font", 32, Color::RGB(220, 220, 220)).unwrap(),
+            hover: phi.ttf_str_sprite(text, "menu_font", 32, Color::RGB(255, 255, 255)).unwrap(),
+            disabled: phi.ttf_str_sprite(text, "menu_font", 32, DISABLED_COLOR).unwrap(),
+        }
+    }
+
+    fn sprite(&self, enabled: bool, selected: bool) -> &Sprite {
+        if !enabled { &self.disabled }
+        else if selected { &self.hover }
+        else { &self.idle }
+    }
+}
+
+/// The themes the player unlocked by reaching their wave
+fn unlocked_themes() -> Vec<Theme> {
+    let best_wave = Leaderboard::load(GameMode::Arcade).best_wave();
+    themes::load_themes(themes::THEMES_PATH).into_iter()
+        .filter(|theme| theme.unlock_wave <= best_wave)
+        .collect()
+}
+
+/// Read a seed written in hexadecimal, as the menus and the high scores show
+/// it, with or without a leading 0x
+fn parse_seed(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let digits = if text.starts_with("0x") { &text[2..] } else { text };
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Define every menu, nested in the main one, with the labels showing the
+/// current settings
+fn menus(phi: &mut Phi) -> Menu {
+    let theme = phi.assets.theme().name.clone();
+    let language = phi.locale.name();
+    let ui_scale = if phi.settings.ui_scale > 0.0 { format!("{}x", phi.settings.ui_scale) }
+                   else { phi.locale.text("menu.ui_scale_auto") };
+    let palette = phi.locale.text(phi.palette().label_key());
+    let game_speed = phi.locale.format("menu.game_speed_value", &[&(phi.settings.game_speed() * 100.0).round()]);
+    let vibration = phi.locale.text(if phi.settings.vibration { "menu.on" } else { "menu.off" });
+    let has_replays = !replay::list().is_empty();
+    let has_themes = unlocked_themes().len() > 1;
+    let cleared = Progress::load(PROGRESS_PATH).cleared;
+    let practice_wave = phi.settings.practice_wave.max(1).min(FINAL_WAVE).to_string();
+    let practice_start = phi.locale.text(if phi.settings.practice_boss { "menu.practice_boss" }
+                                         else { "menu.practice_level" });
+    let practice_seed = format!("{:016x}", phi.settings.practice_seed);
+
+    let practice = Menu::new(phi, "menu.practice", vec![
+        Action::new(phi, "menu.practice_wave", &[], Choice::Change(Box::new(|phi, step| {
+            let current = phi.settings.practice_wave.max(1).min(FINAL_WAVE) - 1;
+            phi.settings.practice_wave = cycle(current as usize, FINAL_WAVE as usize, step) as u32 + 1;
+        }))).with_value(practice_wave),
+        Action::new(phi, "menu.practice_start", &[], Choice::Change(Box::new(|phi, _| {
+            phi.settings.practice_boss = !phi.settings.practice_boss;
+        }))).with_value(practice_start),
+        Action::new(phi, "menu.practice_seed", &[], Choice::Change(Box::new(|phi, _| {
+            // The seed is kept until another one is drawn, for the same
+            // waves to be played again and again
+            phi.settings.practice_seed = ::rand::random();
+        }))).with_value(practice_seed),
+        Action::new(phi, "menu.practice_copy_seed", &[], Choice::Run(Box::new(|phi, _| {
+            let seed = format!("{:016x}", phi.settings.practice_seed);
+            if !phi.set_clipboard_text(&seed) {
+                warn!("Could not copy the seed {} to the clipboard", seed);
+            }
+            ViewAction::None
+        }))),
+        Action::new(phi, "menu.practice_paste_seed", &[], Choice::Change(Box::new(|phi, _| {
+            // Take a seed copied from the high scores, or shared by someone
+            match phi.clipboard_text().and_then(|text| parse_seed(&text)) {
+                Some(seed) => phi.settings.practice_seed = seed,
+                None => shared::play_sound(REFUSED_SOUND),
+            }
+        }))),
+        Action::new(phi, "menu.practice_go", &[], Choice::Run(Box::new(|phi, backgrounds| {
+            ViewAction::ChangeView(Box::new(
+                ::views::game::GameView::new_practice(phi, backgrounds)))
+        }))),
+        Action::new(phi, "menu.back", &[], Choice::Back),
+    ]);
+
+    let video = Menu::new(phi, "menu.video", vec![
+        Action::new(phi, "menu.theme", &[], Choice::Change(Box::new(|phi, step| {
+            // Switch to another theme unlocked
+            let mut themes = unlocked_themes();
+            let current = themes.iter()
+                .position(|theme| theme.name == phi.assets.theme().name)
+                .unwrap_or(0);
+
+            if !themes.is_empty() {
+                let theme = themes.swap_remove(cycle(current, themes.len(), step));
+                phi.settings.theme = theme.name.clone();
+                phi.assets.set_theme(theme);
+            }
+        }))).with_value(theme).enabled(has_themes),
+        Action::new(phi, "menu.ui_scale", &[], Choice::Change(Box::new(|phi, step| {
+            let current = UI_SCALES.iter()
+                .position(|&scale| scale == phi.settings.ui_scale)
+                .unwrap_or(0);
+            phi.settings.ui_scale = UI_SCALES[cycle(current, UI_SCALES.len(), step)];
+        }))).with_value(ui_scale),
+        Action::new(phi, "menu.palette", &[], Choice::Change(Box::new(|phi, step| {
+            let palettes = Palette::all();
+            let current = palettes.iter()
+                .position(|&palette| palette == phi.palette())
+                .unwrap_or(0);
+            phi.settings.palette = palettes[cycle(current, palettes.len(), step)].name().to_string();
+        }))).with_value(palette),
+        Action::new(phi, "menu.back", &[], Choice::Back),
+    ]);
+
+    let options = Menu::new(phi, "menu.options", vec![
+        Action::new(phi, "menu.video", &[], Choice::Open(video)),
+        Action::new(phi, "menu.language", &[], Choice::Change(Box::new(|phi, step| {
+            // Switch to another language, and show every text in it
+            let mut languages = locale::languages();
+            let current = languages.iter()
+                .position(|language| *language == phi.locale.language)
+                .unwrap_or(0);
+
+            if !languages.is_empty() {
+                let language = languages.swap_remove(cycle(current, languages.len(), step));
+                phi.locale = Locale::load(&language);
+                phi.settings.language = language;
+            }
+        }))).with_value(language),
+        Action::new(phi, "menu.game_speed", &[], Choice::Change(Box::new(|phi, step| {
+            // Speed the game up or slow it down by a tenth, from full speed
+            // to the slowest and back
+            let current = GAME_SPEEDS.iter()
+                .position(|&tenths| tenths as f64 == (phi.settings.game_speed() * 10.0).round())
+                .unwrap_or(GAME_SPEEDS.len() - 1);
+            phi.settings.game_speed = GAME_SPEEDS[cycle(current, GAME_SPEEDS.len(), step)] as f64 / 10.0;
+        }))).with_value(game_speed),
+        Action::new(phi, "menu.controls", &[], Choice::Run(Box::new(|phi, backgrounds| {
+            ViewAction::ChangeView(Box::new(
+                ::views::controls::ControlsView::new_with_backgrounds(phi, backgrounds)))
+        }))),
+        Action::new(phi, "menu.vibration", &[], Choice::Change(Box::new(|phi, _| {
+            phi.settings.vibration = !phi.settings.vibration;
+        }))).with_value(vibration),
+        Action::new(phi, "menu.back", &[], Choice::Back),
+    ]);
+
+    Menu {
+        title: String::new(),
+        actions: vec![
+            Action::new(phi, "menu.new_game", &[], Choice::Run(Box::new(|phi, backgrounds| {
+                ViewAction::ChangeView(Box::new(
+                    ::views::game::GameView::new_with_backgrounds(phi, backgrounds)))
+            }))),
+            Action::new(phi, "menu.new_game_plus", &[], Choice::Run(Box::new(|phi, backgrounds| {
+                ViewAction::ChangeView(Box::new(
+                    ::views::game::GameView::new_plus(phi, backgrounds)))
+            }))).enabled(cleared),
+            Action::new(phi, "menu.practice", &[], Choice::Open(practice)),
+            Action::new(phi, "menu.host_lan", &[], Choice::Run(Box::new(|phi, backgrounds| {
+                ViewAction::ChangeView(Box::new(
+                    ::views::lan::LanView::new_with_backgrounds(
+                        phi, backgrounds, ::views::lan::Role::Host)))
+            }))),
+            Action::new(phi, "menu.join_lan", &[], Choice::Run(Box::new(|phi, backgrounds| {
+                ViewAction::ChangeView(Box::new(
+                    ::views::lan::LanView::new_with_backgrounds(
+                        phi, backgrounds, ::views::lan::Role::Join)))
+            }))),
+            Action::new(phi, "menu.high_scores", &[], Choice::Run(Box::new(|phi, backgrounds| {
+                ViewAction::ChangeView(Box::new(
+                    ::views::high_scores::HighScoresView::new_with_backgrounds(phi, backgrounds)))
+            }))),
+            Action::new(phi, "menu.watch_replay", &[], Choice::Run(Box::new(|phi, backgrounds| {
+                ViewAction::ChangeView(Box::new(
+                    ::views::playback::ReplayListView::new_with_backgrounds(phi, backgrounds)))
+            }))).enabled(has_replays),
+            Action::new(phi, "menu.options", &[], Choice::Open(options)),
+            Action::new(phi, "menu.quit", &[], Choice::Run(Box::new(|_, _| {
+                ViewAction::Quit
+            }))),
+        ],
+    }
+}
+
+/// The main menu, and the menus nested in it. Escape goes back to the menu
+/// the one shown is nested in, or quits from the main one.
+pub struct MainMenuView {
+    root: Menu,
+
+    // The positions of the actions which opened the menu shown, from the
+    // main one
+    path: Vec<usize>,
+    focus: Focus,
+
+    // The labels of the actions of the menu shown, with the values of the
+    // settings they change, and where it lies among the menus, as in
+    // "Options > Video"
+    labels: Vec<(Label, Option<Label>)>,
+    breadcrumb: Option<Sprite>,
+
+    // Shown under the main menu once the campaign was cleared
+    cleared: Option<Sprite>,
+
     backgrounds: Backgrounds,
 }
 
 impl MainMenuView {
     pub fn new(phi: &mut Phi) -> MainMenuView {
-        let bg = Backgrounds::new(&mut phi.renderer);
+        let bg = Backgrounds::new(phi);
         MainMenuView::new_with_backgrounds(phi, bg)
     }
 
     pub fn new_with_backgrounds(phi: &mut Phi, backgrounds: Backgrounds) -> MainMenuView {
-        MainMenuView {
-            actions: vec![
-                Action::new(phi, "New Game", Box::new(|phi, backgrounds| {
-                    ViewAction::ChangeView(Box::new(
-                        ::views::game::GameView::new_with_backgrounds(phi, backgrounds)))
-                })),
-                Action::new(phi, "Quit", Box::new(|_, _| {
-                    ViewAction::Quit
-                }))
-            ],
-
-            selected: 0,
-
+        let mut view = MainMenuView {
+            root: menus(phi),
+            path: vec![],
+            focus: Focus::new(0),
+            labels: vec![],
+            breadcrumb: None,
+            cleared: None,
             backgrounds: backgrounds,
+        };
+        if Progress::load(PROGRESS_PATH).cleared {
+            let text = phi.locale.text("menu.cleared");
+            view.cleared = phi.ttf_str_sprite(&text, "menu_font", 20, Color::RGB(240, 200, 40));
+        }
+        view.show(phi, vec![], 0);
+        view
+    }
+
+    /// Open the options, on the controls, as when coming back from them
+    pub fn options(phi: &mut Phi, backgrounds: Backgrounds) -> MainMenuView {
+        let mut view = MainMenuView::new_with_backgrounds(phi, backgrounds);
+        view.show(phi, vec![OPTIONS_ACTION], CONTROLS_ACTION);
+        view
+    }
+
+    /// Return the menu at the end of 'path'
+    fn menu_at<'a>(&'a self, path: &[usize]) -> &'a Menu {
+        path.iter().fold(&self.root, |menu, &i| match menu.actions[i].choice {
+            Choice::Open(ref submenu) => submenu,
+            _ => menu,
+        })
+    }
+
+    /// Show the menu at the end of 'path', with the action 'focused' selected
+    fn show(&mut self, phi: &mut Phi, path: Vec<usize>, focused: usize) {
+        self.path = path;
+
+        let (labels, focus, titles) = {
+            let menu = self.menu_at(&self.path);
+            let labels = menu.actions.iter().map(|action| {
+                (Label::new(phi, &action.label),
+                 action.value.as_ref().map(|value| Label::new(phi, value)))
+            }).collect::<Vec<_>>();
+
+            let mut focus = Focus::new(menu.actions.len());
+            for (i, action) in menu.actions.iter().enumerate() {
+                focus.set_enabled(i, action.enabled);
+            }
+            if menu.actions.get(focused).map_or(false, |action| action.enabled) {
+                focus.focus(focused);
+            }
+
+            let titles = (0..self.path.len())
+                .map(|depth| self.menu_at(&self.path[..depth + 1]).title.clone())
+                .collect::<Vec<_>>();
+            (labels, focus, titles)
+        };
+
+        self.labels = labels;
+        self.focus = focus;
+        self.breadcrumb = titles.into_iter()
+            .fold(None, |crumb: Option<String>, title| Some(match crumb {
+                Some(crumb) => phi.locale.format("menu.breadcrumb", &[&crumb, &title]),
+                None => title,
+            }))
+            .and_then(|crumb| phi.ttf_str_sprite(&crumb, "menu_font", 24, Color::RGB(200, 200, 200)));
+    }
+
+    /// Change the setting of the action 'selected' of the menu shown by
+    /// 'step', if it changes one, and show its new value
+    fn change(&mut self, phi: &mut Phi, selected: usize, step: i32) {
+        let path = self.path.clone();
+        match self.menu_at(&path).actions[selected].choice {
+            Choice::Change(ref func) => func(phi, step),
+            _ => return,
+        }
+
+        if let Err(err) = phi.settings.save(SETTINGS_PATH) {
+            warn!("Could not save the settings: {}", err);
+        }
+
+        // Show the setting changed, in the language, colors and size it may
+        // now have, with the backgrounds where they had scrolled to
+        self.root = menus(phi);
+        let mut backgrounds = Backgrounds::new(phi);
+        backgrounds.back.pos = self.backgrounds.back.pos;
+        backgrounds.middle.pos = self.backgrounds.middle.pos;
+        backgrounds.front.pos = self.backgrounds.front.pos;
+        self.backgrounds = backgrounds;
+        self.show(phi, path, selected);
+    }
+
+    /// Go back to the menu the one shown is nested in, on the action which
+    /// opened it
+    fn back(&mut self, phi: &mut Phi) {
+        let mut path = self.path.clone();
+        if let Some(opened) = path.pop() {
+            self.show(phi, path, opened);
         }
     }
 }
 
 impl View for MainMenuView {
+    fn resume(&mut self, phi: &mut Phi) {
+        shared::show_menu_cursor(phi);
+        // Keep scrolling through the actions while a direction is held
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
     fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
-        if phi.events.now.quit || phi.events.now.key_escape == Some(true) {
+        if phi.events.now.quit {
             return ViewAction::Quit;
         }
+        if self.path.is_empty() {
+            if phi.events.now.key_escape == Some(true) {
+                return ViewAction::Quit;
+            }
+        }
+        else if self.focus.cancelled(phi) {
+            self.back(phi);
+        }
 
-        // Exceute the currently selected option
-        if phi.events.now.key_space == Some(true) || phi.events.now.key_return == Some(true) {
-            // Use Rust functor syntax
-            let bg = self.backgrounds.clone();
-            return (self.actions[self.selected as usize].func)(phi, bg);
+        // Jump to any view while working on it
+        if phi.settings.debug_tools && phi.events.now.key_f2 == Some(true) {
+            return ViewAction::ChangeView(Box::new(
+                ::views::registry::ViewPickerView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())));
         }
 
-        // Change the selected action using the keyboard
-        if phi.events.now.key_up == Some(true) {
-            self.selected -= 1;
-            // Wrap around
-            if self.selected < 0 {
-                self.selected = self.actions.len() as i8 - 1;
-            }
+        // Change the selected action, then exceute it if chosen, or change
+        // the setting it shows with the arrows
+        self.focus.navigate(phi);
+        if self.focus.refused(phi) {
+            shared::play_sound(REFUSED_SOUND);
+        }
+
+        let step = self.focus.adjustment(phi);
+        if step != 0 {
+            let selected = self.focus.index();
+            self.change(phi, selected, step);
         }
-        if phi.events.now.key_down == Some(true) {
-            self.selected += 1;
-            // Wrap around
-            if self.selected >= self.actions.len() as i8 {
-                self.selected = 0;
+        else if self.focus.activated(phi) {
+            let selected = self.focus.index();
+            let path = self.path.clone();
+
+            let (changed, back) = match self.menu_at(&path).actions[selected].choice {
+                Choice::Run(ref func) => return func(phi, self.backgrounds.clone()),
+                Choice::Change(_) => (true, false),
+                Choice::Open(_) => (false, false),
+                Choice::Back => (false, true),
+            };
+
+            if changed {
+                self.change(phi, selected, 1);
+            }
+            else if back {
+                self.back(phi);
+            }
+            else {
+                let mut opened = path;
+                opened.push(selected);
+                self.show(phi, opened, 0);
             }
         }
 
@@ -99,49 +495,95 @@ impl View for MainMenuView {
 
         // Render the labels in the menu
         let (win_w, win_h) = phi.output_size();
-        let label_h = 50.0;
-        let border_width=3.0;
-        let box_w = 360.0;
-        let box_h = self.actions.len() as f64 * label_h;
-        let margin_h = 10.0;
+        let label_h = phi.scaled(50.0);
+        let border_width = phi.scaled(3.0);
+        let margin_h = phi.scaled(10.0);
+        let box_h = self.labels.len() as f64 * label_h;
+
+        // Wide enough for the longest label and its value, which depend on
+        // the language
+        let box_w = self.labels.iter()
+            .map(|&(ref label, ref value)| {
+                label.idle.size().0 + value.as_ref().map_or(0.0, |value| value.idle.size().0 + margin_h * 4.0)
+                    + margin_h * 4.0
+            })
+            .fold(phi.scaled(360.0), f64::max);
 
         // Render the border of the colored box which holds the labels
-        phi.renderer.set_draw_color(Color::RGB(70, 15, 70));
-        phi.renderer.fill_rect(Rectangle {
+        let radius = phi.scaled(10.0);
+        shapes::fill_rounded_rect(&mut phi.renderer, Rectangle {
             w: box_w + border_width * 2.0,
             h: box_h + border_width * 2.0 + margin_h * 2.0,
             x: (win_w - box_w) / 2.0 - border_width,
             y: (win_h - box_h) / 2.0 - margin_h - border_width,
-        }.to_sdl().unwrap());
+        }, radius, Color::RGB(70, 15, 70));
 
         // Render the colored box which holds the labels
-        phi.renderer.set_draw_color(Color::RGB(140, 30, 140));
-        phi.renderer.fill_rect(Rectangle {
+        shared::render_menu_box(phi, Rectangle {
             w: box_w,
             h: box_h + margin_h * 2.0,
             x: (win_w - box_w) / 2.0,
             y: (win_h - box_h) / 2.0 - margin_h,
-        }.to_sdl().unwrap());
+        }, Color::RGB(160, 40, 160));
 
-        for (i, action) in self.actions.iter().enumerate() {
-            if self.selected as usize == i {
-                let (w, h) = action.hover_sprite.size();
-                //phi.renderer.copy_sprite(&action.idle_sprite, Rectangle {
-                action.hover_sprite.render(&mut phi.renderer, Rectangle {
+        // Render where the menu lies among the others, over the box
+        if let Some(ref breadcrumb) = self.breadcrumb {
+            let (w, h) = breadcrumb.size();
+            breadcrumb.render(&mut phi.renderer, Rectangle {
+                w: w,
+                h: h,
+                x: (win_w - w) / 2.0,
+                y: (win_h - box_h) / 2.0 - margin_h * 2.0 - border_width - h,
+            });
+        }
+
+        // The mouse picks an action anywhere on its line of the box
+        self.focus.set_layout((0..self.labels.len()).map(|i| Rectangle {
+            x: (win_w - box_w) / 2.0,
+            y: (win_h - box_h) / 2.0 + label_h * i as f64,
+            w: box_w,
+            h: label_h,
+        }).collect(), (0.0, 0.0), None);
+
+        // Labels are centered, unless some show values, which are then
+        // aligned to the right of the box and the labels to the left
+        let with_values = self.labels.iter().any(|&(_, ref value)| value.is_some());
+        let (box_left, box_right) = ((win_w - box_w) / 2.0 + margin_h * 2.0, (win_w + box_w) / 2.0 - margin_h * 2.0);
+
+        for (i, &(ref label, ref value)) in self.labels.iter().enumerate() {
+            let (enabled, selected) = (self.focus.is_enabled(i), self.focus.index() == i);
+            let y = (win_h - box_h) / 2.0 + label_h * i as f64;
+
+            let sprite = label.sprite(enabled, selected);
+            let (w, h) = sprite.size();
+            sprite.render(&mut phi.renderer, Rectangle {
+                w: w,
+                h: h,
+                x: if with_values { box_left } else { (win_w - w) / 2.0 },
+                y: y + (label_h - h) / 2.0,
+            });
+
+            if let Some(ref value) = *value {
+                let sprite = value.sprite(enabled, selected);
+                let (w, h) = sprite.size();
+                sprite.render(&mut phi.renderer, Rectangle {
                     w: w,
                     h: h,
-                    x: (win_w - w) / 2.0,
-                    y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
+                    x: box_right - w,
+                    y: y + (label_h - h) / 2.0,
                 });
             }
-            else {
-                let (w, h) = action.idle_sprite.size();
-                //phi.renderer.copy_sprite(&action.idle_sprite, Rectangle {
-                action.idle_sprite.render(&mut phi.renderer, Rectangle {
+        }
+
+        // Render the completion marker under the main menu
+        if let Some(ref cleared) = self.cleared {
+            if self.path.is_empty() {
+                let (w, h) = cleared.size();
+                cleared.render(&mut phi.renderer, Rectangle {
                     w: w,
                     h: h,
                     x: (win_w - w) / 2.0,
-                    y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
+                    y: (win_h + box_h) / 2.0 + margin_h * 2.0 + border_width,
                 });
             }
         }
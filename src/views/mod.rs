@@ -1,8 +1,41 @@
 // views/mod.rs
 
+pub mod banners;
+pub mod benchmark;
+pub mod boss;
+pub mod bullets;
+pub mod combo;
+pub mod controls;
+pub mod credits;
+pub mod damage_numbers;
+pub mod determinism;
+pub mod drones;
+pub mod editor;
+pub mod enemies;
 pub mod game;
+pub mod hazards;
+pub mod high_scores;
+pub mod hud;
+pub mod lan;
+pub mod leaderboard;
+pub mod loading;
 pub mod main_menu;
+pub mod parallax;
+pub mod pause;
+pub mod pickups;
+pub mod playback;
+pub mod practice;
+pub mod progress;
+pub mod registry;
+pub mod replay;
+#[cfg(feature = "test-scenes")]
+pub mod scenes;
+pub mod scripting;
 pub mod shared;
+pub mod splash;
+pub mod stats;
+pub mod terrain;
+pub mod timeline;
 
 /*
 use ::std::path::Path;
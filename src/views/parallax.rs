@@ -0,0 +1,263 @@
+// views/parallax.rs
+
+use ::sdl2::keyboard::Keycode;
+use ::sdl2::pixels::Color;
+use ::sdl2::render::BlendMode;
+
+use ::phi::data::Rectangle;
+use ::phi::focus::Focus;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+
+use ::views::shared::{self, BackgroundSet, Backgrounds};
+
+/// How much the arrows change the values of a set
+const SPEED_STEP: f64 = 5.0;
+const OFFSET_STEP: f64 = 10.0;
+const TINT_STEP: i32 = 15;
+
+const ROW_H: f64 = 28.0;
+
+/// A value of a background set which may be tuned
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    /// The speed and the offset of the back, middle or front layer
+    Speed(usize),
+    Offset(usize),
+
+    /// The red, green or blue channel of the tint
+    Tint(usize),
+}
+
+const LAYER_KEYS: [&'static str; 3] = ["parallax.back", "parallax.middle", "parallax.front"];
+const CHANNEL_KEYS: [&'static str; 3] = ["parallax.red", "parallax.green", "parallax.blue"];
+
+/// Lets the speeds, offsets and tint of the background sets be tuned while
+/// they scroll, and written back to their data file. Opened from the level
+/// editor, over which it is pushed.
+pub struct ParallaxView {
+    sets: Vec<BackgroundSet>,
+    current: usize,
+    modified: bool,
+
+    backgrounds: Backgrounds,
+
+    rows: Vec<Row>,
+    focus: Focus,
+
+    title: Sprite,
+    labels: Vec<Sprite>,
+    help: Sprite,
+}
+
+impl ParallaxView {
+    /// Tune the set called 'name', or the default one if there is no such
+    /// set
+    pub fn new(phi: &mut Phi, name: &str) -> ParallaxView {
+        let sets = shared::load_background_sets(shared::BACKGROUNDS_PATH);
+        let current = sets.iter().position(|set| set.name == name).unwrap_or(0);
+        let backgrounds = Backgrounds::from_set(phi, &sets[current]);
+
+        let mut rows = vec![];
+        for layer in 0..3 {
+            rows.push(Row::Speed(layer));
+            rows.push(Row::Offset(layer));
+        }
+        for channel in 0..3 {
+            rows.push(Row::Tint(channel));
+        }
+
+        let help = phi.locale.text("parallax.help");
+
+        let mut view = ParallaxView {
+            sets: sets,
+            current: current,
+            modified: false,
+            backgrounds: backgrounds,
+            focus: Focus::new(rows.len()),
+            rows: rows,
+            title: phi.ttf_str_sprite(" ", "menu_font", 24, Color::RGB(255, 255, 255)).unwrap(),
+            labels: vec![],
+            help: phi.ttf_str_sprite(&help, "menu_font", 16, Color::RGB(200, 200, 200)).unwrap(),
+        };
+        view.refresh(phi);
+        view
+    }
+
+    /// Render the title and the rows again, after a value changed or
+    /// another row was selected
+    fn refresh(&mut self, phi: &mut Phi) {
+        let set = &self.sets[self.current];
+        let modified = if self.modified { "*" } else { "" };
+        let title = phi.locale.format("parallax.title", &[&set.name, &modified]);
+        self.title = phi.ttf_str_sprite(&title, "menu_font", 24, Color::RGB(255, 255, 255)).unwrap();
+
+        let layers = [&set.back, &set.middle, &set.front];
+        let (r, g, b) = set.tint.rgb();
+        let channels = [r, g, b];
+
+        self.labels = self.rows.iter().enumerate().map(|(i, &row)| {
+            let text = match row {
+                Row::Speed(layer) => {
+                    let name = phi.locale.text(LAYER_KEYS[layer]);
+                    phi.locale.format("parallax.speed", &[&name, &layers[layer].speed])
+                },
+                Row::Offset(layer) => {
+                    let name = phi.locale.text(LAYER_KEYS[layer]);
+                    phi.locale.format("parallax.offset", &[&name, &layers[layer].offset])
+                },
+                Row::Tint(channel) => {
+                    let name = phi.locale.text(CHANNEL_KEYS[channel]);
+                    phi.locale.format("parallax.tint", &[&name, &channels[channel]])
+                },
+            };
+            let color = if i == self.focus.index() { Color::RGB(240, 200, 40) }
+                        else { Color::RGB(220, 220, 220) };
+            phi.ttf_str_sprite(&text, "menu_font", 20, color).unwrap()
+        }).collect();
+    }
+
+    /// Change the selected value by 'steps' steps
+    fn adjust(&mut self, steps: i32) {
+        let set = &mut self.sets[self.current];
+        match self.rows[self.focus.index()] {
+            Row::Speed(layer) => {
+                let layer = set.layer_mut(layer);
+                layer.speed = (layer.speed + SPEED_STEP * steps as f64).max(0.0);
+            },
+            Row::Offset(layer) => {
+                set.layer_mut(layer).offset += OFFSET_STEP * steps as f64;
+            },
+            Row::Tint(channel) => {
+                let (r, g, b) = set.tint.rgb();
+                let mut channels = [r, g, b];
+                channels[channel] = (channels[channel] as i32 + TINT_STEP * steps).max(0).min(255) as u8;
+                set.tint = Color::RGB(channels[0], channels[1], channels[2]);
+            },
+        }
+        self.modified = true;
+    }
+
+    /// Show the current set, where the previous backgrounds had scrolled to
+    fn rebuild(&mut self, phi: &mut Phi) {
+        let mut backgrounds = Backgrounds::from_set(phi, &self.sets[self.current]);
+        backgrounds.back.pos = self.backgrounds.back.pos;
+        backgrounds.middle.pos = self.backgrounds.middle.pos;
+        backgrounds.front.pos = self.backgrounds.front.pos;
+        self.backgrounds = backgrounds;
+    }
+}
+
+impl View for ParallaxView {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.3,
+            rate: 12.0,
+        }));
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        if phi.events.now.key_escape == Some(true) {
+            if self.modified {
+                warn!("Leaving the background editor without saving {}", shared::BACKGROUNDS_PATH);
+            }
+            return ViewAction::PopView;
+        }
+
+        let mut changed = self.focus.navigate(phi);
+
+        let steps = self.focus.adjustment(phi);
+        if steps != 0 {
+            self.adjust(steps);
+            self.rebuild(phi);
+            changed = true;
+        }
+
+        if phi.events.pressed(Keycode::Tab) {
+            self.current = (self.current + 1) % self.sets.len();
+            self.rebuild(phi);
+            changed = true;
+        }
+
+        if phi.events.pressed(Keycode::S) {
+            match shared::save_background_sets(shared::BACKGROUNDS_PATH, &self.sets) {
+                Ok(()) => {
+                    info!("Saved the backgrounds {}", shared::BACKGROUNDS_PATH);
+                    self.modified = false;
+                },
+                Err(err) => warn!("Could not save the backgrounds {}: {}",
+                                  shared::BACKGROUNDS_PATH, err),
+            }
+            changed = true;
+        }
+
+        if changed {
+            self.refresh(phi);
+        }
+
+        // Clear the screen . . .
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds as they are tuned
+        self.backgrounds.back.render(&mut phi.renderer, elapsed);
+        self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+        self.backgrounds.front.render(&mut phi.renderer, elapsed);
+
+        // Render the title and the rows over a dark panel, and the help at
+        // the bottom
+        let (_, win_h) = phi.output_size();
+        let margin = phi.scaled(10.0);
+        let row_h = phi.scaled(ROW_H);
+        let panel_w = self.labels.iter().map(|label| label.size().0)
+            .fold(self.title.size().0, f64::max) + margin * 2.0;
+
+        phi.renderer.set_blend_mode(BlendMode::Blend);
+        phi.renderer.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        phi.renderer.fill_rect(Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: panel_w,
+            h: margin * 2.0 + row_h * (self.labels.len() + 1) as f64,
+        }.to_sdl().unwrap());
+        phi.renderer.set_blend_mode(BlendMode::None);
+
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle {
+            x: margin,
+            y: margin,
+            w: w,
+            h: h,
+        });
+        self.focus.set_layout((0..self.labels.len()).map(|i| Rectangle {
+            x: 0.0,
+            y: margin + row_h * (i + 1) as f64,
+            w: panel_w,
+            h: row_h,
+        }).collect(), (0.0, 0.0), None);
+
+        for (i, label) in self.labels.iter().enumerate() {
+            let (w, h) = label.size();
+            label.render(&mut phi.renderer, Rectangle {
+                x: margin,
+                y: margin + row_h * (i + 1) as f64,
+                w: w,
+                h: h,
+            });
+        }
+
+        let (w, h) = self.help.size();
+        self.help.render(&mut phi.renderer, Rectangle {
+            x: margin,
+            y: win_h - h - margin,
+            w: w,
+            h: h,
+        });
+
+        ViewAction::None
+    }
+}
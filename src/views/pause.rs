@@ -0,0 +1,149 @@
+// views/pause.rs
+
+use ::std::cell::Cell;
+use ::std::rc::Rc;
+use ::sdl2::pixels::Color;
+
+use ::phi::data::Rectangle;
+use ::phi::focus::Focus;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+
+use ::views::shared::{self, DISABLED_COLOR, REFUSED_SOUND};
+
+/// The player whose controller may close the menu
+const PLAYER_ID: usize = 0;
+
+const LABEL_H: f64 = 50.0;
+
+/// How much the game under the menu is dimmed
+const PAUSE_DIM: f64 = 0.65;
+
+/// The positions of the entries of the menu
+const RESTART_ENTRY: usize = 1;
+
+/// The menu shown over the game while it is paused. The game stays in the
+/// view stack under it, and is drawn frozen and dimmed behind the menu.
+pub struct PauseView {
+    title: Sprite,
+
+    // Set to ask the game to start the current wave over once it resumes
+    restart: Rc<Cell<bool>>,
+
+    // The labels of the entries, idle, selected, then disabled
+    entries: Vec<(Sprite, Sprite, Sprite)>,
+    focus: Focus,
+}
+
+impl PauseView {
+    /// Pause the game, which may only be asked to start the wave over if
+    /// 'can_restart'
+    pub fn new(phi: &mut Phi, restart: Rc<Cell<bool>>, can_restart: bool) -> PauseView {
+        let title = phi.locale.text("title.paused");
+        let entries: Vec<(Sprite, Sprite, Sprite)> = ["pause.resume", "pause.restart_wave", "pause.quit"].iter().map(|key| {
+            let label = phi.locale.text(key);
+            (phi.ttf_str_sprite(&label, "menu_font", 32, Color::RGB(220, 220, 220)).unwrap(),
+             phi.ttf_str_sprite(&label, "menu_font", 32, Color::RGB(240, 200, 40)).unwrap(),
+             phi.ttf_str_sprite(&label, "menu_font", 32, DISABLED_COLOR).unwrap())
+        }).collect();
+
+        // The game ignores restarts while it offers to continue
+        let mut focus = Focus::new(entries.len());
+        focus.set_enabled(RESTART_ENTRY, can_restart);
+
+        PauseView {
+            title: phi.ttf_str_sprite(&title, "menu_font", 40, Color::RGB(255, 255, 255)).unwrap(),
+            restart: restart,
+            entries: entries,
+            focus: focus,
+        }
+    }
+}
+
+impl View for PauseView {
+    fn resume(&mut self, phi: &mut Phi) {
+        shared::show_menu_cursor(phi);
+        let paused = phi.locale.text("title.paused");
+        phi.set_title_status(Some(&paused));
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
+    fn pause(&mut self, phi: &mut Phi) {
+        phi.set_title_status(None);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+
+    fn render(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        let pause = phi.pad_bindings(PLAYER_ID).pause;
+        if self.focus.cancelled(phi) || phi.events.pad_pressed(PLAYER_ID, pause) {
+            return ViewAction::PopView;
+        }
+
+        self.focus.navigate(phi);
+        if self.focus.refused(phi) {
+            shared::play_sound(REFUSED_SOUND);
+        }
+        if self.focus.activated(phi) {
+            return match self.focus.index() {
+                0 => ViewAction::PopView,
+                RESTART_ENTRY => {
+                    self.restart.set(true);
+                    ViewAction::PopView
+                },
+                _ => ViewAction::ChangeView(Box::new(
+                    ::views::main_menu::MainMenuView::new(phi))),
+            };
+        }
+
+        // Dim the frozen game under the menu
+        phi.overlay.dim(PAUSE_DIM);
+        let (win_w, win_h) = phi.output_size();
+
+        // Render the title, then the entries under it
+        let label_h = phi.scaled(LABEL_H);
+        let top = (win_h - label_h * (self.entries.len() + 1) as f64) / 2.0;
+
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle {
+            x: (win_w - w) / 2.0,
+            y: top + (label_h - h) / 2.0,
+            w: w,
+            h: h,
+        });
+
+        // The mouse picks an entry anywhere on its line, as wide as the
+        // widest entry
+        let entries_w = self.entries.iter().map(|&(ref idle, _, _)| idle.size().0).fold(0.0, f64::max);
+        self.focus.set_layout((0..self.entries.len()).map(|i| Rectangle {
+            x: (win_w - entries_w) / 2.0,
+            y: top + label_h * (i + 1) as f64,
+            w: entries_w,
+            h: label_h,
+        }).collect(), (0.0, 0.0), None);
+
+        for (i, &(ref idle, ref hover, ref disabled)) in self.entries.iter().enumerate() {
+            let label = if !self.focus.is_enabled(i) { disabled }
+                        else if i == self.focus.index() { hover }
+                        else { idle };
+            let (w, h) = label.size();
+            label.render(&mut phi.renderer, Rectangle {
+                x: (win_w - w) / 2.0,
+                y: top + label_h * (i + 1) as f64 + (label_h - h) / 2.0,
+                w: w,
+                h: h,
+            });
+        }
+
+        ViewAction::None
+    }
+}
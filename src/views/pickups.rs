@@ -0,0 +1,153 @@
+// views/pickups.rs
+
+use ::rand::Rng;
+use ::sdl2::pixels::Color;
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::random::Distributions;
+use ::phi::trail::Trail;
+
+/// Pixels travelled to the left every second by a pickup
+const PICKUP_SPEED: f64 = 60.0;
+
+pub const PICKUP_SIDE: f64 = 18.0;
+
+/// The probability that a destroyed enemy drops a pickup
+const DROP_CHANCE: f64 = 0.2;
+
+/// Seconds during which a magnet attracts the pickups
+pub const MAGNET_DURATION: f64 = 8.0;
+
+/// Distance from the ship within which a magnet attracts the pickups
+const MAGNET_RADIUS: f64 = 250.0;
+
+/// Pixels travelled every second by a pickup attracted by a magnet
+const MAGNET_SPEED: f64 = 320.0;
+
+/// Seconds during which bullet time slows the world down
+pub const BULLET_TIME_DURATION: f64 = 5.0;
+
+/// The bonus granted by a pickup once collected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PickupKind {
+    /// A drone which escorts the ship
+    Drone,
+
+    /// Attracts the other pickups toward the ship for a while
+    Magnet,
+
+    /// Slows down everything but the ship for a while
+    BulletTime,
+}
+
+impl PickupKind {
+    /// Pick a kind at random
+    fn random<R: Rng>(rng: &mut R) -> PickupKind {
+        match rng.index(3) {
+            0 => PickupKind::Drone,
+            1 => PickupKind::Magnet,
+            _ => PickupKind::BulletTime,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PickupKind::Drone => Color::RGB(90, 220, 120),
+            PickupKind::Magnet => MAGNET_COLOR,
+            PickupKind::BulletTime => Color::RGB(170, 120, 240),
+        }
+    }
+}
+
+/// The color of magnet pickups, also used to show the magnet's timer
+pub const MAGNET_COLOR: Color = Color::RGB(220, 60, 60);
+
+/// A bonus floating across the screen, collected by flying into it
+#[derive(Clone)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    rect: Rectangle,
+
+    // Seconds since the pickup appeared, used to make it blink
+    total_time: f64,
+
+    // Whether a magnet pulled the pickup toward the ship this frame, in
+    // which case it leaves a trail
+    attracted: bool,
+    trail: Trail,
+}
+
+impl Pickup {
+    pub fn new(kind: PickupKind, center: (f64, f64)) -> Pickup {
+        Pickup {
+            kind: kind,
+            rect: Rectangle {
+                x: 0.0,
+                y: 0.0,
+                w: PICKUP_SIDE,
+                h: PICKUP_SIDE,
+            }.with_center(center),
+            total_time: 0.0,
+            attracted: false,
+            trail: Trail::new(0.03, 0.15),
+        }
+    }
+
+    /// Create a pickup of a random kind at 'center'
+    pub fn random<R: Rng>(rng: &mut R, center: (f64, f64)) -> Pickup {
+        Pickup::new(PickupKind::random(rng), center)
+    }
+
+    /// Randomly create the pickup dropped by an enemy destroyed at 'center'
+    pub fn random_drop<R: Rng>(rng: &mut R, center: (f64, f64)) -> Option<Pickup> {
+        if rng.chance(DROP_CHANCE) {
+            Some(Pickup::random(rng, center))
+        }
+        else {
+            None
+        }
+    }
+
+    /// Move the pickup
+    pub fn update(&mut self, dt: f64) {
+        self.rect.x -= PICKUP_SPEED * dt;
+        self.total_time += dt;
+
+        self.trail.set_active(self.attracted);
+        self.trail.update(self.rect, dt);
+        self.attracted = false;
+    }
+
+    /// Move the pickup toward 'target' if it is within the reach of a
+    /// magnet
+    pub fn attract(&mut self, target: (f64, f64), dt: f64) {
+        let (cx, cy) = self.rect.center();
+        let (dx, dy) = (target.0 - cx, target.1 - cy);
+        let dist = (dx * dx + dy * dy).sqrt();
+
+        if dist > 0.0 && dist < MAGNET_RADIUS {
+            // Never overshoot the target
+            let step = (MAGNET_SPEED * dt).min(dist);
+            self.rect.x += dx / dist * step;
+            self.rect.y += dy / dist * step;
+            self.attracted = true;
+        }
+    }
+
+    pub fn rect(&self) -> Rectangle {
+        self.rect
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        let color = phi.palette_color(self.kind.color());
+        self.trail.render_fill(&mut phi.renderer, color);
+
+        // A pulsing square, so that pickups stand out from the hazards
+        let pulse = 0.85 + 0.15 * (self.total_time * 8.0).sin();
+        phi.renderer.set_draw_color(Color::RGB(255, 255, 255));
+        phi.renderer.fill_rect(self.rect.scale_about_center(pulse).to_sdl().unwrap());
+        phi.renderer.set_draw_color(color);
+        phi.renderer.fill_rect(self.rect.scale_about_center(pulse * 0.7).to_sdl().unwrap());
+    }
+}
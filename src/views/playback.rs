@@ -0,0 +1,429 @@
+// views/playback.rs
+
+use ::sdl2::keyboard::Keycode;
+use ::sdl2::pixels::Color;
+
+use ::phi::data::Rectangle;
+use ::phi::focus::Focus;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::prompts::Prompt;
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+
+use ::views::game::GameView;
+use ::views::replay::{self, Replay};
+use ::views::shared::{self, Backgrounds};
+
+/// The number of replays listed at once
+const ROWS_SHOWN: usize = 10;
+const ROW_H: f64 = 30.0;
+
+/// The speeds at which a replay can be watched, in turn
+const SPEEDS: [f64; 3] = [1.0, 2.0, 4.0];
+
+/// Seconds skipped by the left and right arrows
+const SKIP: f64 = 5.0;
+
+/// The height of the scrub bar, and its margin to the edges of the screen,
+/// before the UI scale is applied
+const BAR_H: f64 = 12.0;
+const BAR_MARGIN: f64 = 20.0;
+
+/// Lists the runs saved at game over, the latest first, to pick one to
+/// watch
+pub struct ReplayListView {
+    backgrounds: Backgrounds,
+
+    paths: Vec<String>,
+    focus: Focus,
+
+    title: Sprite,
+    rows: Vec<Sprite>,
+    help: Prompt,
+}
+
+impl ReplayListView {
+    pub fn new_with_backgrounds(phi: &mut Phi, backgrounds: Backgrounds) -> ReplayListView {
+        let title = phi.locale.text("replays.title");
+        let help = phi.locale.text("replays.help");
+
+        // Replays which tell no run cannot be played back, and are left out
+        let mut paths = vec![];
+        let mut entries = vec![];
+        for path in replay::list() {
+            if let Some(entry) = Replay::load_entry(&path) {
+                paths.push(path);
+                entries.push(entry);
+            }
+        }
+
+        let mut view = ReplayListView {
+            backgrounds: backgrounds,
+            focus: Focus::new(paths.len()),
+            paths: paths,
+            title: phi.ttf_str_sprite(&title, "menu_font", 32, Color::RGB(255, 255, 255)).unwrap(),
+            rows: vec![],
+            help: Prompt::new(&help, "menu_font", 16, Color::RGB(200, 200, 200)),
+        };
+
+        view.rows = entries.iter().map(|entry| {
+            let text = phi.locale.format("replays.entry", &[
+                &entry.date_string(), &entry.name, &entry.score, &entry.wave]);
+            phi.ttf_str_sprite(&text, "menu_font", 20, Color::RGB(230, 230, 230)).unwrap()
+        }).collect();
+        if view.rows.is_empty() {
+            let empty = phi.locale.text("replays.empty");
+            view.rows.push(phi.ttf_str_sprite(&empty, "menu_font", 20,
+                                              Color::RGB(180, 180, 180)).unwrap());
+        }
+
+        view
+    }
+}
+
+impl View for ReplayListView {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+        if self.focus.cancelled(phi) {
+            return ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())));
+        }
+
+        self.focus.navigate(phi);
+        if self.focus.activated(phi) {
+            let path = &self.paths[self.focus.index()];
+            match Replay::load(path) {
+                Some(replay) => return ViewAction::ChangeView(Box::new(
+                    PlaybackView::new(phi, self.backgrounds.clone(), replay))),
+                None => warn!("Could not watch the replay {}", path),
+            }
+        }
+
+        // Clear the screen . . .
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.backgrounds.back.render(&mut phi.renderer, elapsed);
+        self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+        self.backgrounds.front.render(&mut phi.renderer, elapsed);
+
+        // Render the box which holds the list
+        let (win_w, win_h) = phi.output_size();
+        let row_h = phi.scaled(ROW_H);
+        let padding = phi.scaled(20.0);
+        let list_w = self.rows.iter().map(|row| row.size().0)
+            .fold(self.title.size().0, f64::max);
+        let list_h = row_h * (ROWS_SHOWN + 2) as f64;
+        let left = (win_w - list_w) / 2.0;
+        let top = (win_h - list_h) / 2.0;
+
+        shared::render_menu_box(phi, Rectangle {
+            x: left - padding,
+            y: top - padding,
+            w: list_w + padding * 2.0,
+            h: list_h + padding * 2.0,
+        }, Color::RGB(80, 20, 80));
+
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle {
+            x: (win_w - w) / 2.0,
+            y: top,
+            w: w,
+            h: h,
+        });
+
+        // Scroll the list so that the selected replay stays in sight
+        let first = (self.focus.index() + 1).saturating_sub(ROWS_SHOWN);
+        self.focus.set_layout((0..self.paths.len()).map(|i| Rectangle {
+            x: left - padding / 2.0,
+            y: top + row_h * (i + 1) as f64,
+            w: list_w + padding,
+            h: row_h,
+        }).collect(), (0.0, row_h * first as f64), Some(Rectangle {
+            x: left - padding / 2.0,
+            y: top + row_h,
+            w: list_w + padding,
+            h: row_h * ROWS_SHOWN as f64,
+        }));
+
+        for (i, row) in self.rows.iter().enumerate().skip(first).take(ROWS_SHOWN) {
+            let y = top + row_h * (i - first + 1) as f64;
+            if i == self.focus.index() && !self.paths.is_empty() {
+                phi.renderer.set_draw_color(Color::RGB(140, 30, 140));
+                phi.renderer.fill_rect(Rectangle {
+                    x: left - padding / 2.0,
+                    y: y,
+                    w: list_w + padding,
+                    h: row_h,
+                }.to_sdl().unwrap());
+            }
+
+            let (w, h) = row.size();
+            row.render(&mut phi.renderer, Rectangle {
+                x: left,
+                y: y + (row_h - h) / 2.0,
+                w: w,
+                h: h,
+            });
+        }
+
+        let (w, h) = self.help.size(phi);
+        self.help.render(phi, ((win_w - w) / 2.0, top + list_h - h));
+
+        ViewAction::None
+    }
+}
+
+/// Plays a saved run back, frame after frame, over the seed it was played
+/// with. The replay may be paused, fast-forwarded and scrubbed through;
+/// going back in time plays the run again from its start, without drawing
+/// it, up to the frame sought.
+pub struct PlaybackView {
+    replay: Replay,
+    backgrounds: Backgrounds,
+
+    // The run as it is played back, the index of its next frame, and the
+    // seconds of the replay due to be played
+    game: GameView,
+    next: usize,
+    pending: f64,
+
+    // The seconds each frame ends at, to seek a given time
+    ends: Vec<f64>,
+
+    paused: bool,
+    speed: usize,
+
+    // Whether the run ended before its last frame, at game over
+    over: bool,
+
+    // The status shown over the scrub bar, with the state and the second
+    // it was rendered for
+    status: Sprite,
+    status_shown: (bool, usize, bool, u32),
+    help: Sprite,
+}
+
+impl PlaybackView {
+    pub fn new(phi: &mut Phi, backgrounds: Backgrounds, replay: Replay) -> PlaybackView {
+        if replay.size != phi.output_size() {
+            warn!("The replay was recorded in a {}x{} window, and may play differently in this one",
+                  replay.size.0, replay.size.1);
+        }
+
+        let mut ends = Vec::with_capacity(replay.frames.len());
+        let mut time = 0.0;
+        for input in &replay.frames {
+            time += input.elapsed;
+            ends.push(time);
+        }
+
+        let help = phi.locale.text("playback.help");
+
+        PlaybackView {
+            game: GameView::from_replay(phi, backgrounds.clone(), &replay),
+            replay: replay,
+            backgrounds: backgrounds,
+            next: 0,
+            pending: 0.0,
+            ends: ends,
+            paused: false,
+            speed: 0,
+            over: false,
+            status: phi.ttf_str_sprite(" ", "menu_font", 20, Color::RGB(255, 255, 255)).unwrap(),
+            status_shown: (false, 0, false, 0),
+            help: phi.ttf_str_sprite(&help, "menu_font", 16, Color::RGB(200, 200, 200)).unwrap(),
+        }
+    }
+
+    /// Whether every frame of the run was played
+    fn is_finished(&self) -> bool {
+        self.over || self.next >= self.replay.frames.len()
+    }
+
+    /// Return the seconds of the run played so far
+    fn time(&self) -> f64 {
+        if self.next == 0 { 0.0 } else { self.ends[self.next - 1] }
+    }
+
+    /// Play the next frame of the run
+    fn step(&mut self, phi: &mut Phi) {
+        let input = self.replay.frames[self.next].clone();
+        self.next += 1;
+        if self.game.simulate(phi, &input) {
+            self.over = true;
+        }
+    }
+
+    /// Play the run up to 'time', in seconds from its start, starting it
+    /// over if that time has passed
+    fn seek(&mut self, phi: &mut Phi, time: f64) {
+        if time < self.time() {
+            self.game = GameView::from_replay(phi, self.backgrounds.clone(), &self.replay);
+            self.next = 0;
+            self.over = false;
+        }
+
+        while !self.is_finished() && self.ends[self.next] <= time {
+            self.step(phi);
+        }
+        self.pending = 0.0;
+    }
+
+    /// Return the rectangle of the scrub bar, at the bottom of the screen
+    fn bar(&self, phi: &Phi) -> Rectangle {
+        let (win_w, win_h) = phi.output_size();
+        let margin = phi.scaled(BAR_MARGIN);
+        let bar_h = phi.scaled(BAR_H);
+        Rectangle {
+            x: margin,
+            y: win_h - margin - bar_h,
+            w: win_w - margin * 2.0,
+            h: bar_h,
+        }
+    }
+
+    /// Render the status again, if the state of the playback or the second
+    /// shown changed
+    fn refresh_status(&mut self, phi: &mut Phi) {
+        let finished = self.is_finished();
+        let second = self.time() as u32;
+        let shown = (self.paused, self.speed, finished, second);
+        if shown == self.status_shown {
+            return;
+        }
+        self.status_shown = shown;
+
+        let state = if finished { phi.locale.text("playback.ended") }
+                    else if self.paused { phi.locale.text("playback.paused") }
+                    else { phi.locale.format("playback.playing", &[&SPEEDS[self.speed]]) };
+        let duration = self.replay.duration() as u32;
+        let text = phi.locale.format("playback.status", &[
+            &state, &clock(second), &clock(duration), &self.replay.entry.name]);
+        self.status = phi.ttf_str_sprite(&text, "menu_font", 20, Color::RGB(255, 255, 255)).unwrap();
+    }
+}
+
+/// Format 'seconds' as minutes and seconds
+fn clock(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl View for PlaybackView {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.3,
+            rate: 6.0,
+        }));
+    }
+
+    fn pause(&mut self, phi: &mut Phi) {
+        self.game.pause(phi);
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+        if phi.events.now.key_escape == Some(true) {
+            return ViewAction::ChangeView(Box::new(
+                ReplayListView::new_with_backgrounds(phi, self.backgrounds.clone())));
+        }
+
+        // Space pauses the replay, or watches it again once it is over
+        if phi.events.now.key_space == Some(true) {
+            if self.is_finished() {
+                self.seek(phi, 0.0);
+                self.paused = false;
+            }
+            else {
+                self.paused = !self.paused;
+            }
+        }
+        if phi.events.pressed(Keycode::F) {
+            self.speed = (self.speed + 1) % SPEEDS.len();
+        }
+
+        // Scrub with the arrows, or by clicking along the bar
+        if phi.events.now.repeat.key_left {
+            let time = (self.time() - SKIP).max(0.0);
+            self.seek(phi, time);
+        }
+        if phi.events.now.repeat.key_right {
+            let time = self.time() + SKIP;
+            self.seek(phi, time);
+        }
+        if phi.events.now.mouse_left == Some(true) {
+            let bar = self.bar(phi);
+            let (x, y) = phi.events.mouse;
+            if y >= bar.y - bar.h && y <= bar.y + bar.h * 2.0 {
+                let fraction = ((x - bar.x) / bar.w).max(0.0).min(1.0);
+                let time = fraction * self.replay.duration();
+                self.seek(phi, time);
+            }
+        }
+
+        // Play the frames due, as fast as the replay is watched
+        let mut played = 0.0;
+        if !self.paused {
+            self.pending += elapsed * SPEEDS[self.speed];
+            while !self.is_finished() && self.replay.frames[self.next].elapsed <= self.pending {
+                self.pending -= self.replay.frames[self.next].elapsed;
+                played += self.replay.frames[self.next].elapsed;
+                self.step(phi);
+            }
+        }
+        if self.is_finished() {
+            self.pending = 0.0;
+        }
+
+        self.game.draw_frame(phi, played);
+
+        // Render the scrub bar, filled up to the time played, along with
+        // the status and the help above it
+        self.refresh_status(phi);
+        let bar = self.bar(phi);
+        let duration = self.replay.duration();
+        let fraction = if duration > 0.0 { (self.time() / duration).min(1.0) } else { 1.0 };
+
+        phi.renderer.set_draw_color(Color::RGB(70, 15, 70));
+        phi.renderer.fill_rect(bar.to_sdl().unwrap());
+        // The filled part is left out while too thin to be drawn
+        if bar.w * fraction >= 1.0 {
+            phi.renderer.set_draw_color(Color::RGB(240, 200, 40));
+            phi.renderer.fill_rect(Rectangle {
+                w: bar.w * fraction,
+                ..bar
+            }.to_sdl().unwrap());
+        }
+
+        let margin = phi.scaled(6.0);
+        let (w, h) = self.status.size();
+        self.status.render(&mut phi.renderer, Rectangle {
+            x: bar.x,
+            y: bar.y - h - margin,
+            w: w,
+            h: h,
+        });
+
+        let (help_w, help_h) = self.help.size();
+        self.help.render(&mut phi.renderer, Rectangle {
+            x: bar.x + bar.w - help_w,
+            y: bar.y - help_h - margin,
+            w: help_w,
+            h: help_h,
+        });
+
+        ViewAction::None
+    }
+}
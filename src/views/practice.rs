@@ -0,0 +1,88 @@
+// views/practice.rs
+
+use ::std::collections::VecDeque;
+
+use ::sdl2::pixels::Color;
+
+use ::phi::gfx::Sprite;
+use ::phi::Phi;
+
+/// The seconds over which the damage per second is averaged
+const DPS_WINDOW: f64 = 5.0;
+
+/// What a practice run shows in place of the score at stake: the damage the
+/// player deals per second, how long they have been at it, and how often
+/// the ship was destroyed, since practice never ends
+pub struct Practice {
+    // Whether the wave is practiced from its boss on
+    boss: bool,
+
+    time: f64,
+    deaths: u32,
+
+    // The damage dealt over the last DPS_WINDOW seconds, with when it was
+    // dealt
+    hits: VecDeque<(f64, u32)>,
+
+    // The readout, along with the text it shows
+    label: Option<(String, Sprite)>,
+}
+
+impl Practice {
+    pub fn new(boss: bool) -> Practice {
+        Practice {
+            boss: boss,
+            time: 0.0,
+            deaths: 0,
+            hits: VecDeque::new(),
+            label: None,
+        }
+    }
+
+    /// Whether the wave is practiced from its boss on, rather than from its
+    /// start
+    pub fn at_boss(&self) -> bool {
+        self.boss
+    }
+
+    /// Count 'amount' damage dealt by the player right now
+    pub fn dealt(&mut self, amount: u32) {
+        self.hits.push_back((self.time, amount));
+    }
+
+    /// Count the ship being destroyed
+    pub fn died(&mut self) {
+        self.deaths += 1;
+    }
+
+    // Return the damage dealt per second over the last seconds
+    fn dps(&self) -> f64 {
+        let window = DPS_WINDOW.min(self.time);
+        if window <= 0.0 {
+            return 0.0;
+        }
+        self.hits.iter().map(|&(_, amount)| amount as f64).sum::<f64>() / window
+    }
+
+    /// Let 'elapsed' seconds go by, and render the readout again if what it
+    /// shows changed
+    pub fn update(&mut self, phi: &mut Phi, elapsed: f64) {
+        self.time += elapsed;
+        while self.hits.front().map_or(false, |&(time, _)| time < self.time - DPS_WINDOW) {
+            self.hits.pop_front();
+        }
+
+        let seconds = self.time as u32;
+        let clock = format!("{}:{:02}", seconds / 60, seconds % 60);
+        let text = phi.locale.format("practice.readout", &[&(self.dps().round() as u32), &clock, &self.deaths]);
+        if self.label.as_ref().map_or(true, |&(ref shown, _)| *shown != text) {
+            self.label = phi.ttf_str_sprite(&text, "menu_font", 20, Color::RGB(255, 255, 255))
+                .map(|sprite| (text, sprite));
+        }
+    }
+
+    /// The readout to show on the HUD, once it was rendered
+    pub fn label(&self) -> Option<&Sprite> {
+        self.label.as_ref().map(|&(_, ref sprite)| sprite)
+    }
+}
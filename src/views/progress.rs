@@ -0,0 +1,71 @@
+// views/progress.rs
+
+use ::std::fs::File;
+use ::std::io::{self, BufRead, BufReader, Write};
+
+/// Where what the player achieved is kept between sessions
+pub const PROGRESS_PATH: &'static str = "progress.cfg";
+
+/// What the player achieved over every run, stored as `key = value` lines
+/// like the settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// Whether the final boss was ever destroyed, which opens New Game+
+    pub cleared: bool,
+
+    /// The drones escorting the ship when the campaign was last cleared,
+    /// which New Game+ starts with
+    pub drones: usize,
+}
+
+impl Progress {
+    pub fn new() -> Progress {
+        Progress {
+            cleared: false,
+            drones: 0,
+        }
+    }
+
+    /// Read the progress stored at 'path'. Missing, unknown or malformed
+    /// entries are ignored, and nothing is achieved in their place.
+    pub fn load(path: &str) -> Progress {
+        let mut progress = Progress::new();
+
+        if let Ok(file) = File::open(path) {
+            for line in BufReader::new(file).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+
+                let mut parts = line.splitn(2, '=');
+                if let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+                    progress.set(key.trim(), value.trim());
+                }
+            }
+        }
+
+        progress
+    }
+
+    /// Write the progress to 'path', so that it can be loaded back
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = try!(File::create(path));
+        try!(writeln!(file, "cleared = {}", self.cleared));
+        try!(writeln!(file, "drones = {}", self.drones));
+        Ok(())
+    }
+
+    // Assign the value of a single entry read from a progress file
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "cleared" => if let Ok(cleared) = value.parse() {
+                self.cleared = cleared;
+            },
+            "drones" => if let Ok(drones) = value.parse() {
+                self.drones = drones;
+            },
+            _ => {}
+        }
+    }
+}
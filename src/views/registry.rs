@@ -0,0 +1,217 @@
+// views/registry.rs
+
+use ::sdl2::pixels::Color;
+use ::std::env;
+
+use ::phi::data::Rectangle;
+use ::phi::focus::Focus;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+
+use ::views::hazards;
+use ::views::lan::Role;
+use ::views::shared::{self, Backgrounds};
+
+/// The option which opens a view directly, as in `--view=game`; without a
+/// name, or with one no view has, it opens the picker
+const VIEW_OPTION: &'static str = "--view";
+
+/// The number of views listed at once by the picker
+const ROWS_SHOWN: usize = 12;
+const ROW_H: f64 = 28.0;
+
+/// A view which can be opened on its own, without going through the menus
+pub struct ViewEntry {
+    pub name: &'static str,
+    pub build: fn(&mut Phi, Backgrounds) -> Box<View>,
+}
+
+/// Return every view which can be opened directly, in the order the picker
+/// lists them
+pub fn views() -> Vec<ViewEntry> {
+    vec![
+        ViewEntry { name: "loading", build: |_, _| Box::new(::views::loading::LoadingView::new()) },
+        ViewEntry { name: "splash", build: |phi, _| Box::new(::views::splash::SplashView::new(phi)) },
+        ViewEntry { name: "menu", build: |phi, backgrounds| Box::new(
+            ::views::main_menu::MainMenuView::new_with_backgrounds(phi, backgrounds)) },
+        ViewEntry { name: "game", build: |phi, backgrounds| Box::new(
+            ::views::game::GameView::new_with_backgrounds(phi, backgrounds)) },
+        ViewEntry { name: "practice", build: |phi, backgrounds| Box::new(
+            ::views::game::GameView::new_practice(phi, backgrounds)) },
+        ViewEntry { name: "controls", build: |phi, backgrounds| Box::new(
+            ::views::controls::ControlsView::new_with_backgrounds(phi, backgrounds)) },
+        ViewEntry { name: "high_scores", build: |phi, backgrounds| Box::new(
+            ::views::high_scores::HighScoresView::new_with_backgrounds(phi, backgrounds)) },
+        ViewEntry { name: "credits", build: |phi, backgrounds| Box::new(
+            ::views::credits::CreditsView::new_with_backgrounds(phi, backgrounds)) },
+        ViewEntry { name: "replays", build: |phi, backgrounds| Box::new(
+            ::views::playback::ReplayListView::new_with_backgrounds(phi, backgrounds)) },
+        ViewEntry { name: "lan_host", build: |phi, backgrounds| Box::new(
+            ::views::lan::LanView::new_with_backgrounds(phi, backgrounds, Role::Host)) },
+        ViewEntry { name: "lan_join", build: |phi, backgrounds| Box::new(
+            ::views::lan::LanView::new_with_backgrounds(phi, backgrounds, Role::Join)) },
+        ViewEntry { name: "editor", build: |phi, _| Box::new(
+            ::views::editor::EditorView::new(phi, hazards::LEVEL_PATH)) },
+    ].into_iter().chain(test_scenes()).collect()
+}
+
+/// Return the test scenes, when the game is built with them
+#[cfg(feature = "test-scenes")]
+fn test_scenes() -> Vec<ViewEntry> {
+    ::views::scenes::views()
+}
+
+#[cfg(not(feature = "test-scenes"))]
+fn test_scenes() -> Vec<ViewEntry> {
+    vec![]
+}
+
+/// Return the name of the view to open if one was asked for on the command
+/// line, empty to open the picker
+pub fn name_from_args() -> Option<String> {
+    env::args()
+        .find(|arg| arg == VIEW_OPTION || arg.starts_with(&format!("{}=", VIEW_OPTION)))
+        .map(|arg| arg[VIEW_OPTION.len()..].trim_left_matches('=').to_string())
+}
+
+/// Open the view called 'name', or the picker if there is none
+pub fn build(phi: &mut Phi, name: &str) -> Box<View> {
+    let backgrounds = Backgrounds::new(phi);
+    match views().into_iter().find(|entry| entry.name == name) {
+        Some(entry) => (entry.build)(phi, backgrounds),
+        None => {
+            if !name.is_empty() {
+                warn!("There is no view called {}", name);
+            }
+            Box::new(ViewPickerView::new_with_backgrounds(phi, backgrounds))
+        },
+    }
+}
+
+/// Lists every view which can be opened directly, to jump to one of them
+/// while working on it. Opened with `--view`, or with F2 from the main menu
+/// while the debugging tools are on.
+pub struct ViewPickerView {
+    backgrounds: Backgrounds,
+
+    views: Vec<ViewEntry>,
+    focus: Focus,
+
+    title: Sprite,
+    rows: Vec<Sprite>,
+}
+
+impl ViewPickerView {
+    pub fn new_with_backgrounds(phi: &mut Phi, backgrounds: Backgrounds) -> ViewPickerView {
+        let views = views();
+        let rows = views.iter().map(|entry| {
+            phi.ttf_str_sprite(entry.name, "menu_font", 20, Color::RGB(230, 230, 230)).unwrap()
+        }).collect();
+
+        ViewPickerView {
+            backgrounds: backgrounds,
+            focus: Focus::new(views.len()),
+            views: views,
+            title: phi.ttf_str_sprite("Jump to a view", "menu_font", 32, Color::RGB(255, 255, 255)).unwrap(),
+            rows: rows,
+        }
+    }
+}
+
+impl View for ViewPickerView {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+        if self.focus.cancelled(phi) {
+            return ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new_with_backgrounds(
+                    phi, self.backgrounds.clone())));
+        }
+
+        self.focus.navigate(phi);
+        if self.focus.activated(phi) {
+            let entry = &self.views[self.focus.index()];
+            info!("Jumping to the view {}", entry.name);
+            return ViewAction::ChangeView((entry.build)(phi, self.backgrounds.clone()));
+        }
+
+        // Clear the screen . . .
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.backgrounds.back.render(&mut phi.renderer, elapsed);
+        self.backgrounds.middle.render(&mut phi.renderer, elapsed);
+        self.backgrounds.front.render(&mut phi.renderer, elapsed);
+
+        // Render the box which holds the list
+        let (win_w, win_h) = phi.output_size();
+        let row_h = phi.scaled(ROW_H);
+        let padding = phi.scaled(20.0);
+        let list_w = self.rows.iter().map(|row| row.size().0)
+            .fold(self.title.size().0, f64::max);
+        let list_h = row_h * (ROWS_SHOWN.min(self.rows.len()) + 1) as f64;
+        let left = (win_w - list_w) / 2.0;
+        let top = (win_h - list_h) / 2.0;
+
+        shared::render_menu_box(phi, Rectangle {
+            x: left - padding,
+            y: top - padding,
+            w: list_w + padding * 2.0,
+            h: list_h + padding * 2.0,
+        }, Color::RGB(20, 60, 80));
+
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle {
+            x: (win_w - w) / 2.0,
+            y: top,
+            w: w,
+            h: h,
+        });
+
+        // Scroll the list so that the selected view stays in sight
+        let first = (self.focus.index() + 1).saturating_sub(ROWS_SHOWN);
+        self.focus.set_layout((0..self.rows.len()).map(|i| Rectangle {
+            x: left - padding / 2.0,
+            y: top + row_h * (i + 1) as f64,
+            w: list_w + padding,
+            h: row_h,
+        }).collect(), (0.0, row_h * first as f64), Some(Rectangle {
+            x: left - padding / 2.0,
+            y: top + row_h,
+            w: list_w + padding,
+            h: row_h * ROWS_SHOWN as f64,
+        }));
+
+        for (i, row) in self.rows.iter().enumerate().skip(first).take(ROWS_SHOWN) {
+            let y = top + row_h * (i - first + 1) as f64;
+            if i == self.focus.index() {
+                phi.renderer.set_draw_color(Color::RGB(30, 100, 130));
+                phi.renderer.fill_rect(Rectangle {
+                    x: left - padding / 2.0,
+                    y: y,
+                    w: list_w + padding,
+                    h: row_h,
+                }.to_sdl().unwrap());
+            }
+
+            let (w, h) = row.size();
+            row.render(&mut phi.renderer, Rectangle {
+                x: left,
+                y: y + (row_h - h) / 2.0,
+                w: w,
+                h: h,
+            });
+        }
+
+        ViewAction::None
+    }
+}
@@ -0,0 +1,316 @@
+// views/replay.rs
+
+use ::std::cmp::Ordering;
+use ::std::fs::{self, File};
+use ::std::io::{self, BufRead, BufReader, Write};
+use ::std::path::PathBuf;
+
+use ::views::leaderboard::{Entry, GameMode};
+
+pub const REPLAYS_DIR: &'static str = "replays";
+
+/// Seconds of play between two positions of the ship kept with a replay
+pub const POSITION_INTERVAL: f64 = 0.1;
+
+/// What the player does during a frame of the game, as the game view reads
+/// it. Since the game draws its random numbers from the seed of the run,
+/// playing the same frames back over the same seed plays the same run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Input {
+    /// Seconds since the previous frame, and the game speed of the settings
+    pub elapsed: f64,
+    pub game_speed: f64,
+
+    /// The direction in which the ship is steered, each axis between -1
+    /// and 1
+    pub movement: (f64, f64),
+
+    /// The cannons of the ship, from 1 to 3
+    pub cannon: u8,
+    pub fire: bool,
+
+    /// The point the player aims at, when aiming with the mouse
+    pub aim: Option<(f64, f64)>,
+
+    /// Whether the player accepted to continue after a game over
+    pub confirm: bool,
+
+    /// Whether the player chose to start the wave over from the pause menu
+    pub restart: bool,
+}
+
+impl Input {
+    /// Read a frame written by 'to_line'
+    fn parse(words: &[&str]) -> Option<Input> {
+        let flag = |word: &str| match word {
+            "0" => Some(false),
+            "1" => Some(true),
+            _ => None,
+        };
+
+        // Frames recorded before restarts were possible lack their flag
+        let (restart, aim_at) = match words.len() {
+            7 | 9 => (Some(false), 7),
+            8 | 10 => (flag(words[7]), 8),
+            _ => return None,
+        };
+        let aim = if words.len() == aim_at { None }
+                  else {
+                      match (words[aim_at].parse(), words[aim_at + 1].parse()) {
+                          (Ok(x), Ok(y)) => Some((x, y)),
+                          _ => return None,
+                      }
+                  };
+
+        match (words[0].parse(), words[1].parse(), words[2].parse(), words[3].parse(),
+               words[4].parse(), flag(words[5]), flag(words[6]), restart) {
+            (Ok(elapsed), Ok(game_speed), Ok(x), Ok(y), Ok(cannon), Some(fire), Some(confirm), Some(restart)) => Some(Input {
+                elapsed: elapsed,
+                game_speed: game_speed,
+                movement: (x, y),
+                cannon: cannon,
+                fire: fire,
+                aim: aim,
+                confirm: confirm,
+                restart: restart,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return the frame as a line of words. Floats are written with as
+    /// many digits as it takes to read them back exactly.
+    fn to_line(&self) -> String {
+        let mut line = format!("{} {} {} {} {} {} {} {}",
+                               self.elapsed, self.game_speed,
+                               self.movement.0, self.movement.1, self.cannon,
+                               self.fire as u8, self.confirm as u8, self.restart as u8);
+        if let Some((x, y)) = self.aim {
+            line.push_str(&format!(" {} {}", x, y));
+        }
+        line
+    }
+}
+
+/// A finished run, which may be watched again from the main menu
+#[derive(Clone, Debug)]
+pub struct Replay {
+    /// The run as recorded on the leaderboard, which holds its seed
+    pub entry: Entry,
+
+    /// The mode the run was played in, and the drones the ship started
+    /// with
+    pub mode: GameMode,
+    pub drones: usize,
+
+    /// The size of the window when the run started; the run only plays
+    /// back the same in a window of the same size
+    pub size: (f64, f64),
+
+    pub frames: Vec<Input>,
+
+    /// Where the ship was, every POSITION_INTERVAL seconds of play, for it
+    /// to be shown as a ghost without playing the run back
+    pub positions: Vec<(f64, (f64, f64))>,
+}
+
+impl Replay {
+    /// Read the replay at 'path'. Every line holds one of:
+    ///
+    /// * `run ENTRY`, where ENTRY is the run as on the leaderboard
+    /// * `size WIDTH HEIGHT`
+    /// * `mode MODE DRONES`, for runs of another mode than the arcade
+    /// * `pos TIME X Y`, where the ship was TIME seconds into the run
+    /// * `frame ELAPSED SPEED X Y CANNON FIRE CONFIRM [RESTART] [AIM_X AIM_Y]`
+    ///
+    /// Lines starting with `#` are ignored. Return None if the file cannot
+    /// be read or does not tell which run it holds.
+    pub fn load(path: &str) -> Option<Replay> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => {
+                warn!("Could not open the replay {}", path);
+                return None;
+            }
+        };
+
+        let mut entry = None;
+        let mut size = (0.0, 0.0);
+        let mut mode = (GameMode::Arcade, 0);
+        let mut frames = vec![];
+        let mut positions = vec![];
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            // The entry holds tabs and names with spaces, and is read as is
+            if line.starts_with("run ") {
+                entry = Entry::parse(&line[4..]);
+                if entry.is_none() {
+                    warn!("Ignoring malformed line in {}: {}", path, line);
+                }
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match (words.get(0).cloned(), words.len()) {
+                (Some("size"), 3) => match (words[1].parse(), words[2].parse()) {
+                    (Ok(w), Ok(h)) => {
+                        size = (w, h);
+                        true
+                    },
+                    _ => false,
+                },
+                (Some("mode"), 3) => match (GameMode::from_id(words[1]), words[2].parse()) {
+                    (Some(id), Ok(drones)) => {
+                        mode = (id, drones);
+                        true
+                    },
+                    _ => false,
+                },
+                (Some("pos"), 4) => match (words[1].parse(), words[2].parse(), words[3].parse()) {
+                    (Ok(time), Ok(x), Ok(y)) => {
+                        positions.push((time, (x, y)));
+                        true
+                    },
+                    _ => false,
+                },
+                (Some("frame"), _) => match Input::parse(&words[1..]) {
+                    Some(input) => {
+                        frames.push(input);
+                        true
+                    },
+                    None => false,
+                },
+                _ => false,
+            };
+            if !parsed {
+                warn!("Ignoring malformed line in {}: {}", path, line);
+            }
+        }
+
+        match entry {
+            Some(entry) => Some(Replay {
+                entry: entry,
+                mode: mode.0,
+                drones: mode.1,
+                size: size,
+                frames: frames,
+                positions: positions,
+            }),
+            None => {
+                warn!("The replay {} does not tell which run it holds", path);
+                None
+            },
+        }
+    }
+
+    /// Read only which run the replay at 'path' holds, without its frames
+    pub fn load_entry(path: &str) -> Option<Entry> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return None,
+        };
+
+        for line in BufReader::new(file).lines() {
+            match line {
+                Ok(ref line) if line.starts_with("run ") => return Entry::parse(&line[4..]),
+                Ok(_) => {},
+                Err(_) => break,
+            }
+        }
+        None
+    }
+
+    /// Write the replay in the replays directory, named after the time the
+    /// run ended, and return its path
+    pub fn save(&self) -> io::Result<String> {
+        try!(fs::create_dir_all(REPLAYS_DIR));
+        let path = path_of(&self.entry);
+        let mut file = try!(File::create(&path));
+
+        try!(writeln!(file, "# A run of arcade-rs, played back from the main menu"));
+        try!(writeln!(file, "run {}", self.entry.to_line()));
+        try!(writeln!(file, "size {} {}", self.size.0, self.size.1));
+        if self.mode != GameMode::Arcade {
+            try!(writeln!(file, "mode {} {}", self.mode.id(), self.drones));
+        }
+        for input in &self.frames {
+            try!(writeln!(file, "frame {}", input.to_line()));
+        }
+        for &(time, (x, y)) in &self.positions {
+            try!(writeln!(file, "pos {} {} {}", time, x, y));
+        }
+        Ok(path)
+    }
+
+    /// Return the seconds the run lasted
+    pub fn duration(&self) -> f64 {
+        self.frames.iter().map(|input| input.elapsed).sum()
+    }
+}
+
+/// Return the path of the replay of the run 'entry', which is named after
+/// the time it ended and its seed
+pub fn path_of(entry: &Entry) -> String {
+    format!("{}/{}-{}.txt", REPLAYS_DIR, entry.date, entry.seed)
+}
+
+/// The ship of a recorded run, flying along the one being played, such as
+/// the best run of the player
+pub struct Ghost {
+    positions: Vec<(f64, (f64, f64))>,
+}
+
+impl Ghost {
+    /// Return the ghost of the replay at 'path', if it can be read and
+    /// holds the positions of the ship
+    pub fn load(path: &str) -> Option<Ghost> {
+        Replay::load(path)
+            .and_then(|replay| if replay.positions.is_empty() { None } else {
+                Some(Ghost {
+                    positions: replay.positions,
+                })
+            })
+    }
+
+    /// Return where the ship was 'time' seconds into the run, between the
+    /// positions kept around it, or None once the run is over
+    pub fn position(&self, time: f64) -> Option<(f64, f64)> {
+        let found = self.positions.binary_search_by(|&(at, _)| at.partial_cmp(&time).unwrap_or(Ordering::Less));
+        match found {
+            Ok(index) => Some(self.positions[index].1),
+            Err(0) => Some(self.positions[0].1),
+            Err(next) if next == self.positions.len() => None,
+            Err(next) => {
+                let (t0, (x0, y0)) = self.positions[next - 1];
+                let (t1, (x1, y1)) = self.positions[next];
+                let share = if t1 > t0 { (time - t0) / (t1 - t0) } else { 1.0 };
+                Some((x0 + (x1 - x0) * share, y0 + (y1 - y0) * share))
+            },
+        }
+    }
+}
+
+/// Return the paths of the saved replays, the latest first
+pub fn list() -> Vec<String> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(REPLAYS_DIR) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "txt"))
+            .collect(),
+        Err(_) => vec![],
+    };
+
+    // Replays are named after the time their run ended
+    paths.sort();
+    paths.reverse();
+    paths.iter().filter_map(|path| path.to_str().map(|path| path.to_string())).collect()
+}
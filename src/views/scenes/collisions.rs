@@ -0,0 +1,182 @@
+// views/scenes/collisions.rs
+
+use ::sdl2::pixels::Color;
+use ::std::f64::consts::PI;
+
+use ::phi::{Phi, View, ViewAction};
+use ::phi::data::{self, OrientedRect, Polygon, Rectangle};
+use ::views::scenes::{self, Header};
+
+/// Radians the oriented rectangles turn by every second while an arrow is
+/// held
+const TURN_SPEED: f64 = 1.5;
+
+/// The number of sides of the polygons drawn for circles
+const CIRCLE_SIDES: usize = 32;
+
+/// The shapes phi tests for collisions
+#[derive(Clone, Debug)]
+enum Shape {
+    Rect(Rectangle),
+    Oriented(OrientedRect),
+    Polygon(Polygon),
+    Circle((f64, f64), f64),
+}
+
+impl Shape {
+    fn translate(&mut self, dx: f64, dy: f64) {
+        match *self {
+            Shape::Rect(ref mut rect) => {
+                rect.x += dx;
+                rect.y += dy;
+            },
+            Shape::Oriented(ref mut rect) => {
+                rect.center.0 += dx;
+                rect.center.1 += dy;
+            },
+            Shape::Polygon(ref mut polygon) => *polygon = polygon.translate(dx, dy),
+            Shape::Circle(ref mut center, _) => {
+                center.0 += dx;
+                center.1 += dy;
+            },
+        }
+    }
+
+    /// Return the vertices of the outline of the shape
+    fn outline(&self) -> Vec<(f64, f64)> {
+        match *self {
+            Shape::Rect(rect) => vec![(rect.x, rect.y), (rect.x + rect.w, rect.y),
+                                      (rect.x + rect.w, rect.y + rect.h), (rect.x, rect.y + rect.h)],
+            Shape::Oriented(ref rect) => rect.corners().to_vec(),
+            Shape::Polygon(ref polygon) => polygon.points.clone(),
+            Shape::Circle((cx, cy), radius) => (0..CIRCLE_SIDES).map(|i| {
+                let angle = i as f64 * 2.0 * PI / CIRCLE_SIDES as f64;
+                (cx + radius * angle.cos(), cy + radius * angle.sin())
+            }).collect(),
+        }
+    }
+
+    fn bounding_box(&self) -> Rectangle {
+        data::bounding_box(&self.outline())
+    }
+
+    /// Whether 'point' lies within the bounding box of the shape, to pick
+    /// it with the mouse
+    fn grabs(&self, point: (f64, f64)) -> bool {
+        self.bounding_box().contains_point(point)
+    }
+
+    /// Whether both shapes touch, or None if phi has no test for them
+    fn overlaps(&self, other: &Shape) -> Option<bool> {
+        match (self, other) {
+            (&Shape::Rect(a), &Shape::Rect(b)) => Some(a.overlaps(b)),
+            (&Shape::Rect(rect), &Shape::Oriented(ref oriented))
+                | (&Shape::Oriented(ref oriented), &Shape::Rect(rect)) => Some(oriented.overlaps_rect(rect)),
+            (&Shape::Rect(rect), &Shape::Polygon(ref polygon))
+                | (&Shape::Polygon(ref polygon), &Shape::Rect(rect)) => Some(polygon.overlaps_rect(rect)),
+            (&Shape::Rect(rect), &Shape::Circle(center, radius))
+                | (&Shape::Circle(center, radius), &Shape::Rect(rect)) => Some(rect.overlaps_circle(center, radius)),
+            (&Shape::Oriented(ref a), &Shape::Oriented(ref b)) => Some(a.overlaps(b)),
+            (&Shape::Oriented(ref oriented), &Shape::Polygon(ref polygon))
+                | (&Shape::Polygon(ref polygon), &Shape::Oriented(ref oriented)) => Some(polygon.overlaps_oriented(oriented)),
+            (&Shape::Polygon(ref a), &Shape::Polygon(ref b)) => Some(a.overlaps(b)),
+            (&Shape::Circle(a, ra), &Shape::Circle(b, rb)) => {
+                let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+                Some(dx * dx + dy * dy <= (ra + rb) * (ra + rb))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Shapes which may be dragged around with the mouse, each drawn red while
+/// it touches another one and green otherwise. Shapes phi has no test for
+/// are drawn gray while their bounding boxes overlap.
+pub struct CollisionsScene {
+    header: Header,
+
+    shapes: Vec<Shape>,
+
+    // The shape being dragged, and where the cursor was during the last
+    // frame
+    dragged: Option<usize>,
+    last_mouse: (f64, f64),
+}
+
+impl CollisionsScene {
+    pub fn new(phi: &mut Phi) -> CollisionsScene {
+        let triangle = Polygon { points: vec![(520.0, 160.0), (600.0, 300.0), (440.0, 300.0)] };
+        CollisionsScene {
+            header: Header::new(phi, "Collisions", "Drag the shapes with the mouse - Left/Right: turn the rotated rectangles"),
+            shapes: vec![
+                Shape::Rect(Rectangle { x: 80.0, y: 160.0, w: 120.0, h: 80.0 }),
+                Shape::Rect(Rectangle { x: 120.0, y: 320.0, w: 60.0, h: 140.0 }),
+                Shape::Oriented(OrientedRect::from_rect(
+                    Rectangle { x: 260.0, y: 180.0, w: 140.0, h: 50.0 }, PI / 6.0)),
+                Shape::Oriented(OrientedRect::from_rect(
+                    Rectangle { x: 280.0, y: 340.0, w: 80.0, h: 80.0 }, PI / 4.0)),
+                Shape::Polygon(triangle),
+                Shape::Circle((520.0, 420.0), 50.0),
+            ],
+            dragged: None,
+            last_mouse: phi.events.mouse,
+        }
+    }
+}
+
+impl View for CollisionsScene {
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if let Some(action) = scenes::leave(phi) {
+            return action;
+        }
+
+        // Pick the topmost shape under the cursor, drawn last
+        let mouse = phi.events.mouse;
+        if phi.events.now.mouse_left == Some(true) {
+            self.dragged = self.shapes.iter().rposition(|shape| shape.grabs(mouse));
+        }
+        if !phi.events.mouse_left {
+            self.dragged = None;
+        }
+        if let Some(index) = self.dragged {
+            self.shapes[index].translate(mouse.0 - self.last_mouse.0, mouse.1 - self.last_mouse.1);
+        }
+        self.last_mouse = mouse;
+
+        let turn = match (phi.events.key_left, phi.events.key_right) {
+            (true, false) => -TURN_SPEED * elapsed,
+            (false, true) => TURN_SPEED * elapsed,
+            _ => 0.0,
+        };
+        for shape in &mut self.shapes {
+            if let Shape::Oriented(ref mut rect) = *shape {
+                rect.angle += turn;
+            }
+        }
+
+        self.header.render(phi);
+
+        for (i, shape) in self.shapes.iter().enumerate() {
+            let others = self.shapes.iter().enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, other)| other);
+
+            // Shapes without a test between them may only touch if their
+            // bounding boxes do
+            let (mut touches, mut untested) = (false, false);
+            for other in others {
+                match shape.overlaps(other) {
+                    Some(overlaps) => touches = touches || overlaps,
+                    None => untested = untested || shape.bounding_box().overlaps(other.bounding_box()),
+                }
+            }
+
+            let color = if touches { Color::RGB(230, 60, 60) }
+                        else if untested { Color::RGB(140, 140, 140) }
+                        else { Color::RGB(60, 220, 90) };
+            scenes::draw_outline(&mut phi.renderer, &shape.outline(), color);
+        }
+
+        ViewAction::None
+    }
+}
@@ -0,0 +1,138 @@
+// views/scenes/input.rs
+
+use ::sdl2::pixels::Color;
+use ::std::f64::consts::PI;
+
+use ::phi::{Phi, View, ViewAction};
+use ::phi::bitmap_font::BitmapFont;
+use ::phi::controllers::MAX_PLAYERS;
+use ::phi::data::Rectangle;
+use ::phi::input_display::{state_color, HELD, IDLE, PAD_INPUTS};
+use ::views::scenes::{self, Header};
+
+const GLYPHS: &'static str = "abcdefghijklmnopqrstuvwxyz0123456789_ .,:()-";
+
+/// The radius of the circles showing the sticks, before the UI scale is
+/// applied
+const STICK_RADIUS: f64 = 40.0;
+
+/// Shows the state of every input the game reads: the keys tracked by the
+/// events macro, the modifiers, the mouse, and the buttons and sticks of
+/// the controllers, raw and through the dead zone of the settings. A larger
+/// take on the display F1 toggles over any view.
+pub struct InputScene {
+    header: Header,
+    font: Option<BitmapFont>,
+}
+
+impl InputScene {
+    pub fn new(phi: &mut Phi) -> InputScene {
+        InputScene {
+            header: Header::new(phi, "Input", "Yellow: pressed, green: held, red: released"),
+            font: phi.bitmap_font(GLYPHS, "menu_font", 16),
+        }
+    }
+}
+
+impl View for InputScene {
+    fn render(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if let Some(action) = scenes::leave(phi) {
+            return action;
+        }
+
+        let top = self.header.render(phi);
+        let font = match self.font {
+            Some(ref font) => font,
+            None => return ViewAction::None,
+        };
+
+        let margin = phi.scaled(10.0);
+        let line_h = font.text_size("a", 1.0).1;
+
+        // The keys, in a column
+        let mut y = top;
+        for (name, held, changed) in phi.events.tracked_keys() {
+            font.set_color(state_color(held, changed));
+            font.render(&mut phi.renderer, name, (margin, y), 1.0);
+            y += line_h;
+        }
+
+        // The modifiers and the mouse, in the next one
+        let x = margin + phi.scaled(160.0);
+        let mut y = top;
+        let events = &phi.events;
+        let lines = [
+            ("shift", state_color(events.mod_shift, events.now.mod_shift)),
+            ("ctrl", state_color(events.mod_ctrl, events.now.mod_ctrl)),
+            ("alt", state_color(events.mod_alt, events.now.mod_alt)),
+            ("mouse_left", state_color(events.mouse_left, events.now.mouse_left)),
+            ("mouse_right", state_color(events.mouse_right, events.now.mouse_right)),
+        ];
+        for &(name, color) in &lines {
+            font.set_color(color);
+            font.render(&mut phi.renderer, name, (x, y), 1.0);
+            y += line_h;
+        }
+        let (mx, my) = events.mouse;
+        font.set_color(Color::RGB(255, 255, 255));
+        font.render(&mut phi.renderer, &format!("mouse ({:.0}, {:.0})", mx, my), (x, y), 1.0);
+
+        phi.renderer.set_draw_color(Color::RGB(255, 255, 255));
+        phi.renderer.fill_rect(Rectangle { x: mx - 8.0, y: my, w: 17.0, h: 1.0 }.to_sdl().unwrap());
+        phi.renderer.fill_rect(Rectangle { x: mx, y: my - 8.0, w: 1.0, h: 17.0 }.to_sdl().unwrap());
+
+        // Every controller, in a column of its own
+        let dead_zone = phi.settings.dead_zone();
+        let radius = phi.scaled(STICK_RADIUS);
+        for player in 0..MAX_PLAYERS {
+            let x = margin + phi.scaled(360.0 + 220.0 * player as f64);
+            let mut y = top;
+
+            let title = format!("player {}", player + 1);
+            if !phi.events.controllers.is_connected(player) {
+                font.set_color(IDLE);
+                font.render(&mut phi.renderer, &format!("{}: no controller", title), (x, y), 1.0);
+                continue;
+            }
+            font.set_color(Color::RGB(255, 255, 255));
+            font.render(&mut phi.renderer, &title, (x, y), 1.0);
+            y += line_h;
+
+            for &input in &PAD_INPUTS {
+                let held = phi.events.controllers.is_held(player, input);
+                font.set_color(state_color(held, None));
+                font.render(&mut phi.renderer, &input.name(), (x, y), 1.0);
+                y += line_h;
+            }
+
+            // The sticks: the outer circle is the full tilt, the inner one
+            // the dead zone; the white dot is where the stick is, the green
+            // one where the game reads it
+            let sticks = [phi.events.controllers.left_stick(player),
+                          phi.events.controllers.right_stick(player)];
+            for (i, stick) in sticks.iter().enumerate() {
+                let (sx, sy) = stick.unwrap_or((0.0, 0.0));
+                let center = (x + radius + i as f64 * (radius * 2.0 + margin), y + margin + radius);
+                let circle = |r: f64| (0..32).map(|step| {
+                    let angle = step as f64 * PI / 16.0;
+                    (center.0 + r * angle.cos(), center.1 + r * angle.sin())
+                }).collect::<Vec<_>>();
+                scenes::draw_outline(&mut phi.renderer, &circle(radius), Color::RGB(200, 200, 200));
+                scenes::draw_outline(&mut phi.renderer, &circle(radius * dead_zone.inner), IDLE);
+
+                let (dx, dy) = dead_zone.apply((sx, sy));
+                for &((px, py), color) in &[((sx, sy), Color::RGB(255, 255, 255)), ((dx, dy), HELD)] {
+                    phi.renderer.set_draw_color(color);
+                    phi.renderer.fill_rect(Rectangle {
+                        x: center.0 + px * radius - 3.0,
+                        y: center.1 + py * radius - 3.0,
+                        w: 6.0,
+                        h: 6.0,
+                    }.to_sdl().unwrap());
+                }
+            }
+        }
+
+        ViewAction::None
+    }
+}
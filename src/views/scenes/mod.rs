@@ -0,0 +1,89 @@
+// views/scenes/mod.rs
+
+//! Small views showing one feature of phi each, built with the
+//! "test-scenes" feature and listed by the view picker (see
+//! views/registry.rs). They serve to check the engine by hand, and show how
+//! its API is meant to be used.
+
+pub mod collisions;
+pub mod input;
+pub mod particles;
+pub mod text;
+
+use ::sdl2::pixels::Color;
+use ::sdl2::rect::Point;
+use ::sdl2::render::Renderer;
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{Phi, ViewAction};
+use ::views::registry::{ViewEntry, ViewPickerView};
+use ::views::shared::Backgrounds;
+
+/// Return every test scene, for the view picker
+pub fn views() -> Vec<ViewEntry> {
+    vec![
+        ViewEntry { name: "scene_particles", build: |phi, _| Box::new(
+            particles::ParticlesScene::new(phi)) },
+        ViewEntry { name: "scene_text", build: |phi, _| Box::new(
+            text::TextScene::new(phi)) },
+        ViewEntry { name: "scene_collisions", build: |phi, _| Box::new(
+            collisions::CollisionsScene::new(phi)) },
+        ViewEntry { name: "scene_input", build: |phi, _| Box::new(
+            input::InputScene::new(phi)) },
+    ]
+}
+
+/// The title of a scene and what its keys do, shown at its top
+pub struct Header {
+    title: Sprite,
+    help: Sprite,
+}
+
+impl Header {
+    pub fn new(phi: &mut Phi, title: &str, help: &str) -> Header {
+        let help = format!("{} - Escape: back to the views", help);
+        Header {
+            title: phi.ttf_str_sprite(title, "menu_font", 24, Color::RGB(255, 255, 255)).unwrap(),
+            help: phi.ttf_str_sprite(&help, "menu_font", 12, Color::RGB(180, 180, 180)).unwrap(),
+        }
+    }
+
+    /// Clear the screen and render the header, returning the height it
+    /// takes
+    pub fn render(&self, phi: &mut Phi) -> f64 {
+        phi.renderer.set_draw_color(Color::RGB(10, 10, 20));
+        phi.renderer.clear();
+
+        let margin = phi.scaled(10.0);
+        let (w, h) = self.title.size();
+        self.title.render(&mut phi.renderer, Rectangle { x: margin, y: margin, w: w, h: h });
+        let (help_w, help_h) = self.help.size();
+        self.help.render(&mut phi.renderer, Rectangle { x: margin, y: margin + h, w: help_w, h: help_h });
+
+        margin * 2.0 + h + help_h
+    }
+}
+
+/// Return what to do if the player leaves the scene during this frame:
+/// quit the game, or go back to the view picker
+pub fn leave(phi: &mut Phi) -> Option<ViewAction> {
+    if phi.events.now.quit {
+        return Some(ViewAction::Quit);
+    }
+    if phi.events.now.key_escape == Some(true) {
+        let backgrounds = Backgrounds::new(phi);
+        return Some(ViewAction::ChangeView(Box::new(
+            ViewPickerView::new_with_backgrounds(phi, backgrounds))));
+    }
+    None
+}
+
+/// Draw the closed outline through 'points' with 'color'
+pub fn draw_outline(renderer: &mut Renderer, points: &[(f64, f64)], color: Color) {
+    renderer.set_draw_color(color);
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(i + 1) % points.len()];
+        let _ = renderer.draw_line(Point::new(x1 as i32, y1 as i32), Point::new(x2 as i32, y2 as i32));
+    }
+}
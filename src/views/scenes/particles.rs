@@ -0,0 +1,102 @@
+// views/scenes/particles.rs
+
+use ::sdl2::pixels::Color;
+use ::std::f64::consts::PI;
+
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+use ::phi::bitmap_font::BitmapFont;
+use ::phi::gfx::Blending;
+use ::phi::particles::{Emitter, ParticleSystem};
+use ::views::scenes::{self, Header};
+
+/// What every emitter of the scene releases
+const EMITTER: Emitter = Emitter {
+    rate: 400.0,
+    offset: (0.0, 0.0),
+    velocity: (0.0, -120.0),
+    spread: 80.0,
+    life: 1.2,
+    size: 3.0,
+    color: (255, 160, 60),
+    blending: Blending::Additive,
+};
+
+/// The bounds of the number of emitters, and the radius of the ring they
+/// turn on around the mouse cursor
+const MIN_EMITTERS: usize = 1;
+const MAX_EMITTERS: usize = 512;
+const RING_RADIUS: f64 = 80.0;
+
+/// How much each frame counts in the average frame time shown
+const SMOOTHING: f64 = 0.05;
+
+/// Fills the screen with particles from a ring of emitters following the
+/// mouse cursor, to see how many the engine keeps up with
+pub struct ParticlesScene {
+    header: Header,
+    stats_font: Option<BitmapFont>,
+
+    system: ParticleSystem,
+    emitters: usize,
+    time: f64,
+    frame_time: f64,
+}
+
+impl ParticlesScene {
+    pub fn new(phi: &mut Phi) -> ParticlesScene {
+        ParticlesScene {
+            header: Header::new(phi, "Particles", "Up/Down: double/halve the emitters"),
+            // The statistics change every frame, which a bitmap font suits
+            stats_font: phi.bitmap_font("0123456789. abcdefghijklmnopqrstuvwxyz", "menu_font", 16),
+            system: ParticleSystem::new(),
+            emitters: 8,
+            time: 0.0,
+            frame_time: 0.0,
+        }
+    }
+}
+
+impl View for ParticlesScene {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if let Some(action) = scenes::leave(phi) {
+            return action;
+        }
+
+        if phi.events.now.repeat.key_up {
+            self.emitters = (self.emitters * 2).min(MAX_EMITTERS);
+        }
+        if phi.events.now.repeat.key_down {
+            self.emitters = (self.emitters / 2).max(MIN_EMITTERS);
+        }
+
+        self.time += elapsed;
+        self.frame_time += (elapsed - self.frame_time) * SMOOTHING;
+
+        let (mx, my) = phi.events.mouse;
+        for i in 0..self.emitters {
+            let angle = self.time * 2.0 + i as f64 * 2.0 * PI / self.emitters as f64;
+            let pos = (mx + RING_RADIUS * angle.cos(), my + RING_RADIUS * angle.sin());
+            EMITTER.emit(&mut self.system, pos, 1.0, elapsed);
+        }
+        self.system.update(elapsed);
+
+        let top = self.header.render(phi);
+        self.system.render(&mut phi.renderer);
+
+        if let Some(ref font) = self.stats_font {
+            let stats = format!("{} emitters  {} particles  {:.2} ms",
+                                self.emitters, self.system.len(), self.frame_time * 1000.0);
+            font.set_color(Color::RGB(255, 255, 255));
+            font.render(&mut phi.renderer, &stats, (phi.scaled(10.0), top), 1.0);
+        }
+
+        ViewAction::None
+    }
+}
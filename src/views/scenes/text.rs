@@ -0,0 +1,149 @@
+// views/scenes/text.rs
+
+use ::sdl2::pixels::Color;
+
+use ::phi::{KeyRepeat, Phi, View, ViewAction};
+use ::phi::bitmap_font::BitmapFont;
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::views::scenes::{self, Header};
+
+/// The font sizes shown, before the UI scale is applied
+const SIZES: [i32; 5] = [12, 18, 24, 32, 40];
+
+/// Text mixing scripts the menu font may lack, drawn with its fallbacks
+const FALLBACK_TEXT: &'static str = "Fallbacks: Ωμέγα, Ёлка, 日本語, 한국어";
+
+/// The text wrapped and shortened to the width of the box
+const PARAGRAPH: &'static str =
+    "Phi breaks long text into lines no wider than a given width, and shortens \
+     it with an ellipsis when it must fit on a single line. Change the width \
+     of the box to see both follow.";
+const PARAGRAPH_SIZE: i32 = 18;
+
+/// The bounds of the width of the box, and how much the arrows change it,
+/// before the UI scale is applied
+const MIN_WIDTH: f64 = 80.0;
+const MAX_WIDTH: f64 = 700.0;
+const WIDTH_STEP: f64 = 20.0;
+
+/// Shows the ways phi draws text: TrueType text at every size, with the
+/// fallbacks of the font, wrapped and shortened to a width, and bitmap
+/// fonts for text changing every frame
+pub struct TextScene {
+    header: Header,
+
+    sizes: Vec<Sprite>,
+    fallback: Sprite,
+
+    // The width of the box, before the UI scale is applied, and the
+    // paragraph wrapped to it and shortened to it
+    width: f64,
+    wrapped: Vec<Sprite>,
+    shortened: Option<Sprite>,
+
+    clock_font: Option<BitmapFont>,
+    time: f64,
+}
+
+impl TextScene {
+    pub fn new(phi: &mut Phi) -> TextScene {
+        let sizes = SIZES.iter().map(|&size| {
+            let text = format!("{} px: The quick brown fox jumps over the lazy dog", size);
+            phi.ttf_str_sprite(&text, "menu_font", size, Color::RGB(255, 255, 255)).unwrap()
+        }).collect();
+
+        let mut scene = TextScene {
+            header: Header::new(phi, "Text", "Left/Right: narrow/widen the box"),
+            sizes: sizes,
+            fallback: phi.ttf_str_sprite(FALLBACK_TEXT, "menu_font", 24, Color::RGB(255, 255, 255)).unwrap(),
+            width: 360.0,
+            wrapped: vec![],
+            shortened: None,
+            clock_font: phi.bitmap_font("0123456789.: clock", "menu_font", 24),
+            time: 0.0,
+        };
+        scene.layout(phi);
+        scene
+    }
+
+    /// Wrap and shorten the paragraph to the width of the box again
+    fn layout(&mut self, phi: &mut Phi) {
+        let width = phi.scaled(self.width);
+        let lines = phi.wrap_text(PARAGRAPH, "menu_font", PARAGRAPH_SIZE, width);
+        self.wrapped = lines.iter()
+            .filter_map(|line| phi.ttf_str_sprite(line, "menu_font", PARAGRAPH_SIZE, Color::RGB(220, 220, 220)))
+            .collect();
+
+        let shortened = phi.fit_text(PARAGRAPH, "menu_font", PARAGRAPH_SIZE, width);
+        self.shortened = phi.ttf_str_sprite(&shortened, "menu_font", PARAGRAPH_SIZE, Color::RGB(220, 220, 220));
+    }
+}
+
+impl View for TextScene {
+    fn resume(&mut self, phi: &mut Phi) {
+        phi.events.set_key_repeat(Some(KeyRepeat {
+            delay: 0.4,
+            rate: 8.0,
+        }));
+    }
+
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if let Some(action) = scenes::leave(phi) {
+            return action;
+        }
+
+        let width = if phi.events.now.repeat.key_left { self.width - WIDTH_STEP }
+                    else if phi.events.now.repeat.key_right { self.width + WIDTH_STEP }
+                    else { self.width };
+        let width = width.max(MIN_WIDTH).min(MAX_WIDTH);
+        if width != self.width {
+            self.width = width;
+            self.layout(phi);
+        }
+        self.time += elapsed;
+
+        let mut y = self.header.render(phi);
+        let margin = phi.scaled(10.0);
+
+        for sprite in self.sizes.iter().chain(Some(&self.fallback)) {
+            let (w, h) = sprite.size();
+            sprite.render(&mut phi.renderer, Rectangle { x: margin, y: y, w: w, h: h });
+            y += h;
+        }
+        y += margin;
+
+        // The box the paragraph is wrapped to, then shortened to
+        let box_w = phi.scaled(self.width);
+        let text_h: f64 = self.wrapped.iter().map(|line| line.size().1).sum::<f64>()
+            + self.shortened.as_ref().map_or(0.0, |line| line.size().1 + margin);
+        phi.renderer.set_draw_color(Color::RGB(40, 40, 80));
+        phi.renderer.fill_rect(Rectangle {
+            x: margin,
+            y: y,
+            w: box_w,
+            h: text_h.max(1.0),
+        }.to_sdl().unwrap());
+
+        for line in &self.wrapped {
+            let (w, h) = line.size();
+            line.render(&mut phi.renderer, Rectangle { x: margin, y: y, w: w, h: h });
+            y += h;
+        }
+        if let Some(ref line) = self.shortened {
+            y += margin;
+            let (w, h) = line.size();
+            line.render(&mut phi.renderer, Rectangle { x: margin, y: y, w: w, h: h });
+            y += h;
+        }
+        y += margin;
+
+        // Text changing every frame, drawn glyph by glyph
+        if let Some(ref font) = self.clock_font {
+            font.set_color(Color::RGB(120, 220, 255));
+            font.render(&mut phi.renderer, &format!("clock: {:.2}", self.time), (margin, y), 1.0);
+        }
+
+        ViewAction::None
+    }
+}
@@ -0,0 +1,142 @@
+// views/scripting.rs
+
+//! Gameplay scripts written in Lua, found in `assets/scripts/`. They are
+//! only run when the game is built with the `scripting` feature; otherwise
+//! `Scripts` knows no function and every call fails.
+//!
+//! Scripts are reloaded whenever one of their files changes, so enemy
+//! patterns can be tuned while the game is running.
+
+pub const SCRIPTS_DIR: &'static str = "assets/scripts";
+
+#[cfg(feature = "scripting")]
+pub use self::lua::Scripts;
+
+#[cfg(not(feature = "scripting"))]
+pub use self::disabled::Scripts;
+
+#[cfg(feature = "scripting")]
+mod lua {
+    use ::std::fs::{self, File};
+    use ::std::io::Read;
+    use ::std::path::PathBuf;
+    use ::std::time::SystemTime;
+
+    use ::rlua::{Function, Lua};
+
+    /// Seconds between two checks for modified scripts
+    const RELOAD_INTERVAL: f64 = 1.0;
+
+    pub struct Scripts {
+        lua: Lua,
+        dir: String,
+
+        // Every script file, with its modification time when last loaded
+        files: Vec<(PathBuf, Option<SystemTime>)>,
+        reload_timer: f64,
+    }
+
+    impl Scripts {
+        /// Run every `.lua` file of 'dir'
+        pub fn load(dir: &str) -> Scripts {
+            let mut scripts = Scripts {
+                lua: Lua::new(),
+                dir: dir.to_string(),
+                files: vec![],
+                reload_timer: RELOAD_INTERVAL,
+            };
+            scripts.reload();
+            scripts
+        }
+
+        /// Reload the scripts if any of them was added or modified
+        pub fn update(&mut self, dt: f64) {
+            self.reload_timer -= dt;
+            if self.reload_timer > 0.0 {
+                return;
+            }
+            self.reload_timer = RELOAD_INTERVAL;
+
+            if list_scripts(&self.dir) != self.files {
+                info!("Reloading the scripts in {}", self.dir);
+                self.reload();
+            }
+        }
+
+        // Start over from a fresh interpreter, so that removed functions
+        // do not linger
+        fn reload(&mut self) {
+            self.lua = Lua::new();
+            self.files = list_scripts(&self.dir);
+
+            for &(ref path, _) in &self.files {
+                let mut source = String::new();
+                let read = File::open(path).and_then(|mut file| file.read_to_string(&mut source));
+                if read.is_err() {
+                    warn!("Could not read the script {:?}", path);
+                    continue;
+                }
+
+                let name = path.to_string_lossy().into_owned();
+                if let Err(err) = self.lua.exec::<_, ()>(&source, Some(&name)) {
+                    warn!("Error in the script {}: {}", name, err);
+                }
+            }
+        }
+
+        /// Call the global function 'name' with 'progress', expecting a
+        /// point in return
+        pub fn point(&self, name: &str, progress: f64) -> Option<(f64, f64)> {
+            let function: Function = match self.lua.globals().get(name) {
+                Ok(function) => function,
+                Err(_) => return None,
+            };
+
+            match function.call::<_, (f64, f64)>(progress) {
+                Ok(point) => Some(point),
+                Err(err) => {
+                    warn!("Error in the script function {}: {}", name, err);
+                    None
+                }
+            }
+        }
+    }
+
+    // Return the scripts of 'dir', in a stable order, with their
+    // modification time
+    fn list_scripts(dir: &str) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let mut files: Vec<_> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().map_or(false, |ext| ext == "lua"))
+                .map(|path| {
+                    let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+                    (path, modified)
+                })
+                .collect(),
+            Err(_) => vec![],
+        };
+
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        files
+    }
+}
+
+#[cfg(not(feature = "scripting"))]
+mod disabled {
+    pub struct Scripts;
+
+    impl Scripts {
+        pub fn load(_dir: &str) -> Scripts {
+            Scripts
+        }
+
+        pub fn update(&mut self, _dt: f64) {
+        }
+
+        pub fn point(&self, _name: &str, _progress: f64) -> Option<(f64, f64)> {
+            None
+        }
+    }
+}
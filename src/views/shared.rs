@@ -1,19 +1,91 @@
 // shared.rs
 
+use ::sdl2::pixels::Color;
 use ::sdl2::render::Renderer;
+use ::phi::Phi;
+use ::phi::assets::{self, unquote};
+use ::phi::themes;
+use ::std::fs::File;
+use ::std::io::{self, Write};
 use ::phi::data::Rectangle;
-use ::phi::gfx::{Renderable, Sprite};
+use ::phi::gfx::{Cursor, Sprite, SpriteBatch};
+use ::phi::shapes::{self, Gradient};
+
+/// Seconds during which an enemy or an asteroid flashes after a hit
+pub const HIT_FLASH: f64 = 0.08;
+
+/// Render a box of the menus filled with 'color', shaded darker toward the
+/// bottom, with rounded corners
+pub fn render_menu_box(phi: &mut Phi, rect: Rectangle, color: Color) {
+    let radius = phi.scaled(8.0);
+    let bottom = shapes::mix(color, Color::RGB(0, 0, 0), 0.35);
+    shapes::fill_rounded_gradient(&mut phi.renderer, rect, radius, color, bottom, Gradient::Vertical);
+}
+
+/// The color of the labels of menu entries which cannot be chosen
+pub const DISABLED_COLOR: Color = Color::RGB(110, 110, 110);
+
+/// The sound played when a disabled menu entry is chosen
+pub const REFUSED_SOUND: &'static str = "menu_refused";
+
+/// The height of the arrow replacing the mouse cursor in the menus, before
+/// the UI scale is applied
+const MENU_CURSOR_H: f64 = 18.0;
+
+/// Replace the mouse cursor by the arrow of the menus, for as long as the
+/// view calling it is shown
+pub fn show_menu_cursor(phi: &mut Phi) {
+    let height = phi.scaled(MENU_CURSOR_H) as u32;
+    let cursor = Cursor::arrow(&phi.renderer, height);
+    phi.set_cursor(cursor);
+}
+
+/// Return the position 'step' places away from 'index' in a list of 'len'
+/// items, wrapping around
+pub fn cycle(index: usize, len: usize, step: i32) -> usize {
+    let len = len as i32;
+    (((index as i32 + step) % len + len) % len) as usize
+}
+
+/// Play the sound called 'name'
+pub fn play_sound(name: &str) {
+    // The game has no audio yet
+    debug!("Playing the sound {}", name);
+}
+
+/// How far, in pixels, things may go past the left, top and bottom edges
+/// of the screen before they are removed from the game
+pub const DESPAWN_MARGIN: f64 = 128.0;
+
+/// How far things may be past the right edge, where they enter the screen
+pub const ENTRY_MARGIN: f64 = 256.0;
+
+/// Whether 'rect' went far enough past the edges of a screen of 'size'
+/// to be removed from the game
+pub fn is_despawned(rect: Rectangle, (w, h): (f64, f64)) -> bool {
+    let bounds = Rectangle {
+        x: -DESPAWN_MARGIN,
+        y: -DESPAWN_MARGIN,
+        w: w + DESPAWN_MARGIN + ENTRY_MARGIN,
+        h: h + DESPAWN_MARGIN * 2.0,
+    };
+    !bounds.overlaps(rect)
+}
 
 #[derive(Clone)]
 pub struct Background {
     pub pos: f64,
     // The number of pixels to move left every second
     pub vel: f64,
+    // The number of pixels of the image by which it is moved down; it
+    // wraps around vertically
+    pub offset: f64,
     pub sprite: Sprite,
 }
 
 impl Background {
-    pub fn render(&mut self, renderer: &mut Renderer, elapsed: f64) {
+    /// Move the background left by 'elapsed' seconds, without drawing it
+    pub fn scroll(&mut self, elapsed: f64) {
         // We define a logical position as depending solely on the time
         // and on the dimensions of the image, not on the screen size.
         let size = self.sprite.size();
@@ -21,56 +93,256 @@ impl Background {
         if self.pos > size.0 {
             self.pos -= size.0;
         }
+    }
+
+    /// Move the background left by 'elapsed' seconds, then draw it
+    pub fn render(&mut self, renderer: &mut Renderer, elapsed: f64) {
+        self.scroll(elapsed);
+        self.draw(renderer);
+    }
+
+    /// Draw the background where it scrolled to
+    pub fn draw(&self, renderer: &mut Renderer) {
+        let size = self.sprite.size();
 
         // We determine the scale ration of the window to the sprite.
         let (win_w, win_h) = renderer.output_size().unwrap();
         let scale = win_h as f64 / size.1;
 
         // We render as many copies of the background as necessary
-        // to fill the screen.
+        // to fill the screen, submitting them together as a single batch.
+        let mut batch = SpriteBatch::new();
         let mut physical_left = -self.pos * scale;
+        let top = (self.offset % size.1 + size.1) % size.1 * scale;
 
         while physical_left < win_w as f64 {
-            // While the left of the image is still inside the window
-            self.sprite.render(renderer, Rectangle {
-            //renderer.copy_sprite(&self.sprite, Rectangle {
-                x: physical_left,
-                y: 0.0,
-                w: size.0 * scale,
-                h: win_h as f64,
-            });
+            // While the left of the image is still inside the window, with
+            // the bottom of the image wrapped above it if it is moved down
+            for &y in &[top - win_h as f64, top] {
+                if y < win_h as f64 && y + win_h as f64 > 0.0 {
+                    batch.push(&self.sprite, Rectangle {
+                        x: physical_left,
+                        y: y,
+                        w: size.0 * scale,
+                        h: win_h as f64,
+                    });
+                }
+            }
 
             physical_left += size.0 * scale;
         }
+
+        batch.flush(renderer);
+    }
+}
+
+/// The background sets levels may be played over
+pub const BACKGROUNDS_PATH: &'static str = "assets/backgrounds.toml";
+
+/// The set used when none is chosen or the chosen one does not exist
+pub const DEFAULT_BACKGROUND: &'static str = "stars";
+
+/// A layer of a background set: the asset it shows, the pixels it moves
+/// left every second, and the pixels of the image by which it is moved down
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayerDef {
+    pub asset: String,
+    pub speed: f64,
+    pub offset: f64,
+}
+
+/// The three layers of backgrounds, from the back to the front, and the
+/// color they are all tinted with
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundSet {
+    pub name: String,
+    pub back: LayerDef,
+    pub middle: LayerDef,
+    pub front: LayerDef,
+    pub tint: Color,
+}
+
+impl BackgroundSet {
+    /// The stars the game always came with
+    pub fn default() -> BackgroundSet {
+        BackgroundSet {
+            name: DEFAULT_BACKGROUND.to_string(),
+            back: LayerDef { asset: "star_back".to_string(), speed: 20.0, offset: 0.0 },
+            middle: LayerDef { asset: "star_middle".to_string(), speed: 40.0, offset: 0.0 },
+            front: LayerDef { asset: "star_front".to_string(), speed: 80.0, offset: 0.0 },
+            tint: Color::RGB(255, 255, 255),
+        }
     }
+
+    /// The layer at 'index', counting from the back
+    pub fn layer_mut(&mut self, index: usize) -> &mut LayerDef {
+        match index {
+            0 => &mut self.back,
+            1 => &mut self.middle,
+            _ => &mut self.front,
+        }
+    }
+}
+
+/// Read the background sets defined at 'path'. Every set is a table named
+/// after it, holding the `back`, `middle` and `front` asset names, their
+/// `back_speed`, `middle_speed` and `front_speed`, their `back_offset`,
+/// `middle_offset` and `front_offset`, and the `tint` color written
+/// `[r, g, b]`; the default stars fill in whatever is left out. The default
+/// set always comes first.
+pub fn load_background_sets(path: &str) -> Vec<BackgroundSet> {
+    let mut sets = vec![BackgroundSet::default()];
+
+    let tables = match assets::read_tables(path) {
+        Some(tables) => tables,
+        None => {
+            warn!("Could not open the backgrounds {}", path);
+            return sets;
+        }
+    };
+
+    for (name, values) in tables {
+        let mut set = BackgroundSet {
+            name: name.clone(),
+            ..BackgroundSet::default()
+        };
+
+        for (key, value) in values {
+            let valid = match &key[..] {
+                "back" => unquote(&value).map(|asset| set.back.asset = asset.to_string()).is_some(),
+                "middle" => unquote(&value).map(|asset| set.middle.asset = asset.to_string()).is_some(),
+                "front" => unquote(&value).map(|asset| set.front.asset = asset.to_string()).is_some(),
+                "back_speed" => value.parse().ok().map(|speed| set.back.speed = speed).is_some(),
+                "middle_speed" => value.parse().ok().map(|speed| set.middle.speed = speed).is_some(),
+                "front_speed" => value.parse().ok().map(|speed| set.front.speed = speed).is_some(),
+                "back_offset" => value.parse().ok().map(|offset| set.back.offset = offset).is_some(),
+                "middle_offset" => value.parse().ok().map(|offset| set.middle.offset = offset).is_some(),
+                "front_offset" => value.parse().ok().map(|offset| set.front.offset = offset).is_some(),
+                "tint" => themes::parse_color(&value).map(|tint| set.tint = tint).is_some(),
+                _ => false,
+            };
+            if !valid {
+                warn!("Ignoring {} = {} for the backgrounds {} in {}", key, value, name, path);
+            }
+        }
+
+        // A table for the default set amends it
+        if name == DEFAULT_BACKGROUND {
+            sets[0] = set;
+        }
+        else {
+            sets.push(set);
+        }
+    }
+
+    sets
+}
+
+/// Write the background sets to 'path', in the format read by
+/// `load_background_sets`
+pub fn save_background_sets(path: &str, sets: &[BackgroundSet]) -> io::Result<()> {
+    let mut file = try!(File::create(path));
+    try!(writeln!(file, "# Sets of backgrounds levels may be played over, picked with the `background`"));
+    try!(writeln!(file, "# line of a level. Every layer names an asset of the manifest, the pixels it"));
+    try!(writeln!(file, "# moves left every second, and the pixels of the image by which it is moved"));
+    try!(writeln!(file, "# down. Colors are written [r, g, b]. The stars fill in what a set leaves out."));
+
+    for set in sets {
+        try!(writeln!(file, ""));
+        try!(writeln!(file, "[{}]", set.name));
+        for &(name, layer) in &[("back", &set.back), ("middle", &set.middle), ("front", &set.front)] {
+            try!(writeln!(file, "{} = \"{}\"", name, layer.asset));
+            try!(writeln!(file, "{}_speed = {}", name, layer.speed));
+            try!(writeln!(file, "{}_offset = {}", name, layer.offset));
+        }
+        let (r, g, b) = set.tint.rgb();
+        try!(writeln!(file, "tint = [{}, {}, {}]", r, g, b));
+    }
+    Ok(())
 }
 
 // A group of backgrounds that can be passed from view to view
 #[derive(Clone)]
 pub struct Backgrounds {
+    /// The name of the set the backgrounds come from
+    pub name: String,
+
     pub back: Background,
     pub middle: Background,
     pub front: Background,
 }
 
 impl Backgrounds {
-    pub fn new(renderer: &mut Renderer) -> Backgrounds {
-        Backgrounds {
+    pub fn new(phi: &mut Phi) -> Backgrounds {
+        Backgrounds::load(phi, DEFAULT_BACKGROUND)
+    }
+
+    /// Load the background set called 'name', or the default one if there
+    /// is no such set
+    pub fn load(phi: &mut Phi, name: &str) -> Backgrounds {
+        let set = load_background_sets(BACKGROUNDS_PATH).into_iter()
+            .find(|set| set.name == name)
+            .unwrap_or_else(|| {
+                warn!("There are no backgrounds called {}", name);
+                BackgroundSet::default()
+            });
+        Backgrounds::from_set(phi, &set)
+    }
+
+    pub fn from_set(phi: &mut Phi, set: &BackgroundSet) -> Backgrounds {
+        // The stars stand still for players who asked for less motion
+        let speed = if phi.settings.reduce_motion { 0.0 } else { 1.0 };
+        let default = BackgroundSet::default();
+
+        let backgrounds = Backgrounds {
+            name: set.name.clone(),
             back: Background {
                 pos: 0.0,
-                vel: 20.0,
-                sprite: Sprite::load(renderer, "assets/starBG.png").unwrap(),
+                vel: set.back.speed * speed,
+                offset: set.back.offset,
+                sprite: load_layer(phi, &set.back, &default.back),
             },
             middle: Background {
                 pos: 0.0,
-                vel: 40.0,
-                sprite: Sprite::load(renderer, "assets/starMG.png").unwrap(),
+                vel: set.middle.speed * speed,
+                offset: set.middle.offset,
+                sprite: load_layer(phi, &set.middle, &default.middle),
             },
             front: Background {
                 pos: 0.0,
-                vel: 80.0,
-                sprite: Sprite::load(renderer, "assets/starFG.png").unwrap(),
+                vel: set.front.speed * speed,
+                offset: set.front.offset,
+                sprite: load_layer(phi, &set.front, &default.front),
             },
-        }
+        };
+
+        // The tint of the previous theme may linger on shared textures. The
+        // set and the palette may tint it further.
+        let theme_tint = phi.assets.theme().background_tint.unwrap_or(Color::RGB(255, 255, 255));
+        let dimming = phi.palette().background_dimming();
+        let tint = modulate(modulate(theme_tint, set.tint), dimming);
+        backgrounds.back.sprite.set_color_mod(tint);
+        backgrounds.middle.sprite.set_color_mod(tint);
+        backgrounds.front.sprite.set_color_mod(tint);
+
+        backgrounds
+    }
+}
+
+// Load the sprite of a layer, or the one of the same layer in the default
+// set if it cannot be loaded
+fn load_layer(phi: &mut Phi, layer: &LayerDef, default: &LayerDef) -> Sprite {
+    if let Some(sprite) = phi.load_sprite(&layer.asset) {
+        return sprite;
     }
+    warn!("Could not load the background {}, showing {} instead", layer.asset, default.asset);
+    phi.load_sprite(&default.asset).unwrap()
+}
+
+// Multiply two colors, as color modulation does
+fn modulate(a: Color, b: Color) -> Color {
+    let ((ar, ag, ab), (br, bg, bb)) = (a.rgb(), b.rgb());
+    Color::RGB((ar as u32 * br as u32 / 255) as u8,
+               (ag as u32 * bg as u32 / 255) as u8,
+               (ab as u32 * bb as u32 / 255) as u8)
 }
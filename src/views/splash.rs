@@ -0,0 +1,150 @@
+// views/splash.rs
+
+use ::std::fs::File;
+use ::std::io::{BufRead, BufReader};
+
+use ::sdl2::pixels::Color;
+use ::sdl2::render::BlendMode;
+
+use ::phi::data::Rectangle;
+use ::phi::gfx::{Renderable, Sprite};
+use ::phi::{Phi, View, ViewAction};
+
+pub const ATTRIBUTION_PATH: &'static str = "assets/attribution.txt";
+
+/// The seconds each card takes to fade in, stays fully shown, and takes to
+/// fade out
+const FADE: f64 = 0.5;
+const HOLD: f64 = 1.0;
+
+/// The cards shown one after the other: the optional logo of the manifest
+/// shown above the text, and the key of the text
+const CARDS: [(&'static str, &'static str); 2] = [
+    ("logo_engine", "splash.engine"),
+    ("logo_author", "splash.author"),
+];
+
+/// Return the credits for the assets in 'path', one per line. Empty lines
+/// and lines starting with `#` are ignored.
+pub fn load_attribution(path: &str) -> Vec<String> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => {
+            warn!("Could not open the attribution in {}", path);
+            return vec![];
+        }
+    };
+
+    BufReader::new(file).lines()
+        .take_while(|line| line.is_ok())
+        .filter_map(|line| line.ok())
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
+}
+
+// A logo and the lines of text under it
+struct Card {
+    logo: Option<Sprite>,
+    lines: Vec<Sprite>,
+}
+
+/// Shows the logos of the engine and of the authors, then the credits for
+/// the assets, each fading in and out, before moving on to the main menu.
+/// Any key, controller button or click skips to the menu.
+pub struct SplashView {
+    cards: Vec<Card>,
+    card: usize,
+    shown_for: f64,
+}
+
+impl SplashView {
+    pub fn new(phi: &mut Phi) -> SplashView {
+        let white = Color::RGB(255, 255, 255);
+        let mut cards: Vec<Card> = CARDS.iter().map(|&(logo, key)| {
+            let text = phi.locale.text(key);
+            Card {
+                logo: phi.load_sprite(logo),
+                lines: text.lines()
+                    .filter_map(|line| phi.ttf_str_sprite(line, "menu_font", 28, white))
+                    .collect(),
+            }
+        }).collect();
+
+        let credits = load_attribution(ATTRIBUTION_PATH);
+        if !credits.is_empty() {
+            let title = phi.locale.text("splash.attribution");
+            let mut lines: Vec<Sprite> = phi.ttf_str_sprite(&title, "menu_font", 28, white).into_iter().collect();
+            lines.extend(credits.iter()
+                .filter_map(|credit| phi.ttf_str_sprite(credit, "menu_font", 18, Color::RGB(200, 200, 200))));
+            cards.push(Card {
+                logo: None,
+                lines: lines,
+            });
+        }
+
+        SplashView {
+            cards: cards,
+            card: 0,
+            shown_for: 0.0,
+        }
+    }
+}
+
+impl View for SplashView {
+    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        self.shown_for += elapsed;
+        if self.shown_for >= FADE * 2.0 + HOLD {
+            self.shown_for = 0.0;
+            self.card += 1;
+        }
+        if self.card >= self.cards.len() || phi.events.any_pressed() {
+            return ViewAction::ChangeView(Box::new(
+                ::views::main_menu::MainMenuView::new(phi)));
+        }
+
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // The logo and the lines, centered as a whole
+        let (win_w, win_h) = phi.output_size();
+        let gap = phi.scaled(12.0);
+        let card = &self.cards[self.card];
+        let sprites: Vec<&Sprite> = card.logo.iter().chain(card.lines.iter()).collect();
+        let total_h = sprites.iter().map(|sprite| sprite.size().1 + gap).sum::<f64>() - gap;
+
+        let mut y = (win_h - total_h) / 2.0;
+        for sprite in sprites {
+            let (w, h) = sprite.size();
+            sprite.render(&mut phi.renderer, Rectangle {
+                x: (win_w - w) / 2.0,
+                y: y,
+                w: w,
+                h: h,
+            });
+            y += h + gap;
+        }
+
+        // Fade from and to black
+        let shown = (self.shown_for / FADE)
+            .min((FADE * 2.0 + HOLD - self.shown_for) / FADE)
+            .max(0.0).min(1.0);
+        if shown < 1.0 {
+            phi.renderer.set_blend_mode(BlendMode::Blend);
+            phi.renderer.set_draw_color(Color::RGBA(0, 0, 0, ((1.0 - shown) * 255.0) as u8));
+            phi.renderer.fill_rect(Rectangle {
+                x: 0.0,
+                y: 0.0,
+                w: win_w,
+                h: win_h,
+            }.to_sdl().unwrap());
+            phi.renderer.set_blend_mode(BlendMode::None);
+        }
+
+        ViewAction::None
+    }
+}
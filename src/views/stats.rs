@@ -0,0 +1,87 @@
+// views/stats.rs
+
+use ::phi::locale::Locale;
+
+/// The bonus awarded at the end of a wave for an accuracy at least as high
+/// as the first value of every pair; the highest tier reached wins
+const ACCURACY_BONUSES: [(f64, u32); 3] = [
+    (0.9, 5_000),
+    (0.8, 3_000),
+    (0.6, 1_000),
+];
+
+/// The shots fired during a wave and how many of them hit something
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WaveStats {
+    pub shots_fired: u32,
+    pub shots_hit: u32,
+}
+
+impl WaveStats {
+    /// Return the ratio of shots which hit something, or None if the
+    /// player did not fire at all
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.shots_fired == 0 {
+            None
+        }
+        else {
+            // Bullets fired at the end of the previous wave may still hit
+            // during this one, so there can be more hits than shots
+            let hits = self.shots_hit.min(self.shots_fired);
+            Some(hits as f64 / self.shots_fired as f64)
+        }
+    }
+
+    /// Return the bonus earned by the player's accuracy
+    pub fn bonus(&self) -> u32 {
+        let accuracy = self.accuracy().unwrap_or(0.0);
+        ACCURACY_BONUSES.iter()
+            .find(|&&(threshold, _)| accuracy >= threshold)
+            .map_or(0, |&(_, bonus)| bonus)
+    }
+
+    /// Return the text shown once the wave is cleared
+    pub fn summary(&self, locale: &Locale, wave: u32) -> String {
+        let accuracy = (self.accuracy().unwrap_or(0.0) * 100.0).round();
+        locale.format("game.wave_summary", &[&wave, &accuracy, &self.bonus()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(shots_fired: u32, shots_hit: u32) -> WaveStats {
+        WaveStats { shots_fired: shots_fired, shots_hit: shots_hit }
+    }
+
+    #[test]
+    fn accuracy_needs_a_shot_fired() {
+        assert_eq!(WaveStats::default().accuracy(), None);
+        assert_eq!(stats(4, 1).accuracy(), Some(0.25));
+        assert_eq!(stats(4, 4).accuracy(), Some(1.0));
+    }
+
+    #[test]
+    fn bonus_awards_the_highest_tier_reached() {
+        assert_eq!(stats(10, 10).bonus(), 5_000);
+        assert_eq!(stats(10, 9).bonus(), 5_000);
+        assert_eq!(stats(100, 89).bonus(), 3_000);
+        assert_eq!(stats(10, 8).bonus(), 3_000);
+        assert_eq!(stats(10, 6).bonus(), 1_000);
+        assert_eq!(stats(100, 59).bonus(), 0);
+        assert_eq!(stats(10, 0).bonus(), 0);
+    }
+
+    #[test]
+    fn accuracy_counts_no_more_hits_than_shots() {
+        assert_eq!(stats(2, 5).accuracy(), Some(1.0));
+        assert_eq!(stats(2, 5).bonus(), 5_000);
+        assert_eq!(stats(0, 3).accuracy(), None);
+    }
+
+    #[test]
+    fn bonus_is_nothing_without_shots() {
+        assert_eq!(WaveStats::default().bonus(), 0);
+    }
+}
@@ -0,0 +1,112 @@
+// views/terrain.rs
+
+use ::phi::Phi;
+use ::phi::data::Rectangle;
+use ::phi::gfx::Tilemap;
+
+use ::views::hazards;
+
+/// The layer of a terrain map whose tiles are solid; the other layers are
+/// only drawn
+pub const SOLID_LAYER: &'static str = "terrain";
+
+/// The ground of a level: a tilemap scrolling along with the hazards, whose
+/// solid tiles damage the ship and stop bullets and enemies
+pub struct Terrain {
+    map: Tilemap,
+
+    // The index of the solid layer of the map, if it has one
+    solid: Option<usize>,
+
+    // How far the terrain scrolled, and how much larger than in the map
+    // it is drawn, as of the last update
+    scroll: f64,
+    scale: f64,
+}
+
+impl Terrain {
+    /// Load the terrain drawn from the Tiled map at 'path'
+    pub fn load(phi: &mut Phi, path: &str) -> Option<Terrain> {
+        let map = match Tilemap::load(&phi.renderer, path) {
+            Some(map) => map,
+            None => return None,
+        };
+
+        let solid = map.layers.iter().position(|layer| layer.name == SOLID_LAYER);
+        if solid.is_none() {
+            warn!("The map {} has no {} layer, nothing will collide with it", path, SOLID_LAYER);
+        }
+
+        let (_, win_h) = phi.output_size();
+        let scale = map.scale(win_h);
+        Some(Terrain {
+            map: map,
+            solid: solid,
+            scroll: 0.0,
+            scale: scale,
+        })
+    }
+
+    /// Drift along with the hazards for 'dt' seconds
+    pub fn update(&mut self, phi: &Phi, dt: f64) {
+        let (_, win_h) = phi.output_size();
+        self.scale = self.map.scale(win_h);
+        self.scroll += hazards::DRIFT_SPEED * dt;
+    }
+
+    /// Scroll back to the start of the map
+    pub fn reset(&mut self) {
+        self.scroll = 0.0;
+    }
+
+    /// Return how far the terrain scrolled, in pixels of the map
+    pub fn scroll(&self) -> f64 {
+        self.scroll
+    }
+
+    pub fn set_scroll(&mut self, scroll: f64) {
+        self.scroll = scroll;
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        self.map.render(&mut phi.renderer, self.scroll);
+    }
+
+    /// Return the solid tiles overlapping 'rect', in pixels of the screen
+    pub fn solid_tiles(&self, rect: Rectangle) -> Vec<Rectangle> {
+        let layer = match self.solid {
+            Some(solid) => &self.map.layers[solid],
+            None => return vec![],
+        };
+
+        let tile_w = self.map.tile_w * self.scale;
+        let tile_h = self.map.tile_h * self.scale;
+        let first_column = ((rect.x + self.scroll) / tile_w).floor() as i64;
+        let last_column = ((rect.x + rect.w + self.scroll) / tile_w).floor() as i64;
+        let first_row = (rect.y / tile_h).floor().max(0.0) as usize;
+        let last_row = ((rect.y + rect.h) / tile_h).floor().max(0.0) as usize;
+
+        let columns = self.map.columns as i64;
+        let mut tiles = vec![];
+        for column in first_column..last_column + 1 {
+            // The map repeats horizontally, as it is drawn
+            let wrapped = ((column % columns + columns) % columns) as usize;
+            for row in first_row..(last_row + 1).min(self.map.rows) {
+                if layer.tile(self.map.columns, wrapped, row) != 0 {
+                    tiles.push(Rectangle {
+                        x: column as f64 * tile_w - self.scroll,
+                        y: row as f64 * tile_h,
+                        w: tile_w,
+                        h: tile_h,
+                    });
+                }
+            }
+        }
+        tiles
+    }
+
+    /// Whether 'rect', in pixels of the screen, runs into a solid tile
+    pub fn overlaps(&self, rect: Rectangle) -> bool {
+        !self.solid_tiles(rect).is_empty()
+    }
+}
@@ -0,0 +1,185 @@
+// views/timeline.rs
+
+use ::std::fs::File;
+use ::std::io::{BufRead, BufReader};
+
+use ::views::hazards::HazardSpawn;
+
+pub const INTRO_PATH: &'static str = "assets/timelines/intro.txt";
+pub const BOSS_PATH: &'static str = "assets/timelines/boss.txt";
+pub const ENDING_PATH: &'static str = "assets/timelines/ending.txt";
+pub const FINAL_PATH: &'static str = "assets/timelines/final.txt";
+
+/// Something a timeline brings into the game
+#[derive(Clone, Debug, PartialEq)]
+pub enum Spawn {
+    Hazard(HazardSpawn),
+
+    /// An asteroid of a random size, entering from the right
+    Asteroid,
+
+    /// The formation of enemies called after the name, or a random one
+    Formation(Option<String>),
+
+    Boss,
+}
+
+/// A step of a timeline, run by the game view
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Hold the following actions for some seconds
+    Wait(f64),
+
+    Spawn(Spawn),
+
+    /// Show the text of the string tables under 'key' for some seconds
+    Text { key: String, duration: f64 },
+
+    /// Move the camera to an offset, in pixels, over some seconds
+    Camera { x: f64, y: f64, duration: f64 },
+
+    /// Take the ship away from the player and fly it to a point, in
+    /// fractions of the screen, over some seconds; it stays there until
+    /// the run ends
+    Ship { x: f64, y: f64, duration: f64 },
+
+    /// Play the sound called after the name
+    Sound(String),
+}
+
+/// A script of timed actions, such as the introduction of the level or the
+/// entrance of the boss. Actions run one after the other, as soon as the
+/// previous ones are done; only `wait` takes time.
+///
+/// While a timeline plays, it takes over the spawning of the game view: the
+/// hazards of the level, the formations and the asteroids only come from it.
+#[derive(Clone, Debug)]
+pub struct Timeline {
+    actions: Vec<Action>,
+    next: usize,
+
+    // Seconds left before the next action runs
+    wait: f64,
+}
+
+impl Timeline {
+    /// Read the timeline at 'path'. Every line holds an action:
+    ///
+    /// * `wait SECONDS`
+    /// * `spawn mine Y`, `spawn debris Y COUNT`, `spawn well Y`,
+    ///   `spawn asteroid`, `spawn formation [NAME]` or `spawn boss`, where Y
+    ///   is a height in fractions of the screen
+    /// * `text KEY SECONDS`
+    /// * `camera X Y SECONDS`
+    /// * `ship X Y SECONDS`, where X and Y are fractions of the screen, and
+    ///   may lie outside of it for the ship to fly off
+    /// * `sound NAME`
+    ///
+    /// Empty lines and lines starting with `#` are ignored. A timeline which
+    /// cannot be read is empty, and ends as soon as it starts.
+    pub fn load(path: &str) -> Timeline {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => {
+                warn!("Could not open the timeline {}", path);
+                return Timeline::new(vec![]);
+            }
+        };
+
+        let mut actions = vec![];
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match parse_action(&words) {
+                Some(action) => actions.push(action),
+                None => warn!("Ignoring malformed line in {}: {}", path, line),
+            }
+        }
+
+        Timeline::new(actions)
+    }
+
+    pub fn new(actions: Vec<Action>) -> Timeline {
+        Timeline {
+            actions: actions,
+            next: 0,
+            wait: 0.0,
+        }
+    }
+
+    /// Let 'dt' seconds pass, and return the actions to run, in order
+    pub fn update(&mut self, dt: f64) -> Vec<Action> {
+        self.wait -= dt;
+
+        let mut due = vec![];
+        while self.wait <= 0.0 && self.next < self.actions.len() {
+            let action = self.actions[self.next].clone();
+            self.next += 1;
+
+            match action {
+                // What is left of this frame counts toward the wait
+                Action::Wait(seconds) => self.wait += seconds,
+                action => due.push(action),
+            }
+        }
+        due
+    }
+
+    /// Whether the boss is among the things the timeline brings in
+    pub fn spawns_boss(&self) -> bool {
+        self.actions.iter().any(|action| *action == Action::Spawn(Spawn::Boss))
+    }
+
+    /// Whether every action ran, and the last wait is over
+    pub fn is_finished(&self) -> bool {
+        self.next == self.actions.len() && self.wait <= 0.0
+    }
+}
+
+fn parse_action(words: &[&str]) -> Option<Action> {
+    match (words.get(0).cloned(), words.len()) {
+        (Some("wait"), 2) => words[1].parse().ok().map(Action::Wait),
+        (Some("spawn"), _) => parse_spawn(&words[1..]).map(Action::Spawn),
+        (Some("text"), 3) => words[2].parse().ok().map(|duration| Action::Text {
+            key: words[1].to_string(),
+            duration: duration,
+        }),
+        (Some("camera"), 4) => match (words[1].parse(), words[2].parse(), words[3].parse()) {
+            (Ok(x), Ok(y), Ok(duration)) => Some(Action::Camera { x: x, y: y, duration: duration }),
+            _ => None,
+        },
+        (Some("ship"), 4) => match (words[1].parse(), words[2].parse(), words[3].parse()) {
+            (Ok(x), Ok(y), Ok(duration)) => Some(Action::Ship { x: x, y: y, duration: duration }),
+            _ => None,
+        },
+        (Some("sound"), 2) => Some(Action::Sound(words[1].to_string())),
+        _ => None,
+    }
+}
+
+fn parse_spawn(words: &[&str]) -> Option<Spawn> {
+    match (words.get(0).cloned(), words.len()) {
+        (Some("mine"), 2) => words[1].parse().ok()
+            .map(|y| Spawn::Hazard(HazardSpawn::Mine { y: y })),
+        (Some("well"), 2) => words[1].parse().ok()
+            .map(|y| Spawn::Hazard(HazardSpawn::Well { y: y })),
+        (Some("debris"), 3) => match (words[1].parse(), words[2].parse()) {
+            (Ok(y), Ok(count)) => Some(Spawn::Hazard(HazardSpawn::Debris { y: y, count: count })),
+            _ => None,
+        },
+        (Some("asteroid"), 1) => Some(Spawn::Asteroid),
+        (Some("formation"), 1) => Some(Spawn::Formation(None)),
+        (Some("formation"), 2) => Some(Spawn::Formation(Some(words[1].to_string()))),
+        (Some("boss"), 1) => Some(Spawn::Boss),
+        _ => None,
+    }
+}